@@ -0,0 +1,219 @@
+//! A backend-neutral record of what a frame submits.
+//!
+//! Frame code records [`Command`]s into a [`CommandRecorder`]; the D3D12 backend translates
+//! them into command list calls, and tests and frame captures can inspect them without a device.
+
+/// Number of 32-bit values in [`Command::SetDrawConstants`], as declared in the root signature.
+pub const DRAW_CONSTANT_COUNT: u32 = 4;
+
+/// One step of a recorded frame.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    SetViewport(Viewport),
+    SetScissor(ScissorRect),
+    /// Put `back_buffer` in `state` before the next command; the backend batches these into
+    /// barriers using the tracked current state.
+    RequireState {
+        back_buffer: usize,
+        state: ResourceState,
+    },
+    /// A transition written out by hand, checked against the tracked state in debug builds.
+    Transition {
+        back_buffer: usize,
+        before: ResourceState,
+        after: ResourceState,
+    },
+    SetRenderTarget {
+        back_buffer: usize,
+    },
+    Clear {
+        back_buffer: usize,
+        color: [f32; 4],
+    },
+    BindPipeline(PipelineId),
+    /// Bind the late-latched cursor constants at `gpu_address`, which come from the per-frame
+    /// upload ring.
+    BindCursorConstants {
+        gpu_address: u64,
+    },
+    SetDrawConstants([u32; DRAW_CONSTANT_COUNT as usize]),
+    BindVertexRange(VertexBufferRange),
+    Draw(VertexRange),
+    Present,
+}
+
+impl Command {
+    /// Whether the command only changes resource states, which the backend batches.
+    pub fn is_state_change(&self) -> bool {
+        matches!(self, Self::RequireState { .. } | Self::Transition { .. })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub min_depth: f32,
+    pub max_depth: f32,
+}
+
+impl Viewport {
+    pub fn full(width: u32, height: u32) -> Self {
+        Self {
+            x: 0.0,
+            y: 0.0,
+            width: width as f32,
+            height: height as f32,
+            min_depth: 0.0,
+            max_depth: 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScissorRect {
+    pub left: i32,
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+}
+
+impl ScissorRect {
+    pub fn full(width: u32, height: u32) -> Self {
+        Self {
+            left: 0,
+            top: 0,
+            right: width as i32,
+            bottom: height as i32,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ResourceState {
+    Present,
+    RenderTarget,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PipelineId(pub u32);
+
+impl PipelineId {
+    pub const TRANSPARENT: Self = Self(0);
+}
+
+/// A slice of a vertex buffer bound to the input assembler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VertexBufferRange {
+    pub offset_bytes: u64,
+    pub size_bytes: u32,
+    pub stride_bytes: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VertexRange {
+    pub start: u32,
+    pub count: u32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CommandRecorder {
+    commands: Vec<Command>,
+}
+
+impl CommandRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn clear(&mut self) {
+        self.commands.clear();
+    }
+
+    pub fn commands(&self) -> &[Command] {
+        &self.commands
+    }
+
+    pub fn set_viewport(&mut self, viewport: Viewport) {
+        self.commands.push(Command::SetViewport(viewport));
+    }
+
+    pub fn set_scissor(&mut self, scissor: ScissorRect) {
+        self.commands.push(Command::SetScissor(scissor));
+    }
+
+    pub fn require_state(&mut self, back_buffer: usize, state: ResourceState) {
+        self.commands
+            .push(Command::RequireState { back_buffer, state });
+    }
+
+    pub fn transition(&mut self, back_buffer: usize, before: ResourceState, after: ResourceState) {
+        self.commands.push(Command::Transition {
+            back_buffer,
+            before,
+            after,
+        });
+    }
+
+    pub fn set_render_target(&mut self, back_buffer: usize) {
+        self.commands.push(Command::SetRenderTarget { back_buffer });
+    }
+
+    pub fn clear_render_target(&mut self, back_buffer: usize, color: [f32; 4]) {
+        self.commands.push(Command::Clear { back_buffer, color });
+    }
+
+    pub fn bind_pipeline(&mut self, pipeline: PipelineId) {
+        self.commands.push(Command::BindPipeline(pipeline));
+    }
+
+    pub fn bind_cursor_constants(&mut self, gpu_address: u64) {
+        self.commands
+            .push(Command::BindCursorConstants { gpu_address });
+    }
+
+    pub fn set_draw_constants(&mut self, constants: [u32; DRAW_CONSTANT_COUNT as usize]) {
+        self.commands.push(Command::SetDrawConstants(constants));
+    }
+
+    pub fn bind_vertex_range(&mut self, range: VertexBufferRange) {
+        self.commands.push(Command::BindVertexRange(range));
+    }
+
+    pub fn draw(&mut self, vertices: VertexRange) {
+        self.commands.push(Command::Draw(vertices));
+    }
+
+    pub fn present(&mut self) {
+        self.commands.push(Command::Present);
+    }
+
+    /// The vertex ranges drawn, in submission order.
+    pub fn draws(&self) -> impl Iterator<Item = VertexRange> + '_ {
+        self.commands.iter().filter_map(|command| match command {
+            Command::Draw(range) => Some(*range),
+            _ => None,
+        })
+    }
+
+    /// The states `back_buffer` is put in, in submission order, whether requested or
+    /// transitioned by hand.
+    pub fn states(&self, back_buffer: usize) -> impl Iterator<Item = ResourceState> + '_ {
+        self.commands
+            .iter()
+            .filter_map(move |command| match command {
+                Command::RequireState {
+                    back_buffer: target,
+                    state,
+                } if *target == back_buffer => Some(*state),
+                Command::Transition {
+                    back_buffer: target,
+                    after,
+                    ..
+                } if *target == back_buffer => Some(*after),
+                _ => None,
+            })
+    }
+}
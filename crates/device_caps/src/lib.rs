@@ -0,0 +1,182 @@
+//! Device capability reports for `device caps`: what an adapter supports, as plain name/value
+//! strings that serialize to JSON and can be compared across machines.
+
+use facet::Facet;
+use std::fmt::Write as _;
+
+/// An adapter as `IDXGIAdapter1::GetDesc1` describes it, so that selection can be decided on
+/// plain data.
+#[derive(Facet, Debug, Clone, PartialEq, Eq)]
+pub struct AdapterInfo {
+    /// Position in enumeration order for the GPU preference used to list it.
+    pub index: u32,
+    pub description: String,
+    pub vendor_id: u32,
+    pub device_id: u32,
+    /// Locally unique identifier; stable until the next reboot.
+    pub luid: u64,
+    pub dedicated_video_memory: u64,
+    pub software: bool,
+    pub remote: bool,
+}
+
+impl AdapterInfo {
+    pub fn flags(&self) -> &'static str {
+        match (self.software, self.remote) {
+            (true, true) => "software, remote",
+            (true, false) => "software",
+            (false, true) => "remote",
+            (false, false) => "hardware",
+        }
+    }
+}
+
+/// Bumped when capability names or value formats change, so reports from different versions are
+/// not compared value by value.
+pub const DEVICE_CAPS_VERSION: u32 = 1;
+
+/// What a device supports, as reported by `device caps`.
+///
+/// Capabilities are name/value strings in query order so that the JSON form stays stable and two
+/// machines' reports can be compared without knowing every field.
+#[derive(Facet, Debug, Clone, PartialEq, Eq)]
+pub struct DeviceCaps {
+    pub version: u32,
+    pub adapter: AdapterInfo,
+    pub capabilities: Vec<Capability>,
+}
+
+#[derive(Facet, Debug, Clone, PartialEq, Eq)]
+pub struct Capability {
+    pub name: String,
+    pub value: String,
+}
+
+impl DeviceCaps {
+    pub fn new(adapter: AdapterInfo) -> Self {
+        Self {
+            version: DEVICE_CAPS_VERSION,
+            adapter,
+            capabilities: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, name: &str, value: impl ToString) {
+        self.capabilities.push(Capability {
+            name: name.to_string(),
+            value: value.to_string(),
+        });
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.capabilities
+            .iter()
+            .find(|capability| capability.name == name)
+            .map(|capability| capability.value.as_str())
+    }
+
+    pub fn to_json(&self) -> eyre::Result<String> {
+        facet_json::to_string_pretty(self)
+            .map_err(|error| eyre::eyre!("Failed to serialize device caps: {error}"))
+    }
+
+    pub fn from_json(json: &str) -> eyre::Result<Self> {
+        let caps: Self = facet_json::from_str(json)
+            .map_err(|error| eyre::eyre!("Failed to parse device caps: {error}"))?;
+        if caps.version != DEVICE_CAPS_VERSION {
+            eyre::bail!(
+                "Device caps report version {} is not supported; expected {DEVICE_CAPS_VERSION}",
+                caps.version
+            );
+        }
+        Ok(caps)
+    }
+
+    pub fn describe(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "adapter: {} (vendor {:#06x}, device {:#06x}, {} MiB, {})",
+            self.adapter.description,
+            self.adapter.vendor_id,
+            self.adapter.device_id,
+            self.adapter.dedicated_video_memory / (1024 * 1024),
+            self.adapter.flags()
+        );
+        let width = self
+            .capabilities
+            .iter()
+            .map(|capability| capability.name.len())
+            .max()
+            .unwrap_or(0);
+        for capability in &self.capabilities {
+            let _ = writeln!(out, "{:<width$}  {}", capability.name, capability.value);
+        }
+        out
+    }
+
+    /// Capabilities that differ from `other`, as `name: ours -> theirs`.
+    pub fn diff(&self, other: &Self) -> Vec<String> {
+        let mut differences = Vec::new();
+        if self.adapter.description != other.adapter.description {
+            differences.push(format!(
+                "adapter: {} -> {}",
+                self.adapter.description, other.adapter.description
+            ));
+        }
+
+        for capability in &self.capabilities {
+            match other.get(&capability.name) {
+                Some(value) if value == capability.value => {}
+                Some(value) => differences.push(format!(
+                    "{}: {} -> {value}",
+                    capability.name, capability.value
+                )),
+                None => differences.push(format!(
+                    "{}: {} -> (not reported)",
+                    capability.name, capability.value
+                )),
+            }
+        }
+        for capability in &other.capabilities {
+            if self.get(&capability.name).is_none() {
+                differences.push(format!(
+                    "{}: (not reported) -> {}",
+                    capability.name, capability.value
+                ));
+            }
+        }
+        differences
+    }
+}
+
+/// A plain `D3D12_*_TIER` value, where 0 means not supported.
+pub fn tier(value: i32) -> String {
+    match value {
+        0 => "not supported".to_string(),
+        value => format!("tier {value}"),
+    }
+}
+
+/// A versioned tier such as `D3D12_RAYTRACING_TIER_1_1` (11) or
+/// `D3D12_SAMPLER_FEEDBACK_TIER_0_9` (90), where `per_major` is the value of version 1.0.
+pub fn versioned_tier(value: i32, per_major: i32) -> String {
+    match value {
+        0 => "not supported".to_string(),
+        value => format!(
+            "tier {}_{}",
+            value / per_major,
+            value % per_major * 10 / per_major
+        ),
+    }
+}
+
+/// A `D3D_SHADER_MODEL` value such as 0x66 as `6_6`.
+pub fn shader_model(value: i32) -> String {
+    format!("{}_{}", value >> 4, value & 0xF)
+}
+
+/// A `D3D_ROOT_SIGNATURE_VERSION` value, where 1 is version 1.0.
+pub fn root_signature_version(value: i32) -> String {
+    format!("1_{}", value - 1)
+}
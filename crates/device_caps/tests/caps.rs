@@ -0,0 +1,114 @@
+use device_caps::AdapterInfo;
+use device_caps::DeviceCaps;
+use device_caps::root_signature_version;
+use device_caps::shader_model;
+use device_caps::tier;
+use device_caps::versioned_tier;
+
+/// A report as `device caps --json` writes it.
+const RTX_4090: &str = include_str!("fixtures/rtx_4090.json");
+
+fn caps(description: &str) -> DeviceCaps {
+    let mut caps = DeviceCaps::new(AdapterInfo {
+        index: 0,
+        description: description.to_string(),
+        vendor_id: 0x10de,
+        device_id: 0x2684,
+        luid: 5,
+        dedicated_video_memory: 24 * 1024 * 1024 * 1024,
+        software: false,
+        remote: false,
+    });
+    caps.push("shader model", shader_model(0x66));
+    caps.push("raytracing tier", versioned_tier(11, 10));
+    caps.push("tearing", true);
+    caps
+}
+
+#[test]
+fn fixture_parses_into_the_report_it_was_written_from() {
+    let parsed = DeviceCaps::from_json(RTX_4090).unwrap();
+    assert_eq!(parsed, caps("NVIDIA GeForce RTX 4090"));
+    assert_eq!(parsed.adapter.flags(), "hardware");
+    assert_eq!(parsed.get("raytracing tier"), Some("tier 1_1"));
+}
+
+#[test]
+fn json_round_trips() {
+    let caps = DeviceCaps::from_json(RTX_4090).unwrap();
+    let json = caps.to_json().unwrap();
+    assert_eq!(DeviceCaps::from_json(&json).unwrap(), caps);
+}
+
+#[test]
+fn other_versions_and_malformed_reports_are_rejected() {
+    let newer = RTX_4090.replace("\"version\": 1", "\"version\": 2");
+    let error = DeviceCaps::from_json(&newer).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Device caps report version 2 is not supported; expected 1"
+    );
+
+    let truncated = &RTX_4090[..RTX_4090.len() / 2];
+    assert!(DeviceCaps::from_json(truncated).is_err());
+    assert!(DeviceCaps::from_json("{\"version\": 1}").is_err());
+}
+
+#[test]
+fn tiers_are_formatted_with_their_version() {
+    assert_eq!(tier(0), "not supported");
+    assert_eq!(tier(3), "tier 3");
+    assert_eq!(versioned_tier(0, 10), "not supported");
+    assert_eq!(versioned_tier(10, 10), "tier 1_0");
+    assert_eq!(versioned_tier(11, 10), "tier 1_1");
+    assert_eq!(versioned_tier(90, 100), "tier 0_9");
+    assert_eq!(versioned_tier(100, 100), "tier 1_0");
+}
+
+#[test]
+fn shader_models_and_root_signature_versions_are_formatted() {
+    assert_eq!(shader_model(0x51), "5_1");
+    assert_eq!(shader_model(0x66), "6_6");
+    assert_eq!(root_signature_version(1), "1_0");
+    assert_eq!(root_signature_version(3), "1_2");
+}
+
+#[test]
+fn description_aligns_values_under_the_adapter_line() {
+    assert_eq!(
+        caps("NVIDIA GeForce RTX 4090").describe(),
+        "adapter: NVIDIA GeForce RTX 4090 (vendor 0x10de, device 0x2684, 24576 MiB, hardware)\n\
+         shader model     6_6\n\
+         raytracing tier  tier 1_1\n\
+         tearing          true\n"
+    );
+}
+
+#[test]
+fn capabilities_are_looked_up_by_name() {
+    let caps = caps("NVIDIA GeForce RTX 4090");
+    assert_eq!(caps.get("tearing"), Some("true"));
+    assert_eq!(caps.get("mesh shader tier"), None);
+}
+
+#[test]
+fn diff_reports_changed_missing_and_added_capabilities() {
+    let ours = caps("NVIDIA GeForce RTX 4090");
+    assert!(ours.diff(&ours).is_empty());
+
+    let mut theirs = caps("Intel(R) Arc(TM) A770");
+    theirs
+        .capabilities
+        .retain(|capability| capability.name != "shader model");
+    theirs.capabilities[0].value = versioned_tier(10, 10);
+    theirs.push("mesh shader tier", versioned_tier(10, 10));
+    assert_eq!(
+        ours.diff(&theirs),
+        [
+            "adapter: NVIDIA GeForce RTX 4090 -> Intel(R) Arc(TM) A770",
+            "shader model: 6_6 -> (not reported)",
+            "raytracing tier: tier 1_1 -> tier 1_0",
+            "mesh shader tier: (not reported) -> tier 1_0",
+        ]
+    );
+}
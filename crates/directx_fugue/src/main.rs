@@ -1,4 +1,5 @@
 use directx_fugue::init_dx11;
+use vertex_layout::VertexLayout;
 use windows::Win32::Foundation::{COLORREF, HWND, LPARAM, LRESULT, RECT, WPARAM}; // Added COLORREF
 use windows::Win32::Graphics::Direct3D::D3D11_PRIMITIVE_TOPOLOGY_TRIANGLELIST;
 use windows::Win32::Graphics::Direct3D11::*;
@@ -12,7 +13,9 @@ use windows::core::PCWSTR;
 static CLASS_NAME: &str = "MyWindowClass";
 
 #[repr(C)]
+#[derive(VertexLayout)]
 struct Vertex {
+    #[semantic("POSITION")]
     pos: [f32; 2], // x, y
 }
 
@@ -123,6 +126,19 @@ pub fn main() -> eyre::Result<()> {
     let vs_bytecode = include_bytes!("../shaders/triangle_vs.cso");
     let ps_bytecode = include_bytes!("../shaders/triangle_ps.cso");
 
+    // --- Check the Vertex Shader Reads What `Vertex` Provides ---
+    let vs_container = dxbc::DxbcContainer::parse(vs_bytecode)?;
+    let problems = vs_container
+        .input_signature
+        .unwrap_or_default()
+        .check_input_layout(Vertex::ELEMENTS);
+    if !problems.is_empty() {
+        eyre::bail!(
+            "triangle_vs.cso does not match the vertex layout:\n  {}",
+            problems.join("\n  ")
+        );
+    }
+
     // --- Create Shaders ---
     let mut vertex_shader = None;
     unsafe {
@@ -155,7 +171,7 @@ pub fn main() -> eyre::Result<()> {
 
     // --- Create Vertex Buffer ---
     let buffer_desc = D3D11_BUFFER_DESC {
-        ByteWidth: (vertices.len() * size_of::<Vertex>()) as u32,
+        ByteWidth: vertices.len() as u32 * Vertex::STRIDE,
         Usage: D3D11_USAGE_IMMUTABLE,
         BindFlags: D3D11_BIND_VERTEX_BUFFER.0 as u32,
         ..Default::default()
@@ -176,15 +192,18 @@ pub fn main() -> eyre::Result<()> {
     let vertex_buffer = vertex_buffer.unwrap();
 
     // --- Create Input Layout ---
-    let input_element_desc = [D3D11_INPUT_ELEMENT_DESC {
-        SemanticName: windows::core::s!("POSITION"),
-        SemanticIndex: 0,
-        Format: DXGI_FORMAT_R32G32_FLOAT,
-        InputSlot: 0,
-        AlignedByteOffset: 0,
-        InputSlotClass: D3D11_INPUT_PER_VERTEX_DATA,
-        InstanceDataStepRate: 0,
-    }];
+    let input_element_desc: Vec<D3D11_INPUT_ELEMENT_DESC> = Vertex::ELEMENTS
+        .iter()
+        .map(|element| D3D11_INPUT_ELEMENT_DESC {
+            SemanticName: windows::core::PCSTR(element.semantic.as_ptr().cast()),
+            SemanticIndex: element.semantic_index,
+            Format: DXGI_FORMAT(element.format.raw()),
+            InputSlot: 0,
+            AlignedByteOffset: element.offset,
+            InputSlotClass: D3D11_INPUT_PER_VERTEX_DATA,
+            InstanceDataStepRate: 0,
+        })
+        .collect();
 
     let mut input_layout = None;
     unsafe {
@@ -228,7 +247,7 @@ pub fn main() -> eyre::Result<()> {
             // Input Assembler (IA)
             // 1) build the little arrays on the stack
             let vertex_buffers = [Some(vertex_buffer.clone())];
-            let strides = [size_of::<Vertex>() as u32];
+            let strides = [Vertex::STRIDE];
             let offsets = [0u32];
 
             // 2) pass their .as_ptr() into IASetVertexBuffers
@@ -0,0 +1,91 @@
+//! The container checksum: MD5 over everything after the checksum field, with the message length
+//! placed differently from standard MD5 padding.
+
+const INITIAL_STATE: [u32; 4] = [0x6745_2301, 0xefcd_ab89, 0x98ba_dcfe, 0x1032_5476];
+
+const SHIFTS: [u32; 64] = [
+    7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, //
+    5, 9, 14, 20, 5, 9, 14, 20, 5, 9, 14, 20, 5, 9, 14, 20, //
+    4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, //
+    6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21,
+];
+
+#[rustfmt::skip]
+const SINES: [u32; 64] = [
+    0xd76a_a478, 0xe8c7_b756, 0x2420_70db, 0xc1bd_ceee,
+    0xf57c_0faf, 0x4787_c62a, 0xa830_4613, 0xfd46_9501,
+    0x6980_98d8, 0x8b44_f7af, 0xffff_5bb1, 0x895c_d7be,
+    0x6b90_1122, 0xfd98_7193, 0xa679_438e, 0x49b4_0821,
+    0xf61e_2562, 0xc040_b340, 0x265e_5a51, 0xe9b6_c7aa,
+    0xd62f_105d, 0x0244_1453, 0xd8a1_e681, 0xe7d3_fbc8,
+    0x21e1_cde6, 0xc337_07d6, 0xf4d5_0d87, 0x455a_14ed,
+    0xa9e3_e905, 0xfcef_a3f8, 0x676f_02d9, 0x8d2a_4c8a,
+    0xfffa_3942, 0x8771_f681, 0x6d9d_6122, 0xfde5_380c,
+    0xa4be_ea44, 0x4bde_cfa9, 0xf6bb_4b60, 0xbebf_bc70,
+    0x289b_7ec6, 0xeaa1_27fa, 0xd4ef_3085, 0x0488_1d05,
+    0xd9d4_d039, 0xe6db_99e5, 0x1fa2_7cf8, 0xc4ac_5665,
+    0xf429_2244, 0x432a_ff97, 0xab94_23a7, 0xfc93_a039,
+    0x655b_59c3, 0x8f0c_cc92, 0xffef_f47d, 0x8584_5dd1,
+    0x6fa8_7e4f, 0xfe2c_e6e0, 0xa301_4314, 0x4e08_11a1,
+    0xf753_7e82, 0xbd3a_f235, 0x2ad7_d2bb, 0xeb86_d391,
+];
+
+/// Bytes at the start of a container that are not covered by the checksum: the magic and the
+/// checksum itself.
+pub const CHECKSUM_SKIPPED_BYTES: usize = 20;
+
+/// The checksum of a whole container, as the four words stored after the magic.
+pub fn dxbc_checksum(container: &[u8]) -> [u32; 4] {
+    let data = container.get(CHECKSUM_SKIPPED_BYTES..).unwrap_or_default();
+    let mut state = INITIAL_STATE;
+    let mut blocks = data.chunks_exact(64);
+    for block in &mut blocks {
+        transform(&mut state, block);
+    }
+
+    let tail = blocks.remainder();
+    let bits = (data.len() as u32).wrapping_mul(8);
+    let mut block = [0u8; 64];
+    if tail.len() < 56 {
+        // The length goes first and the tail after it, unlike standard MD5.
+        block[..4].copy_from_slice(&bits.to_le_bytes());
+        block[4..4 + tail.len()].copy_from_slice(tail);
+        block[4 + tail.len()] = 0x80;
+    } else {
+        block[..tail.len()].copy_from_slice(tail);
+        block[tail.len()] = 0x80;
+        transform(&mut state, &block);
+        block = [0; 64];
+        block[..4].copy_from_slice(&bits.to_le_bytes());
+    }
+    block[60..].copy_from_slice(&((bits >> 2) | 1).to_le_bytes());
+    transform(&mut state, &block);
+    state
+}
+
+fn transform(state: &mut [u32; 4], block: &[u8]) {
+    let mut words = [0u32; 16];
+    for (word, bytes) in words.iter_mut().zip(block.chunks_exact(4)) {
+        *word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    }
+    let [mut a, mut b, mut c, mut d] = *state;
+    for round in 0..64 {
+        let (mixed, index) = match round / 16 {
+            0 => ((b & c) | (!b & d), round),
+            1 => ((d & b) | (!d & c), (5 * round + 1) % 16),
+            2 => (b ^ c ^ d, (3 * round + 5) % 16),
+            _ => (c ^ (b | !d), (7 * round) % 16),
+        };
+        let sum = mixed
+            .wrapping_add(a)
+            .wrapping_add(SINES[round])
+            .wrapping_add(words[index]);
+        a = d;
+        d = c;
+        c = b;
+        b = b.wrapping_add(sum.rotate_left(SHIFTS[round]));
+    }
+    for (word, value) in state.iter_mut().zip([a, b, c, d]) {
+        *word = word.wrapping_add(value);
+    }
+}
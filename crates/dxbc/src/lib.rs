@@ -0,0 +1,290 @@
+//! A reader for DXBC containers, the format `fxc` writes `.cso` files and `D3DCompile` blobs in.
+//!
+//! A container is a header with a checksum and a table of chunks, each a four-character code, a
+//! length and a payload. This reads the chunks that describe the shader's interface: `RDEF`
+//! (constant buffers and bindings), `ISGN`/`OSGN` (input and output signatures), `STAT` and the
+//! version token of the `SHDR`/`SHEX` program. Other chunks are listed but not parsed.
+
+mod checksum;
+mod program;
+mod reader;
+mod resources;
+mod signature;
+mod statistics;
+
+pub use checksum::*;
+pub use program::*;
+pub use resources::*;
+pub use signature::*;
+pub use statistics::*;
+
+use crate::reader::ChunkReader;
+use eyre::Context;
+use eyre::bail;
+use std::fmt::Write as _;
+
+pub const DXBC_MAGIC: &[u8; 4] = b"DXBC";
+const HEADER_SIZE: usize = 32;
+
+pub const CHUNK_RDEF: [u8; 4] = *b"RDEF";
+pub const CHUNK_ISGN: [u8; 4] = *b"ISGN";
+pub const CHUNK_OSGN: [u8; 4] = *b"OSGN";
+pub const CHUNK_STAT: [u8; 4] = *b"STAT";
+pub const CHUNK_SHDR: [u8; 4] = *b"SHDR";
+pub const CHUNK_SHEX: [u8; 4] = *b"SHEX";
+
+/// A parsed container. Nothing borrows from the bytes it was read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DxbcContainer {
+    pub size: u32,
+    /// The checksum stored in the header.
+    pub checksum: [u32; 4],
+    /// The checksum of the bytes as read; differs from `checksum` when the file was modified.
+    pub computed_checksum: [u32; 4],
+    pub chunks: Vec<Chunk>,
+    pub program: Option<ProgramVersion>,
+    pub resources: Option<ResourceDefinitions>,
+    pub input_signature: Option<Signature>,
+    pub output_signature: Option<Signature>,
+    pub statistics: Option<Statistics>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Chunk {
+    pub fourcc: [u8; 4],
+    /// Offset of the chunk's payload within the container.
+    pub offset: u32,
+    pub size: u32,
+}
+
+impl Chunk {
+    pub fn name(&self) -> String {
+        String::from_utf8_lossy(&self.fourcc).into_owned()
+    }
+}
+
+impl DxbcContainer {
+    pub fn parse(bytes: &[u8]) -> eyre::Result<Self> {
+        let header = ChunkReader {
+            name: "DXBC header",
+            bytes,
+        };
+        if header.slice(0, 4)? != DXBC_MAGIC {
+            bail!("Not a DXBC container (bad magic)");
+        }
+        let checksum = [
+            header.u32(4)?,
+            header.u32(8)?,
+            header.u32(12)?,
+            header.u32(16)?,
+        ];
+        let version = header.u32(20)?;
+        if version != 1 {
+            bail!("Unsupported DXBC container version {version}");
+        }
+        let size = header.u32(24)?;
+        if size as usize != bytes.len() {
+            bail!(
+                "DXBC container says it is {size} bytes but {} were read",
+                bytes.len()
+            );
+        }
+        let chunk_count = header.u32(28)? as usize;
+        // Every chunk needs an offset in the table, so a count the file cannot hold is corrupt
+        // rather than a reason to allocate.
+        let max_chunks = (bytes.len() - HEADER_SIZE) / 4;
+        if chunk_count > max_chunks {
+            bail!(
+                "DXBC container lists {chunk_count} chunks but has room for at most {max_chunks}"
+            );
+        }
+
+        let mut container = Self {
+            size,
+            checksum,
+            computed_checksum: dxbc_checksum(bytes),
+            chunks: Vec::with_capacity(chunk_count),
+            program: None,
+            resources: None,
+            input_signature: None,
+            output_signature: None,
+            statistics: None,
+        };
+        for index in 0..chunk_count {
+            let start = header.u32(HEADER_SIZE + index * 4)? as usize;
+            let fourcc: [u8; 4] = header.slice(start, 4)?.try_into()?;
+            let chunk_size = header.u32(start + 4)?;
+            let chunk = Chunk {
+                fourcc,
+                offset: (start + 8) as u32,
+                size: chunk_size,
+            };
+            let payload = ChunkReader {
+                name: "chunk",
+                bytes: header.slice(start + 8, chunk_size as usize)?,
+            };
+            container
+                .read_chunk(chunk, payload)
+                .wrap_err_with(|| format!("Failed to read {} chunk", chunk.name()))?;
+            container.chunks.push(chunk);
+        }
+        Ok(container)
+    }
+
+    fn read_chunk(&mut self, chunk: Chunk, payload: ChunkReader<'_>) -> eyre::Result<()> {
+        match chunk.fourcc {
+            CHUNK_RDEF => {
+                self.resources = Some(ResourceDefinitions::parse(ChunkReader {
+                    name: "RDEF",
+                    ..payload
+                })?)
+            }
+            CHUNK_ISGN => {
+                self.input_signature = Some(Signature::parse(ChunkReader {
+                    name: "ISGN",
+                    ..payload
+                })?)
+            }
+            CHUNK_OSGN => {
+                self.output_signature = Some(Signature::parse(ChunkReader {
+                    name: "OSGN",
+                    ..payload
+                })?)
+            }
+            CHUNK_STAT => {
+                self.statistics = Some(Statistics::parse(ChunkReader {
+                    name: "STAT",
+                    ..payload
+                })?)
+            }
+            CHUNK_SHDR | CHUNK_SHEX => {
+                self.program = Some(ProgramVersion::parse(ChunkReader {
+                    name: "program",
+                    ..payload
+                })?)
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    pub fn checksum_matches(&self) -> bool {
+        self.checksum == self.computed_checksum
+    }
+
+    pub fn chunk(&self, fourcc: [u8; 4]) -> Option<&Chunk> {
+        self.chunks.iter().find(|chunk| chunk.fourcc == fourcc)
+    }
+
+    /// A human-readable summary of the container, as printed by `shader inspect`.
+    pub fn describe(&self) -> String {
+        let mut out = String::new();
+        let _ = write!(
+            out,
+            "DXBC container, {} bytes, checksum {}",
+            self.size,
+            hex(&self.checksum)
+        );
+        if self.checksum_matches() {
+            let _ = writeln!(out, " (ok)");
+        } else {
+            let _ = writeln!(
+                out,
+                " (MISMATCH, contents hash to {})",
+                hex(&self.computed_checksum)
+            );
+        }
+        if let Some(program) = &self.program {
+            let _ = writeln!(out, "program: {program}, {} tokens", program.token_count);
+        }
+        let _ = writeln!(out, "chunks:");
+        for chunk in &self.chunks {
+            let _ = writeln!(
+                out,
+                "  {} {:5} bytes at {}",
+                chunk.name(),
+                chunk.size,
+                chunk.offset
+            );
+        }
+
+        if let Some(resources) = &self.resources {
+            let _ = writeln!(out, "creator: {}", resources.creator);
+            let _ = writeln!(out, "compile flags: {:#x}", resources.flags);
+            let _ = writeln!(out, "constant buffers:");
+            for buffer in &resources.constant_buffers {
+                let _ = writeln!(out, "  {} ({} bytes)", buffer.name, buffer.size);
+                for variable in &buffer.variables {
+                    let _ = writeln!(
+                        out,
+                        "    {:4} {} ({} bytes){}",
+                        variable.offset,
+                        variable.name,
+                        variable.size,
+                        if variable.used { "" } else { ", unused" }
+                    );
+                }
+            }
+            let _ = writeln!(out, "bindings:");
+            for binding in &resources.bindings {
+                let _ = writeln!(out, "  {binding}");
+            }
+        }
+
+        for (name, signature) in [
+            ("input signature", &self.input_signature),
+            ("output signature", &self.output_signature),
+        ] {
+            let Some(signature) = signature else {
+                continue;
+            };
+            let _ = writeln!(out, "{name}:");
+            for element in &signature.elements {
+                let _ = writeln!(out, "  {element}");
+            }
+        }
+
+        if let Some(statistics) = &self.statistics {
+            let _ = writeln!(out, "statistics:");
+            let _ = writeln!(
+                out,
+                "  instructions: {} ({} float, {} int, {} uint, {} mov, {} movc, {} conversion)",
+                statistics.instruction_count,
+                statistics.float_instruction_count,
+                statistics.int_instruction_count,
+                statistics.uint_instruction_count,
+                statistics.mov_instruction_count,
+                statistics.movc_instruction_count,
+                statistics.conversion_instruction_count
+            );
+            let _ = writeln!(
+                out,
+                "  declarations: {}, temp registers: {}, temp arrays: {}",
+                statistics.dcl_count, statistics.temp_register_count, statistics.temp_array_count
+            );
+            let _ = writeln!(
+                out,
+                "  flow control: {} static, {} dynamic",
+                statistics.static_flow_control_count, statistics.dynamic_flow_control_count
+            );
+            let _ = writeln!(
+                out,
+                "  texture: {} sample, {} load, {} compare, {} bias, {} gradient",
+                statistics.texture_normal_instructions,
+                statistics.texture_load_instructions,
+                statistics.texture_comp_instructions,
+                statistics.texture_bias_instructions,
+                statistics.texture_gradient_instructions
+            );
+        }
+        out
+    }
+}
+
+fn hex(words: &[u32; 4]) -> String {
+    words
+        .iter()
+        .flat_map(|word| word.to_le_bytes())
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
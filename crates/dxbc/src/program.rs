@@ -0,0 +1,83 @@
+use crate::reader::ChunkReader;
+use eyre::bail;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShaderKind {
+    Pixel,
+    Vertex,
+    Geometry,
+    Hull,
+    Domain,
+    Compute,
+}
+
+impl ShaderKind {
+    /// The kind in a `SHDR`/`SHEX` version token.
+    pub fn from_program_type(value: u32) -> Option<Self> {
+        Some(match value {
+            0 => Self::Pixel,
+            1 => Self::Vertex,
+            2 => Self::Geometry,
+            3 => Self::Hull,
+            4 => Self::Domain,
+            5 => Self::Compute,
+            _ => return None,
+        })
+    }
+
+    /// The kind in the `RDEF` version field, which uses the Direct3D 9 style values.
+    pub fn from_resource_type(value: u16) -> Option<Self> {
+        Some(match value {
+            0xffff => Self::Pixel,
+            0xfffe => Self::Vertex,
+            0x4753 => Self::Geometry,
+            0x4853 => Self::Hull,
+            0x4453 => Self::Domain,
+            0x4353 => Self::Compute,
+            _ => return None,
+        })
+    }
+
+    /// The target prefix, as in `vs_5_0`.
+    pub fn prefix(self) -> &'static str {
+        match self {
+            Self::Pixel => "ps",
+            Self::Vertex => "vs",
+            Self::Geometry => "gs",
+            Self::Hull => "hs",
+            Self::Domain => "ds",
+            Self::Compute => "cs",
+        }
+    }
+}
+
+/// The version token and length at the start of a `SHDR` or `SHEX` chunk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProgramVersion {
+    pub kind: ShaderKind,
+    pub major: u8,
+    pub minor: u8,
+    /// Length of the program in 32-bit tokens, including the version token.
+    pub token_count: u32,
+}
+
+impl ProgramVersion {
+    pub(crate) fn parse(chunk: ChunkReader<'_>) -> eyre::Result<Self> {
+        let token = chunk.u32(0)?;
+        let Some(kind) = ShaderKind::from_program_type(token >> 16) else {
+            bail!("{} has unknown program type {}", chunk.name, token >> 16);
+        };
+        Ok(Self {
+            kind,
+            major: ((token >> 4) & 0xf) as u8,
+            minor: (token & 0xf) as u8,
+            token_count: chunk.u32(4)?,
+        })
+    }
+}
+
+impl std::fmt::Display for ProgramVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}_{}_{}", self.kind.prefix(), self.major, self.minor)
+    }
+}
@@ -0,0 +1,48 @@
+use eyre::bail;
+
+/// Bounds-checked little-endian reads from a chunk, with offsets relative to its start.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ChunkReader<'a> {
+    pub name: &'static str,
+    pub bytes: &'a [u8],
+}
+
+impl<'a> ChunkReader<'a> {
+    pub fn slice(&self, offset: usize, count: usize) -> eyre::Result<&'a [u8]> {
+        match offset
+            .checked_add(count)
+            .and_then(|end| self.bytes.get(offset..end))
+        {
+            Some(slice) => Ok(slice),
+            None => bail!(
+                "{} is truncated: needed {count} bytes at offset {offset}, it has {}",
+                self.name,
+                self.bytes.len()
+            ),
+        }
+    }
+
+    pub fn u8(&self, offset: usize) -> eyre::Result<u8> {
+        Ok(self.slice(offset, 1)?[0])
+    }
+
+    pub fn u16(&self, offset: usize) -> eyre::Result<u16> {
+        Ok(u16::from_le_bytes(self.slice(offset, 2)?.try_into()?))
+    }
+
+    pub fn u32(&self, offset: usize) -> eyre::Result<u32> {
+        Ok(u32::from_le_bytes(self.slice(offset, 4)?.try_into()?))
+    }
+
+    /// The nul-terminated string at the offset stored at `offset`.
+    pub fn string(&self, offset: usize) -> eyre::Result<String> {
+        let start = self.u32(offset)? as usize;
+        let Some(rest) = self.bytes.get(start..) else {
+            bail!("{} has a string offset {start} past its end", self.name);
+        };
+        let Some(length) = rest.iter().position(|&byte| byte == 0) else {
+            bail!("{} has an unterminated string at {start}", self.name);
+        };
+        Ok(String::from_utf8_lossy(&rest[..length]).into_owned())
+    }
+}
@@ -0,0 +1,190 @@
+use crate::program::ShaderKind;
+use crate::reader::ChunkReader;
+use eyre::Context;
+use eyre::bail;
+
+/// Sizes of the `RDEF` records before Shader Model 5, which has no `RD11` header to say.
+const LEGACY_BINDING_SIZE: usize = 32;
+const LEGACY_VARIABLE_SIZE: usize = 24;
+const CONSTANT_BUFFER_SIZE: usize = 24;
+
+/// The `D3D_SVF_USED` variable flag.
+const VARIABLE_USED: u32 = 0x2;
+
+/// The contents of an `RDEF` chunk: what the shader binds and who compiled it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResourceDefinitions {
+    pub kind: ShaderKind,
+    pub major: u8,
+    pub minor: u8,
+    /// `D3DCOMPILE_*` flags the shader was compiled with.
+    pub flags: u32,
+    pub creator: String,
+    pub constant_buffers: Vec<ConstantBuffer>,
+    pub bindings: Vec<ResourceBinding>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConstantBuffer {
+    pub name: String,
+    pub size: u32,
+    pub variables: Vec<ConstantVariable>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConstantVariable {
+    pub name: String,
+    /// Byte offset within the constant buffer.
+    pub offset: u32,
+    pub size: u32,
+    /// Whether the shader reads the variable.
+    pub used: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResourceBinding {
+    pub name: String,
+    pub input_type: InputType,
+    pub bind_point: u32,
+    pub bind_count: u32,
+    /// Register space; always 0 before Shader Model 5.1.
+    pub space: u32,
+}
+
+impl std::fmt::Display for ResourceBinding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {}: {}{}",
+            self.input_type.name(),
+            self.name,
+            self.input_type.register(),
+            self.bind_point
+        )?;
+        if self.bind_count != 1 {
+            write!(f, " x{}", self.bind_count)?;
+        }
+        if self.space != 0 {
+            write!(f, ", space{}", self.space)?;
+        }
+        Ok(())
+    }
+}
+
+/// `D3D_SHADER_INPUT_TYPE`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputType(pub u32);
+
+impl InputType {
+    pub fn name(self) -> &'static str {
+        match self.0 {
+            0 => "cbuffer",
+            1 => "tbuffer",
+            2 => "texture",
+            3 => "sampler",
+            4 => "rwtexture",
+            5 => "structured",
+            6 => "rwstructured",
+            7 => "byteaddress",
+            8 => "rwbyteaddress",
+            9 => "append-structured",
+            10 => "consume-structured",
+            11 => "rwstructured-with-counter",
+            12 => "acceleration-structure",
+            13 => "feedback-texture",
+            _ => "unknown",
+        }
+    }
+
+    /// The HLSL register class the resource binds to.
+    pub fn register(self) -> char {
+        match self.0 {
+            0 => 'b',
+            3 => 's',
+            4 | 6 | 8..=11 | 13 => 'u',
+            _ => 't',
+        }
+    }
+}
+
+impl ResourceDefinitions {
+    pub(crate) fn parse(chunk: ChunkReader<'_>) -> eyre::Result<Self> {
+        let constant_buffer_count = chunk.u32(0)? as usize;
+        let constant_buffer_offset = chunk.u32(4)? as usize;
+        let binding_count = chunk.u32(8)? as usize;
+        let binding_offset = chunk.u32(12)? as usize;
+        let minor = chunk.u8(16)?;
+        let major = chunk.u8(17)?;
+        let program_type = chunk.u16(18)?;
+        let Some(kind) = ShaderKind::from_resource_type(program_type) else {
+            bail!(
+                "{} has unknown program type {program_type:#06x}",
+                chunk.name
+            );
+        };
+        let flags = chunk.u32(20)?;
+        let creator = chunk.string(24)?;
+
+        let (binding_size, variable_size) = if major >= 5 {
+            if chunk.slice(28, 4)? != b"RD11" {
+                bail!("{} is missing its RD11 header", chunk.name);
+            }
+            (chunk.u32(40)? as usize, chunk.u32(44)? as usize)
+        } else {
+            (LEGACY_BINDING_SIZE, LEGACY_VARIABLE_SIZE)
+        };
+
+        let bindings = (0..binding_count)
+            .map(|index| {
+                let offset = binding_offset + index * binding_size;
+                Ok(ResourceBinding {
+                    name: chunk.string(offset)?,
+                    input_type: InputType(chunk.u32(offset + 4)?),
+                    bind_point: chunk.u32(offset + 20)?,
+                    bind_count: chunk.u32(offset + 24)?,
+                    space: if binding_size >= 40 {
+                        chunk.u32(offset + 32)?
+                    } else {
+                        0
+                    },
+                })
+            })
+            .collect::<eyre::Result<Vec<_>>>()
+            .wrap_err("Failed to read resource bindings")?;
+
+        let constant_buffers = (0..constant_buffer_count)
+            .map(|index| {
+                let offset = constant_buffer_offset + index * CONSTANT_BUFFER_SIZE;
+                let variable_count = chunk.u32(offset + 4)? as usize;
+                let variable_offset = chunk.u32(offset + 8)? as usize;
+                let variables = (0..variable_count)
+                    .map(|variable| {
+                        let offset = variable_offset + variable * variable_size;
+                        Ok(ConstantVariable {
+                            name: chunk.string(offset)?,
+                            offset: chunk.u32(offset + 4)?,
+                            size: chunk.u32(offset + 8)?,
+                            used: chunk.u32(offset + 12)? & VARIABLE_USED != 0,
+                        })
+                    })
+                    .collect::<eyre::Result<Vec<_>>>()?;
+                Ok(ConstantBuffer {
+                    name: chunk.string(offset)?,
+                    size: chunk.u32(offset + 12)?,
+                    variables,
+                })
+            })
+            .collect::<eyre::Result<Vec<_>>>()
+            .wrap_err("Failed to read constant buffers")?;
+
+        Ok(Self {
+            kind,
+            major,
+            minor,
+            flags,
+            creator,
+            constant_buffers,
+            bindings,
+        })
+    }
+}
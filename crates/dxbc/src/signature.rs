@@ -0,0 +1,165 @@
+use crate::reader::ChunkReader;
+use vertex_layout::VertexElement;
+use vertex_layout::VertexFormat;
+
+const ELEMENT_SIZE: usize = 24;
+
+/// The contents of an `ISGN` or `OSGN` chunk.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Signature {
+    pub elements: Vec<SignatureElement>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignatureElement {
+    pub semantic: String,
+    pub semantic_index: u32,
+    /// `D3D_NAME`; 0 for elements that are not system values.
+    pub system_value: u32,
+    pub component_type: ComponentType,
+    pub register: u32,
+    /// Components the element declares, `x` in the lowest bit.
+    pub mask: u8,
+    /// For inputs, the components the shader reads; for outputs, those it never writes.
+    pub read_write_mask: u8,
+}
+
+impl SignatureElement {
+    pub fn is_system_value(&self) -> bool {
+        self.system_value != 0
+    }
+}
+
+impl std::fmt::Display for SignatureElement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}{} ({}, register {}, {})",
+            self.semantic,
+            self.semantic_index,
+            self.component_type.name(),
+            self.register,
+            components(self.mask)
+        )
+    }
+}
+
+/// `D3D_REGISTER_COMPONENT_TYPE`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComponentType {
+    Unknown,
+    Uint,
+    Sint,
+    Float,
+}
+
+impl ComponentType {
+    pub fn from_raw(value: u32) -> Self {
+        match value {
+            1 => Self::Uint,
+            2 => Self::Sint,
+            3 => Self::Float,
+            _ => Self::Unknown,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Unknown => "unknown",
+            Self::Uint => "uint",
+            Self::Sint => "int",
+            Self::Float => "float",
+        }
+    }
+
+    /// The register type a vertex format is read as; normalized formats read as floats.
+    pub fn of_format(format: VertexFormat) -> Self {
+        match format {
+            VertexFormat::R32Float
+            | VertexFormat::R32G32Float
+            | VertexFormat::R32G32B32Float
+            | VertexFormat::R32G32B32A32Float
+            | VertexFormat::R8G8B8A8Unorm => Self::Float,
+            VertexFormat::R32Uint
+            | VertexFormat::R32G32Uint
+            | VertexFormat::R32G32B32Uint
+            | VertexFormat::R32G32B32A32Uint => Self::Uint,
+            VertexFormat::R32Sint
+            | VertexFormat::R32G32Sint
+            | VertexFormat::R32G32B32Sint
+            | VertexFormat::R32G32B32A32Sint => Self::Sint,
+        }
+    }
+}
+
+/// A component mask as `xyzw` letters.
+pub fn components(mask: u8) -> String {
+    "xyzw"
+        .chars()
+        .enumerate()
+        .filter(|(index, _)| mask & (1 << index) != 0)
+        .map(|(_, component)| component)
+        .collect()
+}
+
+impl Signature {
+    pub(crate) fn parse(chunk: ChunkReader<'_>) -> eyre::Result<Self> {
+        let count = chunk.u32(0)? as usize;
+        let first = chunk.u32(4)? as usize;
+        let elements = (0..count)
+            .map(|index| {
+                let offset = first + index * ELEMENT_SIZE;
+                Ok(SignatureElement {
+                    semantic: chunk.string(offset)?,
+                    semantic_index: chunk.u32(offset + 4)?,
+                    system_value: chunk.u32(offset + 8)?,
+                    component_type: ComponentType::from_raw(chunk.u32(offset + 12)?),
+                    register: chunk.u32(offset + 16)?,
+                    mask: chunk.u8(offset + 20)?,
+                    read_write_mask: chunk.u8(offset + 21)?,
+                })
+            })
+            .collect::<eyre::Result<Vec<_>>>()?;
+        Ok(Self { elements })
+    }
+
+    /// Everything wrong with feeding this vertex shader input signature from `layout`: elements
+    /// the layout does not provide, and elements whose type does not match the format.
+    ///
+    /// System values such as `SV_VertexID` come from the input assembler and are skipped.
+    /// Formats with fewer components than the shader declares are allowed; the rest read as
+    /// `0, 0, 0, 1`.
+    pub fn check_input_layout(&self, layout: &[VertexElement]) -> Vec<String> {
+        let mut problems = Vec::new();
+        for element in self
+            .elements
+            .iter()
+            .filter(|element| !element.is_system_value())
+        {
+            let Some(provided) = layout.iter().find(|provided| {
+                provided
+                    .semantic
+                    .to_str()
+                    .is_ok_and(|semantic| semantic.eq_ignore_ascii_case(&element.semantic))
+                    && provided.semantic_index == element.semantic_index
+            }) else {
+                problems.push(format!(
+                    "the shader reads {element} but the vertex layout has no {}{}",
+                    element.semantic, element.semantic_index
+                ));
+                continue;
+            };
+            let provided_type = ComponentType::of_format(provided.format);
+            if element.component_type != ComponentType::Unknown
+                && element.component_type != provided_type
+            {
+                problems.push(format!(
+                    "the shader reads {element} but the vertex layout provides {:?}, which reads as {}",
+                    provided.format,
+                    provided_type.name()
+                ));
+            }
+        }
+        problems
+    }
+}
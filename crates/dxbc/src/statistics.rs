@@ -0,0 +1,58 @@
+use crate::reader::ChunkReader;
+
+/// The leading counters of a `STAT` chunk, in the order of `D3D11_SHADER_DESC`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Statistics {
+    pub instruction_count: u32,
+    pub temp_register_count: u32,
+    pub def_count: u32,
+    pub dcl_count: u32,
+    pub float_instruction_count: u32,
+    pub int_instruction_count: u32,
+    pub uint_instruction_count: u32,
+    pub static_flow_control_count: u32,
+    pub dynamic_flow_control_count: u32,
+    pub macro_instruction_count: u32,
+    pub temp_array_count: u32,
+    pub array_instruction_count: u32,
+    pub cut_instruction_count: u32,
+    pub emit_instruction_count: u32,
+    pub texture_normal_instructions: u32,
+    pub texture_load_instructions: u32,
+    pub texture_comp_instructions: u32,
+    pub texture_bias_instructions: u32,
+    pub texture_gradient_instructions: u32,
+    pub mov_instruction_count: u32,
+    pub movc_instruction_count: u32,
+    pub conversion_instruction_count: u32,
+}
+
+impl Statistics {
+    pub(crate) fn parse(chunk: ChunkReader<'_>) -> eyre::Result<Self> {
+        let word = |index: usize| chunk.u32(index * 4);
+        Ok(Self {
+            instruction_count: word(0)?,
+            temp_register_count: word(1)?,
+            def_count: word(2)?,
+            dcl_count: word(3)?,
+            float_instruction_count: word(4)?,
+            int_instruction_count: word(5)?,
+            uint_instruction_count: word(6)?,
+            static_flow_control_count: word(7)?,
+            dynamic_flow_control_count: word(8)?,
+            macro_instruction_count: word(9)?,
+            temp_array_count: word(10)?,
+            array_instruction_count: word(11)?,
+            cut_instruction_count: word(12)?,
+            emit_instruction_count: word(13)?,
+            texture_normal_instructions: word(14)?,
+            texture_load_instructions: word(15)?,
+            texture_comp_instructions: word(16)?,
+            texture_bias_instructions: word(17)?,
+            texture_gradient_instructions: word(18)?,
+            mov_instruction_count: word(19)?,
+            movc_instruction_count: word(20)?,
+            conversion_instruction_count: word(21)?,
+        })
+    }
+}
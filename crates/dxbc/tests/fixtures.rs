@@ -0,0 +1,212 @@
+use dxbc::CHUNK_ISGN;
+use dxbc::CHUNK_RDEF;
+use dxbc::ComponentType;
+use dxbc::DxbcContainer;
+use dxbc::ShaderKind;
+use dxbc::dxbc_checksum;
+use vertex_layout::VertexLayout;
+
+/// `directx_fugue`'s triangle, compiled by fxc for `vs_5_0`.
+const TRIANGLE_VS: &[u8] = include_bytes!("../../directx_fugue/shaders/triangle_vs.cso");
+const TRIANGLE_PS: &[u8] = include_bytes!("../../directx_fugue/shaders/triangle_ps.cso");
+/// The improved-v4 sample's shaders, whose pixel shader has a `$Globals` constant buffer.
+const SAMPLE_VS: &[u8] =
+    include_bytes!("../../windows-rs-sample-direct3d12-improved-v4/src/shaders_vs.cso");
+const SAMPLE_PS: &[u8] =
+    include_bytes!("../../windows-rs-sample-direct3d12-improved-v4/src/shaders_ps.cso");
+
+const FXC: &str = "Microsoft (R) HLSL Shader Compiler 10.1";
+
+#[repr(C)]
+#[derive(VertexLayout)]
+struct TriangleVertex {
+    position: [f32; 2],
+}
+
+#[repr(C)]
+#[derive(VertexLayout)]
+struct SampleVertex {
+    position: [f32; 3],
+    color: [f32; 4],
+}
+
+#[repr(C)]
+#[derive(VertexLayout)]
+struct IntegerVertex {
+    position: [u32; 3],
+}
+
+fn parse(bytes: &[u8]) -> DxbcContainer {
+    DxbcContainer::parse(bytes).unwrap()
+}
+
+fn signature(elements: &[dxbc::SignatureElement]) -> Vec<String> {
+    elements.iter().map(ToString::to_string).collect()
+}
+
+#[test]
+fn fixtures_have_valid_checksums() {
+    for bytes in [TRIANGLE_VS, TRIANGLE_PS, SAMPLE_VS, SAMPLE_PS] {
+        let container = parse(bytes);
+        assert!(container.checksum_matches());
+        assert_eq!(container.size as usize, bytes.len());
+        assert_eq!(container.computed_checksum, dxbc_checksum(bytes));
+        let names: Vec<String> = container.chunks.iter().map(|chunk| chunk.name()).collect();
+        assert_eq!(names, ["RDEF", "ISGN", "OSGN", "SHEX", "STAT"]);
+    }
+}
+
+#[test]
+fn program_versions_and_chunk_table() {
+    let container = parse(TRIANGLE_VS);
+    let program = container.program.unwrap();
+    assert_eq!(program.kind, ShaderKind::Vertex);
+    assert_eq!(program.to_string(), "vs_5_0");
+    assert_eq!(program.token_count, 24);
+    let rdef = container.chunk(CHUNK_RDEF).unwrap();
+    assert_eq!((rdef.offset, rdef.size), (60, 100));
+    let isgn = container.chunk(CHUNK_ISGN).unwrap();
+    assert_eq!((isgn.offset, isgn.size), (168, 44));
+
+    let program = parse(SAMPLE_PS).program.unwrap();
+    assert_eq!(program.to_string(), "ps_5_0");
+    assert_eq!(program.token_count, 73);
+}
+
+#[test]
+fn signatures_are_read() {
+    let container = parse(SAMPLE_VS);
+    let input = container.input_signature.unwrap();
+    assert_eq!(
+        signature(&input.elements),
+        [
+            "POSITION0 (float, register 0, xyzw)",
+            "COLOR0 (float, register 1, xyzw)"
+        ]
+    );
+    assert!(!input.elements[0].is_system_value());
+    let output = container.output_signature.unwrap();
+    assert_eq!(output.elements[0].semantic, "SV_POSITION");
+    assert!(output.elements[0].is_system_value());
+    assert_eq!(output.elements[1].component_type, ComponentType::Float);
+
+    let input = parse(TRIANGLE_VS).input_signature.unwrap();
+    assert_eq!(
+        signature(&input.elements),
+        ["POSITION0 (float, register 0, xy)"]
+    );
+    assert_eq!(input.elements[0].mask, 0b11);
+}
+
+#[test]
+fn input_layouts_are_checked_against_the_signature() {
+    let triangle = parse(TRIANGLE_VS).input_signature.unwrap();
+    assert!(
+        triangle
+            .check_input_layout(TriangleVertex::ELEMENTS)
+            .is_empty()
+    );
+
+    let sample = parse(SAMPLE_VS).input_signature.unwrap();
+    assert!(sample.check_input_layout(SampleVertex::ELEMENTS).is_empty());
+    assert_eq!(
+        sample.check_input_layout(IntegerVertex::ELEMENTS),
+        [
+            "the shader reads POSITION0 (float, register 0, xyzw) but the vertex layout provides R32G32B32Uint, which reads as uint",
+            "the shader reads COLOR0 (float, register 1, xyzw) but the vertex layout has no COLOR0",
+        ]
+    );
+}
+
+#[test]
+fn resource_bindings_and_constant_buffers_are_read() {
+    let resources = parse(SAMPLE_PS).resources.unwrap();
+    assert_eq!(resources.creator, FXC);
+    assert_eq!(resources.kind, ShaderKind::Pixel);
+    assert_eq!((resources.major, resources.minor), (5, 0));
+    assert_eq!(resources.flags, 0x100);
+
+    let [globals] = resources.constant_buffers.as_slice() else {
+        panic!("expected one constant buffer");
+    };
+    assert_eq!((globals.name.as_str(), globals.size), ("$Globals", 16));
+    let variables: Vec<_> = globals
+        .variables
+        .iter()
+        .map(|variable| (variable.name.as_str(), variable.offset, variable.size))
+        .collect();
+    assert_eq!(variables, [("window_width", 0, 4), ("current_time", 4, 4)]);
+
+    let bindings: Vec<String> = resources.bindings.iter().map(ToString::to_string).collect();
+    assert_eq!(bindings, ["cbuffer $Globals: b0"]);
+
+    let resources = parse(TRIANGLE_VS).resources.unwrap();
+    assert!(resources.constant_buffers.is_empty());
+    assert!(resources.bindings.is_empty());
+}
+
+#[test]
+fn statistics_are_read() {
+    let statistics = parse(TRIANGLE_VS).statistics.unwrap();
+    assert_eq!(statistics.instruction_count, 3);
+    assert_eq!(statistics.mov_instruction_count, 2);
+    assert_eq!(statistics.dcl_count, 2);
+    assert_eq!(statistics.static_flow_control_count, 1);
+
+    let statistics = parse(SAMPLE_PS).statistics.unwrap();
+    assert_eq!(statistics.instruction_count, 8);
+    assert_eq!(statistics.float_instruction_count, 6);
+    assert_eq!(statistics.temp_register_count, 1);
+}
+
+#[test]
+fn modified_bytes_fail_the_checksum() {
+    let mut bytes = TRIANGLE_VS.to_vec();
+    // A byte of the SHEX program, which nothing else checks.
+    bytes[300] ^= 1;
+    let container = parse(&bytes);
+    assert!(!container.checksum_matches());
+    assert!(container.describe().contains("MISMATCH"));
+}
+
+#[test]
+fn truncated_containers_are_rejected() {
+    for length in 0..TRIANGLE_VS.len() {
+        assert!(
+            DxbcContainer::parse(&TRIANGLE_VS[..length]).is_err(),
+            "{length} bytes parsed"
+        );
+    }
+}
+
+#[test]
+fn truncated_chunks_are_rejected() {
+    // Keep the header's size in step so the chunk itself is what runs out.
+    let mut bytes = TRIANGLE_VS[..300].to_vec();
+    bytes[24..28].copy_from_slice(&300u32.to_le_bytes());
+    let error = DxbcContainer::parse(&bytes).unwrap_err();
+    assert!(format!("{error:#}").contains("truncated"), "{error:#}");
+}
+
+#[test]
+fn corrupted_headers_are_rejected() {
+    let corrupt = |offset: usize, value: u32| {
+        let mut bytes = TRIANGLE_VS.to_vec();
+        bytes[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+        DxbcContainer::parse(&bytes).unwrap_err().to_string()
+    };
+    assert_eq!(
+        corrupt(0, u32::from_le_bytes(*b"DXIL")),
+        "Not a DXBC container (bad magic)"
+    );
+    assert_eq!(corrupt(20, 2), "Unsupported DXBC container version 2");
+    assert_eq!(
+        corrupt(24, 1024),
+        "DXBC container says it is 1024 bytes but 524 were read"
+    );
+    assert_eq!(
+        corrupt(28, u32::MAX),
+        "DXBC container lists 4294967295 chunks but has room for at most 123"
+    );
+    assert!(corrupt(32, 4096).contains("truncated"));
+}
@@ -0,0 +1,515 @@
+//! Frame capture files: everything that went into one submitted frame, written by
+//! `window show --capture-frames` and read back by `capture inspect`.
+
+use command_ir::Command;
+use command_ir::ScissorRect;
+use command_ir::VertexRange;
+use command_ir::Viewport;
+use eyre::Context;
+use eyre::bail;
+use std::fmt::Write as _;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// Capture files start with this magic, followed by a little-endian `u32` format version and a
+/// sequence of chunks. Each chunk is a four-byte tag, a `u32` payload length and the payload, so
+/// readers can skip chunks they do not understand. Strings are a `u32` byte length followed by
+/// UTF-8.
+pub const CAPTURE_MAGIC: &[u8; 8] = b"DXLCAPT\0";
+pub const CAPTURE_VERSION: u32 = 2;
+pub const CAPTURE_FILE_EXTENSION: &str = "dxlcap";
+
+const TAG_FRAME: [u8; 4] = *b"FRAM";
+const TAG_VIEWPORT: [u8; 4] = *b"VIEW";
+const TAG_SCISSOR: [u8; 4] = *b"SCIS";
+const TAG_CLEAR_COLOR: [u8; 4] = *b"CLRC";
+const TAG_PIPELINE: [u8; 4] = *b"PIPE";
+const TAG_CURSOR: [u8; 4] = *b"CURS";
+const TAG_DRAWS: [u8; 4] = *b"DRAW";
+const TAG_VERTICES: [u8; 4] = *b"VERT";
+
+/// Everything that went into one submitted frame.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FrameCapture {
+    pub frame_number: u64,
+    pub width: u32,
+    pub height: u32,
+    pub viewport: Option<Viewport>,
+    pub scissor: Option<ScissorRect>,
+    pub clear_color: Option<[f32; 4]>,
+    /// Pipeline and blend settings as name and value pairs.
+    pub pipeline: Vec<(String, String)>,
+    pub cursor: CursorSample,
+    pub draws: Vec<VertexRange>,
+    pub vertex_stride: u32,
+    /// The exact bytes uploaded to the vertex buffer.
+    pub vertex_bytes: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CursorSample {
+    /// Client-space pixels, or `None` when the cursor was outside the window.
+    pub position: Option<(f32, f32)>,
+    pub ndc: [f32; 2],
+}
+
+impl FrameCapture {
+    /// Start a capture from the frame's recorded commands; the pipeline, cursor and vertex data
+    /// are filled in by the caller.
+    pub fn from_commands(frame_number: u64, width: u32, height: u32, commands: &[Command]) -> Self {
+        let mut capture = Self {
+            frame_number,
+            width,
+            height,
+            ..Default::default()
+        };
+
+        for command in commands {
+            match command {
+                Command::SetViewport(viewport) => capture.viewport = Some(*viewport),
+                Command::SetScissor(scissor) => capture.scissor = Some(*scissor),
+                Command::Clear { color, .. } => capture.clear_color = Some(*color),
+                Command::Draw(range) => capture.draws.push(*range),
+                _ => {}
+            }
+        }
+
+        capture
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut writer = ChunkWriter::default();
+
+        let mut frame = Vec::new();
+        frame.extend_from_slice(&self.frame_number.to_le_bytes());
+        frame.extend_from_slice(&self.width.to_le_bytes());
+        frame.extend_from_slice(&self.height.to_le_bytes());
+        writer.chunk(TAG_FRAME, &frame);
+
+        if let Some(viewport) = self.viewport {
+            writer.chunk(
+                TAG_VIEWPORT,
+                &f32_bytes(&[
+                    viewport.x,
+                    viewport.y,
+                    viewport.width,
+                    viewport.height,
+                    viewport.min_depth,
+                    viewport.max_depth,
+                ]),
+            );
+        }
+
+        if let Some(scissor) = self.scissor {
+            let bytes: Vec<u8> = [scissor.left, scissor.top, scissor.right, scissor.bottom]
+                .iter()
+                .flat_map(|value| value.to_le_bytes())
+                .collect();
+            writer.chunk(TAG_SCISSOR, &bytes);
+        }
+
+        if let Some(color) = self.clear_color {
+            writer.chunk(TAG_CLEAR_COLOR, &f32_bytes(&color));
+        }
+
+        let mut pipeline = Vec::new();
+        for (name, value) in &self.pipeline {
+            push_string(&mut pipeline, name);
+            push_string(&mut pipeline, value);
+        }
+        writer.chunk(TAG_PIPELINE, &pipeline);
+
+        let mut cursor = Vec::new();
+        let (x, y) = self.cursor.position.unwrap_or_default();
+        cursor.push(u8::from(self.cursor.position.is_some()));
+        cursor.extend_from_slice(&f32_bytes(&[x, y, self.cursor.ndc[0], self.cursor.ndc[1]]));
+        writer.chunk(TAG_CURSOR, &cursor);
+
+        let draws: Vec<u8> = self
+            .draws
+            .iter()
+            .flat_map(|range| [range.start, range.count])
+            .flat_map(|value| value.to_le_bytes())
+            .collect();
+        writer.chunk(TAG_DRAWS, &draws);
+
+        let mut vertices = Vec::with_capacity(4 + self.vertex_bytes.len());
+        vertices.extend_from_slice(&self.vertex_stride.to_le_bytes());
+        vertices.extend_from_slice(&self.vertex_bytes);
+        writer.chunk(TAG_VERTICES, &vertices);
+
+        writer.finish()
+    }
+
+    pub fn decode(bytes: &[u8]) -> eyre::Result<Self> {
+        let mut reader = Reader::new(bytes);
+        if reader.take(CAPTURE_MAGIC.len())? != CAPTURE_MAGIC {
+            bail!("Not a frame capture file (bad magic)");
+        }
+        let version = reader.u32()?;
+        if version != CAPTURE_VERSION {
+            bail!("Unsupported capture version {version}, expected {CAPTURE_VERSION}");
+        }
+
+        let mut frame = None;
+        let mut capture = Self::default();
+
+        while !reader.is_empty() {
+            let tag: [u8; 4] = reader.take(4)?.try_into()?;
+            let length = reader.u32()? as usize;
+            let mut payload = Reader::new(reader.take(length)?);
+
+            match tag {
+                TAG_FRAME => {
+                    frame = Some((payload.u64()?, payload.u32()?, payload.u32()?));
+                }
+                TAG_VIEWPORT => {
+                    capture.viewport = Some(Viewport {
+                        x: payload.f32()?,
+                        y: payload.f32()?,
+                        width: payload.f32()?,
+                        height: payload.f32()?,
+                        min_depth: payload.f32()?,
+                        max_depth: payload.f32()?,
+                    });
+                }
+                TAG_SCISSOR => {
+                    capture.scissor = Some(ScissorRect {
+                        left: payload.i32()?,
+                        top: payload.i32()?,
+                        right: payload.i32()?,
+                        bottom: payload.i32()?,
+                    });
+                }
+                TAG_CLEAR_COLOR => {
+                    capture.clear_color = Some([
+                        payload.f32()?,
+                        payload.f32()?,
+                        payload.f32()?,
+                        payload.f32()?,
+                    ]);
+                }
+                TAG_PIPELINE => {
+                    while !payload.is_empty() {
+                        capture
+                            .pipeline
+                            .push((payload.string()?, payload.string()?));
+                    }
+                }
+                TAG_CURSOR => {
+                    let visible = payload.take(1)?[0] != 0;
+                    let position = (payload.f32()?, payload.f32()?);
+                    capture.cursor = CursorSample {
+                        position: visible.then_some(position),
+                        ndc: [payload.f32()?, payload.f32()?],
+                    };
+                }
+                TAG_DRAWS => {
+                    while !payload.is_empty() {
+                        capture.draws.push(VertexRange {
+                            start: payload.u32()?,
+                            count: payload.u32()?,
+                        });
+                    }
+                }
+                TAG_VERTICES => {
+                    capture.vertex_stride = payload.u32()?;
+                    capture.vertex_bytes = payload.rest().to_vec();
+                }
+                // Unknown chunks come from newer writers and are skipped.
+                _ => {}
+            }
+        }
+
+        let Some((frame_number, width, height)) = frame else {
+            bail!("Capture is missing its FRAM chunk");
+        };
+        capture.frame_number = frame_number;
+        capture.width = width;
+        capture.height = height;
+        Ok(capture)
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        if self.vertex_stride == 0 {
+            return 0;
+        }
+        self.vertex_bytes.len() / self.vertex_stride as usize
+    }
+
+    /// Vertex `index` as a list of `f32`s, assuming an all-float layout.
+    pub fn vertex_floats(&self, index: usize) -> Option<Vec<f32>> {
+        let stride = self.vertex_stride as usize;
+        let bytes = self
+            .vertex_bytes
+            .get(index * stride..(index + 1) * stride)?;
+        Some(
+            bytes
+                .chunks_exact(4)
+                .map(|chunk| f32::from_le_bytes(chunk.try_into().expect("chunk is 4 bytes")))
+                .collect(),
+        )
+    }
+
+    pub fn describe(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "frame {} ({}x{})",
+            self.frame_number, self.width, self.height
+        );
+        let _ = writeln!(out, "viewport: {:?}", self.viewport);
+        let _ = writeln!(out, "scissor: {:?}", self.scissor);
+        let _ = writeln!(out, "clear color: {:?}", self.clear_color);
+        let _ = writeln!(
+            out,
+            "cursor: {:?} (ndc {:?})",
+            self.cursor.position, self.cursor.ndc
+        );
+        let _ = writeln!(out, "pipeline:");
+        for (name, value) in &self.pipeline {
+            let _ = writeln!(out, "  {name} = {value}");
+        }
+        let _ = writeln!(out, "draws:");
+        for range in &self.draws {
+            let _ = writeln!(
+                out,
+                "  vertices {}..{}",
+                range.start,
+                range.start + range.count
+            );
+        }
+        let _ = writeln!(
+            out,
+            "vertices: {} x {} bytes",
+            self.vertex_count(),
+            self.vertex_stride
+        );
+        for index in 0..self.vertex_count() {
+            let _ = writeln!(
+                out,
+                "  {index:4}: {:?}",
+                self.vertex_floats(index).unwrap_or_default()
+            );
+        }
+        out
+    }
+
+    /// Human-readable differences between two captures; empty when they match.
+    pub fn diff(&self, other: &Self) -> Vec<String> {
+        let mut differences = Vec::new();
+        let mut compare = |name: &str, left: String, right: String| {
+            if left != right {
+                differences.push(format!("{name}: {left} -> {right}"));
+            }
+        };
+
+        compare(
+            "size",
+            format!("{}x{}", self.width, self.height),
+            format!("{}x{}", other.width, other.height),
+        );
+        compare(
+            "viewport",
+            format!("{:?}", self.viewport),
+            format!("{:?}", other.viewport),
+        );
+        compare(
+            "scissor",
+            format!("{:?}", self.scissor),
+            format!("{:?}", other.scissor),
+        );
+        compare(
+            "clear color",
+            format!("{:?}", self.clear_color),
+            format!("{:?}", other.clear_color),
+        );
+        compare(
+            "cursor",
+            format!("{:?}", self.cursor),
+            format!("{:?}", other.cursor),
+        );
+        compare(
+            "draws",
+            format!("{:?}", self.draws),
+            format!("{:?}", other.draws),
+        );
+        compare(
+            "vertex stride",
+            self.vertex_stride.to_string(),
+            other.vertex_stride.to_string(),
+        );
+
+        for (name, value) in &self.pipeline {
+            let other_value = other
+                .pipeline
+                .iter()
+                .find(|(other_name, _)| other_name == name)
+                .map(|(_, value)| value.clone());
+            compare(
+                &format!("pipeline.{name}"),
+                value.clone(),
+                other_value.unwrap_or_else(|| "<missing>".to_string()),
+            );
+        }
+        for (name, value) in &other.pipeline {
+            if !self.pipeline.iter().any(|(self_name, _)| self_name == name) {
+                compare(
+                    &format!("pipeline.{name}"),
+                    "<missing>".to_string(),
+                    value.clone(),
+                );
+            }
+        }
+
+        let vertex_count = self.vertex_count().max(other.vertex_count());
+        for index in 0..vertex_count {
+            compare(
+                &format!("vertex {index}"),
+                format!("{:?}", self.vertex_floats(index)),
+                format!("{:?}", other.vertex_floats(index)),
+            );
+        }
+
+        differences
+    }
+}
+
+#[derive(Default)]
+struct ChunkWriter {
+    bytes: Vec<u8>,
+}
+
+impl ChunkWriter {
+    fn chunk(&mut self, tag: [u8; 4], payload: &[u8]) {
+        if self.bytes.is_empty() {
+            self.bytes.extend_from_slice(CAPTURE_MAGIC);
+            self.bytes.extend_from_slice(&CAPTURE_VERSION.to_le_bytes());
+        }
+        self.bytes.extend_from_slice(&tag);
+        self.bytes
+            .extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        self.bytes.extend_from_slice(payload);
+    }
+
+    fn finish(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    fn take(&mut self, count: usize) -> eyre::Result<&'a [u8]> {
+        if self.bytes.len() < count {
+            bail!(
+                "Capture is truncated: needed {count} bytes, {} left",
+                self.bytes.len()
+            );
+        }
+        let (head, tail) = self.bytes.split_at(count);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn rest(&mut self) -> &'a [u8] {
+        std::mem::take(&mut self.bytes)
+    }
+
+    fn u32(&mut self) -> eyre::Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into()?))
+    }
+
+    fn i32(&mut self) -> eyre::Result<i32> {
+        Ok(i32::from_le_bytes(self.take(4)?.try_into()?))
+    }
+
+    fn u64(&mut self) -> eyre::Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into()?))
+    }
+
+    fn f32(&mut self) -> eyre::Result<f32> {
+        Ok(f32::from_le_bytes(self.take(4)?.try_into()?))
+    }
+
+    fn string(&mut self) -> eyre::Result<String> {
+        let length = self.u32()? as usize;
+        let bytes = self.take(length)?;
+        Ok(std::str::from_utf8(bytes)
+            .wrap_err("Capture string is not UTF-8")?
+            .to_string())
+    }
+}
+
+fn push_string(bytes: &mut Vec<u8>, value: &str) {
+    bytes.extend_from_slice(&(value.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(value.as_bytes());
+}
+
+fn f32_bytes(values: &[f32]) -> Vec<u8> {
+    values
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect()
+}
+
+/// Where and how many frames `window show --capture-frames` writes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaptureOptions {
+    pub frames: u32,
+    pub directory: PathBuf,
+}
+
+/// Writes the first `frames` submitted frames to numbered files in the capture directory.
+#[derive(Debug)]
+pub struct FrameCapturer {
+    options: CaptureOptions,
+    written: u32,
+}
+
+impl FrameCapturer {
+    pub fn new(options: CaptureOptions) -> eyre::Result<Self> {
+        std::fs::create_dir_all(&options.directory).wrap_err_with(|| {
+            format!(
+                "Failed to create capture directory {}",
+                options.directory.display()
+            )
+        })?;
+        Ok(Self {
+            options,
+            written: 0,
+        })
+    }
+
+    pub fn wants_frame(&self) -> bool {
+        self.written < self.options.frames
+    }
+
+    pub fn write(&mut self, capture: &FrameCapture) -> eyre::Result<PathBuf> {
+        let path = self.options.directory.join(format!(
+            "frame_{:05}.{CAPTURE_FILE_EXTENSION}",
+            capture.frame_number
+        ));
+        std::fs::write(&path, capture.encode())
+            .wrap_err_with(|| format!("Failed to write capture {}", path.display()))?;
+        self.written += 1;
+        Ok(path)
+    }
+}
+
+pub fn read_capture(path: &Path) -> eyre::Result<FrameCapture> {
+    let bytes = std::fs::read(path)
+        .wrap_err_with(|| format!("Failed to read capture {}", path.display()))?;
+    FrameCapture::decode(&bytes)
+        .wrap_err_with(|| format!("Failed to parse capture {}", path.display()))
+}
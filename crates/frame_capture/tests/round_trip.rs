@@ -0,0 +1,141 @@
+use command_ir::CommandRecorder;
+use command_ir::ResourceState;
+use command_ir::ScissorRect;
+use command_ir::VertexRange;
+use command_ir::Viewport;
+use frame_capture::CAPTURE_MAGIC;
+use frame_capture::CaptureOptions;
+use frame_capture::CursorSample;
+use frame_capture::FrameCapture;
+use frame_capture::FrameCapturer;
+use frame_capture::read_capture;
+
+fn f32_bytes(values: &[f32]) -> Vec<u8> {
+    values
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect()
+}
+
+fn capture() -> FrameCapture {
+    FrameCapture {
+        frame_number: 7,
+        width: 640,
+        height: 480,
+        viewport: Some(Viewport::full(640, 480)),
+        scissor: Some(ScissorRect::full(640, 480)),
+        clear_color: Some([0.0, 0.0, 0.0, 0.5]),
+        pipeline: vec![
+            ("blend".to_string(), "src_alpha, inv_src_alpha".to_string()),
+            ("defines".to_string(), "GRAYSCALE=1\nTINT=0".to_string()),
+            (String::new(), String::new()),
+        ],
+        cursor: CursorSample {
+            position: Some((12.0, 34.5)),
+            ndc: [-0.25, 0.75],
+        },
+        draws: vec![
+            VertexRange { start: 0, count: 3 },
+            VertexRange { start: 3, count: 6 },
+        ],
+        vertex_stride: 8,
+        vertex_bytes: f32_bytes(&[1.0, 2.0, 3.0, 4.0]),
+    }
+}
+
+#[test]
+fn encode_decode_round_trip() {
+    let capture = capture();
+    let decoded = FrameCapture::decode(&capture.encode()).unwrap();
+    assert_eq!(decoded, capture);
+    assert!(decoded.diff(&capture).is_empty());
+    assert_eq!(decoded.vertex_count(), 2);
+    assert_eq!(decoded.vertex_floats(1), Some(vec![3.0, 4.0]));
+}
+
+#[test]
+fn round_trip_through_a_file() {
+    let directory = std::env::temp_dir().join(format!("frame_capture_{}", std::process::id()));
+    let mut capturer = FrameCapturer::new(CaptureOptions {
+        frames: 1,
+        directory: directory.clone(),
+    })
+    .unwrap();
+    assert!(capturer.wants_frame());
+    let path = capturer.write(&capture()).unwrap();
+    assert!(!capturer.wants_frame());
+
+    let read = read_capture(&path);
+    std::fs::remove_dir_all(&directory).unwrap();
+    assert_eq!(read.unwrap(), capture());
+}
+
+#[test]
+fn decode_rejects_bad_magic_version_and_truncation() {
+    let bytes = capture().encode();
+
+    let mut bad_magic = bytes.clone();
+    bad_magic[0] = b'X';
+    assert!(FrameCapture::decode(&bad_magic).is_err());
+
+    let mut bad_version = bytes.clone();
+    bad_version[CAPTURE_MAGIC.len()] += 1;
+    assert!(FrameCapture::decode(&bad_version).is_err());
+
+    for length in [4, CAPTURE_MAGIC.len() + 6, bytes.len() - 1] {
+        assert!(FrameCapture::decode(&bytes[..length]).is_err());
+    }
+}
+
+#[test]
+fn decode_skips_unknown_chunks() {
+    let mut bytes = capture().encode();
+    bytes.extend_from_slice(b"NEWS");
+    bytes.extend_from_slice(&2u32.to_le_bytes());
+    bytes.extend_from_slice(&[1, 2]);
+    assert_eq!(FrameCapture::decode(&bytes).unwrap(), capture());
+}
+
+#[test]
+fn capture_starts_from_the_recorded_commands() {
+    let mut recorder = CommandRecorder::new();
+    recorder.set_viewport(Viewport::full(320, 200));
+    recorder.set_scissor(ScissorRect::full(320, 200));
+    recorder.require_state(0, ResourceState::RenderTarget);
+    recorder.clear_render_target(0, [0.0, 0.0, 0.0, 0.0]);
+    recorder.draw(VertexRange { start: 0, count: 3 });
+    recorder.draw(VertexRange { start: 3, count: 6 });
+    recorder.present();
+
+    let capture = FrameCapture::from_commands(2, 320, 200, recorder.commands());
+    assert_eq!(capture.frame_number, 2);
+    assert_eq!(capture.viewport, Some(Viewport::full(320, 200)));
+    assert_eq!(capture.scissor, Some(ScissorRect::full(320, 200)));
+    assert_eq!(capture.clear_color, Some([0.0, 0.0, 0.0, 0.0]));
+    assert_eq!(
+        capture.draws,
+        [
+            VertexRange { start: 0, count: 3 },
+            VertexRange { start: 3, count: 6 }
+        ]
+    );
+}
+
+#[test]
+fn diff_lists_changed_fields() {
+    let mut other = capture();
+    other.width = 800;
+    other.pipeline[0].1 = "one, zero".to_string();
+    other
+        .pipeline
+        .push(("topology".to_string(), "triangle_list".to_string()));
+
+    assert_eq!(
+        capture().diff(&other),
+        [
+            "size: 640x480 -> 800x480",
+            "pipeline.blend: src_alpha, inv_src_alpha -> one, zero",
+            "pipeline.topology: <missing> -> triangle_list",
+        ]
+    );
+}
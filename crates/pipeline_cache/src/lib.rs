@@ -0,0 +1,332 @@
+//! An on-disk cache of driver-compiled pipeline state blobs, keyed by everything that went into
+//! creating each pipeline and kept within a size budget by least-recently-used eviction.
+
+use eyre::Context;
+use eyre::bail;
+use std::path::Path;
+use std::path::PathBuf;
+use tracing::warn;
+
+/// Cache files start with this magic, followed by a little-endian `u32` format version, the `u64`
+/// use clock and a `u32` entry count. Each entry is its `u64` key, the `u64` clock value it was
+/// last used at, a `u64` checksum of its blob, a `u32` blob length and the blob.
+pub const PIPELINE_CACHE_MAGIC: &[u8; 8] = b"DXLPSOC\0";
+pub const PIPELINE_CACHE_VERSION: u32 = 1;
+/// Least recently used blobs are evicted once the cache grows past this.
+pub const PIPELINE_CACHE_MAX_BYTES: usize = 16 * 1024 * 1024;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// 64-bit FNV-1a, used both to derive keys and to detect corrupted blobs.
+pub fn checksum(bytes: &[u8]) -> u64 {
+    fnv1a(FNV_OFFSET_BASIS, bytes)
+}
+
+fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    for &byte in bytes {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
+
+/// Identifies a pipeline state by everything that went into creating it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PipelineCacheKey(pub u64);
+
+impl std::fmt::Display for PipelineCacheKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+/// Hashes the parts of a pipeline description into a [`PipelineCacheKey`].
+///
+/// Every part is length-prefixed, so moving bytes from one part to the next changes the key.
+#[derive(Debug, Clone)]
+pub struct PipelineKeyBuilder {
+    hash: u64,
+}
+
+impl Default for PipelineKeyBuilder {
+    fn default() -> Self {
+        Self {
+            hash: FNV_OFFSET_BASIS,
+        }
+    }
+}
+
+impl PipelineKeyBuilder {
+    pub fn bytes(mut self, bytes: &[u8]) -> Self {
+        self.hash = fnv1a(self.hash, &(bytes.len() as u64).to_le_bytes());
+        self.hash = fnv1a(self.hash, bytes);
+        self
+    }
+
+    pub fn str(self, value: &str) -> Self {
+        self.bytes(value.as_bytes())
+    }
+
+    pub fn u32(self, value: u32) -> Self {
+        self.bytes(&value.to_le_bytes())
+    }
+
+    pub fn finish(self) -> PipelineCacheKey {
+        PipelineCacheKey(self.hash)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CacheEntry {
+    key: PipelineCacheKey,
+    last_used: u64,
+    blob: Vec<u8>,
+}
+
+/// `ID3D12PipelineState::GetCachedBlob` output keyed by [`PipelineCacheKey`], kept within a size
+/// budget by evicting the least recently used blobs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PipelineCache {
+    entries: Vec<CacheEntry>,
+    clock: u64,
+    max_bytes: usize,
+    /// Whether an insert, removal or eviction changed the cache since it was loaded or saved.
+    /// Reads only move use clocks, which are written along with the next real change.
+    dirty: bool,
+}
+
+impl PipelineCache {
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            entries: Vec::new(),
+            clock: 0,
+            max_bytes,
+            dirty: false,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    pub fn total_bytes(&self) -> usize {
+        self.entries.iter().map(|entry| entry.blob.len()).sum()
+    }
+
+    /// The blob for `key`, marking it as the most recently used. A hit alone does not make the
+    /// cache dirty, so runs that only read it never rewrite the file.
+    pub fn get(&mut self, key: PipelineCacheKey) -> Option<&[u8]> {
+        let clock = self.tick();
+        let entry = self.entries.iter_mut().find(|entry| entry.key == key)?;
+        entry.last_used = clock;
+        Some(&entry.blob)
+    }
+
+    /// Store `blob` for `key`, replacing any older blob and evicting the least recently used
+    /// ones to stay within the size budget. Blobs larger than the whole budget are not stored.
+    pub fn insert(&mut self, key: PipelineCacheKey, blob: Vec<u8>) {
+        self.remove(key);
+        if blob.len() > self.max_bytes {
+            return;
+        }
+        let last_used = self.tick();
+        self.entries.push(CacheEntry {
+            key,
+            last_used,
+            blob,
+        });
+        self.dirty = true;
+        while self.total_bytes() > self.max_bytes {
+            let Some(oldest) = self
+                .entries
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(index, _)| index)
+            else {
+                break;
+            };
+            self.entries.swap_remove(oldest);
+        }
+    }
+
+    /// Drop the blob for `key`, e.g. because the driver rejected it.
+    pub fn remove(&mut self, key: PipelineCacheKey) -> bool {
+        let count = self.entries.len();
+        self.entries.retain(|entry| entry.key != key);
+        let removed = self.entries.len() != count;
+        self.dirty |= removed;
+        removed
+    }
+
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(24 + self.total_bytes() + self.entries.len() * 28);
+        bytes.extend_from_slice(PIPELINE_CACHE_MAGIC);
+        bytes.extend_from_slice(&PIPELINE_CACHE_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&self.clock.to_le_bytes());
+        bytes.extend_from_slice(&(self.entries.len() as u32).to_le_bytes());
+        for entry in &self.entries {
+            bytes.extend_from_slice(&entry.key.0.to_le_bytes());
+            bytes.extend_from_slice(&entry.last_used.to_le_bytes());
+            bytes.extend_from_slice(&checksum(&entry.blob).to_le_bytes());
+            bytes.extend_from_slice(&(entry.blob.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(&entry.blob);
+        }
+        bytes
+    }
+
+    /// Parse a cache file, returning the cache and how many entries were dropped as corrupt.
+    ///
+    /// A bad header fails the whole file; entries whose checksum does not match are skipped and
+    /// a truncated tail is discarded.
+    pub fn decode(bytes: &[u8], max_bytes: usize) -> eyre::Result<(Self, usize)> {
+        let mut reader = Reader { bytes };
+        if reader.take(PIPELINE_CACHE_MAGIC.len())? != PIPELINE_CACHE_MAGIC {
+            bail!("Not a pipeline cache file (bad magic)");
+        }
+        let version = reader.u32()?;
+        if version != PIPELINE_CACHE_VERSION {
+            bail!(
+                "Unsupported pipeline cache version {version}, expected {PIPELINE_CACHE_VERSION}"
+            );
+        }
+        let mut cache = Self::new(max_bytes);
+        cache.clock = reader.u64()?;
+        let count = reader.u32()? as usize;
+
+        let mut corrupt = 0;
+        for _ in 0..count {
+            let Ok(entry) = reader.entry() else {
+                // Everything from the truncated entry on is lost.
+                corrupt = count - cache.entries.len();
+                break;
+            };
+            if let Some(entry) = entry {
+                cache.clock = cache.clock.max(entry.last_used);
+                cache.entries.push(entry);
+            } else {
+                corrupt += 1;
+            }
+        }
+        cache.dirty = corrupt > 0;
+        Ok((cache, corrupt))
+    }
+
+    /// Load the cache at `path`, starting empty when it does not exist or cannot be used.
+    pub fn load(path: &Path, max_bytes: usize) -> Self {
+        let bytes = match std::fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                return Self::new(max_bytes);
+            }
+            Err(error) => {
+                warn!(path = %path.display(), %error, "Failed to read pipeline cache, starting empty");
+                return Self::new(max_bytes);
+            }
+        };
+        match Self::decode(&bytes, max_bytes) {
+            Ok((cache, 0)) => cache,
+            Ok((cache, corrupt)) => {
+                warn!(path = %path.display(), corrupt, "Dropped corrupt pipeline cache entries");
+                cache
+            }
+            Err(error) => {
+                warn!(path = %path.display(), %error, "Discarding unusable pipeline cache");
+                Self {
+                    dirty: true,
+                    ..Self::new(max_bytes)
+                }
+            }
+        }
+    }
+
+    /// Write the cache to `path` if it changed. The file is replaced atomically, so a crash
+    /// mid-write leaves the previous cache intact.
+    pub fn save(&mut self, path: &Path) -> eyre::Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).wrap_err_with(|| {
+                format!(
+                    "Failed to create pipeline cache directory {}",
+                    parent.display()
+                )
+            })?;
+        }
+        let temporary = path.with_extension("tmp");
+        std::fs::write(&temporary, self.encode())
+            .wrap_err_with(|| format!("Failed to write pipeline cache {}", temporary.display()))?;
+        std::fs::rename(&temporary, path)
+            .wrap_err_with(|| format!("Failed to replace pipeline cache {}", path.display()))?;
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+/// `pipelines.psocache` under `application`'s directory in the user's local cache directory.
+pub fn default_pipeline_cache_path(application: &str) -> PathBuf {
+    let cache_directory = std::env::var_os("LOCALAPPDATA")
+        .or_else(|| std::env::var_os("XDG_CACHE_HOME"))
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+        .unwrap_or_else(std::env::temp_dir);
+    cache_directory
+        .join("DirectX-Learning")
+        .join(application)
+        .join("pipelines.psocache")
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> eyre::Result<&'a [u8]> {
+        if self.bytes.len() < count {
+            bail!(
+                "Pipeline cache is truncated: needed {count} bytes, {} left",
+                self.bytes.len()
+            );
+        }
+        let (head, tail) = self.bytes.split_at(count);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn u32(&mut self) -> eyre::Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into()?))
+    }
+
+    fn u64(&mut self) -> eyre::Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into()?))
+    }
+
+    /// The next entry, or `None` when its blob does not match its checksum.
+    fn entry(&mut self) -> eyre::Result<Option<CacheEntry>> {
+        let key = PipelineCacheKey(self.u64()?);
+        let last_used = self.u64()?;
+        let expected = self.u64()?;
+        let length = self.u32()? as usize;
+        let blob = self.take(length)?;
+        Ok((checksum(blob) == expected).then(|| CacheEntry {
+            key,
+            last_used,
+            blob: blob.to_vec(),
+        }))
+    }
+}
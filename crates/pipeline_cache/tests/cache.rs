@@ -0,0 +1,208 @@
+use pipeline_cache::PIPELINE_CACHE_MAGIC;
+use pipeline_cache::PIPELINE_CACHE_MAX_BYTES;
+use pipeline_cache::PIPELINE_CACHE_VERSION;
+use pipeline_cache::PipelineCache;
+use pipeline_cache::PipelineCacheKey;
+use pipeline_cache::PipelineKeyBuilder;
+use pipeline_cache::checksum;
+
+const MIB: usize = 1024 * 1024;
+
+fn key(name: &str) -> PipelineCacheKey {
+    PipelineKeyBuilder::default().str(name).finish()
+}
+
+fn filled() -> PipelineCache {
+    let mut cache = PipelineCache::new(PIPELINE_CACHE_MAX_BYTES);
+    cache.insert(key("opaque"), vec![1; 16]);
+    cache.insert(key("transparent"), vec![2; 32]);
+    cache
+}
+
+#[test]
+fn checksum_is_fnv1a() {
+    assert_eq!(checksum(b""), 0xcbf2_9ce4_8422_2325);
+    assert_eq!(checksum(b"a"), 0xaf63_dc4c_8601_ec8c);
+    assert_eq!(checksum(b"foobar"), 0x8594_4171_f739_67e8);
+}
+
+#[test]
+fn keys_depend_on_every_part_and_its_boundaries() {
+    let base = PipelineKeyBuilder::default()
+        .bytes(b"vs")
+        .bytes(b"ps")
+        .u32(28);
+    assert_eq!(base.clone().finish(), base.clone().finish());
+    assert_ne!(
+        base.clone().finish(),
+        PipelineKeyBuilder::default()
+            .bytes(b"vs")
+            .bytes(b"ps")
+            .u32(29)
+            .finish()
+    );
+    assert_ne!(
+        PipelineKeyBuilder::default().str("ab").str("c").finish(),
+        PipelineKeyBuilder::default().str("a").str("bc").finish()
+    );
+    assert_ne!(
+        PipelineKeyBuilder::default().finish(),
+        PipelineKeyBuilder::default().bytes(b"").finish()
+    );
+    assert_eq!(key("opaque").to_string().len(), 16);
+}
+
+#[test]
+fn encode_decode_round_trip() {
+    let cache = filled();
+    let bytes = cache.encode();
+    assert_eq!(&bytes[..8], PIPELINE_CACHE_MAGIC);
+    assert_eq!(bytes.len(), 24 + 2 * 28 + 16 + 32);
+
+    let (mut decoded, corrupt) = PipelineCache::decode(&bytes, PIPELINE_CACHE_MAX_BYTES).unwrap();
+    assert_eq!(corrupt, 0);
+    assert!(!decoded.is_dirty());
+    assert_eq!(decoded.len(), 2);
+    assert_eq!(decoded.get(key("opaque")), Some(&[1; 16][..]));
+    assert_eq!(decoded.get(key("transparent")), Some(&[2; 32][..]));
+    assert_eq!(decoded.get(key("wireframe")), None);
+}
+
+#[test]
+fn decode_rejects_bad_magic_and_version() {
+    let mut bytes = filled().encode();
+    bytes[0] = b'X';
+    let error = PipelineCache::decode(&bytes, PIPELINE_CACHE_MAX_BYTES).unwrap_err();
+    assert!(error.to_string().contains("bad magic"), "{error}");
+
+    let mut bytes = filled().encode();
+    bytes[8..12].copy_from_slice(&(PIPELINE_CACHE_VERSION + 1).to_le_bytes());
+    let error = PipelineCache::decode(&bytes, PIPELINE_CACHE_MAX_BYTES).unwrap_err();
+    assert!(error.to_string().contains("version 2"), "{error}");
+
+    assert!(PipelineCache::decode(&bytes[..20], PIPELINE_CACHE_MAX_BYTES).is_err());
+}
+
+#[test]
+fn decode_skips_entries_whose_checksum_does_not_match() {
+    let mut bytes = filled().encode();
+    // The first entry's blob starts after the header and its own 28-byte entry header.
+    bytes[24 + 28] ^= 0xff;
+    let (mut cache, corrupt) = PipelineCache::decode(&bytes, PIPELINE_CACHE_MAX_BYTES).unwrap();
+    assert_eq!(corrupt, 1);
+    assert!(cache.is_dirty());
+    assert_eq!(cache.get(key("opaque")), None);
+    assert_eq!(cache.get(key("transparent")), Some(&[2; 32][..]));
+}
+
+#[test]
+fn decode_drops_a_truncated_tail() {
+    let bytes = filled().encode();
+    let (mut cache, corrupt) =
+        PipelineCache::decode(&bytes[..bytes.len() - 1], PIPELINE_CACHE_MAX_BYTES).unwrap();
+    assert_eq!(corrupt, 1);
+    assert_eq!(cache.len(), 1);
+    assert!(cache.get(key("opaque")).is_some());
+
+    let (cache, corrupt) =
+        PipelineCache::decode(&bytes[..24 + 4], PIPELINE_CACHE_MAX_BYTES).unwrap();
+    assert_eq!(corrupt, 2);
+    assert!(cache.is_empty());
+}
+
+#[test]
+fn least_recently_used_blobs_are_evicted_past_the_budget() {
+    let mut cache = PipelineCache::new(PIPELINE_CACHE_MAX_BYTES);
+    for name in ["a", "b", "c", "d"] {
+        cache.insert(key(name), vec![0; 4 * MIB]);
+    }
+    assert_eq!(cache.total_bytes(), PIPELINE_CACHE_MAX_BYTES);
+
+    // Using "a" makes "b" the least recently used.
+    assert!(cache.get(key("a")).is_some());
+    cache.insert(key("e"), vec![0; 4 * MIB]);
+    assert_eq!(cache.len(), 4);
+    assert!(cache.get(key("b")).is_none());
+    for name in ["a", "c", "d", "e"] {
+        assert!(cache.get(key(name)).is_some(), "{name} was evicted");
+    }
+
+    // A larger blob evicts as many as it needs.
+    cache.insert(key("f"), vec![0; 10 * MIB]);
+    assert!(cache.total_bytes() <= PIPELINE_CACHE_MAX_BYTES);
+    assert_eq!(cache.len(), 2);
+    assert!(cache.get(key("f")).is_some());
+}
+
+#[test]
+fn blobs_larger_than_the_budget_are_not_stored() {
+    let mut cache = filled();
+    cache.insert(key("opaque"), vec![0; PIPELINE_CACHE_MAX_BYTES + 1]);
+    assert_eq!(cache.len(), 1);
+    assert_eq!(cache.get(key("opaque")), None);
+}
+
+#[test]
+fn load_and_save_through_a_file() {
+    let directory =
+        std::env::temp_dir().join(format!("pipeline_cache_test_{}", std::process::id()));
+    let path = directory.join("pipelines.psocache");
+    let _ = std::fs::remove_dir_all(&directory);
+
+    let mut cache = PipelineCache::load(&path, PIPELINE_CACHE_MAX_BYTES);
+    assert!(cache.is_empty());
+    assert!(!cache.is_dirty());
+    cache.insert(key("opaque"), vec![7; 64]);
+    cache.save(&path).unwrap();
+    assert!(!cache.is_dirty());
+
+    let mut loaded = PipelineCache::load(&path, PIPELINE_CACHE_MAX_BYTES);
+    assert_eq!(loaded.get(key("opaque")), Some(&[7; 64][..]));
+
+    std::fs::write(&path, b"garbage").unwrap();
+    let discarded = PipelineCache::load(&path, PIPELINE_CACHE_MAX_BYTES);
+    assert!(discarded.is_empty());
+    assert!(discarded.is_dirty());
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn reads_do_not_dirty_the_cache_but_their_clocks_are_saved_with_the_next_change() {
+    let directory =
+        std::env::temp_dir().join(format!("pipeline_cache_clocks_{}", std::process::id()));
+    let path = directory.join("pipelines.psocache");
+    let _ = std::fs::remove_dir_all(&directory);
+
+    let mut cache = PipelineCache::new(PIPELINE_CACHE_MAX_BYTES);
+    for name in ["a", "b", "c", "d"] {
+        cache.insert(key(name), vec![0; 4 * MIB]);
+    }
+    cache.save(&path).unwrap();
+    let saved = std::fs::read(&path).unwrap();
+
+    // A run that only hits the cache leaves the file alone.
+    let mut cache = PipelineCache::load(&path, PIPELINE_CACHE_MAX_BYTES);
+    assert!(cache.get(key("a")).is_some());
+    assert!(cache.get(key("missing")).is_none());
+    assert!(!cache.is_dirty());
+    std::fs::remove_file(&path).unwrap();
+    cache.save(&path).unwrap();
+    assert!(!path.exists());
+    std::fs::write(&path, &saved).unwrap();
+
+    // Reading "a" and inserting "e" evicts "b", and the save records when "a" was read.
+    let mut cache = PipelineCache::load(&path, PIPELINE_CACHE_MAX_BYTES);
+    assert!(cache.get(key("a")).is_some());
+    cache.insert(key("e"), vec![0; MIB]);
+    assert!(cache.is_dirty());
+    cache.save(&path).unwrap();
+
+    // So after reloading, "c" is the least recently used rather than "a".
+    let mut cache = PipelineCache::load(&path, PIPELINE_CACHE_MAX_BYTES);
+    cache.insert(key("f"), vec![0; 4 * MIB]);
+    assert!(cache.get(key("c")).is_none());
+    for name in ["a", "d", "e", "f"] {
+        assert!(cache.get(key(name)).is_some(), "{name} was evicted");
+    }
+    std::fs::remove_dir_all(&directory).unwrap();
+}
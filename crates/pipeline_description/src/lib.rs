@@ -0,0 +1,9 @@
+//! Pipeline description files: the shaders, fixed-function state, render target formats, input
+//! layout and shader permutation keys of a graphics pipeline, written as `.pipeline.toml` and
+//! validated into plain values the renderer turns into a D3D12 pipeline state.
+
+mod permutation;
+mod pipeline;
+
+pub use permutation::*;
+pub use pipeline::*;
@@ -0,0 +1,196 @@
+use eyre::bail;
+
+/// A preprocessor define a pipeline's shaders can be compiled with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PermutationKey {
+    pub name: String,
+    pub kind: PermutationKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PermutationKind {
+    /// Defined as `0` or `1`.
+    Bool { default: bool },
+    /// Defined as the index of the chosen value. `<NAME>_<VALUE>` is defined as each value's
+    /// index too, so shaders can write `#if SHADING == SHADING_FLAT`.
+    Enum { values: Vec<String>, default: usize },
+}
+
+impl PermutationKey {
+    /// The index of `value` among the key's values; `0` and `1` for a bool.
+    pub fn parse_value(&self, value: &str) -> Result<usize, String> {
+        match &self.kind {
+            PermutationKind::Bool { .. } => match value.to_ascii_lowercase().as_str() {
+                "0" | "false" | "off" => Ok(0),
+                "1" | "true" | "on" => Ok(1),
+                _ => Err(format!(
+                    "{} is a bool and cannot be {value:?}; use 0 or 1",
+                    self.name
+                )),
+            },
+            PermutationKind::Enum { values, .. } => values
+                .iter()
+                .position(|candidate| candidate.eq_ignore_ascii_case(value))
+                .ok_or_else(|| {
+                    format!(
+                        "{} cannot be {value:?}; expected one of {}",
+                        self.name,
+                        values.join(", ")
+                    )
+                }),
+        }
+    }
+
+    pub fn default_index(&self) -> usize {
+        match &self.kind {
+            PermutationKind::Bool { default } => usize::from(*default),
+            PermutationKind::Enum { default, .. } => *default,
+        }
+    }
+
+    /// How the value at `index` is written on the command line and in permutation names.
+    pub fn value_name(&self, index: usize) -> &str {
+        match &self.kind {
+            PermutationKind::Bool { .. } if index == 0 => "0",
+            PermutationKind::Bool { .. } => "1",
+            PermutationKind::Enum { values, .. } => &values[index],
+        }
+    }
+}
+
+/// A `KEY=VALUE` define, as given with `--define`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Define {
+    pub name: String,
+    pub value: String,
+}
+
+impl std::str::FromStr for Define {
+    type Err = eyre::Report;
+
+    /// `KEY=VALUE`, or `KEY` alone for `KEY=1` as with the compiler's `/D`.
+    fn from_str(text: &str) -> eyre::Result<Self> {
+        let (name, value) = text.split_once('=').unwrap_or((text, "1"));
+        let (name, value) = (name.trim(), value.trim());
+        if !is_identifier(name) {
+            bail!("Invalid define {text:?}: {name:?} is not a preprocessor identifier");
+        }
+        if value.is_empty() {
+            bail!("Invalid define {text:?}: the value is empty");
+        }
+        Ok(Self {
+            name: name.to_string(),
+            value: value.to_string(),
+        })
+    }
+}
+
+/// One value for every permutation key of a pipeline.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct ShaderPermutation {
+    /// Key names and value names, in the pipeline's key order.
+    settings: Vec<(String, String)>,
+    /// What the compiler is given, in the same order.
+    defines: Vec<(String, String)>,
+}
+
+impl ShaderPermutation {
+    /// The permutation with every key at its default, except those in `overrides`. A key given
+    /// more than once takes the last value; keys the pipeline does not declare are an error.
+    pub fn select(keys: &[PermutationKey], overrides: &[Define]) -> eyre::Result<Self> {
+        let mut chosen: Vec<usize> = keys.iter().map(PermutationKey::default_index).collect();
+        let mut problems = Vec::new();
+        for define in overrides {
+            let Some(position) = keys.iter().position(|key| key.name == define.name) else {
+                let known: Vec<&str> = keys.iter().map(|key| key.name.as_str()).collect();
+                problems.push(if known.is_empty() {
+                    format!(
+                        "{} is not a permutation key; the pipeline has none",
+                        define.name
+                    )
+                } else {
+                    format!(
+                        "{} is not a permutation key; expected one of {}",
+                        define.name,
+                        known.join(", ")
+                    )
+                });
+                continue;
+            };
+            match keys[position].parse_value(&define.value) {
+                Ok(index) => chosen[position] = index,
+                Err(problem) => problems.push(problem),
+            }
+        }
+        if !problems.is_empty() {
+            bail!("Invalid shader defines:\n  {}", problems.join("\n  "));
+        }
+
+        let mut permutation = Self::default();
+        for (key, index) in keys.iter().zip(chosen) {
+            permutation
+                .settings
+                .push((key.name.clone(), key.value_name(index).to_string()));
+            permutation
+                .defines
+                .push((key.name.clone(), index.to_string()));
+            if let PermutationKind::Enum { values, .. } = &key.kind {
+                for (value_index, value) in values.iter().enumerate() {
+                    permutation.defines.push((
+                        format!("{}_{}", key.name, value.to_ascii_uppercase()),
+                        value_index.to_string(),
+                    ));
+                }
+            }
+        }
+        Ok(permutation)
+    }
+
+    /// `(name, definition)` pairs for `D3D_SHADER_MACRO`s.
+    pub fn defines(&self) -> &[(String, String)] {
+        &self.defines
+    }
+
+    pub fn settings(&self) -> &[(String, String)] {
+        &self.settings
+    }
+
+    /// A stable FNV-1a hash of the settings, independent of the order keys are declared in, for
+    /// naming the permutation in caches.
+    pub fn key(&self) -> u64 {
+        let mut settings: Vec<&(String, String)> = self.settings.iter().collect();
+        settings.sort();
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for (name, value) in settings {
+            for byte in name.bytes().chain([b'=']).chain(value.bytes()).chain([0]) {
+                hash ^= u64::from(byte);
+                hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+            }
+        }
+        hash
+    }
+}
+
+impl std::fmt::Display for ShaderPermutation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.settings.is_empty() {
+            return f.write_str("default");
+        }
+        for (index, (name, value)) in self.settings.iter().enumerate() {
+            if index > 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{name}={value}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether `text` can be a preprocessor macro name.
+pub fn is_identifier(text: &str) -> bool {
+    let mut characters = text.chars();
+    characters
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && characters.all(|character| character.is_ascii_alphanumeric() || character == '_')
+}
@@ -0,0 +1,714 @@
+use crate::permutation::PermutationKey;
+use crate::permutation::PermutationKind;
+use crate::permutation::is_identifier;
+use eyre::Context;
+use facet::Facet;
+use std::path::Path;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// `D3D12_SIMULTANEOUS_RENDER_TARGET_COUNT`.
+pub const MAX_RENDER_TARGETS: usize = 8;
+
+/// A pipeline as written in a `.pipeline.toml` file, before validation. Every field is optional
+/// here so that validation can report everything that is missing at once.
+#[derive(Facet, Debug, Clone, Default, PartialEq, Eq)]
+pub struct PipelineFile {
+    /// The HLSL file, relative to the pipeline file.
+    pub shader: Option<String>,
+    /// Directories searched for `#include`s, relative to the pipeline file.
+    #[facet(default)]
+    pub include_paths: Vec<String>,
+    pub vertex_shader: Option<ShaderStageFile>,
+    pub pixel_shader: Option<ShaderStageFile>,
+    /// One blend preset for every render target, or one per render target; opaque when empty.
+    #[facet(default)]
+    pub blend: Vec<String>,
+    pub fill: Option<String>,
+    pub cull: Option<String>,
+    pub topology: Option<String>,
+    /// Render target formats, such as `B8G8R8A8_UNORM`.
+    #[facet(default)]
+    pub render_targets: Vec<String>,
+    /// The renderer's own vertex layout is used when this is empty.
+    #[facet(default)]
+    pub input_layout: Vec<InputElementFile>,
+    /// Preprocessor defines the shaders can be compiled with, chosen with `--define`.
+    #[facet(default)]
+    pub permutations: Vec<PermutationKeyFile>,
+}
+
+#[derive(Facet, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShaderStageFile {
+    pub entry_point: Option<String>,
+    /// A shader model target such as `vs_5_0`.
+    pub target: Option<String>,
+}
+
+#[derive(Facet, Debug, Clone, Default, PartialEq, Eq)]
+pub struct InputElementFile {
+    pub semantic: String,
+    pub semantic_index: Option<u32>,
+    pub format: String,
+    /// Byte offset in the vertex; defaults to right after the previous element in the same slot.
+    pub offset: Option<u32>,
+    pub slot: Option<u32>,
+}
+
+#[derive(Facet, Debug, Clone, Default, PartialEq, Eq)]
+pub struct PermutationKeyFile {
+    pub name: String,
+    /// The names an enum key can take; a bool key when empty.
+    #[facet(default)]
+    pub values: Vec<String>,
+    /// The first value, or false, when missing.
+    pub default: Option<String>,
+}
+
+/// A validated pipeline, with every value in its D3D12 representation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PipelineDescription {
+    pub shader: PathBuf,
+    pub include_paths: Vec<PathBuf>,
+    pub vertex_shader: ShaderStage,
+    pub pixel_shader: ShaderStage,
+    /// One per render target.
+    pub blend: Vec<BlendPreset>,
+    pub fill: FillMode,
+    pub cull: CullMode,
+    pub topology: Topology,
+    pub render_targets: Vec<Format>,
+    pub input_layout: Vec<InputElement>,
+    pub permutation_keys: Vec<PermutationKey>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShaderStage {
+    pub entry_point: String,
+    pub target: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputElement {
+    pub semantic: String,
+    pub semantic_index: u32,
+    pub format: Format,
+    pub offset: u32,
+    pub slot: u32,
+}
+
+/// Every problem found in a pipeline file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PipelineValidationError {
+    pub problems: Vec<String>,
+}
+
+impl std::error::Error for PipelineValidationError {}
+
+impl std::fmt::Display for PipelineValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid pipeline description:")?;
+        for problem in &self.problems {
+            write!(f, "\n  {problem}")?;
+        }
+        Ok(())
+    }
+}
+
+impl PipelineFile {
+    /// Check the file and resolve its names; `directory` is where relative shader paths start.
+    pub fn validate(
+        &self,
+        directory: &Path,
+    ) -> Result<PipelineDescription, PipelineValidationError> {
+        let mut problems = Vec::new();
+
+        let shader = match &self.shader {
+            Some(shader) => directory.join(shader),
+            None => {
+                problems.push("shader is missing".to_string());
+                PathBuf::new()
+            }
+        };
+        let include_paths = self
+            .include_paths
+            .iter()
+            .map(|include_path| directory.join(include_path))
+            .collect();
+        let vertex_shader = validate_stage(
+            "vertex_shader",
+            "vs",
+            self.vertex_shader.as_ref(),
+            &mut problems,
+        );
+        let pixel_shader = validate_stage(
+            "pixel_shader",
+            "ps",
+            self.pixel_shader.as_ref(),
+            &mut problems,
+        );
+
+        let fill = parse_or_default("fill", self.fill.as_deref(), &mut problems);
+        let cull = parse_or_default("cull", self.cull.as_deref(), &mut problems);
+        let topology = parse_or_default("topology", self.topology.as_deref(), &mut problems);
+
+        if self.render_targets.is_empty() || self.render_targets.len() > MAX_RENDER_TARGETS {
+            problems.push(format!(
+                "render_targets must list 1 to {MAX_RENDER_TARGETS} formats, found {}",
+                self.render_targets.len()
+            ));
+        }
+        let render_targets: Vec<Format> = self
+            .render_targets
+            .iter()
+            .enumerate()
+            .filter_map(|(index, format)| {
+                parse_problem(&format!("render_targets[{index}]"), format, &mut problems)
+            })
+            .collect();
+
+        let presets: Vec<BlendPreset> = self
+            .blend
+            .iter()
+            .enumerate()
+            .filter_map(|(index, preset)| {
+                parse_problem(&format!("blend[{index}]"), preset, &mut problems)
+            })
+            .collect();
+        let blend = match self.blend.len() {
+            0 => vec![BlendPreset::Opaque; self.render_targets.len()],
+            1 => vec![presets.first().copied().unwrap_or_default(); self.render_targets.len()],
+            count if count == self.render_targets.len() => presets,
+            count => {
+                problems.push(format!(
+                    "blend lists {count} presets for {} render targets; give one for all or one per target",
+                    self.render_targets.len()
+                ));
+                presets
+            }
+        };
+
+        let input_layout = validate_input_layout(&self.input_layout, &mut problems);
+        let permutation_keys = validate_permutation_keys(&self.permutations, &mut problems);
+
+        if !problems.is_empty() {
+            return Err(PipelineValidationError { problems });
+        }
+        Ok(PipelineDescription {
+            shader,
+            include_paths,
+            vertex_shader,
+            pixel_shader,
+            blend,
+            fill,
+            cull,
+            topology,
+            render_targets,
+            input_layout,
+            permutation_keys,
+        })
+    }
+}
+
+fn validate_stage(
+    name: &str,
+    prefix: &str,
+    stage: Option<&ShaderStageFile>,
+    problems: &mut Vec<String>,
+) -> ShaderStage {
+    let Some(stage) = stage else {
+        problems.push(format!("{name} is missing"));
+        return ShaderStage {
+            entry_point: String::new(),
+            target: String::new(),
+        };
+    };
+    let entry_point = match stage.entry_point.as_deref().map(str::trim) {
+        Some(entry_point) if !entry_point.is_empty() => entry_point.to_string(),
+        _ => {
+            problems.push(format!("{name}.entry_point is missing"));
+            String::new()
+        }
+    };
+    let target = match stage.target.as_deref() {
+        Some(target) if is_shader_target(target, prefix) => target.to_string(),
+        Some(target) => {
+            problems.push(format!(
+                "{name}.target {target:?} is not a shader model target for this stage; expected {prefix}_<major>_<minor>"
+            ));
+            String::new()
+        }
+        None => {
+            problems.push(format!("{name}.target is missing"));
+            String::new()
+        }
+    };
+    ShaderStage {
+        entry_point,
+        target,
+    }
+}
+
+/// Whether `target` is `<prefix>_<major>_<minor>`, such as `vs_5_0`.
+pub fn is_shader_target(target: &str, prefix: &str) -> bool {
+    let Some(model) = target
+        .strip_prefix(prefix)
+        .and_then(|rest| rest.strip_prefix('_'))
+    else {
+        return false;
+    };
+    let mut parts = model.split('_');
+    matches!(
+        (parts.next(), parts.next(), parts.next()),
+        (Some(major), Some(minor), None)
+            if !major.is_empty()
+                && !minor.is_empty()
+                && major.chars().all(|c| c.is_ascii_digit())
+                && minor.chars().all(|c| c.is_ascii_digit() || c == 'x')
+    )
+}
+
+fn validate_input_layout(
+    elements: &[InputElementFile],
+    problems: &mut Vec<String>,
+) -> Vec<InputElement> {
+    let mut layout: Vec<InputElement> = Vec::new();
+    for (index, element) in elements.iter().enumerate() {
+        let name = format!("input_layout[{index}]");
+        if element.semantic.trim().is_empty() {
+            problems.push(format!("{name}.semantic is missing"));
+        }
+        let Some(format) = parse_problem(&format!("{name}.format"), &element.format, problems)
+        else {
+            continue;
+        };
+        let semantic_index = element.semantic_index.unwrap_or(0);
+        let slot = element.slot.unwrap_or(0);
+        if layout.iter().any(|existing| {
+            existing.semantic.eq_ignore_ascii_case(&element.semantic)
+                && existing.semantic_index == semantic_index
+        }) {
+            problems.push(format!(
+                "{name} repeats semantic {}{semantic_index}",
+                element.semantic
+            ));
+        }
+        let offset = element.offset.unwrap_or_else(|| {
+            layout
+                .iter()
+                .filter(|existing| existing.slot == slot)
+                .map(|existing| existing.offset + existing.format.size())
+                .max()
+                .unwrap_or(0)
+        });
+        layout.push(InputElement {
+            semantic: element.semantic.clone(),
+            semantic_index,
+            format,
+            offset,
+            slot,
+        });
+    }
+    layout
+}
+
+fn validate_permutation_keys(
+    keys: &[PermutationKeyFile],
+    problems: &mut Vec<String>,
+) -> Vec<PermutationKey> {
+    let mut validated: Vec<PermutationKey> = Vec::new();
+    for (index, key) in keys.iter().enumerate() {
+        let name = format!("permutations[{index}]");
+        if !is_identifier(&key.name) {
+            problems.push(format!(
+                "{name}.name {:?} is not a preprocessor identifier",
+                key.name
+            ));
+        }
+        if validated.iter().any(|existing| existing.name == key.name) {
+            problems.push(format!("{name} repeats key {}", key.name));
+        }
+        for (value_index, value) in key.values.iter().enumerate() {
+            if !is_identifier(value) {
+                problems.push(format!(
+                    "{name}.values[{value_index}] {value:?} is not a preprocessor identifier"
+                ));
+            }
+            if key.values[..value_index]
+                .iter()
+                .any(|existing| existing.eq_ignore_ascii_case(value))
+            {
+                problems.push(format!("{name}.values repeats {value}"));
+            }
+        }
+
+        let kind = if key.values.is_empty() {
+            PermutationKind::Bool { default: false }
+        } else {
+            PermutationKind::Enum {
+                values: key.values.clone(),
+                default: 0,
+            }
+        };
+        let mut permutation_key = PermutationKey {
+            name: key.name.clone(),
+            kind,
+        };
+        if let Some(default) = &key.default {
+            match permutation_key.parse_value(default) {
+                Ok(default_index) => match &mut permutation_key.kind {
+                    PermutationKind::Bool { default } => *default = default_index == 1,
+                    PermutationKind::Enum { default, .. } => *default = default_index,
+                },
+                Err(problem) => problems.push(format!("{name}.default: {problem}")),
+            }
+        }
+        validated.push(permutation_key);
+    }
+    validated
+}
+
+fn parse_problem<T: FromStr<Err = eyre::Report>>(
+    name: &str,
+    value: &str,
+    problems: &mut Vec<String>,
+) -> Option<T> {
+    match value.parse() {
+        Ok(value) => Some(value),
+        Err(error) => {
+            problems.push(format!("{name}: {error}"));
+            None
+        }
+    }
+}
+
+fn parse_or_default<T: FromStr<Err = eyre::Report> + Default>(
+    name: &str,
+    value: Option<&str>,
+    problems: &mut Vec<String>,
+) -> T {
+    value
+        .and_then(|value| parse_problem(name, value, problems))
+        .unwrap_or_default()
+}
+
+impl PipelineDescription {
+    /// The pipeline settings recorded in frame captures.
+    pub fn capture_settings(&self) -> Vec<(String, String)> {
+        let blend = self.blend.first().copied().unwrap_or_default().target();
+        [
+            ("blend.enable", blend.enable.to_string()),
+            ("blend.src", blend.src.to_string()),
+            ("blend.dest", blend.dest.to_string()),
+            ("blend.op", blend.op.to_string()),
+            ("blend.src_alpha", blend.src_alpha.to_string()),
+            ("blend.dest_alpha", blend.dest_alpha.to_string()),
+            ("blend.op_alpha", blend.op_alpha.to_string()),
+            ("blend.write_mask", blend.write_mask.to_string()),
+            ("rasterizer.fill_mode", self.fill.raw().to_string()),
+            ("rasterizer.cull_mode", self.cull.raw().to_string()),
+            ("depth.enable", false.to_string()),
+            ("topology", self.topology.raw().to_string()),
+            (
+                "rtv_format",
+                self.render_targets
+                    .first()
+                    .map_or(0, |format| format.raw())
+                    .to_string(),
+            ),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value))
+        .collect()
+    }
+}
+
+/// Read and validate a pipeline file.
+pub fn load_pipeline(path: &Path) -> eyre::Result<PipelineDescription> {
+    let text = std::fs::read_to_string(path)
+        .wrap_err_with(|| format!("Failed to read pipeline {}", path.display()))?;
+    parse_pipeline(&text, path)
+}
+
+/// Parse and validate the text of the pipeline file at `path`.
+pub fn parse_pipeline(text: &str, path: &Path) -> eyre::Result<PipelineDescription> {
+    let file: PipelineFile = facet_toml::from_str(text)
+        .map_err(|error| eyre::eyre!("Failed to parse pipeline {}: {error}", path.display()))?;
+    let directory = path.parent().unwrap_or(Path::new("."));
+    file.validate(directory)
+        .wrap_err_with(|| format!("Failed to load pipeline {}", path.display()))
+}
+
+/// Named blend states, so pipeline files do not spell out blend factors.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BlendPreset {
+    #[default]
+    Opaque,
+    /// Straight alpha: `src * a + dest * (1 - a)`.
+    Alpha,
+    /// Colours already multiplied by alpha: `src + dest * (1 - a)`.
+    PremultipliedAlpha,
+    Additive,
+}
+
+/// A `D3D12_RENDER_TARGET_BLEND_DESC` as raw `D3D12_BLEND` and `D3D12_BLEND_OP` values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlendTarget {
+    pub enable: bool,
+    pub src: i32,
+    pub dest: i32,
+    pub op: i32,
+    pub src_alpha: i32,
+    pub dest_alpha: i32,
+    pub op_alpha: i32,
+    pub write_mask: u8,
+}
+
+const BLEND_ZERO: i32 = 1;
+const BLEND_ONE: i32 = 2;
+const BLEND_SRC_ALPHA: i32 = 5;
+const BLEND_INV_SRC_ALPHA: i32 = 6;
+const BLEND_OP_ADD: i32 = 1;
+const COLOR_WRITE_ENABLE_ALL: u8 = 0xF;
+
+impl BlendPreset {
+    pub fn target(self) -> BlendTarget {
+        let (enable, src, dest, src_alpha, dest_alpha) = match self {
+            Self::Opaque => (false, BLEND_ONE, BLEND_ZERO, BLEND_ONE, BLEND_ZERO),
+            Self::Alpha => (
+                true,
+                BLEND_SRC_ALPHA,
+                BLEND_INV_SRC_ALPHA,
+                BLEND_ONE,
+                BLEND_INV_SRC_ALPHA,
+            ),
+            Self::PremultipliedAlpha => (
+                true,
+                BLEND_ONE,
+                BLEND_INV_SRC_ALPHA,
+                BLEND_ONE,
+                BLEND_INV_SRC_ALPHA,
+            ),
+            Self::Additive => (true, BLEND_ONE, BLEND_ONE, BLEND_ONE, BLEND_ONE),
+        };
+        BlendTarget {
+            enable,
+            src,
+            dest,
+            op: BLEND_OP_ADD,
+            src_alpha,
+            dest_alpha,
+            op_alpha: BLEND_OP_ADD,
+            write_mask: COLOR_WRITE_ENABLE_ALL,
+        }
+    }
+}
+
+impl FromStr for BlendPreset {
+    type Err = eyre::Report;
+
+    fn from_str(value: &str) -> eyre::Result<Self> {
+        match value {
+            "opaque" => Ok(Self::Opaque),
+            "alpha" => Ok(Self::Alpha),
+            "premultiplied-alpha" => Ok(Self::PremultipliedAlpha),
+            "additive" => Ok(Self::Additive),
+            _ => eyre::bail!(
+                "Unknown blend preset {value:?}; expected opaque, alpha, premultiplied-alpha or additive"
+            ),
+        }
+    }
+}
+
+/// `D3D12_FILL_MODE`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FillMode {
+    Wireframe,
+    #[default]
+    Solid,
+}
+
+impl FillMode {
+    pub fn raw(self) -> i32 {
+        match self {
+            Self::Wireframe => 2,
+            Self::Solid => 3,
+        }
+    }
+}
+
+impl FromStr for FillMode {
+    type Err = eyre::Report;
+
+    fn from_str(value: &str) -> eyre::Result<Self> {
+        match value {
+            "wireframe" => Ok(Self::Wireframe),
+            "solid" => Ok(Self::Solid),
+            _ => eyre::bail!("Unknown fill mode {value:?}; expected solid or wireframe"),
+        }
+    }
+}
+
+/// `D3D12_CULL_MODE`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CullMode {
+    None,
+    Front,
+    #[default]
+    Back,
+}
+
+impl CullMode {
+    pub fn raw(self) -> i32 {
+        match self {
+            Self::None => 1,
+            Self::Front => 2,
+            Self::Back => 3,
+        }
+    }
+}
+
+impl FromStr for CullMode {
+    type Err = eyre::Report;
+
+    fn from_str(value: &str) -> eyre::Result<Self> {
+        match value {
+            "none" => Ok(Self::None),
+            "front" => Ok(Self::Front),
+            "back" => Ok(Self::Back),
+            _ => eyre::bail!("Unknown cull mode {value:?}; expected none, front or back"),
+        }
+    }
+}
+
+/// `D3D12_PRIMITIVE_TOPOLOGY_TYPE`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Topology {
+    Point,
+    Line,
+    #[default]
+    Triangle,
+}
+
+impl Topology {
+    pub fn raw(self) -> i32 {
+        match self {
+            Self::Point => 1,
+            Self::Line => 2,
+            Self::Triangle => 3,
+        }
+    }
+}
+
+impl FromStr for Topology {
+    type Err = eyre::Report;
+
+    fn from_str(value: &str) -> eyre::Result<Self> {
+        match value {
+            "point" => Ok(Self::Point),
+            "line" => Ok(Self::Line),
+            "triangle" => Ok(Self::Triangle),
+            _ => eyre::bail!("Unknown topology {value:?}; expected point, line or triangle"),
+        }
+    }
+}
+
+/// The `DXGI_FORMAT`s pipeline files can use for vertex elements and render targets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    R32G32B32A32Float,
+    R32G32B32Float,
+    R16G16B16A16Float,
+    R32G32Float,
+    R10G10B10A2Unorm,
+    R8G8B8A8Unorm,
+    R8G8B8A8UnormSrgb,
+    R32Float,
+    R32Uint,
+    B8G8R8A8Unorm,
+    B8G8R8A8UnormSrgb,
+}
+
+impl Format {
+    pub const ALL: [Self; 11] = [
+        Self::R32G32B32A32Float,
+        Self::R32G32B32Float,
+        Self::R16G16B16A16Float,
+        Self::R32G32Float,
+        Self::R10G10B10A2Unorm,
+        Self::R8G8B8A8Unorm,
+        Self::R8G8B8A8UnormSrgb,
+        Self::R32Float,
+        Self::R32Uint,
+        Self::B8G8R8A8Unorm,
+        Self::B8G8R8A8UnormSrgb,
+    ];
+
+    /// The `DXGI_FORMAT` value.
+    pub fn raw(self) -> i32 {
+        match self {
+            Self::R32G32B32A32Float => 2,
+            Self::R32G32B32Float => 6,
+            Self::R16G16B16A16Float => 10,
+            Self::R32G32Float => 16,
+            Self::R10G10B10A2Unorm => 24,
+            Self::R8G8B8A8Unorm => 28,
+            Self::R8G8B8A8UnormSrgb => 29,
+            Self::R32Float => 41,
+            Self::R32Uint => 42,
+            Self::B8G8R8A8Unorm => 87,
+            Self::B8G8R8A8UnormSrgb => 91,
+        }
+    }
+
+    /// Bytes per element.
+    pub fn size(self) -> u32 {
+        match self {
+            Self::R32G32B32A32Float => 16,
+            Self::R32G32B32Float => 12,
+            Self::R16G16B16A16Float | Self::R32G32Float => 8,
+            Self::R10G10B10A2Unorm
+            | Self::R8G8B8A8Unorm
+            | Self::R8G8B8A8UnormSrgb
+            | Self::R32Float
+            | Self::R32Uint
+            | Self::B8G8R8A8Unorm
+            | Self::B8G8R8A8UnormSrgb => 4,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::R32G32B32A32Float => "R32G32B32A32_FLOAT",
+            Self::R32G32B32Float => "R32G32B32_FLOAT",
+            Self::R16G16B16A16Float => "R16G16B16A16_FLOAT",
+            Self::R32G32Float => "R32G32_FLOAT",
+            Self::R10G10B10A2Unorm => "R10G10B10A2_UNORM",
+            Self::R8G8B8A8Unorm => "R8G8B8A8_UNORM",
+            Self::R8G8B8A8UnormSrgb => "R8G8B8A8_UNORM_SRGB",
+            Self::R32Float => "R32_FLOAT",
+            Self::R32Uint => "R32_UINT",
+            Self::B8G8R8A8Unorm => "B8G8R8A8_UNORM",
+            Self::B8G8R8A8UnormSrgb => "B8G8R8A8_UNORM_SRGB",
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = eyre::Report;
+
+    /// The `DXGI_FORMAT_` name, with or without the prefix.
+    fn from_str(value: &str) -> eyre::Result<Self> {
+        let name = value.strip_prefix("DXGI_FORMAT_").unwrap_or(value);
+        Self::ALL
+            .into_iter()
+            .find(|format| format.name().eq_ignore_ascii_case(name))
+            .ok_or_else(|| eyre::eyre!("Unknown format {value:?}"))
+    }
+}
+
+impl std::fmt::Display for Format {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
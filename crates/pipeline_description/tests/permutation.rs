@@ -0,0 +1,181 @@
+use pipeline_description::Define;
+use pipeline_description::PermutationKey;
+use pipeline_description::PermutationKind;
+use pipeline_description::ShaderPermutation;
+use pipeline_description::is_identifier;
+
+fn bool_key(name: &str, default: bool) -> PermutationKey {
+    PermutationKey {
+        name: name.to_string(),
+        kind: PermutationKind::Bool { default },
+    }
+}
+
+fn enum_key(name: &str, values: &[&str], default: usize) -> PermutationKey {
+    PermutationKey {
+        name: name.to_string(),
+        kind: PermutationKind::Enum {
+            values: values.iter().map(|value| value.to_string()).collect(),
+            default,
+        },
+    }
+}
+
+fn keys() -> Vec<PermutationKey> {
+    vec![
+        bool_key("GRAYSCALE", false),
+        enum_key("SHADING", &["Flat", "Smooth"], 1),
+    ]
+}
+
+fn define(text: &str) -> Define {
+    text.parse().unwrap()
+}
+
+fn pairs(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+    pairs
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+}
+
+#[test]
+fn defines_parse_like_the_compilers_d_flag() {
+    assert_eq!(
+        define("GRAYSCALE"),
+        Define {
+            name: "GRAYSCALE".to_string(),
+            value: "1".to_string(),
+        }
+    );
+    assert_eq!(define(" SHADING = Flat ").value, "Flat");
+    assert_eq!(define("_LEVEL2=a=b").value, "a=b");
+}
+
+#[test]
+fn invalid_defines_are_rejected() {
+    let error = |text: &str| text.parse::<Define>().unwrap_err().to_string();
+    assert_eq!(
+        error("2D=1"),
+        "Invalid define \"2D=1\": \"2D\" is not a preprocessor identifier"
+    );
+    assert_eq!(
+        error("=1"),
+        "Invalid define \"=1\": \"\" is not a preprocessor identifier"
+    );
+    assert_eq!(
+        error("SHADING="),
+        "Invalid define \"SHADING=\": the value is empty"
+    );
+    assert!("MY-KEY".parse::<Define>().is_err());
+}
+
+#[test]
+fn defaults_generate_value_defines() {
+    let permutation = ShaderPermutation::select(&keys(), &[]).unwrap();
+    assert_eq!(
+        permutation.settings(),
+        pairs(&[("GRAYSCALE", "0"), ("SHADING", "Smooth")])
+    );
+    assert_eq!(
+        permutation.defines(),
+        pairs(&[
+            ("GRAYSCALE", "0"),
+            ("SHADING", "1"),
+            ("SHADING_FLAT", "0"),
+            ("SHADING_SMOOTH", "1"),
+        ])
+    );
+    assert_eq!(permutation.to_string(), "GRAYSCALE=0 SHADING=Smooth");
+    assert_eq!(
+        ShaderPermutation::select(&[], &[]).unwrap().to_string(),
+        "default"
+    );
+}
+
+#[test]
+fn overrides_replace_defaults_and_the_last_one_wins() {
+    let overrides = [
+        define("SHADING=smooth"),
+        define("GRAYSCALE"),
+        define("SHADING=FLAT"),
+    ];
+    let permutation = ShaderPermutation::select(&keys(), &overrides).unwrap();
+    assert_eq!(
+        permutation.settings(),
+        pairs(&[("GRAYSCALE", "1"), ("SHADING", "Flat")])
+    );
+    assert_eq!(
+        permutation.defines()[1],
+        ("SHADING".to_string(), "0".to_string())
+    );
+}
+
+#[test]
+fn unknown_keys_and_values_are_reported_together() {
+    let overrides = [
+        define("WIREFRAME"),
+        define("GRAYSCALE=maybe"),
+        define("SHADING=Toon"),
+    ];
+    let error = ShaderPermutation::select(&keys(), &overrides).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Invalid shader defines:\n  \
+         WIREFRAME is not a permutation key; expected one of GRAYSCALE, SHADING\n  \
+         GRAYSCALE is a bool and cannot be \"maybe\"; use 0 or 1\n  \
+         SHADING cannot be \"Toon\"; expected one of Flat, Smooth"
+    );
+
+    let error = ShaderPermutation::select(&[], &[define("GRAYSCALE")]).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Invalid shader defines:\n  GRAYSCALE is not a permutation key; the pipeline has none"
+    );
+}
+
+#[test]
+fn bool_values_accept_common_spellings() {
+    let key = bool_key("GRAYSCALE", false);
+    for (value, index) in [
+        ("0", 0),
+        ("false", 0),
+        ("OFF", 0),
+        ("1", 1),
+        ("True", 1),
+        ("on", 1),
+    ] {
+        assert_eq!(key.parse_value(value), Ok(index), "{value}");
+    }
+}
+
+#[test]
+fn keys_hash_settings_independently_of_declaration_order() {
+    let forward = keys();
+    let reversed: Vec<_> = keys().into_iter().rev().collect();
+    let overrides = [define("SHADING=Flat")];
+    let a = ShaderPermutation::select(&forward, &overrides).unwrap();
+    let b = ShaderPermutation::select(&reversed, &overrides).unwrap();
+    assert_ne!(a, b);
+    assert_eq!(a.key(), b.key());
+
+    let default = ShaderPermutation::select(&forward, &[]).unwrap();
+    assert_ne!(a.key(), default.key());
+    assert_eq!(
+        default.key(),
+        ShaderPermutation::select(&forward, &[]).unwrap().key()
+    );
+    // Settings are delimited, so moving characters between name and value changes the key.
+    let split = ShaderPermutation::select(&[enum_key("AB", &["C"], 0)], &[]).unwrap();
+    let moved = ShaderPermutation::select(&[enum_key("A", &["BC"], 0)], &[]).unwrap();
+    assert_ne!(split.key(), moved.key());
+}
+
+#[test]
+fn identifiers_follow_the_preprocessor() {
+    assert!(is_identifier("GRAYSCALE"));
+    assert!(is_identifier("_private2"));
+    assert!(!is_identifier(""));
+    assert!(!is_identifier("2D"));
+    assert!(!is_identifier("PER-PIXEL"));
+}
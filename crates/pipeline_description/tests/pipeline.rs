@@ -0,0 +1,384 @@
+use pipeline_description::BlendPreset;
+use pipeline_description::CullMode;
+use pipeline_description::FillMode;
+use pipeline_description::Format;
+use pipeline_description::InputElementFile;
+use pipeline_description::MAX_RENDER_TARGETS;
+use pipeline_description::PermutationKeyFile;
+use pipeline_description::PermutationKind;
+use pipeline_description::PipelineFile;
+use pipeline_description::PipelineValidationError;
+use pipeline_description::ShaderStageFile;
+use pipeline_description::Topology;
+use pipeline_description::is_shader_target;
+use pipeline_description::parse_pipeline;
+use std::path::Path;
+
+fn stage(entry_point: &str, target: &str) -> Option<ShaderStageFile> {
+    Some(ShaderStageFile {
+        entry_point: Some(entry_point.to_string()),
+        target: Some(target.to_string()),
+    })
+}
+
+fn strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value| value.to_string()).collect()
+}
+
+fn element(semantic: &str, format: &str) -> InputElementFile {
+    InputElementFile {
+        semantic: semantic.to_string(),
+        format: format.to_string(),
+        ..InputElementFile::default()
+    }
+}
+
+fn permutation(name: &str, values: &[&str], default: Option<&str>) -> PermutationKeyFile {
+    PermutationKeyFile {
+        name: name.to_string(),
+        values: strings(values),
+        default: default.map(str::to_string),
+    }
+}
+
+fn valid() -> PipelineFile {
+    PipelineFile {
+        shader: Some("shaders.hlsl".to_string()),
+        vertex_shader: stage("VSMain", "vs_5_0"),
+        pixel_shader: stage("PSMain", "ps_5_0"),
+        render_targets: strings(&["B8G8R8A8_UNORM"]),
+        ..PipelineFile::default()
+    }
+}
+
+fn problems(file: &PipelineFile) -> Vec<String> {
+    file.validate(Path::new("pipelines")).unwrap_err().problems
+}
+
+#[test]
+fn shipped_pipeline_parses() {
+    let path = Path::new("src/transparent_triangle.pipeline.toml");
+    let pipeline = parse_pipeline(
+        include_str!(
+            "../../windows-rs-sample-direct3d12-improved-v7/src/transparent_triangle.pipeline.toml"
+        ),
+        path,
+    )
+    .unwrap();
+    assert_eq!(pipeline.shader, Path::new("src/shaders.hlsl"));
+    assert_eq!(pipeline.vertex_shader.entry_point, "VSMain");
+    assert_eq!(pipeline.pixel_shader.target, "ps_5_0");
+    assert_eq!(pipeline.blend, [BlendPreset::PremultipliedAlpha]);
+    assert_eq!(pipeline.cull, CullMode::None);
+    assert_eq!(pipeline.render_targets, [Format::B8G8R8A8Unorm]);
+    assert_eq!(pipeline.permutation_keys[0].name, "GRAYSCALE");
+}
+
+#[test]
+fn toml_parses_tables_and_arrays_of_tables() {
+    let text = r#"
+        shader = "lit.hlsl"
+        include_paths = ["include"]
+        render_targets = ["R16G16B16A16_FLOAT", "R8G8B8A8_UNORM"]
+
+        [vertex_shader]
+        entry_point = "VSMain"
+        target = "vs_6_0"
+
+        [pixel_shader]
+        entry_point = "PSMain"
+        target = "ps_6_0"
+
+        [[input_layout]]
+        semantic = "POSITION"
+        format = "R32G32B32_FLOAT"
+
+        [[input_layout]]
+        semantic = "TEXCOORD"
+        semantic_index = 1
+        format = "R32G32_FLOAT"
+
+        [[permutations]]
+        name = "SHADING"
+        values = ["FLAT", "SMOOTH"]
+        default = "SMOOTH"
+    "#;
+    let pipeline = parse_pipeline(text, Path::new("pipelines/lit.pipeline.toml")).unwrap();
+    assert_eq!(pipeline.shader, Path::new("pipelines/lit.hlsl"));
+    assert_eq!(pipeline.include_paths, [Path::new("pipelines/include")]);
+    assert_eq!(pipeline.blend, [BlendPreset::Opaque; 2]);
+    assert_eq!(pipeline.input_layout[1].semantic_index, 1);
+    assert_eq!(pipeline.input_layout[1].offset, 12);
+    assert_eq!(pipeline.permutation_keys[0].default_index(), 1);
+}
+
+#[test]
+fn toml_syntax_and_type_errors_name_the_file() {
+    let path = Path::new("broken.pipeline.toml");
+    let error = parse_pipeline("[[[", path).unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .starts_with("Failed to parse pipeline broken.pipeline.toml"),
+        "{error}"
+    );
+    assert!(parse_pipeline("render_targets = \"R8G8B8A8_UNORM\"", path).is_err());
+}
+
+#[test]
+fn validation_errors_are_wrapped_with_the_file() {
+    let error = parse_pipeline("", Path::new("empty.pipeline.toml")).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Failed to load pipeline empty.pipeline.toml"
+    );
+    let validation = error.downcast_ref::<PipelineValidationError>().unwrap();
+    assert_eq!(validation.problems.len(), 4);
+}
+
+#[test]
+fn valid_file_resolves_defaults() {
+    let pipeline = valid().validate(Path::new("pipelines")).unwrap();
+    assert_eq!(pipeline.shader, Path::new("pipelines/shaders.hlsl"));
+    assert_eq!(pipeline.blend, [BlendPreset::Opaque]);
+    assert_eq!(pipeline.fill, FillMode::Solid);
+    assert_eq!(pipeline.cull, CullMode::Back);
+    assert_eq!(pipeline.topology, Topology::Triangle);
+    assert!(pipeline.input_layout.is_empty());
+    assert!(pipeline.permutation_keys.is_empty());
+}
+
+#[test]
+fn empty_file_reports_everything_missing() {
+    assert_eq!(
+        problems(&PipelineFile::default()),
+        [
+            "shader is missing",
+            "vertex_shader is missing",
+            "pixel_shader is missing",
+            "render_targets must list 1 to 8 formats, found 0",
+        ]
+    );
+}
+
+#[test]
+fn stages_need_an_entry_point_and_a_matching_target() {
+    let file = PipelineFile {
+        vertex_shader: Some(ShaderStageFile {
+            entry_point: Some("  ".to_string()),
+            target: None,
+        }),
+        pixel_shader: stage("PSMain", "vs_5_0"),
+        ..valid()
+    };
+    assert_eq!(
+        problems(&file),
+        [
+            "vertex_shader.entry_point is missing",
+            "vertex_shader.target is missing",
+            "pixel_shader.target \"vs_5_0\" is not a shader model target for this stage; expected ps_<major>_<minor>",
+        ]
+    );
+}
+
+#[test]
+fn shader_targets_are_prefix_major_minor() {
+    assert!(is_shader_target("vs_5_0", "vs"));
+    assert!(is_shader_target("ps_6_6", "ps"));
+    assert!(!is_shader_target("vs_4_0_level_9_1", "vs"));
+    assert!(!is_shader_target("vs_5", "vs"));
+    assert!(!is_shader_target("vs__0", "vs"));
+    assert!(!is_shader_target("vs_a_0", "vs"));
+    assert!(!is_shader_target("vs5_0", "vs"));
+}
+
+#[test]
+fn unknown_rasterizer_settings_are_reported() {
+    let file = PipelineFile {
+        fill: Some("dotted".to_string()),
+        cull: Some("sideways".to_string()),
+        topology: Some("strip".to_string()),
+        ..valid()
+    };
+    assert_eq!(
+        problems(&file),
+        [
+            "fill: Unknown fill mode \"dotted\"; expected solid or wireframe",
+            "cull: Unknown cull mode \"sideways\"; expected none, front or back",
+            "topology: Unknown topology \"strip\"; expected point, line or triangle",
+        ]
+    );
+}
+
+#[test]
+fn render_targets_are_counted_and_parsed() {
+    let file = PipelineFile {
+        render_targets: vec!["R8G8B8A8_UNORM".to_string(); MAX_RENDER_TARGETS + 1],
+        ..valid()
+    };
+    assert_eq!(
+        problems(&file),
+        ["render_targets must list 1 to 8 formats, found 9"]
+    );
+
+    let file = PipelineFile {
+        render_targets: strings(&["DXGI_FORMAT_R8G8B8A8_UNORM", "R8G8B8_UNORM"]),
+        ..valid()
+    };
+    assert_eq!(
+        problems(&file),
+        ["render_targets[1]: Unknown format \"R8G8B8_UNORM\""]
+    );
+}
+
+#[test]
+fn blend_presets_apply_to_all_targets_or_one_each() {
+    let file = PipelineFile {
+        blend: strings(&["alpha"]),
+        render_targets: strings(&["R8G8B8A8_UNORM", "R32_FLOAT"]),
+        ..valid()
+    };
+    let pipeline = file.validate(Path::new(".")).unwrap();
+    assert_eq!(pipeline.blend, [BlendPreset::Alpha; 2]);
+
+    let file = PipelineFile {
+        blend: strings(&["alpha", "additive"]),
+        ..file
+    };
+    let pipeline = file.validate(Path::new(".")).unwrap();
+    assert_eq!(pipeline.blend, [BlendPreset::Alpha, BlendPreset::Additive]);
+
+    let file = PipelineFile {
+        blend: strings(&["alpha", "additive", "glow"]),
+        ..file
+    };
+    assert_eq!(
+        problems(&file),
+        [
+            "blend[2]: Unknown blend preset \"glow\"; expected opaque, alpha, premultiplied-alpha or additive",
+            "blend lists 3 presets for 2 render targets; give one for all or one per target",
+        ]
+    );
+}
+
+#[test]
+fn input_elements_are_packed_per_slot() {
+    let file = PipelineFile {
+        input_layout: vec![
+            element("POSITION", "R32G32B32_FLOAT"),
+            InputElementFile {
+                slot: Some(1),
+                ..element("COLOR", "R8G8B8A8_UNORM")
+            },
+            InputElementFile {
+                semantic_index: Some(1),
+                ..element("POSITION", "R32G32_FLOAT")
+            },
+            InputElementFile {
+                offset: Some(64),
+                ..element("NORMAL", "R32G32B32_FLOAT")
+            },
+        ],
+        ..valid()
+    };
+    let layout = file.validate(Path::new(".")).unwrap().input_layout;
+    let placement: Vec<_> = layout
+        .iter()
+        .map(|element| (element.semantic_index, element.slot, element.offset))
+        .collect();
+    assert_eq!(placement, [(0, 0, 0), (0, 1, 0), (1, 0, 12), (0, 0, 64)]);
+}
+
+#[test]
+fn input_element_problems_are_reported() {
+    let file = PipelineFile {
+        input_layout: vec![
+            element("POSITION", "R32G32B32_FLOAT"),
+            element(" ", "R32_FLOAT"),
+            element("TEXCOORD", "R64_FLOAT"),
+            element("position", "R32G32_FLOAT"),
+        ],
+        ..valid()
+    };
+    assert_eq!(
+        problems(&file),
+        [
+            "input_layout[1].semantic is missing",
+            "input_layout[2].format: Unknown format \"R64_FLOAT\"",
+            "input_layout[3] repeats semantic position0",
+        ]
+    );
+}
+
+#[test]
+fn permutation_defaults_are_resolved() {
+    let file = PipelineFile {
+        permutations: vec![
+            permutation("GRAYSCALE", &[], None),
+            permutation("WIREFRAME", &[], Some("on")),
+            permutation("SHADING", &["FLAT", "SMOOTH"], Some("smooth")),
+        ],
+        ..valid()
+    };
+    let keys = file.validate(Path::new(".")).unwrap().permutation_keys;
+    assert_eq!(keys[0].kind, PermutationKind::Bool { default: false });
+    assert_eq!(keys[1].kind, PermutationKind::Bool { default: true });
+    assert_eq!(
+        keys[2].kind,
+        PermutationKind::Enum {
+            values: strings(&["FLAT", "SMOOTH"]),
+            default: 1,
+        }
+    );
+}
+
+#[test]
+fn permutation_problems_are_reported() {
+    let file = PipelineFile {
+        permutations: vec![
+            permutation("2SIDED", &[], None),
+            permutation("SHADING", &["FLAT", "flat", "PER-PIXEL"], None),
+            permutation("SHADING", &[], Some("maybe")),
+            permutation("QUALITY", &["LOW", "HIGH"], Some("ULTRA")),
+        ],
+        ..valid()
+    };
+    assert_eq!(
+        problems(&file),
+        [
+            "permutations[0].name \"2SIDED\" is not a preprocessor identifier",
+            "permutations[1].values repeats flat",
+            "permutations[1].values[2] \"PER-PIXEL\" is not a preprocessor identifier",
+            "permutations[2] repeats key SHADING",
+            "permutations[2].default: SHADING is a bool and cannot be \"maybe\"; use 0 or 1",
+            "permutations[3].default: QUALITY cannot be \"ULTRA\"; expected one of LOW, HIGH",
+        ]
+    );
+}
+
+#[test]
+fn validation_error_lists_every_problem() {
+    let error = PipelineFile::default()
+        .validate(Path::new("."))
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Invalid pipeline description:\n  shader is missing\n  vertex_shader is missing\n  \
+         pixel_shader is missing\n  render_targets must list 1 to 8 formats, found 0"
+    );
+}
+
+#[test]
+fn formats_parse_by_dxgi_name() {
+    for format in Format::ALL {
+        assert_eq!(format.name().parse::<Format>().unwrap(), format);
+        assert_eq!(
+            format!("DXGI_FORMAT_{format}").parse::<Format>().unwrap(),
+            format
+        );
+    }
+    assert_eq!(
+        "b8g8r8a8_unorm".parse::<Format>().unwrap(),
+        Format::B8G8R8A8Unorm
+    );
+}
@@ -0,0 +1,146 @@
+//! Structured diagnostics from the HLSL compilers' error blobs.
+//!
+//! `fxc` (and `D3DCompile`) report `file(line,column-end): error X3004: message`, while `dxc`
+//! reports `file:line:column: error: message` followed by the source line and a caret. Both are
+//! parsed into [`Diagnostic`]s that can be rendered against the source, codespan-style, and
+//! emitted as tracing events. Lines that are neither, such as `compilation failed; no code
+//! produced`, are kept as they are.
+
+mod parse;
+mod render;
+
+/// How bad a diagnostic is, as the compiler reported it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Note,
+    Warning,
+    Error,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Note => "note",
+            Self::Warning => "warning",
+            Self::Error => "error",
+        })
+    }
+}
+
+/// Where in the source a diagnostic points. Lines and columns are 1-based, as the compilers
+/// print them.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Location {
+    /// The file as the compiler named it: the source name given to it, or an include's path.
+    pub file: String,
+    pub line: u32,
+    pub column: Option<u32>,
+    /// The column just past the span, when the compiler gave one.
+    pub end_column: Option<u32>,
+}
+
+impl std::fmt::Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.file, self.line)?;
+        if let Some(column) = self.column {
+            write!(f, ":{column}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// `X3004` from fxc, or the warning flag such as `-Wconversion` from dxc.
+    pub code: Option<String>,
+    pub location: Option<Location>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// Log the diagnostic at its severity's level, with the location and code as fields.
+    pub fn emit(&self) {
+        let code = self.code.as_deref();
+        let file = self
+            .location
+            .as_ref()
+            .map(|location| location.file.as_str());
+        let line = self.location.as_ref().map(|location| location.line);
+        let column = self.location.as_ref().and_then(|location| location.column);
+        let message = self.message.as_str();
+        match self.severity {
+            Severity::Error => {
+                tracing::error!(code, file, line, column, message, "Shader compile error");
+            }
+            Severity::Warning => {
+                tracing::warn!(code, file, line, column, message, "Shader compile warning");
+            }
+            Severity::Note => {
+                tracing::info!(code, file, line, column, message, "Shader compile note");
+            }
+        }
+    }
+}
+
+/// Everything in one error blob.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Diagnostics {
+    pub items: Vec<Diagnostic>,
+    /// Lines that are not diagnostics, in order.
+    pub unparsed: Vec<String>,
+}
+
+impl Diagnostics {
+    pub fn parse(text: &str) -> Self {
+        parse::parse(text)
+    }
+
+    /// Parse an error blob's bytes, which end in a NUL when they come from the compiler.
+    pub fn parse_bytes(bytes: &[u8]) -> Self {
+        let bytes = bytes.strip_suffix(&[0]).unwrap_or(bytes);
+        Self::parse(&String::from_utf8_lossy(bytes))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty() && self.unparsed.is_empty()
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.with_severity(Severity::Error)
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.with_severity(Severity::Warning)
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.errors().next().is_some()
+    }
+
+    pub fn has_warnings(&self) -> bool {
+        self.warnings().next().is_some()
+    }
+
+    /// Report warnings as errors, for builds that must compile cleanly.
+    pub fn promote_warnings(&mut self) {
+        for diagnostic in &mut self.items {
+            if diagnostic.severity == Severity::Warning {
+                diagnostic.severity = Severity::Error;
+            }
+        }
+    }
+
+    /// [`Diagnostic::emit`] every diagnostic.
+    pub fn emit(&self) {
+        for diagnostic in &self.items {
+            diagnostic.emit();
+        }
+    }
+
+    fn with_severity(&self, severity: Severity) -> impl Iterator<Item = &Diagnostic> {
+        self.items
+            .iter()
+            .filter(move |diagnostic| diagnostic.severity == severity)
+    }
+}
@@ -0,0 +1,138 @@
+use crate::Diagnostic;
+use crate::Diagnostics;
+use crate::Location;
+use crate::Severity;
+
+pub(crate) fn parse(text: &str) -> Diagnostics {
+    let mut diagnostics = Diagnostics::default();
+    // The unparsed line right after a diagnostic, which dxc uses to echo the source; dropped when
+    // the caret line under it follows.
+    let mut echo: Option<usize> = None;
+    for line in text.lines() {
+        let line = line.trim_end();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(diagnostic) = parse_line(line) {
+            diagnostics.items.push(diagnostic);
+            echo = None;
+            continue;
+        }
+        if is_caret_line(line) {
+            if let Some(index) = echo.take() {
+                diagnostics.unparsed.truncate(index);
+            }
+            continue;
+        }
+        echo = Some(diagnostics.unparsed.len());
+        diagnostics.unparsed.push(line.to_string());
+    }
+    diagnostics
+}
+
+fn parse_line(line: &str) -> Option<Diagnostic> {
+    parse_fxc(line)
+        .or_else(|| parse_dxc(line))
+        .or_else(|| parse_message(line, None))
+}
+
+/// `file(line,column-end): error X3004: message`, where the file may itself contain parentheses.
+fn parse_fxc(line: &str) -> Option<Diagnostic> {
+    line.match_indices("): ").find_map(|(close, _)| {
+        let open = line[..close].rfind('(')?;
+        let file = &line[..open];
+        if file.is_empty() {
+            return None;
+        }
+        let location = parse_fxc_location(file, &line[open + 1..close])?;
+        parse_message(&line[close + 3..], Some(location))
+    })
+}
+
+/// `12`, `12,5` or `12,5-20`.
+fn parse_fxc_location(file: &str, text: &str) -> Option<Location> {
+    let (line, columns) = match text.split_once(',') {
+        Some((line, columns)) => (line, Some(columns)),
+        None => (text, None),
+    };
+    let (column, end_column) = match columns {
+        Some(columns) => match columns.split_once('-') {
+            Some((start, end)) => (Some(start.parse().ok()?), end.parse().ok()),
+            None => (Some(columns.parse().ok()?), None),
+        },
+        None => (None, None),
+    };
+    Some(Location {
+        file: file.to_string(),
+        line: line.parse().ok()?,
+        column,
+        end_column,
+    })
+}
+
+/// `file:line:column: error: message`, where the file may start with a drive letter.
+fn parse_dxc(line: &str) -> Option<Diagnostic> {
+    line.match_indices(": ").find_map(|(colon, _)| {
+        let location = parse_dxc_location(&line[..colon])?;
+        parse_message(&line[colon + 2..], Some(location))
+    })
+}
+
+fn parse_dxc_location(text: &str) -> Option<Location> {
+    let (rest, last) = text.rsplit_once(':')?;
+    let last: u32 = last.parse().ok()?;
+    let (file, line, column) = match rest.rsplit_once(':') {
+        Some((file, line)) if line.parse::<u32>().is_ok() => (file, line.parse().ok()?, Some(last)),
+        _ => (rest, last, None),
+    };
+    if file.is_empty() {
+        return None;
+    }
+    Some(Location {
+        file: file.to_string(),
+        line,
+        column,
+        end_column: None,
+    })
+}
+
+/// `error X3004: message`, `warning: message [-Wflag]` or `fatal error: message`.
+fn parse_message(text: &str, location: Option<Location>) -> Option<Diagnostic> {
+    let (severity, rest) = [
+        ("fatal error", Severity::Error),
+        ("error", Severity::Error),
+        ("warning", Severity::Warning),
+        ("note", Severity::Note),
+    ]
+    .into_iter()
+    .find_map(|(word, severity)| Some((severity, text.strip_prefix(word)?)))?;
+
+    let (code, message) = if let Some(message) = rest.strip_prefix(": ") {
+        (None, message)
+    } else {
+        let (code, message) = rest.strip_prefix(' ')?.split_once(": ")?;
+        if code.is_empty() || !code.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return None;
+        }
+        (Some(code.to_string()), message)
+    };
+
+    let (code, message) = match message
+        .strip_suffix(']')
+        .and_then(|m| m.rsplit_once(" [-W"))
+    {
+        Some((message, flag)) if code.is_none() => (Some(format!("-W{flag}")), message),
+        _ => (code, message),
+    };
+    Some(Diagnostic {
+        severity,
+        code,
+        location,
+        message: message.trim().to_string(),
+    })
+}
+
+/// The `    ^~~~` dxc prints under the echoed source line.
+fn is_caret_line(line: &str) -> bool {
+    line.contains('^') && line.chars().all(|c| matches!(c, ' ' | '\t' | '^' | '~'))
+}
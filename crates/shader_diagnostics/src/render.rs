@@ -0,0 +1,70 @@
+use crate::Diagnostic;
+use crate::Diagnostics;
+use std::fmt::Write as _;
+
+impl Diagnostics {
+    /// Every diagnostic with the source line it points at, then the unparsed lines. `source`
+    /// returns the text of a file as the compiler named it; diagnostics in files it does not know
+    /// are rendered without the source line.
+    pub fn render<'a>(&self, source: impl Fn(&str) -> Option<&'a str>) -> String {
+        let mut rendered: Vec<String> = self
+            .items
+            .iter()
+            .map(|diagnostic| diagnostic.render(&source))
+            .collect();
+        if !self.unparsed.is_empty() {
+            rendered.push(self.unparsed.join("\n"));
+        }
+        rendered.join("\n\n")
+    }
+}
+
+impl Diagnostic {
+    /// ```text
+    /// error[X3004]: undeclared identifier 'colour'
+    ///   --> shaders.hlsl:12:12
+    ///    |
+    /// 12 |     return colour;
+    ///    |            ^^^^^^
+    /// ```
+    pub fn render<'a>(&self, source: impl Fn(&str) -> Option<&'a str>) -> String {
+        let mut rendered = String::new();
+        match &self.code {
+            Some(code) => write!(rendered, "{}[{code}]: {}", self.severity, self.message),
+            None => write!(rendered, "{}: {}", self.severity, self.message),
+        }
+        .unwrap();
+        let Some(location) = &self.location else {
+            return rendered;
+        };
+
+        let number = location.line.to_string();
+        let gutter = " ".repeat(number.len());
+        write!(rendered, "\n{gutter}--> {location}").unwrap();
+
+        let line = source(&location.file)
+            .and_then(|text| text.lines().nth(location.line.checked_sub(1)? as usize));
+        let Some(line) = line.map(|line| line.trim_end()) else {
+            return rendered;
+        };
+        write!(rendered, "\n{gutter} |\n{number} | {line}").unwrap();
+
+        if let Some(column) = location.column.filter(|&column| column > 0) {
+            // Keep tabs so the carets line up however wide the terminal draws them.
+            let prefix: String = line
+                .chars()
+                .take(column as usize - 1)
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect();
+            let remaining = line.chars().count().saturating_sub(column as usize - 1);
+            let width = location
+                .end_column
+                .filter(|&end| end > column)
+                .map_or(1, |end| (end - column) as usize)
+                .min(remaining)
+                .max(1);
+            write!(rendered, "\n{gutter} | {prefix}{}", "^".repeat(width)).unwrap();
+        }
+        rendered
+    }
+}
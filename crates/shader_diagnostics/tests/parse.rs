@@ -0,0 +1,178 @@
+use shader_diagnostics::Diagnostic;
+use shader_diagnostics::Diagnostics;
+use shader_diagnostics::Location;
+use shader_diagnostics::Severity;
+
+/// `D3DCompile`'s error blob: CRLF line endings and a trailing nul.
+const FXC: &str = "C:\\Users\\dev\\shaders\\shaders.hlsl(27,12-16): error X3004: undeclared identifier 'colr'\r\n\
+C:\\Users\\dev\\shaders\\shaders.hlsl(31,17-31): warning X3206: implicit truncation of vector type\r\n\
+C:\\Program Files (x86)\\shaders\\common.hlsli(4,1): error X3000: syntax error: unexpected token 'float4'\r\n\
+error X3501: 'VSMain': entrypoint not found\r\n\
+\r\n\
+compilation failed; no code produced\r\n\0";
+
+/// dxc echoes the offending source line and marks it with a caret.
+const DXC: &str = "\
+shaders.hlsl:27:12: error: use of undeclared identifier 'colr'
+    return colr;
+           ^
+shaders.hlsl:31:17: warning: implicit truncation of vector type [-Wconversion]
+    float2 uv = input.position;
+                ^~~~~~~~~~~~~~
+In file included from shaders.hlsl:1:
+C:\\dev\\common.hlsli:4:1: error: unknown type name 'flaot4'
+flaot4 tint;
+^
+note: candidate found here
+";
+
+fn location(file: &str, line: u32, column: Option<u32>, end_column: Option<u32>) -> Location {
+    Location {
+        file: file.to_string(),
+        line,
+        column,
+        end_column,
+    }
+}
+
+#[test]
+fn fxc_diagnostics_have_codes_and_column_spans() {
+    let diagnostics = Diagnostics::parse_bytes(FXC.as_bytes());
+    assert_eq!(diagnostics.items.len(), 4, "{diagnostics:#?}");
+    assert_eq!(
+        diagnostics.items[0],
+        Diagnostic {
+            severity: Severity::Error,
+            code: Some("X3004".to_string()),
+            location: Some(location(
+                "C:\\Users\\dev\\shaders\\shaders.hlsl",
+                27,
+                Some(12),
+                Some(16)
+            )),
+            message: "undeclared identifier 'colr'".to_string(),
+        }
+    );
+    assert_eq!(diagnostics.items[1].severity, Severity::Warning);
+    assert_eq!(diagnostics.items[1].code.as_deref(), Some("X3206"));
+}
+
+#[test]
+fn fxc_file_names_may_contain_parentheses() {
+    let diagnostics = Diagnostics::parse_bytes(FXC.as_bytes());
+    assert_eq!(
+        diagnostics.items[2].location,
+        Some(location(
+            "C:\\Program Files (x86)\\shaders\\common.hlsli",
+            4,
+            Some(1),
+            None
+        ))
+    );
+    assert_eq!(
+        diagnostics.items[2].message,
+        "syntax error: unexpected token 'float4'"
+    );
+}
+
+#[test]
+fn fxc_diagnostics_without_a_location_are_parsed() {
+    let diagnostics = Diagnostics::parse_bytes(FXC.as_bytes());
+    assert_eq!(diagnostics.items[3].location, None);
+    assert_eq!(diagnostics.items[3].code.as_deref(), Some("X3501"));
+    assert_eq!(
+        diagnostics.items[3].message,
+        "'VSMain': entrypoint not found"
+    );
+    assert_eq!(
+        diagnostics.unparsed,
+        ["compilation failed; no code produced"]
+    );
+    assert_eq!(diagnostics.errors().count(), 3);
+    assert_eq!(diagnostics.warnings().count(), 1);
+}
+
+#[test]
+fn fxc_locations_may_omit_columns() {
+    let diagnostics = Diagnostics::parse("shaders.hlsl(12): error X3000: syntax error");
+    assert_eq!(
+        diagnostics.items[0].location,
+        Some(location("shaders.hlsl", 12, None, None))
+    );
+}
+
+#[test]
+fn dxc_drops_the_echoed_source_and_caret() {
+    let diagnostics = Diagnostics::parse(DXC);
+    assert_eq!(diagnostics.items.len(), 4, "{diagnostics:#?}");
+    assert_eq!(
+        diagnostics.items[0],
+        Diagnostic {
+            severity: Severity::Error,
+            code: None,
+            location: Some(location("shaders.hlsl", 27, Some(12), None)),
+            message: "use of undeclared identifier 'colr'".to_string(),
+        }
+    );
+    // Only the include trail is left; every echoed line had a caret under it.
+    assert_eq!(
+        diagnostics.unparsed,
+        ["In file included from shaders.hlsl:1:"]
+    );
+}
+
+#[test]
+fn dxc_warning_flags_become_codes() {
+    let diagnostics = Diagnostics::parse(DXC);
+    assert_eq!(diagnostics.items[1].severity, Severity::Warning);
+    assert_eq!(diagnostics.items[1].code.as_deref(), Some("-Wconversion"));
+    assert_eq!(
+        diagnostics.items[1].message,
+        "implicit truncation of vector type"
+    );
+}
+
+#[test]
+fn dxc_file_names_may_start_with_a_drive_letter() {
+    let diagnostics = Diagnostics::parse(DXC);
+    assert_eq!(
+        diagnostics.items[2].location,
+        Some(location("C:\\dev\\common.hlsli", 4, Some(1), None))
+    );
+    assert_eq!(diagnostics.items[3].severity, Severity::Note);
+    assert_eq!(diagnostics.items[3].location, None);
+}
+
+#[test]
+fn warnings_only_output_has_no_errors() {
+    let mut diagnostics = Diagnostics::parse(
+        "shaders.hlsl(31,17-31): warning X3206: implicit truncation of vector type\n\
+         shaders.hlsl:40:5: warning: unused variable 'tint' [-Wunused-variable]\n",
+    );
+    assert_eq!(diagnostics.items.len(), 2);
+    assert!(diagnostics.has_warnings());
+    assert!(!diagnostics.has_errors());
+    assert!(diagnostics.unparsed.is_empty());
+
+    diagnostics.promote_warnings();
+    assert!(diagnostics.has_errors());
+    assert!(!diagnostics.has_warnings());
+}
+
+#[test]
+fn unparseable_text_is_kept_as_raw_lines() {
+    let text = "internal compiler error, unexpected exit\n\nwarning\nfoo.hlsl(abc): error X1: nope\n  ^ not a caret line\n";
+    let diagnostics = Diagnostics::parse(text);
+    assert!(diagnostics.items.is_empty(), "{diagnostics:#?}");
+    assert_eq!(
+        diagnostics.unparsed,
+        [
+            "internal compiler error, unexpected exit",
+            "warning",
+            "foo.hlsl(abc): error X1: nope",
+            "  ^ not a caret line",
+        ]
+    );
+    assert!(!diagnostics.is_empty());
+    assert!(Diagnostics::parse("\n\n").is_empty());
+}
@@ -0,0 +1,35 @@
+use shader_diagnostics::Diagnostics;
+
+#[test]
+fn diagnostics_render_with_their_source_line() {
+    let source = "a\nb\n\tfloat4 x = colr;\n";
+    let diagnostics = Diagnostics::parse(
+        "shaders.hlsl(3,13-17): error X3004: undeclared identifier 'colr'\n\
+         other.hlsl(1,1): warning X1: w\n\
+         error: bare\n\
+         compilation failed",
+    );
+    let rendered = diagnostics.render(|file| (file == "shaders.hlsl").then_some(source));
+    assert_eq!(
+        rendered,
+        "error[X3004]: undeclared identifier 'colr'\n \
+         --> shaders.hlsl:3:13\n  \
+         |\n\
+         3 | \tfloat4 x = colr;\n  \
+         | \t           ^^^^\n\
+         \n\
+         warning[X1]: w\n \
+         --> other.hlsl:1:1\n\
+         \n\
+         error: bare\n\
+         \n\
+         compilation failed"
+    );
+}
+
+#[test]
+fn carets_are_clamped_to_the_line() {
+    let diagnostics = Diagnostics::parse("shaders.hlsl:1:5: error: past the end");
+    let rendered = diagnostics.render(|_| Some("abcdef"));
+    assert!(rendered.ends_with("1 | abcdef\n  |     ^"), "{rendered}");
+}
@@ -0,0 +1,136 @@
+//! Vertex input layouts derived from the vertex struct, so that element offsets, formats and the
+//! stride cannot drift from the data that is uploaded.
+//!
+//! ```
+//! use vertex_layout::VertexFormat;
+//! use vertex_layout::VertexLayout;
+//!
+//! #[repr(C)]
+//! #[derive(VertexLayout)]
+//! pub struct Vertex {
+//!     pub position: [f32; 3],
+//!     #[semantic("COLOR")]
+//!     pub color: [f32; 4],
+//! }
+//!
+//! assert_eq!(Vertex::STRIDE, 28);
+//! assert_eq!(Vertex::ELEMENTS[1].semantic, c"COLOR");
+//! assert_eq!(Vertex::ELEMENTS[1].format, VertexFormat::R32G32B32A32Float);
+//! assert_eq!(Vertex::ELEMENTS[1].offset, 12);
+//! ```
+//!
+//! The elements are plain data rather than `D3D12_INPUT_ELEMENT_DESC`s so that samples on
+//! different `windows` versions and Direct3D versions can share them.
+
+use std::ffi::CStr;
+
+pub use vertex_layout_derive::VertexLayout;
+
+/// A vertex type whose input layout is known at compile time.
+pub trait VertexLayout: Sized {
+    /// One element per field, in declaration order.
+    const ELEMENTS: &'static [VertexElement];
+    /// Bytes between consecutive vertices in a vertex buffer.
+    const STRIDE: u32 = std::mem::size_of::<Self>() as u32;
+}
+
+/// One input element, as in `D3D12_INPUT_ELEMENT_DESC` or `D3D11_INPUT_ELEMENT_DESC` for
+/// per-vertex data in slot 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VertexElement {
+    /// Nul-terminated so it can be passed to the API as a `PCSTR`.
+    pub semantic: &'static CStr,
+    pub semantic_index: u32,
+    pub format: VertexFormat,
+    /// Byte offset of the field within the vertex.
+    pub offset: u32,
+}
+
+/// The `DXGI_FORMAT`s vertex fields map to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VertexFormat {
+    R32Float,
+    R32G32Float,
+    R32G32B32Float,
+    R32G32B32A32Float,
+    R32Uint,
+    R32G32Uint,
+    R32G32B32Uint,
+    R32G32B32A32Uint,
+    R32Sint,
+    R32G32Sint,
+    R32G32B32Sint,
+    R32G32B32A32Sint,
+    R8G8B8A8Unorm,
+}
+
+impl VertexFormat {
+    /// The `DXGI_FORMAT` value.
+    pub const fn raw(self) -> i32 {
+        match self {
+            Self::R32G32B32A32Float => 2,
+            Self::R32G32B32A32Uint => 3,
+            Self::R32G32B32A32Sint => 4,
+            Self::R32G32B32Float => 6,
+            Self::R32G32B32Uint => 7,
+            Self::R32G32B32Sint => 8,
+            Self::R32G32Float => 16,
+            Self::R32G32Uint => 17,
+            Self::R32G32Sint => 18,
+            Self::R8G8B8A8Unorm => 28,
+            Self::R32Float => 41,
+            Self::R32Uint => 42,
+            Self::R32Sint => 43,
+        }
+    }
+
+    /// Bytes per element.
+    pub const fn size(self) -> u32 {
+        match self {
+            Self::R32Float | Self::R32Uint | Self::R32Sint | Self::R8G8B8A8Unorm => 4,
+            Self::R32G32Float | Self::R32G32Uint | Self::R32G32Sint => 8,
+            Self::R32G32B32Float | Self::R32G32B32Uint | Self::R32G32B32Sint => 12,
+            Self::R32G32B32A32Float | Self::R32G32B32A32Uint | Self::R32G32B32A32Sint => 16,
+        }
+    }
+}
+
+/// A field type with a vertex format.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` cannot be used as a vertex field",
+    label = "no vertex format for this type",
+    note = "vertex fields must be f32, u32 or i32 scalars or arrays of up to four, or [u8; 4]"
+)]
+pub trait VertexAttribute {
+    const FORMAT: VertexFormat;
+}
+
+macro_rules! vertex_attributes {
+    ($($ty:ty => $format:ident),* $(,)?) => {
+        $(
+            impl VertexAttribute for $ty {
+                const FORMAT: VertexFormat = VertexFormat::$format;
+            }
+        )*
+    };
+}
+
+vertex_attributes! {
+    f32 => R32Float,
+    [f32; 1] => R32Float,
+    [f32; 2] => R32G32Float,
+    [f32; 3] => R32G32B32Float,
+    [f32; 4] => R32G32B32A32Float,
+    u32 => R32Uint,
+    [u32; 1] => R32Uint,
+    [u32; 2] => R32G32Uint,
+    [u32; 3] => R32G32B32Uint,
+    [u32; 4] => R32G32B32A32Uint,
+    i32 => R32Sint,
+    [i32; 1] => R32Sint,
+    [i32; 2] => R32G32Sint,
+    [i32; 3] => R32G32B32Sint,
+    [i32; 4] => R32G32B32A32Sint,
+    // Packed colours, normalised to 0..1 in the shader.
+    [u8; 4] => R8G8B8A8Unorm,
+}
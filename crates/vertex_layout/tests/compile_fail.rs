@@ -0,0 +1,4 @@
+#[test]
+fn derive_rejects_unsupported_structs() {
+    trybuild::TestCases::new().compile_fail("tests/ui/*.rs");
+}
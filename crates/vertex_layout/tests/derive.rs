@@ -0,0 +1,101 @@
+use std::mem::offset_of;
+use std::mem::size_of;
+use vertex_layout::VertexAttribute;
+use vertex_layout::VertexFormat;
+use vertex_layout::VertexLayout;
+
+#[repr(C)]
+#[derive(VertexLayout)]
+struct Vertex {
+    position: [f32; 3],
+    #[semantic("COLOR")]
+    color: [f32; 4],
+}
+
+#[repr(C, align(16))]
+#[derive(VertexLayout)]
+struct Mixed {
+    position: [f32; 2],
+    #[semantic("TEXCOORD", 1)]
+    uv: [f32; 2],
+    #[semantic("TEXCOORD", 2)]
+    lightmap_uv: [f32; 2],
+    packed_color: [u8; 4],
+    id: u32,
+    weight: f32,
+    bone: [i32; 4],
+}
+
+#[test]
+fn offsets_match_offset_of() {
+    let offsets: Vec<u32> = Mixed::ELEMENTS
+        .iter()
+        .map(|element| element.offset)
+        .collect();
+    let expected = [
+        offset_of!(Mixed, position),
+        offset_of!(Mixed, uv),
+        offset_of!(Mixed, lightmap_uv),
+        offset_of!(Mixed, packed_color),
+        offset_of!(Mixed, id),
+        offset_of!(Mixed, weight),
+        offset_of!(Mixed, bone),
+    ]
+    .map(|offset| offset as u32);
+    assert_eq!(offsets, expected);
+    assert_eq!(Vertex::ELEMENTS[1].offset, offset_of!(Vertex, color) as u32);
+}
+
+#[test]
+fn formats_follow_field_types() {
+    let formats: Vec<VertexFormat> = Mixed::ELEMENTS
+        .iter()
+        .map(|element| element.format)
+        .collect();
+    assert_eq!(
+        formats,
+        [
+            <[f32; 2]>::FORMAT,
+            <[f32; 2]>::FORMAT,
+            <[f32; 2]>::FORMAT,
+            VertexFormat::R8G8B8A8Unorm,
+            VertexFormat::R32Uint,
+            VertexFormat::R32Float,
+            VertexFormat::R32G32B32A32Sint,
+        ]
+    );
+    assert_eq!(Vertex::ELEMENTS[0].format, VertexFormat::R32G32B32Float);
+    assert_eq!(Vertex::ELEMENTS[0].format.raw(), 6);
+    for element in Mixed::ELEMENTS {
+        assert!(element.offset + element.format.size() <= Mixed::STRIDE);
+    }
+}
+
+#[test]
+fn semantics_and_indices_come_from_the_attribute_or_the_field_name() {
+    let semantics: Vec<_> = Mixed::ELEMENTS
+        .iter()
+        .map(|element| (element.semantic.to_str().unwrap(), element.semantic_index))
+        .collect();
+    assert_eq!(
+        semantics,
+        [
+            ("POSITION", 0),
+            ("TEXCOORD", 1),
+            ("TEXCOORD", 2),
+            ("PACKED_COLOR", 0),
+            ("ID", 0),
+            ("WEIGHT", 0),
+            ("BONE", 0),
+        ]
+    );
+    assert_eq!(Vertex::ELEMENTS[1].semantic, c"COLOR");
+}
+
+#[test]
+fn stride_is_the_struct_size() {
+    assert_eq!(Vertex::STRIDE, 28);
+    assert_eq!(Vertex::STRIDE, size_of::<Vertex>() as u32);
+    assert_eq!(Mixed::STRIDE, size_of::<Mixed>() as u32);
+    assert_eq!(Mixed::STRIDE, 64);
+}
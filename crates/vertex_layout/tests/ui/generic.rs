@@ -0,0 +1,9 @@
+use vertex_layout::VertexLayout;
+
+#[repr(C)]
+#[derive(VertexLayout)]
+struct Vertex<T> {
+    position: T,
+}
+
+fn main() {}
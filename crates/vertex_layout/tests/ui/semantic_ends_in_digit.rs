@@ -0,0 +1,10 @@
+use vertex_layout::VertexLayout;
+
+#[repr(C)]
+#[derive(VertexLayout)]
+struct Vertex {
+    #[semantic("TEXCOORD1")]
+    uv: [f32; 2],
+}
+
+fn main() {}
@@ -0,0 +1,156 @@
+//! `#[derive(VertexLayout)]`; see the `vertex_layout` crate.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use std::ffi::CString;
+use syn::Data;
+use syn::DeriveInput;
+use syn::Field;
+use syn::Fields;
+use syn::LitCStr;
+use syn::LitInt;
+use syn::LitStr;
+use syn::Token;
+use syn::parse::ParseStream;
+use syn::parse_macro_input;
+
+/// Derive `vertex_layout::VertexLayout` for a `#[repr(C)]` struct with named fields.
+///
+/// Every field becomes one input element at its `offset_of!` offset, with the format of its type.
+/// `#[semantic("COLOR")]` or `#[semantic("TEXCOORD", 1)]` sets the semantic name and index; a
+/// field without one uses its name in upper case and index 0.
+#[proc_macro_derive(VertexLayout, attributes(semantic))]
+pub fn derive_vertex_layout(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let ident = &input.ident;
+    if !is_repr_c(input)? {
+        return Err(syn::Error::new_spanned(
+            ident,
+            "VertexLayout requires #[repr(C)] so that field offsets follow declaration order",
+        ));
+    }
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.generics,
+            "VertexLayout cannot be derived for generic structs",
+        ));
+    }
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            ident,
+            "VertexLayout can only be derived for structs",
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new_spanned(
+            &data.fields,
+            "VertexLayout requires named fields",
+        ));
+    };
+
+    let elements = fields
+        .named
+        .iter()
+        .map(|field| {
+            let name = field.ident.as_ref().expect("named fields have identifiers");
+            let ty = &field.ty;
+            let (semantic, semantic_index) = semantic(field)?;
+            Ok(quote! {
+                ::vertex_layout::VertexElement {
+                    semantic: #semantic,
+                    semantic_index: #semantic_index,
+                    format: <#ty as ::vertex_layout::VertexAttribute>::FORMAT,
+                    offset: ::core::mem::offset_of!(#ident, #name) as u32,
+                }
+            })
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    Ok(quote! {
+        impl ::vertex_layout::VertexLayout for #ident {
+            const ELEMENTS: &'static [::vertex_layout::VertexElement] = &[#(#elements),*];
+        }
+    })
+}
+
+fn is_repr_c(input: &DeriveInput) -> syn::Result<bool> {
+    let mut repr_c = false;
+    for attribute in input
+        .attrs
+        .iter()
+        .filter(|attribute| attribute.path().is_ident("repr"))
+    {
+        attribute.parse_nested_meta(|meta| {
+            if meta.path.is_ident("C") {
+                repr_c = true;
+            } else if meta.input.peek(syn::token::Paren) {
+                // `align(16)` or `packed(2)`.
+                let arguments;
+                syn::parenthesized!(arguments in meta.input);
+                arguments.parse::<TokenStream2>()?;
+            }
+            Ok(())
+        })?;
+    }
+    Ok(repr_c)
+}
+
+/// The field's semantic name as a C string literal, and its semantic index.
+fn semantic(field: &Field) -> syn::Result<(LitCStr, u32)> {
+    let name = field.ident.as_ref().expect("named fields have identifiers");
+    let mut semantics = field
+        .attrs
+        .iter()
+        .filter(|attribute| attribute.path().is_ident("semantic"));
+    let (semantic, index) = match semantics.next() {
+        Some(attribute) => {
+            if let Some(duplicate) = semantics.next() {
+                return Err(syn::Error::new_spanned(
+                    duplicate,
+                    "a field can only have one #[semantic]",
+                ));
+            }
+            attribute.parse_args_with(|input: ParseStream| {
+                let semantic: LitStr = input.parse()?;
+                let index = if input.is_empty() {
+                    0
+                } else {
+                    input.parse::<Token![,]>()?;
+                    input.parse::<LitInt>()?.base10_parse()?
+                };
+                Ok((semantic, index))
+            })?
+        }
+        None => (
+            LitStr::new(&name.to_string().to_uppercase(), name.span()),
+            0,
+        ),
+    };
+
+    let value = semantic.value();
+    if value.is_empty()
+        || !value
+            .chars()
+            .all(|character| character.is_ascii_alphanumeric() || character == '_')
+    {
+        return Err(syn::Error::new_spanned(
+            &semantic,
+            "semantic names must be non-empty and contain only ASCII letters, digits and underscores",
+        ));
+    }
+    if value.ends_with(|character: char| character.is_ascii_digit()) {
+        return Err(syn::Error::new_spanned(
+            &semantic,
+            "semantic names cannot end in a digit; pass the index separately, as in #[semantic(\"TEXCOORD\", 1)]",
+        ));
+    }
+    let value = CString::new(value).expect("semantic names contain no nul bytes");
+    Ok((LitCStr::new(&value, semantic.span()), index))
+}
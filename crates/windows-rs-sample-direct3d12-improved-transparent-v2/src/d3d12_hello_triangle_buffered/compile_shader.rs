@@ -1,3 +1,4 @@
+use shader_diagnostics::Diagnostics;
 use tracing::error;
 use tracing::info;
 use windows::core::*;
@@ -28,21 +29,30 @@ pub fn compile_shader(
         )
     };
 
+    // Warnings come back in the error blob even when compilation succeeds.
+    let diagnostics = error_blob
+        .map(|error| unsafe {
+            Diagnostics::parse_bytes(std::slice::from_raw_parts(
+                error.GetBufferPointer() as *const u8,
+                error.GetBufferSize(),
+            ))
+        })
+        .unwrap_or_default();
+    diagnostics.emit();
+
     if let Err(e) = result {
         error!("Shader compilation failed: {:?}", e);
-        if let Some(error) = error_blob {
-            let error_msg = unsafe {
-                String::from_utf8_lossy(std::slice::from_raw_parts(
-                    error.GetBufferPointer() as *const u8,
-                    error.GetBufferSize(),
-                ))
-            };
+        if !diagnostics.is_empty() {
+            // Without an include handler every message is about this file.
+            let source = std::fs::read_to_string(hlsl_path.to_string()).ok();
             // Use from_utf8_lossy for safe display of potentially non-UTF8 PCSTR
             let entry_point_str = unsafe { String::from_utf8_lossy(entry_point.as_bytes()) };
             let target_str = unsafe { String::from_utf8_lossy(target.as_bytes()) };
             error!(
-                "Shader Compile Error ({} {}): {}",
-                entry_point_str, target_str, error_msg
+                "Shader Compile Error ({} {}):\n{}",
+                entry_point_str,
+                target_str,
+                diagnostics.render(|_| source.as_deref())
             );
         }
         Err(e)
@@ -13,6 +13,7 @@ pub mod sample_bind_to_window;
 pub mod sample_new;
 pub mod sample_on_destroy;
 pub mod sample_render;
+pub mod timeline;
 pub mod transition_barrier;
 pub mod wait_for_gpu;
 pub mod wait_for_gpu_idle;
@@ -1,39 +1,82 @@
 use windows::core::*;
-use windows::Win32::System::Threading::*;
 
 use super::resources::Resources;
+use super::timeline::FenceTimeline;
+use super::timeline::Timeline;
+
 // Prepare to render the next frame.
 pub fn move_to_next_frame(resources: &mut Resources) -> Result<()> {
-    // Schedule a Signal command in the queue for the *current* frame.
-    let current_frame_index = resources.frame_index as usize;
-    let fence_value_to_signal = resources.fence_values[current_frame_index];
-    unsafe {
-        resources
-            .command_queue
-            .Signal(&resources.fence, fence_value_to_signal)?
+    let mut timeline = FenceTimeline {
+        command_queue: &resources.command_queue,
+        fence: &resources.fence,
+        fence_event: resources.fence_event,
     };
+    let current_frame_index = resources.frame_index as usize;
 
     // Update the frame index to the next buffer in the swap chain.
-    resources.frame_index = unsafe { resources.swap_chain.GetCurrentBackBufferIndex() };
-    let next_frame_index = resources.frame_index as usize;
+    let next_frame_index = unsafe { resources.swap_chain.GetCurrentBackBufferIndex() };
+    advance_frame(
+        &mut timeline,
+        &mut resources.fence_values,
+        current_frame_index,
+        next_frame_index as usize,
+    )?;
+    resources.frame_index = next_frame_index;
+
+    Ok(())
+}
+
+pub fn advance_frame(
+    timeline: &mut impl Timeline,
+    fence_values: &mut [u64],
+    current_frame_index: usize,
+    next_frame_index: usize,
+) -> Result<()> {
+    // Schedule a Signal command in the queue for the *current* frame.
+    let fence_value_to_signal = fence_values[current_frame_index];
+    timeline.signal(fence_value_to_signal)?;
 
     // Check if the next frame's command allocator is ready (i.e., GPU finished using it).
     // We check against the fence value that *will be* signaled when the work for
     // that frame index *last time* it was used is complete.
-    let fence_value_to_check = resources.fence_values[next_frame_index];
-    if unsafe { resources.fence.GetCompletedValue() } < fence_value_to_check {
-        unsafe {
-            resources
-                .fence
-                .SetEventOnCompletion(fence_value_to_check, resources.fence_event)?;
-            WaitForSingleObjectEx(resources.fence_event, INFINITE, false);
-        }
-    }
+    timeline.wait_until(fence_values[next_frame_index])?;
 
     // Set the fence value for the *next* time we render to this frame index.
     // This value will be signaled by the command queue when the commands we are
     // *about* to record for this frame index have finished executing.
-    resources.fence_values[next_frame_index] = fence_value_to_signal + 1;
+    fence_values[next_frame_index] = fence_value_to_signal + 1;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::d3d12_hello_triangle_buffered::timeline::SimulatedTimeline;
+
+    #[test]
+    fn command_allocators_are_not_reused_while_in_flight() {
+        let mut timeline = SimulatedTimeline::new(vec![5, 1, 9, 3]);
+        // The fence values `bind_to_window` leaves behind after its first wait for the GPU.
+        let mut fence_values = [1, 0];
+        let mut submitted_fence_values = [None; 2];
+        let mut frame_index = 0;
+        for _ in 0..100 {
+            timeline.advance(2);
+            submitted_fence_values[frame_index] = Some(fence_values[frame_index]);
+
+            let next_frame_index = (frame_index + 1) % fence_values.len();
+            advance_frame(
+                &mut timeline,
+                &mut fence_values,
+                frame_index,
+                next_frame_index,
+            )
+            .unwrap();
+            if let Some(fence_value) = submitted_fence_values[next_frame_index] {
+                assert!(timeline.completed_value() >= fence_value);
+            }
+            frame_index = next_frame_index;
+        }
+    }
+}
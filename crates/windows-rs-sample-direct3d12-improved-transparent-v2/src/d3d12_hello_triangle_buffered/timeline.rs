@@ -0,0 +1,119 @@
+use windows::core::*;
+use windows::Win32::Foundation::*;
+use windows::Win32::Graphics::Direct3D12::*;
+use windows::Win32::System::Threading::*;
+
+// A monotonically increasing series of values the GPU signals as it finishes work.
+pub trait Timeline {
+    // Schedule `value` to be signalled once the work submitted so far has finished.
+    fn signal(&mut self, value: u64) -> Result<()>;
+    // The highest value signalled so far.
+    fn completed_value(&self) -> u64;
+    // Block until `value` has been signalled.
+    fn wait_until(&mut self, value: u64) -> Result<()>;
+}
+
+// The fence and event in `Resources`, signalled on its command queue.
+pub struct FenceTimeline<'a> {
+    pub command_queue: &'a ID3D12CommandQueue,
+    pub fence: &'a ID3D12Fence,
+    pub fence_event: HANDLE,
+}
+
+impl Timeline for FenceTimeline<'_> {
+    fn signal(&mut self, value: u64) -> Result<()> {
+        unsafe { self.command_queue.Signal(self.fence, value) }
+    }
+
+    fn completed_value(&self) -> u64 {
+        unsafe { self.fence.GetCompletedValue() }
+    }
+
+    fn wait_until(&mut self, value: u64) -> Result<()> {
+        if self.completed_value() < value {
+            unsafe {
+                self.fence.SetEventOnCompletion(value, self.fence_event)?;
+                WaitForSingleObjectEx(self.fence_event, INFINITE, false);
+            }
+        }
+        Ok(())
+    }
+}
+
+// A queue that finishes each signal `execution_delays` ticks after the one before it, so
+// frame pacing can be checked without a device.
+#[cfg(test)]
+pub struct SimulatedTimeline {
+    now: u64,
+    queue_idle_at: u64,
+    completed: u64,
+    execution_delays: Vec<u64>,
+    signal_count: usize,
+    // Signalled values and the tick each one completes at, in submission order.
+    pending: std::collections::VecDeque<(u64, u64)>,
+}
+
+#[cfg(test)]
+impl SimulatedTimeline {
+    pub fn new(execution_delays: Vec<u64>) -> Self {
+        Self {
+            now: 0,
+            queue_idle_at: 0,
+            completed: 0,
+            execution_delays,
+            signal_count: 0,
+            pending: std::collections::VecDeque::new(),
+        }
+    }
+
+    // Let `ticks` of CPU time pass without waiting on the GPU.
+    pub fn advance(&mut self, ticks: u64) {
+        self.now += ticks;
+        self.retire();
+    }
+
+    fn retire(&mut self) {
+        while let Some(&(value, completes_at)) = self.pending.front() {
+            if completes_at > self.now {
+                break;
+            }
+            self.completed = self.completed.max(value);
+            self.pending.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+impl Timeline for SimulatedTimeline {
+    fn signal(&mut self, value: u64) -> Result<()> {
+        let last_value = self
+            .pending
+            .back()
+            .map_or(self.completed, |&(value, _)| value);
+        assert!(value > last_value, "signalled {value} after {last_value}");
+
+        let delay = self.execution_delays[self.signal_count % self.execution_delays.len()];
+        self.signal_count += 1;
+        self.queue_idle_at = self.queue_idle_at.max(self.now) + delay;
+        self.pending.push_back((value, self.queue_idle_at));
+        self.retire();
+        Ok(())
+    }
+
+    fn completed_value(&self) -> u64 {
+        self.completed
+    }
+
+    fn wait_until(&mut self, value: u64) -> Result<()> {
+        if self.completed < value {
+            let &(_, completes_at) = self
+                .pending
+                .iter()
+                .find(|&&(pending, _)| pending >= value)
+                .expect("waited for a value that was never signalled");
+            self.now = self.now.max(completes_at);
+            self.retire();
+        }
+        Ok(())
+    }
+}
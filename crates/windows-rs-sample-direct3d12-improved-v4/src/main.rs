@@ -3,6 +3,7 @@ pub mod create_window;
 pub mod my_behaviour;
 pub mod my_behaviour_bind;
 pub mod my_window_data;
+pub mod timeline;
 pub mod window_class;
 pub mod windy_error;
 pub mod windy_window_class_id;
@@ -1,5 +1,7 @@
 use crate::my_behaviour_bind::Resources;
 use crate::my_window_data::MyWindowData;
+use crate::timeline::FenceTimeline;
+use crate::timeline::Timeline;
 use crate::window_class::WindowClass;
 use crate::windy_error::MyResult;
 use std::mem::ManuallyDrop;
@@ -14,7 +16,6 @@ use windows::Win32::Graphics::Direct3D12::ID3D12Device;
 use windows::Win32::Graphics::Direct3D12::*;
 use windows::Win32::Graphics::Dxgi::IDXGIFactory4;
 use windows::Win32::Graphics::Dxgi::*;
-use windows::Win32::System::Threading::*;
 use windows::Win32::UI::WindowsAndMessaging::*;
 
 pub struct MyBehaviour {}
@@ -146,37 +147,43 @@ fn populate_command_list(resources: &mut Resources) -> MyResult<()> {
 
 // Prepare to render the next frame.
 fn move_to_next_frame(resources: &mut Resources) -> MyResult<()> {
-    // Schedule a Signal command in the queue for the *current* frame.
-    let current_fence_value = resources.fence_values[resources.frame_index as usize];
-    unsafe {
-        resources
-            .command_queue
-            .Signal(&resources.fence, current_fence_value)?
+    let mut timeline = FenceTimeline {
+        command_queue: &resources.command_queue,
+        fence: &resources.fence,
+        fence_event: resources.fence_event,
     };
-
+    let current_frame_index = resources.frame_index as usize;
     // Update the frame index to the next frame.
-    resources.frame_index = unsafe { resources.swap_chain.GetCurrentBackBufferIndex() };
+    let next_frame_index = unsafe { resources.swap_chain.GetCurrentBackBufferIndex() };
+    advance_frame(
+        &mut timeline,
+        &mut resources.fence_values,
+        current_frame_index,
+        next_frame_index as usize,
+    )?;
+    resources.frame_index = next_frame_index;
+    Ok(())
+}
+
+fn advance_frame(
+    timeline: &mut impl Timeline,
+    fence_values: &mut [u64],
+    current_frame_index: usize,
+    next_frame_index: usize,
+) -> MyResult<()> {
+    // Schedule a Signal command in the queue for the *current* frame.
+    let current_fence_value = fence_values[current_frame_index];
+    timeline.signal(current_fence_value)?;
 
     // Check if the next frame is ready to be rendered to yet.
     // We need to wait if the GPU hasn't finished processing the commands
     // associated with this frame index yet (indicated by its fence value).
-    if unsafe { resources.fence.GetCompletedValue() }
-        < resources.fence_values[resources.frame_index as usize]
-    {
-        unsafe {
-            resources.fence.SetEventOnCompletion(
-                resources.fence_values[resources.frame_index as usize],
-                resources.fence_event,
-            )?;
-            // Consider adding a timeout
-            WaitForSingleObjectEx(resources.fence_event, INFINITE, false);
-        }
-    }
+    timeline.wait_until(fence_values[next_frame_index])?;
 
     // Set the fence value for the *next* frame (which is now the current frame index).
     // This value will be signaled by the command queue when the commands for this frame
     // (which we are about to record) have finished executing on the GPU.
-    resources.fence_values[resources.frame_index as usize] = current_fence_value + 1;
+    fence_values[next_frame_index] = current_fence_value + 1;
 
     Ok(())
 }
@@ -200,3 +207,35 @@ fn transition_barrier(
         },
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timeline::SimulatedTimeline;
+
+    #[test]
+    fn command_allocators_are_not_reused_while_in_flight() {
+        let mut timeline = SimulatedTimeline::new(vec![5, 1, 9, 3]);
+        // The fence values `bind` leaves behind after its first wait for the GPU.
+        let mut fence_values = [1, 0];
+        let mut submitted_fence_values = [None; 2];
+        let mut frame_index = 0;
+        for _ in 0..100 {
+            timeline.advance(2);
+            submitted_fence_values[frame_index] = Some(fence_values[frame_index]);
+
+            let next_frame_index = (frame_index + 1) % fence_values.len();
+            advance_frame(
+                &mut timeline,
+                &mut fence_values,
+                frame_index,
+                next_frame_index,
+            )
+            .unwrap();
+            if let Some(fence_value) = submitted_fence_values[next_frame_index] {
+                assert!(timeline.completed_value() >= fence_value);
+            }
+            frame_index = next_frame_index;
+        }
+    }
+}
@@ -0,0 +1,24 @@
+use crate::cli::adapters::list::AdaptersListArgs;
+use eyre::Result;
+use facet::Facet;
+use figue::{self as args};
+
+#[derive(Facet, Debug)]
+pub struct AdaptersArgs {
+    #[facet(args::subcommand)]
+    pub command: AdaptersCommand,
+}
+
+#[derive(Facet, Debug)]
+#[repr(u8)]
+pub enum AdaptersCommand {
+    List(AdaptersListArgs),
+}
+
+impl AdaptersArgs {
+    pub async fn invoke(self) -> Result<()> {
+        match self.command {
+            AdaptersCommand::List(args) => args.invoke().await,
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use crate::graphics::adapter_selection::GpuPreference;
+use crate::graphics::adapter_selection::describe_adapters;
+use crate::graphics::list_adapters;
+use facet::Facet;
+use figue::{self as args};
+
+#[derive(Facet, Debug)]
+#[facet(rename_all = "kebab-case")]
+pub struct AdaptersListArgs {
+    /// Print the adapters as JSON.
+    #[facet(args::named, default)]
+    pub json: bool,
+
+    /// List in this order instead: high-performance or minimum-power.
+    #[facet(args::named)]
+    pub gpu_preference: Option<String>,
+}
+
+impl AdaptersListArgs {
+    pub async fn invoke(self) -> eyre::Result<()> {
+        let preference: GpuPreference = self
+            .gpu_preference
+            .as_deref()
+            .map(str::parse)
+            .transpose()?
+            .unwrap_or_default();
+        let adapters = list_adapters(preference)?;
+
+        if self.json {
+            let json = facet_json::to_string_pretty(&adapters)
+                .map_err(|error| eyre::eyre!("Failed to serialize adapters: {error}"))?;
+            println!("{json}");
+        } else {
+            print!("{}", describe_adapters(&adapters));
+        }
+        Ok(())
+    }
+}
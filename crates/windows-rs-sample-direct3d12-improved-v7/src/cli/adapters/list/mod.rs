@@ -0,0 +1,3 @@
+mod adapters_list_cli;
+
+pub use adapters_list_cli::*;
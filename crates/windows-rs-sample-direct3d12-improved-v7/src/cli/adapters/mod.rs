@@ -0,0 +1,4 @@
+mod adapters_cli;
+pub mod list;
+
+pub use adapters_cli::*;
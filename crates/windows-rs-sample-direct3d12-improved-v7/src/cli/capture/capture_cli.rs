@@ -0,0 +1,24 @@
+use crate::cli::capture::inspect::CaptureInspectArgs;
+use eyre::Result;
+use facet::Facet;
+use figue::{self as args};
+
+#[derive(Facet, Debug)]
+pub struct CaptureArgs {
+    #[facet(args::subcommand)]
+    pub command: CaptureCommand,
+}
+
+#[derive(Facet, Debug)]
+#[repr(u8)]
+pub enum CaptureCommand {
+    Inspect(CaptureInspectArgs),
+}
+
+impl CaptureArgs {
+    pub async fn invoke(self) -> Result<()> {
+        match self.command {
+            CaptureCommand::Inspect(args) => args.invoke().await,
+        }
+    }
+}
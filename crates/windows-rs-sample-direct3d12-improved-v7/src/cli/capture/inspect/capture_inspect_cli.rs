@@ -0,0 +1,37 @@
+use facet::Facet;
+use figue::{self as args};
+use frame_capture::read_capture;
+use std::path::Path;
+
+#[derive(Facet, Debug)]
+#[facet(rename_all = "kebab-case")]
+pub struct CaptureInspectArgs {
+    /// Capture file to print.
+    #[facet(args::positional)]
+    pub file: String,
+
+    /// Print the differences against this capture instead.
+    #[facet(args::named)]
+    pub diff: Option<String>,
+}
+
+impl CaptureInspectArgs {
+    pub async fn invoke(self) -> eyre::Result<()> {
+        let capture = read_capture(Path::new(&self.file))?;
+
+        let Some(other) = self.diff else {
+            print!("{}", capture.describe());
+            return Ok(());
+        };
+
+        let other = read_capture(Path::new(&other))?;
+        let differences = capture.diff(&other);
+        if differences.is_empty() {
+            println!("captures are identical");
+        }
+        for difference in differences {
+            println!("{difference}");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,3 @@
+mod capture_inspect_cli;
+
+pub use capture_inspect_cli::*;
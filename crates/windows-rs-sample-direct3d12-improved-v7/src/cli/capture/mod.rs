@@ -0,0 +1,4 @@
+mod capture_cli;
+pub mod inspect;
+
+pub use capture_cli::*;
@@ -0,0 +1,64 @@
+use crate::graphics::adapter_selection::AdapterOptions;
+use crate::graphics::query_device_caps;
+use device_caps::DeviceCaps;
+use eyre::Context;
+use facet::Facet;
+use figue::{self as args};
+
+#[derive(Facet, Debug)]
+#[facet(rename_all = "kebab-case")]
+pub struct DeviceCapsArgs {
+    /// Print the report as JSON.
+    #[facet(args::named, default)]
+    pub json: bool,
+
+    /// Print the differences against a JSON report from another machine instead.
+    #[facet(args::named)]
+    pub compare: Option<String>,
+
+    #[facet(args::named, default)]
+    pub warp: bool,
+
+    /// Adapter to query: an index from `adapters list`, a LUID or part of the adapter name.
+    #[facet(args::named)]
+    pub adapter: Option<String>,
+
+    /// Adapter enumeration order: high-performance or minimum-power.
+    #[facet(args::named)]
+    pub gpu_preference: Option<String>,
+
+    /// Minimum feature level the device must support: 11_0 (default), 11_1, 12_0, 12_1 or 12_2.
+    #[facet(args::named)]
+    pub feature_level: Option<String>,
+}
+
+impl DeviceCapsArgs {
+    pub async fn invoke(self) -> eyre::Result<()> {
+        if self.warp && self.adapter.is_some() {
+            eyre::bail!("--warp and --adapter cannot be combined");
+        }
+        let adapter = AdapterOptions::parse(
+            self.adapter.as_deref(),
+            self.gpu_preference.as_deref(),
+            self.feature_level.as_deref(),
+        )?;
+        let caps = query_device_caps(self.warp, &adapter)?;
+
+        if let Some(other) = self.compare {
+            let json = std::fs::read_to_string(&other)
+                .wrap_err_with(|| format!("Failed to read device caps report {other}"))?;
+            let differences = caps.diff(&DeviceCaps::from_json(&json)?);
+            if differences.is_empty() {
+                println!("device caps are identical");
+            }
+            for difference in differences {
+                println!("{difference}");
+            }
+        } else if self.json {
+            println!("{}", caps.to_json()?);
+        } else {
+            print!("{}", caps.describe());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,3 @@
+mod device_caps_cli;
+
+pub use device_caps_cli::*;
@@ -0,0 +1,24 @@
+use crate::cli::device::caps::DeviceCapsArgs;
+use eyre::Result;
+use facet::Facet;
+use figue::{self as args};
+
+#[derive(Facet, Debug)]
+pub struct DeviceArgs {
+    #[facet(args::subcommand)]
+    pub command: DeviceCommand,
+}
+
+#[derive(Facet, Debug)]
+#[repr(u8)]
+pub enum DeviceCommand {
+    Caps(DeviceCapsArgs),
+}
+
+impl DeviceArgs {
+    pub async fn invoke(self) -> Result<()> {
+        match self.command {
+            DeviceCommand::Caps(args) => args.invoke().await,
+        }
+    }
+}
@@ -0,0 +1,4 @@
+pub mod caps;
+mod device_cli;
+
+pub use device_cli::*;
@@ -12,4 +12,21 @@ pub struct GlobalArgs {
 
     #[facet(args::named)]
     pub log_file: Option<String>,
+
+    /// Enable the D3D12 debug layer in release builds; debug builds always enable it.
+    #[facet(args::named, default)]
+    pub debug_layer: bool,
+
+    /// Enable GPU-based validation, which implies --debug-layer and slows rendering considerably.
+    #[facet(args::named, default)]
+    pub gpu_validation: bool,
+
+    /// Comma-separated debug layer message IDs to drop, e.g. "1328,1356".
+    #[facet(args::named)]
+    pub mute_debug_messages: Option<String>,
+
+    /// Break into the debugger on debug layer messages of this severity or worse: corruption,
+    /// error, warning, info or message.
+    #[facet(args::named)]
+    pub break_on_debug_severity: Option<String>,
 }
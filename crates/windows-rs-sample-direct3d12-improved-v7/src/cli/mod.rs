@@ -1,7 +1,15 @@
+pub mod adapters;
+pub mod capture;
+pub mod device;
 pub mod global_args;
+pub mod shader;
 pub mod window;
 
+use crate::cli::adapters::AdaptersArgs;
+use crate::cli::capture::CaptureArgs;
+use crate::cli::device::DeviceArgs;
 use crate::cli::global_args::GlobalArgs;
+use crate::cli::shader::ShaderArgs;
 use crate::cli::window::WindowArgs;
 use eyre::Context;
 use facet::Facet;
@@ -35,12 +43,20 @@ impl Cli {
 #[repr(u8)]
 pub enum Command {
     Window(WindowArgs),
+    Capture(CaptureArgs),
+    Adapters(AdaptersArgs),
+    Device(DeviceArgs),
+    Shader(ShaderArgs),
 }
 
 impl Command {
     pub async fn invoke(self) -> eyre::Result<()> {
         match self {
             Self::Window(args) => args.invoke().await,
+            Self::Capture(args) => args.invoke().await,
+            Self::Adapters(args) => args.invoke().await,
+            Self::Device(args) => args.invoke().await,
+            Self::Shader(args) => args.invoke().await,
         }
     }
 }
@@ -0,0 +1,3 @@
+mod shader_inspect_cli;
+
+pub use shader_inspect_cli::*;
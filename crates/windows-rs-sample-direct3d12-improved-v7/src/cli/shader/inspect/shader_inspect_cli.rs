@@ -0,0 +1,48 @@
+use crate::graphics::scene::Vertex;
+use dxbc::DxbcContainer;
+use eyre::Context;
+use facet::Facet;
+use figue::{self as args};
+use vertex_layout::VertexLayout;
+
+#[derive(Facet, Debug)]
+#[facet(rename_all = "kebab-case")]
+pub struct ShaderInspectArgs {
+    /// Compiled shader (`.cso`) to print.
+    #[facet(args::positional)]
+    pub file: String,
+
+    /// Also check the input signature against the renderer's vertex layout.
+    #[facet(args::named, default)]
+    pub check_vertex_layout: bool,
+}
+
+impl ShaderInspectArgs {
+    pub async fn invoke(self) -> eyre::Result<()> {
+        let bytes =
+            std::fs::read(&self.file).wrap_err_with(|| format!("Failed to read {}", self.file))?;
+        let container = DxbcContainer::parse(&bytes)
+            .wrap_err_with(|| format!("Failed to parse {}", self.file))?;
+        print!("{}", container.describe());
+
+        if !self.check_vertex_layout {
+            return Ok(());
+        }
+        let Some(signature) = &container.input_signature else {
+            eyre::bail!("{} has no input signature", self.file);
+        };
+        let problems = signature.check_input_layout(Vertex::ELEMENTS);
+        if problems.is_empty() {
+            println!("input signature matches the vertex layout");
+            return Ok(());
+        }
+        for problem in &problems {
+            println!("{problem}");
+        }
+        eyre::bail!(
+            "{} does not match the vertex layout ({} problems)",
+            self.file,
+            problems.len()
+        );
+    }
+}
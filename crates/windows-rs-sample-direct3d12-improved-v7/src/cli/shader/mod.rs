@@ -0,0 +1,4 @@
+pub mod inspect;
+mod shader_cli;
+
+pub use shader_cli::*;
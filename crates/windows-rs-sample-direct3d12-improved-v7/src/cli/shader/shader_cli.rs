@@ -0,0 +1,24 @@
+use crate::cli::shader::inspect::ShaderInspectArgs;
+use eyre::Result;
+use facet::Facet;
+use figue::{self as args};
+
+#[derive(Facet, Debug)]
+pub struct ShaderArgs {
+    #[facet(args::subcommand)]
+    pub command: ShaderCommand,
+}
+
+#[derive(Facet, Debug)]
+#[repr(u8)]
+pub enum ShaderCommand {
+    Inspect(ShaderInspectArgs),
+}
+
+impl ShaderArgs {
+    pub async fn invoke(self) -> Result<()> {
+        match self.command {
+            ShaderCommand::Inspect(args) => args.invoke().await,
+        }
+    }
+}
@@ -1,6 +1,11 @@
 use crate::graphics::TransparentTriangleOptions;
+use crate::graphics::adapter_selection::AdapterOptions;
+use crate::graphics::shader_source::ShaderSource;
 use facet::Facet;
 use figue::{self as args};
+use frame_capture::CaptureOptions;
+use pipeline_cache::default_pipeline_cache_path;
+use pipeline_description::Define;
 
 #[derive(Facet, Debug)]
 #[facet(rename_all = "kebab-case")]
@@ -14,19 +19,87 @@ pub struct WindowShowArgs {
     #[facet(args::named, default)]
     pub warp: bool,
 
+    /// Adapter to render on: an index from `adapters list`, a LUID such as 0x0000000000012345,
+    /// or part of the adapter name.
+    #[facet(args::named)]
+    pub adapter: Option<String>,
+
+    /// Adapter enumeration order: high-performance or minimum-power.
+    #[facet(args::named)]
+    pub gpu_preference: Option<String>,
+
+    /// Minimum feature level the device must support: 11_0 (default), 11_1, 12_0, 12_1 or 12_2.
+    #[facet(args::named)]
+    pub feature_level: Option<String>,
+
     #[facet(args::named)]
     pub title: Option<String>,
+
+    /// Write the first N submitted frames to capture files.
+    #[facet(args::named)]
+    pub capture_frames: Option<u32>,
+
+    /// Directory for capture files; defaults to `captures`.
+    #[facet(args::named)]
+    pub capture_dir: Option<String>,
+
+    /// Rebuild pipeline states instead of reusing the on-disk pipeline cache.
+    #[facet(args::named, default)]
+    pub no_pipeline_cache: bool,
+
+    /// Rebuild the pipeline when its shader or pipeline file changes, without restarting. Needs
+    /// a shader directory.
+    #[facet(args::named, default)]
+    pub hot_reload: bool,
+
+    /// Read the pipeline file and shaders from this directory instead of the ones built into the
+    /// executable; defaults to the D3D12_SAMPLE_SHADER_DIR environment variable.
+    #[facet(args::named)]
+    pub shader_dir: Option<String>,
+
+    /// Set one of the pipeline's permutation keys, as KEY=VALUE or KEY for KEY=1. Repeatable.
+    #[facet(args::named, default)]
+    pub define: Vec<String>,
+
+    /// Fail when the shader compiler reports warnings instead of logging them.
+    #[facet(args::named, default)]
+    pub warnings_as_errors: bool,
 }
 
 impl WindowShowArgs {
     pub async fn invoke(self) -> eyre::Result<()> {
+        if self.warp && self.adapter.is_some() {
+            eyre::bail!("--warp and --adapter cannot be combined");
+        }
+        let defines = self
+            .define
+            .iter()
+            .map(|define| define.parse())
+            .collect::<eyre::Result<Vec<Define>>>()?;
+        let adapter = AdapterOptions::parse(
+            self.adapter.as_deref(),
+            self.gpu_preference.as_deref(),
+            self.feature_level.as_deref(),
+        )?;
+
         crate::graphics::run(TransparentTriangleOptions {
             width: self.width.unwrap_or(1280),
             height: self.height.unwrap_or(720),
             use_warp_device: self.warp,
+            adapter,
             title: self
                 .title
                 .unwrap_or_else(|| "D3D12 transparent triangle v6".to_string()),
+            capture: self.capture_frames.map(|frames| CaptureOptions {
+                frames,
+                directory: self.capture_dir.unwrap_or_else(|| "captures".to_string()).into(),
+            }),
+            pipeline_cache: (!self.no_pipeline_cache)
+                .then(|| default_pipeline_cache_path(env!("CARGO_PKG_NAME"))),
+            hot_reload: self.hot_reload,
+            shader_source: ShaderSource::from_environment(self.shader_dir.map(Into::into)),
+            defines,
+            warnings_as_errors: self.warnings_as_errors,
         })
     }
 }
@@ -0,0 +1,531 @@
+use device_caps::AdapterInfo;
+use std::fmt::Write as _;
+use std::str::FromStr;
+
+/// How `--adapter` picks an adapter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AdapterSelector {
+    Index(u32),
+    Luid(u64),
+    /// Case-insensitive substring of the description; the first match in preference order wins.
+    Name(String),
+}
+
+impl AdapterSelector {
+    pub fn matches(&self, adapter: &AdapterInfo) -> bool {
+        match self {
+            Self::Index(index) => adapter.index == *index,
+            Self::Luid(luid) => adapter.luid == *luid,
+            Self::Name(name) => adapter
+                .description
+                .to_lowercase()
+                .contains(&name.to_lowercase()),
+        }
+    }
+}
+
+impl FromStr for AdapterSelector {
+    type Err = eyre::Report;
+
+    /// `0x`-prefixed hex is a LUID, a plain number is an index and anything else is a name.
+    fn from_str(value: &str) -> eyre::Result<Self> {
+        let value = value.trim();
+        if let Some(hex) = value
+            .strip_prefix("0x")
+            .or_else(|| value.strip_prefix("0X"))
+        {
+            return u64::from_str_radix(hex, 16)
+                .map(Self::Luid)
+                .map_err(|error| eyre::eyre!("Invalid adapter LUID {value:?}: {error}"));
+        }
+        if let Ok(index) = value.parse() {
+            return Ok(Self::Index(index));
+        }
+        if value.is_empty() {
+            eyre::bail!("Adapter selector is empty");
+        }
+        Ok(Self::Name(value.to_string()))
+    }
+}
+
+impl std::fmt::Display for AdapterSelector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Index(index) => write!(f, "index {index}"),
+            Self::Luid(luid) => write!(f, "LUID {luid:#018x}"),
+            Self::Name(name) => write!(f, "name {name:?}"),
+        }
+    }
+}
+
+/// The order adapters are enumerated in, as in `DXGI_GPU_PREFERENCE`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GpuPreference {
+    /// `EnumAdapters1` order, which puts the adapter driving the primary display first.
+    #[default]
+    Unspecified,
+    HighPerformance,
+    MinimumPower,
+}
+
+impl FromStr for GpuPreference {
+    type Err = eyre::Report;
+
+    fn from_str(value: &str) -> eyre::Result<Self> {
+        match value {
+            "unspecified" => Ok(Self::Unspecified),
+            "high-performance" => Ok(Self::HighPerformance),
+            "minimum-power" => Ok(Self::MinimumPower),
+            _ => eyre::bail!(
+                "Unknown GPU preference {value:?}; expected high-performance, minimum-power or unspecified"
+            ),
+        }
+    }
+}
+
+/// The minimum Direct3D feature level the device must support.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FeatureLevel {
+    #[default]
+    Level11_0,
+    Level11_1,
+    Level12_0,
+    Level12_1,
+    Level12_2,
+}
+
+impl FeatureLevel {
+    pub const ALL: [Self; 5] = [
+        Self::Level11_0,
+        Self::Level11_1,
+        Self::Level12_0,
+        Self::Level12_1,
+        Self::Level12_2,
+    ];
+
+    pub fn from_raw(raw: i32) -> Option<Self> {
+        Self::ALL.into_iter().find(|level| level.raw() == raw)
+    }
+
+    /// The `D3D_FEATURE_LEVEL` value.
+    pub fn raw(self) -> i32 {
+        match self {
+            Self::Level11_0 => 0xb000,
+            Self::Level11_1 => 0xb100,
+            Self::Level12_0 => 0xc000,
+            Self::Level12_1 => 0xc100,
+            Self::Level12_2 => 0xc200,
+        }
+    }
+}
+
+impl FromStr for FeatureLevel {
+    type Err = eyre::Report;
+
+    fn from_str(value: &str) -> eyre::Result<Self> {
+        match value.replace('.', "_").as_str() {
+            "11_0" => Ok(Self::Level11_0),
+            "11_1" => Ok(Self::Level11_1),
+            "12_0" => Ok(Self::Level12_0),
+            "12_1" => Ok(Self::Level12_1),
+            "12_2" => Ok(Self::Level12_2),
+            _ => eyre::bail!(
+                "Unknown feature level {value:?}; expected 11_0, 11_1, 12_0, 12_1 or 12_2"
+            ),
+        }
+    }
+}
+
+impl std::fmt::Display for FeatureLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Level11_0 => "11_0",
+            Self::Level11_1 => "11_1",
+            Self::Level12_0 => "12_0",
+            Self::Level12_1 => "12_1",
+            Self::Level12_2 => "12_2",
+        })
+    }
+}
+
+/// Which adapter a device should be created on.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AdapterOptions {
+    /// `None` picks the first hardware adapter that supports `minimum_feature_level`.
+    pub selector: Option<AdapterSelector>,
+    pub gpu_preference: GpuPreference,
+    pub minimum_feature_level: FeatureLevel,
+}
+
+impl AdapterOptions {
+    /// Parse the `--adapter`, `--gpu-preference` and `--feature-level` arguments.
+    pub fn parse(
+        adapter: Option<&str>,
+        gpu_preference: Option<&str>,
+        feature_level: Option<&str>,
+    ) -> eyre::Result<Self> {
+        Ok(Self {
+            selector: adapter.map(str::parse).transpose()?,
+            gpu_preference: gpu_preference
+                .map(str::parse)
+                .transpose()?
+                .unwrap_or_default(),
+            minimum_feature_level: feature_level
+                .map(str::parse)
+                .transpose()?
+                .unwrap_or_default(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AdapterSelectionError {
+    NoMatch {
+        selector: AdapterSelector,
+        available: Vec<String>,
+    },
+    /// Every adapter the selector matched is below the feature level.
+    Unsupported {
+        adapter: String,
+        feature_level: FeatureLevel,
+    },
+    /// No hardware adapter supports the feature level; `rejected` says why each adapter was
+    /// passed over.
+    NoHardwareAdapter {
+        feature_level: FeatureLevel,
+        rejected: Vec<(String, RejectionReason)>,
+    },
+}
+
+/// Why selection without a selector passed over an adapter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RejectionReason {
+    /// Software adapters such as WARP are only used when asked for with `--warp` or `--adapter`.
+    Software,
+    BelowFeatureLevel,
+}
+
+impl std::error::Error for AdapterSelectionError {}
+
+impl std::fmt::Display for AdapterSelectionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoMatch {
+                selector,
+                available,
+            } => {
+                write!(f, "No adapter matches {selector}; available adapters:")?;
+                for adapter in available {
+                    write!(f, "\n  {adapter}")?;
+                }
+                Ok(())
+            }
+            Self::Unsupported {
+                adapter,
+                feature_level,
+            } => write!(
+                f,
+                "Adapter {adapter} does not support feature level {feature_level}"
+            ),
+            Self::NoHardwareAdapter {
+                feature_level,
+                rejected,
+            } => {
+                write!(
+                    f,
+                    "No hardware adapter supports feature level {feature_level}"
+                )?;
+                if rejected.is_empty() {
+                    return f.write_str("; no adapters were enumerated");
+                }
+                f.write_str("; rejected adapters:")?;
+                for (adapter, reason) in rejected {
+                    match reason {
+                        RejectionReason::Software => write!(
+                            f,
+                            "\n  {adapter}: software adapter, pass --warp or --adapter to use it"
+                        )?,
+                        RejectionReason::BelowFeatureLevel => write!(
+                            f,
+                            "\n  {adapter}: does not support feature level {feature_level}"
+                        )?,
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Pick the adapter to create a device on. `adapters` is in preference order and
+/// `supports_feature_level` reports whether a device can be created on an adapter at a level.
+///
+/// An explicit selector may pick a software adapter; without one, software adapters are skipped
+/// and never used as a fallback.
+pub fn select_adapter<'a>(
+    adapters: &'a [AdapterInfo],
+    selector: Option<&AdapterSelector>,
+    feature_level: FeatureLevel,
+    mut supports_feature_level: impl FnMut(&AdapterInfo, FeatureLevel) -> bool,
+) -> Result<&'a AdapterInfo, AdapterSelectionError> {
+    let Some(selector) = selector else {
+        let mut rejected = Vec::new();
+        for adapter in adapters {
+            if adapter.software {
+                rejected.push((adapter_summary(adapter), RejectionReason::Software));
+            } else if supports_feature_level(adapter, feature_level) {
+                return Ok(adapter);
+            } else {
+                rejected.push((adapter_summary(adapter), RejectionReason::BelowFeatureLevel));
+            }
+        }
+        return Err(AdapterSelectionError::NoHardwareAdapter {
+            feature_level,
+            rejected,
+        });
+    };
+
+    let mut matching = adapters
+        .iter()
+        .filter(|adapter| selector.matches(adapter))
+        .peekable();
+    let Some(first) = matching.peek().copied() else {
+        return Err(AdapterSelectionError::NoMatch {
+            selector: selector.clone(),
+            available: adapters.iter().map(adapter_summary).collect(),
+        });
+    };
+    matching
+        .find(|adapter| supports_feature_level(adapter, feature_level))
+        .ok_or_else(|| AdapterSelectionError::Unsupported {
+            adapter: adapter_summary(first),
+            feature_level,
+        })
+}
+
+fn adapter_summary(adapter: &AdapterInfo) -> String {
+    format!(
+        "{}: {} (LUID {:#018x}, {})",
+        adapter.index,
+        adapter.description,
+        adapter.luid,
+        adapter.flags()
+    )
+}
+
+/// A table of `adapters` for `adapters list`.
+pub fn describe_adapters(adapters: &[AdapterInfo]) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "{:<5}  {:<18}  {:<6}  {:<6}  {:>10}  {:<16}  description",
+        "index", "luid", "vendor", "device", "memory", "flags"
+    );
+    for adapter in adapters {
+        let _ = writeln!(
+            out,
+            "{:<5}  {:#018x}  {:#06x}  {:#06x}  {:>6} MiB  {:<16}  {}",
+            adapter.index,
+            adapter.luid,
+            adapter.vendor_id,
+            adapter.device_id,
+            adapter.dedicated_video_memory / (1024 * 1024),
+            adapter.flags(),
+            adapter.description
+        );
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GIB: u64 = 1024 * 1024 * 1024;
+
+    fn adapter(index: u32, description: &str, software: bool) -> AdapterInfo {
+        AdapterInfo {
+            index,
+            description: description.to_string(),
+            vendor_id: if software { 0x1414 } else { 0x10de },
+            device_id: 0x2684,
+            luid: 0x1_0000_0000 + u64::from(index),
+            dedicated_video_memory: if software { 0 } else { 8 * GIB },
+            software,
+            remote: false,
+        }
+    }
+
+    /// WARP first, as `EnumAdapters1` lists it when no display is attached to a GPU.
+    fn adapters() -> Vec<AdapterInfo> {
+        vec![
+            adapter(0, "Microsoft Basic Render Driver", true),
+            adapter(1, "Intel(R) UHD Graphics 630", false),
+            adapter(2, "NVIDIA GeForce RTX 4090", false),
+        ]
+    }
+
+    fn any_level(_: &AdapterInfo, _: FeatureLevel) -> bool {
+        true
+    }
+
+    #[test]
+    fn selectors_parse_as_luid_index_or_name() {
+        assert_eq!(
+            "1".parse::<AdapterSelector>().unwrap(),
+            AdapterSelector::Index(1)
+        );
+        assert_eq!(
+            "0x0000000100000002".parse::<AdapterSelector>().unwrap(),
+            AdapterSelector::Luid(0x1_0000_0002)
+        );
+        assert_eq!(
+            " NVIDIA ".parse::<AdapterSelector>().unwrap(),
+            AdapterSelector::Name("NVIDIA".to_string())
+        );
+        assert!("0xzz".parse::<AdapterSelector>().is_err());
+        assert!("".parse::<AdapterSelector>().is_err());
+    }
+
+    #[test]
+    fn options_parse_preference_and_feature_level() {
+        let options = AdapterOptions::parse(None, Some("high-performance"), Some("12.1")).unwrap();
+        assert_eq!(options.selector, None);
+        assert_eq!(options.gpu_preference, GpuPreference::HighPerformance);
+        assert_eq!(options.minimum_feature_level, FeatureLevel::Level12_1);
+        assert_eq!(
+            AdapterOptions::parse(None, None, None).unwrap(),
+            AdapterOptions::default()
+        );
+        assert!(AdapterOptions::parse(None, Some("fast"), None).is_err());
+        assert!(AdapterOptions::parse(None, None, Some("13_0")).is_err());
+    }
+
+    #[test]
+    fn feature_levels_round_trip_through_raw_values() {
+        for level in FeatureLevel::ALL {
+            assert_eq!(FeatureLevel::from_raw(level.raw()), Some(level));
+            assert_eq!(level.to_string().parse::<FeatureLevel>().unwrap(), level);
+        }
+        assert_eq!(FeatureLevel::from_raw(0x9300), None);
+    }
+
+    #[test]
+    fn first_capable_hardware_adapter_in_preference_order_wins() {
+        let adapters = adapters();
+        let selected = select_adapter(&adapters, None, FeatureLevel::Level11_0, any_level).unwrap();
+        assert_eq!(selected.index, 1);
+
+        // Adapters below the feature level are skipped.
+        let only_nvidia = |adapter: &AdapterInfo, level: FeatureLevel| {
+            adapter.index == 2 && level <= FeatureLevel::Level12_1
+        };
+        let selected =
+            select_adapter(&adapters, None, FeatureLevel::Level12_0, only_nvidia).unwrap();
+        assert_eq!(selected.index, 2);
+    }
+
+    #[test]
+    fn warp_is_never_a_silent_fallback() {
+        let adapters = adapters();
+        let software_only = |adapter: &AdapterInfo, _: FeatureLevel| adapter.software;
+        let error =
+            select_adapter(&adapters, None, FeatureLevel::Level12_0, software_only).unwrap_err();
+        assert_eq!(
+            error,
+            AdapterSelectionError::NoHardwareAdapter {
+                feature_level: FeatureLevel::Level12_0,
+                rejected: vec![
+                    (
+                        "0: Microsoft Basic Render Driver (LUID 0x0000000100000000, software)"
+                            .to_string(),
+                        RejectionReason::Software,
+                    ),
+                    (
+                        "1: Intel(R) UHD Graphics 630 (LUID 0x0000000100000001, hardware)"
+                            .to_string(),
+                        RejectionReason::BelowFeatureLevel,
+                    ),
+                    (
+                        "2: NVIDIA GeForce RTX 4090 (LUID 0x0000000100000002, hardware)"
+                            .to_string(),
+                        RejectionReason::BelowFeatureLevel,
+                    ),
+                ],
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "No hardware adapter supports feature level 12_0; rejected adapters:\n  \
+             0: Microsoft Basic Render Driver (LUID 0x0000000100000000, software): software adapter, pass --warp or --adapter to use it\n  \
+             1: Intel(R) UHD Graphics 630 (LUID 0x0000000100000001, hardware): does not support feature level 12_0\n  \
+             2: NVIDIA GeForce RTX 4090 (LUID 0x0000000100000002, hardware): does not support feature level 12_0"
+        );
+
+        assert_eq!(
+            select_adapter(&[], None, FeatureLevel::Level11_0, any_level)
+                .unwrap_err()
+                .to_string(),
+            "No hardware adapter supports feature level 11_0; no adapters were enumerated"
+        );
+    }
+
+    #[test]
+    fn explicit_selectors_may_pick_any_adapter() {
+        let adapters = adapters();
+        let select = |selector: AdapterSelector| {
+            select_adapter(
+                &adapters,
+                Some(&selector),
+                FeatureLevel::Level11_0,
+                any_level,
+            )
+            .map(|adapter| adapter.index)
+        };
+        assert_eq!(select(AdapterSelector::Index(0)), Ok(0));
+        assert_eq!(select(AdapterSelector::Luid(0x1_0000_0002)), Ok(2));
+        assert_eq!(select(AdapterSelector::Name("rtx".to_string())), Ok(2));
+        // The first match in preference order.
+        assert_eq!(select(AdapterSelector::Name("r".to_string())), Ok(0));
+
+        let Err(AdapterSelectionError::NoMatch { available, .. }) =
+            select(AdapterSelector::Name("radeon".to_string()))
+        else {
+            panic!("an unknown name should not match");
+        };
+        assert_eq!(available.len(), 3);
+        assert_eq!(
+            available[1],
+            "1: Intel(R) UHD Graphics 630 (LUID 0x0000000100000001, hardware)"
+        );
+    }
+
+    #[test]
+    fn explicit_selector_below_the_feature_level_is_an_error() {
+        let adapters = adapters();
+        let error = select_adapter(
+            &adapters,
+            Some(&AdapterSelector::Index(1)),
+            FeatureLevel::Level12_0,
+            |adapter, _| adapter.index == 2,
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Adapter 1: Intel(R) UHD Graphics 630 (LUID 0x0000000100000001, hardware) does not support feature level 12_0"
+        );
+    }
+
+    #[test]
+    fn adapters_are_listed_as_a_table() {
+        let table = describe_adapters(&adapters()[..2]);
+        let lines: Vec<_> = table.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("index  luid "));
+        assert_eq!(
+            lines[2],
+            "1      0x0000000100000001  0x10de  0x2684    8192 MiB  hardware          Intel(R) UHD Graphics 630"
+        );
+        assert!(lines[1].contains("     0 MiB  software  "));
+    }
+}
@@ -0,0 +1,180 @@
+use command_ir::DRAW_CONSTANT_COUNT;
+
+/// Constant buffer views must start on a 256-byte boundary.
+pub const CONSTANT_BUFFER_ALIGNMENT: usize = 256;
+
+/// Per-draw root constants (`b0` in `shaders.hlsl`).
+///
+/// Vertex positions are multiplied by `position_scale`, then offset by the late-latched cursor
+/// position weighted by `cursor_weight`. Scene geometry authored in NDC uses
+/// [`DrawConstants::IDENTITY`]; cursor geometry authored in pixels relative to the cursor uses
+/// [`DrawConstants::cursor`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DrawConstants {
+    pub position_scale: [f32; 2],
+    pub cursor_weight: f32,
+}
+
+impl DrawConstants {
+    pub const IDENTITY: Self = Self {
+        position_scale: [1.0, 1.0],
+        cursor_weight: 0.0,
+    };
+
+    pub fn cursor(width: f32, height: f32) -> Self {
+        Self {
+            position_scale: pixel_to_ndc_scale(width, height),
+            cursor_weight: 1.0,
+        }
+    }
+
+    pub fn to_root_constants(&self) -> [u32; DRAW_CONSTANT_COUNT as usize] {
+        [
+            self.position_scale[0].to_bits(),
+            self.position_scale[1].to_bits(),
+            self.cursor_weight.to_bits(),
+            0,
+        ]
+    }
+
+    /// Where a vertex at `position` ends up in NDC, mirroring `VSMain`.
+    pub fn apply(&self, position: [f32; 2], cursor: &CursorConstants) -> [f32; 2] {
+        [
+            position[0] * self.position_scale[0] + cursor.cursor_ndc[0] * self.cursor_weight,
+            position[1] * self.position_scale[1] + cursor.cursor_ndc[1] * self.cursor_weight,
+        ]
+    }
+}
+
+/// The late-latched cursor constant buffer (`b1` in `shaders.hlsl`), written just before the
+/// frame's command list is submitted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CursorConstants {
+    pub cursor_ndc: [f32; 2],
+    pub visible: bool,
+}
+
+impl CursorConstants {
+    pub const HIDDEN: Self = Self {
+        cursor_ndc: [0.0, 0.0],
+        visible: false,
+    };
+
+    /// `cursor_position` is in client pixels, or `None` when the cursor is outside the window.
+    pub fn from_cursor_position(
+        width: f32,
+        height: f32,
+        cursor_position: Option<(f32, f32)>,
+    ) -> Self {
+        match cursor_position {
+            Some((x, y)) => Self {
+                cursor_ndc: pixel_to_ndc(width, height, x, y),
+                visible: true,
+            },
+            None => Self::HIDDEN,
+        }
+    }
+
+    /// The HLSL layout: `float2 cursor_ndc; float cursor_visible; float padding;`.
+    pub fn to_shader_layout(&self) -> [f32; 4] {
+        [
+            self.cursor_ndc[0],
+            self.cursor_ndc[1],
+            if self.visible { 1.0 } else { 0.0 },
+            0.0,
+        ]
+    }
+}
+
+/// Scale that turns a pixel-space offset (y down) into an NDC offset (y up).
+pub fn pixel_to_ndc_scale(width: f32, height: f32) -> [f32; 2] {
+    [2.0 / width, -2.0 / height]
+}
+
+pub fn pixel_to_ndc(width: f32, height: f32, x: f32, y: f32) -> [f32; 2] {
+    [(x / width) * 2.0 - 1.0, 1.0 - (y / height) * 2.0]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window_corners_map_to_ndc_corners() {
+        assert_eq!(pixel_to_ndc(800.0, 600.0, 0.0, 0.0), [-1.0, 1.0]);
+        assert_eq!(pixel_to_ndc(800.0, 600.0, 800.0, 0.0), [1.0, 1.0]);
+        assert_eq!(pixel_to_ndc(800.0, 600.0, 0.0, 600.0), [-1.0, -1.0]);
+        assert_eq!(pixel_to_ndc(800.0, 600.0, 800.0, 600.0), [1.0, -1.0]);
+        assert_eq!(pixel_to_ndc(800.0, 600.0, 400.0, 300.0), [0.0, 0.0]);
+    }
+
+    #[test]
+    fn non_square_windows_scale_each_axis_separately() {
+        assert_eq!(pixel_to_ndc(1000.0, 250.0, 250.0, 50.0), [-0.5, 0.6]);
+        assert_eq!(pixel_to_ndc_scale(1000.0, 250.0), [0.002, -0.008]);
+        // A pixel offset is the difference between two pixel positions.
+        let [x0, y0] = pixel_to_ndc(1000.0, 250.0, 100.0, 100.0);
+        let [x1, y1] = pixel_to_ndc(1000.0, 250.0, 110.0, 125.0);
+        let [scale_x, scale_y] = pixel_to_ndc_scale(1000.0, 250.0);
+        assert!((x1 - x0 - 10.0 * scale_x).abs() < 1e-6);
+        assert!((y1 - y0 - 25.0 * scale_y).abs() < 1e-6);
+    }
+
+    #[test]
+    fn root_constants_are_the_float_bits_in_shader_order() {
+        let constants = DrawConstants {
+            position_scale: [0.25, -0.5],
+            cursor_weight: 1.0,
+        };
+        assert_eq!(
+            constants.to_root_constants(),
+            [
+                0.25_f32.to_bits(),
+                (-0.5_f32).to_bits(),
+                1.0_f32.to_bits(),
+                0
+            ]
+        );
+        assert_eq!(
+            DrawConstants::IDENTITY.to_root_constants(),
+            [1.0_f32.to_bits(), 1.0_f32.to_bits(), 0, 0]
+        );
+    }
+
+    #[test]
+    fn identity_leaves_scene_geometry_in_place() {
+        let cursor = CursorConstants::from_cursor_position(800.0, 600.0, Some((200.0, 150.0)));
+        assert_eq!(
+            DrawConstants::IDENTITY.apply([0.5, -0.25], &cursor),
+            [0.5, -0.25]
+        );
+    }
+
+    #[test]
+    fn cursor_geometry_follows_the_cursor() {
+        let cursor = CursorConstants::from_cursor_position(800.0, 600.0, Some((200.0, 150.0)));
+        let draw = DrawConstants::cursor(800.0, 600.0);
+        assert_eq!(draw.apply([0.0, 0.0], &cursor), [-0.5, 0.5]);
+        // Twenty pixels right of and ten pixels below the cursor.
+        let [x, y] = draw.apply([20.0, 10.0], &cursor);
+        let [expected_x, expected_y] = pixel_to_ndc(800.0, 600.0, 220.0, 160.0);
+        assert!((x - expected_x).abs() < 1e-6);
+        assert!((y - expected_y).abs() < 1e-6);
+    }
+
+    #[test]
+    fn cursor_constants_pack_into_the_hlsl_layout() {
+        let cursor = CursorConstants::from_cursor_position(640.0, 480.0, Some((480.0, 120.0)));
+        assert_eq!(cursor.cursor_ndc, [0.5, 0.5]);
+        assert!(cursor.visible);
+        assert_eq!(cursor.to_shader_layout(), [0.5, 0.5, 1.0, 0.0]);
+        assert_eq!(std::mem::size_of_val(&cursor.to_shader_layout()), 16);
+    }
+
+    #[test]
+    fn cursor_outside_the_window_is_hidden() {
+        let cursor = CursorConstants::from_cursor_position(640.0, 480.0, None);
+        assert_eq!(cursor, CursorConstants::HIDDEN);
+        assert_eq!(cursor.to_shader_layout(), [0.0, 0.0, 0.0, 0.0]);
+    }
+}
@@ -0,0 +1,224 @@
+use crate::graphics::descriptor_allocator::CpuDescriptorHandle;
+use crate::graphics::descriptor_allocator::GpuDescriptorHandle;
+use crate::graphics::resource_state::ResourceId;
+use crate::graphics::resource_state::ResourceStateTracker;
+use crate::graphics::resource_state::StateTransition;
+use command_ir::Command;
+use command_ir::PipelineId;
+use command_ir::ResourceState;
+use windows::Win32::Foundation::RECT;
+use windows::Win32::Graphics::Direct3D::D3D_PRIMITIVE_TOPOLOGY_TRIANGLELIST;
+use windows::Win32::Graphics::Direct3D12::*;
+
+/// The D3D12 objects the command IR refers to by index or id.
+pub struct D3d12Bindings<'a> {
+    pub render_targets: &'a [ID3D12Resource],
+    /// The tracker ids of `render_targets`, index for index.
+    pub render_target_ids: &'a [ResourceId],
+    pub rtv_handles: &'a [D3D12_CPU_DESCRIPTOR_HANDLE],
+    pub root_signature: &'a ID3D12RootSignature,
+    pub pipeline_state: &'a ID3D12PipelineState,
+    pub vertex_buffer: &'a ID3D12Resource,
+}
+
+/// Whether the recorded commands ended with a present, which has to be issued on the swap chain
+/// after the command list is executed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PresentRequest {
+    None,
+    Present,
+}
+
+/// Translate `commands` into calls on an open command list.
+///
+/// State requirements are accumulated and issued as a single `ResourceBarrier` call before the
+/// next command that is not a state change.
+pub fn record_commands(
+    command_list: &ID3D12GraphicsCommandList,
+    bindings: &D3d12Bindings<'_>,
+    states: &mut ResourceStateTracker<ResourceState>,
+    commands: &[Command],
+) -> eyre::Result<PresentRequest> {
+    let mut present = PresentRequest::None;
+    let mut batch = Vec::new();
+
+    for command in commands {
+        if present == PresentRequest::Present {
+            eyre::bail!("Command {command:?} was recorded after Present");
+        }
+
+        if !command.is_state_change() {
+            batch.extend(states.flush());
+            issue_barriers(command_list, bindings, &batch)?;
+            batch.clear();
+        }
+
+        match command {
+            Command::RequireState { back_buffer, state } => {
+                states.require(render_target_id(bindings, *back_buffer)?, *state)?;
+            }
+            Command::Transition {
+                back_buffer,
+                before,
+                after,
+            } => {
+                // Keep hand-written transitions ordered after earlier requests.
+                batch.extend(states.flush());
+                batch.extend(states.transition(
+                    render_target_id(bindings, *back_buffer)?,
+                    *before,
+                    *after,
+                )?);
+            }
+            Command::SetViewport(viewport) => unsafe {
+                command_list.RSSetViewports(&[D3D12_VIEWPORT {
+                    TopLeftX: viewport.x,
+                    TopLeftY: viewport.y,
+                    Width: viewport.width,
+                    Height: viewport.height,
+                    MinDepth: viewport.min_depth,
+                    MaxDepth: viewport.max_depth,
+                }]);
+            },
+            Command::SetScissor(scissor) => unsafe {
+                command_list.RSSetScissorRects(&[RECT {
+                    left: scissor.left,
+                    top: scissor.top,
+                    right: scissor.right,
+                    bottom: scissor.bottom,
+                }]);
+            },
+            Command::SetRenderTarget { back_buffer } => unsafe {
+                command_list.OMSetRenderTargets(
+                    1,
+                    Some(&bindings.rtv_handles[*back_buffer]),
+                    false,
+                    None,
+                );
+            },
+            Command::Clear { back_buffer, color } => unsafe {
+                command_list.ClearRenderTargetView(bindings.rtv_handles[*back_buffer], color, None);
+            },
+            Command::BindPipeline(pipeline) => {
+                if *pipeline != PipelineId::TRANSPARENT {
+                    eyre::bail!("Unknown pipeline {pipeline:?}");
+                }
+                unsafe {
+                    command_list.SetPipelineState(bindings.pipeline_state);
+                    command_list.SetGraphicsRootSignature(bindings.root_signature);
+                    command_list.IASetPrimitiveTopology(D3D_PRIMITIVE_TOPOLOGY_TRIANGLELIST);
+                }
+            }
+            Command::BindCursorConstants { gpu_address } => unsafe {
+                command_list.SetGraphicsRootConstantBufferView(1, *gpu_address);
+            },
+            Command::SetDrawConstants(values) => unsafe {
+                command_list.SetGraphicsRoot32BitConstants(
+                    0,
+                    values.len() as u32,
+                    values.as_ptr() as *const _,
+                    0,
+                );
+            },
+            Command::BindVertexRange(range) => unsafe {
+                command_list.IASetVertexBuffers(
+                    0,
+                    Some(&[D3D12_VERTEX_BUFFER_VIEW {
+                        BufferLocation: bindings.vertex_buffer.GetGPUVirtualAddress()
+                            + range.offset_bytes,
+                        SizeInBytes: range.size_bytes,
+                        StrideInBytes: range.stride_bytes,
+                    }]),
+                );
+            },
+            Command::Draw(range) => unsafe {
+                command_list.DrawInstanced(range.count, 1, range.start, 0);
+            },
+            Command::Present => present = PresentRequest::Present,
+        }
+    }
+
+    batch.extend(states.flush());
+    issue_barriers(command_list, bindings, &batch)?;
+
+    Ok(present)
+}
+
+fn render_target_id(bindings: &D3d12Bindings<'_>, back_buffer: usize) -> eyre::Result<ResourceId> {
+    bindings
+        .render_target_ids
+        .get(back_buffer)
+        .copied()
+        .ok_or_else(|| eyre::eyre!("Back buffer {back_buffer} has no tracked resource"))
+}
+
+fn issue_barriers(
+    command_list: &ID3D12GraphicsCommandList,
+    bindings: &D3d12Bindings<'_>,
+    transitions: &[StateTransition<ResourceState>],
+) -> eyre::Result<()> {
+    if transitions.is_empty() {
+        return Ok(());
+    }
+
+    let mut barriers = Vec::with_capacity(transitions.len());
+    for transition in transitions {
+        let Some(index) = bindings
+            .render_target_ids
+            .iter()
+            .position(|id| *id == transition.resource)
+        else {
+            eyre::bail!("No D3D12 resource is bound for {:?}", transition.resource);
+        };
+        barriers.push(transition_barrier(
+            &bindings.render_targets[index],
+            d3d12_resource_state(transition.before),
+            d3d12_resource_state(transition.after),
+        ));
+    }
+
+    unsafe { command_list.ResourceBarrier(&barriers) };
+
+    // `transition_barrier` holds a reference to each resource; release them now that the
+    // barriers have been recorded.
+    for barrier in barriers {
+        let transition = std::mem::ManuallyDrop::into_inner(unsafe { barrier.Anonymous.Transition });
+        drop(std::mem::ManuallyDrop::into_inner(transition.pResource));
+    }
+
+    Ok(())
+}
+
+pub fn d3d12_resource_state(state: ResourceState) -> D3D12_RESOURCE_STATES {
+    match state {
+        ResourceState::Present => D3D12_RESOURCE_STATE_PRESENT,
+        ResourceState::RenderTarget => D3D12_RESOURCE_STATE_RENDER_TARGET,
+    }
+}
+
+pub fn d3d12_cpu_handle(handle: CpuDescriptorHandle) -> D3D12_CPU_DESCRIPTOR_HANDLE {
+    D3D12_CPU_DESCRIPTOR_HANDLE { ptr: handle.0 }
+}
+
+pub fn d3d12_gpu_handle(handle: GpuDescriptorHandle) -> D3D12_GPU_DESCRIPTOR_HANDLE {
+    D3D12_GPU_DESCRIPTOR_HANDLE { ptr: handle.0 }
+}
+
+pub fn transition_barrier(
+    resource: &ID3D12Resource,
+    before: D3D12_RESOURCE_STATES,
+    after: D3D12_RESOURCE_STATES,
+) -> D3D12_RESOURCE_BARRIER {
+    D3D12_RESOURCE_BARRIER {
+        Type: D3D12_RESOURCE_BARRIER_TYPE_TRANSITION,
+        Flags: D3D12_RESOURCE_BARRIER_FLAG_NONE,
+        Anonymous: D3D12_RESOURCE_BARRIER_0 {
+            Transition: std::mem::ManuallyDrop::new(D3D12_RESOURCE_TRANSITION_BARRIER {
+                pResource: std::mem::ManuallyDrop::new(Some(resource.clone())),
+                StateBefore: before,
+                StateAfter: after,
+                Subresource: D3D12_RESOURCE_BARRIER_ALL_SUBRESOURCES,
+            }),
+        },
+    }
+}
@@ -0,0 +1,369 @@
+use std::ops::Range;
+use std::str::FromStr;
+use std::sync::OnceLock;
+use tracing::Level;
+
+static DEBUG_LAYER_OPTIONS: OnceLock<DebugLayerOptions> = OnceLock::new();
+
+/// `D3D12_MESSAGE_SEVERITY` and `DXGI_INFO_QUEUE_MESSAGE_SEVERITY`, which share their values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageSeverity {
+    Corruption,
+    Error,
+    Warning,
+    Info,
+    Message,
+}
+
+impl MessageSeverity {
+    pub const ALL: [Self; 5] = [
+        Self::Corruption,
+        Self::Error,
+        Self::Warning,
+        Self::Info,
+        Self::Message,
+    ];
+
+    /// Unknown values are treated as plain messages.
+    pub fn from_raw(raw: i32) -> Self {
+        match raw {
+            0 => Self::Corruption,
+            1 => Self::Error,
+            2 => Self::Warning,
+            3 => Self::Info,
+            _ => Self::Message,
+        }
+    }
+
+    pub fn raw(self) -> i32 {
+        match self {
+            Self::Corruption => 0,
+            Self::Error => 1,
+            Self::Warning => 2,
+            Self::Info => 3,
+            Self::Message => 4,
+        }
+    }
+
+    /// Whether this severity is `threshold` or worse.
+    pub fn at_least(self, threshold: Self) -> bool {
+        self.raw() <= threshold.raw()
+    }
+
+    pub fn level(self) -> Level {
+        match self {
+            Self::Corruption | Self::Error => Level::ERROR,
+            Self::Warning => Level::WARN,
+            Self::Info => Level::INFO,
+            Self::Message => Level::DEBUG,
+        }
+    }
+}
+
+impl FromStr for MessageSeverity {
+    type Err = eyre::Report;
+
+    fn from_str(value: &str) -> eyre::Result<Self> {
+        match value {
+            "corruption" => Ok(Self::Corruption),
+            "error" => Ok(Self::Error),
+            "warning" => Ok(Self::Warning),
+            "info" => Ok(Self::Info),
+            "message" => Ok(Self::Message),
+            _ => eyre::bail!(
+                "Unknown message severity {value:?}; expected corruption, error, warning, info or message"
+            ),
+        }
+    }
+}
+
+impl std::fmt::Display for MessageSeverity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Corruption => "corruption",
+            Self::Error => "error",
+            Self::Warning => "warning",
+            Self::Info => "info",
+            Self::Message => "message",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageSource {
+    D3d12,
+    Dxgi,
+}
+
+impl std::fmt::Display for MessageSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::D3d12 => "D3D12",
+            Self::Dxgi => "DXGI",
+        })
+    }
+}
+
+/// The name of a `D3D12_MESSAGE_CATEGORY` or `DXGI_INFO_QUEUE_MESSAGE_CATEGORY`, which share
+/// their values.
+pub fn category_name(raw: i32) -> &'static str {
+    match raw {
+        0 => "application defined",
+        1 => "miscellaneous",
+        2 => "initialization",
+        3 => "cleanup",
+        4 => "compilation",
+        5 => "state creation",
+        6 => "state setting",
+        7 => "state getting",
+        8 => "resource manipulation",
+        9 => "execution",
+        10 => "shader",
+        _ => "unknown",
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebugMessage {
+    pub source: MessageSource,
+    pub severity: MessageSeverity,
+    pub category: i32,
+    pub id: i32,
+    pub description: String,
+}
+
+impl DebugMessage {
+    /// Forward this message as a `tracing` event.
+    pub fn emit(&self) {
+        let category = category_name(self.category);
+        macro_rules! emit_at {
+            ($level:expr) => {
+                tracing::event!(
+                    target: "debug_layer",
+                    $level,
+                    source = %self.source,
+                    severity = %self.severity,
+                    category,
+                    id = self.id,
+                    "{}",
+                    self.description
+                )
+            };
+        }
+        match self.severity.level() {
+            Level::ERROR => emit_at!(Level::ERROR),
+            Level::WARN => emit_at!(Level::WARN),
+            Level::INFO => emit_at!(Level::INFO),
+            _ => emit_at!(Level::DEBUG),
+        }
+    }
+}
+
+/// How the D3D12 debug layer is set up, from the global arguments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebugLayerOptions {
+    pub enabled: bool,
+    /// Slow; only takes effect when the layer is enabled.
+    pub gpu_based_validation: bool,
+    /// Message IDs to drop instead of forwarding.
+    pub muted_ids: Vec<i32>,
+    /// Break into the debugger on messages of this severity or worse.
+    pub break_on: Option<MessageSeverity>,
+}
+
+impl Default for DebugLayerOptions {
+    fn default() -> Self {
+        Self {
+            enabled: cfg!(debug_assertions),
+            gpu_based_validation: false,
+            muted_ids: Vec::new(),
+            break_on: None,
+        }
+    }
+}
+
+impl DebugLayerOptions {
+    /// Parse the debug layer arguments. The layer is always enabled in debug builds, and GPU-based
+    /// validation implies it.
+    pub fn parse(
+        debug_layer: bool,
+        gpu_based_validation: bool,
+        muted_ids: Option<&str>,
+        break_on: Option<&str>,
+    ) -> eyre::Result<Self> {
+        let muted_ids = muted_ids
+            .into_iter()
+            .flat_map(|ids| ids.split(','))
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .map(|id| {
+                id.parse()
+                    .map_err(|error| eyre::eyre!("Invalid debug message ID {id:?}: {error}"))
+            })
+            .collect::<eyre::Result<Vec<i32>>>()?;
+
+        Ok(Self {
+            enabled: cfg!(debug_assertions) || debug_layer || gpu_based_validation,
+            gpu_based_validation,
+            muted_ids,
+            break_on: break_on.map(str::parse).transpose()?,
+        })
+    }
+
+    pub fn forwards(&self, message: &DebugMessage) -> bool {
+        !self.muted_ids.contains(&message.id)
+    }
+
+    /// The severities the info queues should break on.
+    pub fn break_severities(&self) -> Vec<MessageSeverity> {
+        let Some(threshold) = self.break_on else {
+            return Vec::new();
+        };
+        MessageSeverity::ALL
+            .into_iter()
+            .filter(|severity| severity.at_least(threshold))
+            .collect()
+    }
+}
+
+/// Set the process-wide debug layer options; the debug layer is per process, so this is set once
+/// from the global arguments before any device is created.
+pub fn set_debug_layer_options(options: DebugLayerOptions) {
+    let _ = DEBUG_LAYER_OPTIONS.set(options);
+}
+
+pub fn debug_layer_options() -> &'static DebugLayerOptions {
+    DEBUG_LAYER_OPTIONS.get_or_init(DebugLayerOptions::default)
+}
+
+/// Tracks which messages of an info queue have been forwarded.
+///
+/// Info queues keep their messages and discard the oldest once the count limit is reached, so a
+/// message's stored index shifts as older ones are discarded. The cursor counts messages ever
+/// stored instead.
+#[derive(Debug, Clone, Default)]
+pub struct MessageCursor {
+    forwarded: u64,
+}
+
+impl MessageCursor {
+    /// Given the queue's stored and discarded counts, return the stored indices not forwarded
+    /// yet and how many were discarded before they could be, then mark them forwarded.
+    pub fn advance(&mut self, stored: u64, discarded: u64) -> (Range<u64>, u64) {
+        let total = discarded + stored;
+        let missed = discarded.saturating_sub(self.forwarded);
+        let first = self.forwarded.max(discarded).min(total);
+        self.forwarded = total;
+        (first - discarded..stored, missed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(id: i32) -> DebugMessage {
+        DebugMessage {
+            source: MessageSource::D3d12,
+            severity: MessageSeverity::Warning,
+            category: 5,
+            id,
+            description: "CreateGraphicsPipelineState: mismatched input layout".to_string(),
+        }
+    }
+
+    #[test]
+    fn severities_map_to_tracing_levels() {
+        let levels: Vec<_> = MessageSeverity::ALL
+            .into_iter()
+            .map(MessageSeverity::level)
+            .collect();
+        assert_eq!(
+            levels,
+            [
+                Level::ERROR,
+                Level::ERROR,
+                Level::WARN,
+                Level::INFO,
+                Level::DEBUG
+            ]
+        );
+    }
+
+    #[test]
+    fn severities_round_trip_through_raw_values_and_names() {
+        for severity in MessageSeverity::ALL {
+            assert_eq!(MessageSeverity::from_raw(severity.raw()), severity);
+            assert_eq!(
+                severity.to_string().parse::<MessageSeverity>().unwrap(),
+                severity
+            );
+        }
+        assert_eq!(MessageSeverity::from_raw(42), MessageSeverity::Message);
+        assert!("fatal".parse::<MessageSeverity>().is_err());
+        assert_eq!(category_name(5), "state creation");
+        assert_eq!(category_name(-1), "unknown");
+    }
+
+    #[test]
+    fn muted_ids_are_not_forwarded() {
+        let options = DebugLayerOptions::parse(false, false, Some(" 820, ,1328"), None).unwrap();
+        assert_eq!(options.muted_ids, [820, 1328]);
+        assert!(!options.forwards(&message(820)));
+        assert!(!options.forwards(&message(1328)));
+        assert!(options.forwards(&message(821)));
+        assert!(DebugLayerOptions::parse(false, false, Some("820,abc"), None).is_err());
+    }
+
+    #[test]
+    fn gpu_based_validation_enables_the_layer() {
+        let options = DebugLayerOptions::parse(false, true, None, None).unwrap();
+        assert!(options.enabled);
+        assert!(options.gpu_based_validation);
+        let options = DebugLayerOptions::parse(true, false, None, None).unwrap();
+        assert!(options.enabled);
+    }
+
+    #[test]
+    fn breaking_covers_the_threshold_and_worse() {
+        let options = DebugLayerOptions::parse(false, false, None, Some("warning")).unwrap();
+        assert_eq!(
+            options.break_severities(),
+            [
+                MessageSeverity::Corruption,
+                MessageSeverity::Error,
+                MessageSeverity::Warning
+            ]
+        );
+        assert!(DebugLayerOptions::default().break_severities().is_empty());
+        assert!(DebugLayerOptions::parse(false, false, None, Some("loud")).is_err());
+    }
+
+    #[test]
+    fn cursor_forwards_each_stored_message_once() {
+        let mut cursor = MessageCursor::default();
+        assert_eq!(cursor.advance(0, 0), (0..0, 0));
+        assert_eq!(cursor.advance(3, 0), (0..3, 0));
+        assert_eq!(cursor.advance(3, 0), (3..3, 0));
+        assert_eq!(cursor.advance(5, 0), (3..5, 0));
+    }
+
+    #[test]
+    fn cursor_follows_indices_shifted_by_discards() {
+        let mut cursor = MessageCursor::default();
+        assert_eq!(cursor.advance(3, 0), (0..3, 0));
+        // Four stored at most: two more arrived and the oldest was discarded.
+        assert_eq!(cursor.advance(4, 1), (2..4, 0));
+        assert_eq!(cursor.advance(4, 1), (4..4, 0));
+    }
+
+    #[test]
+    fn cursor_counts_messages_discarded_before_they_were_forwarded() {
+        let mut cursor = MessageCursor::default();
+        assert_eq!(cursor.advance(4, 0), (0..4, 0));
+        // Ten more arrived between polls; six were discarded without being seen.
+        assert_eq!(cursor.advance(4, 10), (0..4, 6));
+        assert_eq!(cursor.advance(4, 10), (4..4, 0));
+        assert_eq!(cursor.advance(4, 11), (3..4, 0));
+    }
+}
@@ -0,0 +1,252 @@
+use crate::graphics::ring_allocator::RingAllocator;
+
+/// A CPU descriptor handle, as in `D3D12_CPU_DESCRIPTOR_HANDLE::ptr`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CpuDescriptorHandle(pub usize);
+
+/// A GPU descriptor handle, as in `D3D12_GPU_DESCRIPTOR_HANDLE::ptr`; only shader-visible heaps
+/// have one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GpuDescriptorHandle(pub u64);
+
+/// Where a descriptor heap lives and how far apart its descriptors are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DescriptorHeapLayout {
+    pub cpu_start: CpuDescriptorHandle,
+    pub gpu_start: Option<GpuDescriptorHandle>,
+    /// `GetDescriptorHandleIncrementSize` for the heap type.
+    pub increment: u32,
+    pub capacity: u32,
+}
+
+impl DescriptorHeapLayout {
+    pub fn cpu_handle(&self, index: u32) -> CpuDescriptorHandle {
+        CpuDescriptorHandle(self.cpu_start.0 + index as usize * self.increment as usize)
+    }
+
+    pub fn gpu_handle(&self, index: u32) -> Option<GpuDescriptorHandle> {
+        self.gpu_start.map(|start| {
+            GpuDescriptorHandle(start.0 + u64::from(index) * u64::from(self.increment))
+        })
+    }
+
+    fn descriptor(&self, index: u32) -> Descriptor {
+        Descriptor {
+            index,
+            cpu: self.cpu_handle(index),
+            gpu: self.gpu_handle(index),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Descriptor {
+    /// Index of the descriptor within its heap.
+    pub index: u32,
+    pub cpu: CpuDescriptorHandle,
+    pub gpu: Option<GpuDescriptorHandle>,
+}
+
+/// Contiguous transient descriptors, suitable for a descriptor table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DescriptorRange {
+    pub first: Descriptor,
+    pub count: u32,
+    increment: u32,
+}
+
+impl DescriptorRange {
+    pub fn cpu(&self, offset: u32) -> CpuDescriptorHandle {
+        debug_assert!(offset < self.count);
+        CpuDescriptorHandle(self.first.cpu.0 + offset as usize * self.increment as usize)
+    }
+
+    pub fn gpu(&self, offset: u32) -> Option<GpuDescriptorHandle> {
+        debug_assert!(offset < self.count);
+        self.first.gpu.map(|start| {
+            GpuDescriptorHandle(start.0 + u64::from(offset) * u64::from(self.increment))
+        })
+    }
+}
+
+/// Hands out descriptors from one heap.
+///
+/// The first `persistent_capacity` descriptors are long-lived allocations recycled through a
+/// free list. The rest of the heap is a ring of transient descriptors: each frame allocates
+/// linearly, [`DescriptorAllocator::finish_frame`] tags what it used with the frame's fence
+/// value, and [`DescriptorAllocator::retire`] reclaims it once that fence has completed.
+#[derive(Debug, Clone)]
+pub struct DescriptorAllocator {
+    layout: DescriptorHeapLayout,
+    persistent_capacity: u32,
+    next_persistent: u32,
+    free_list: Vec<u32>,
+    ring: RingAllocator,
+}
+
+impl DescriptorAllocator {
+    pub fn new(layout: DescriptorHeapLayout, persistent_capacity: u32) -> eyre::Result<Self> {
+        if persistent_capacity > layout.capacity {
+            eyre::bail!(
+                "Persistent capacity {persistent_capacity} exceeds the heap capacity {}",
+                layout.capacity
+            );
+        }
+
+        Ok(Self {
+            layout,
+            persistent_capacity,
+            next_persistent: 0,
+            free_list: Vec::new(),
+            ring: RingAllocator::new(u64::from(layout.capacity - persistent_capacity)),
+        })
+    }
+
+    pub fn layout(&self) -> &DescriptorHeapLayout {
+        &self.layout
+    }
+
+    pub fn allocate(&mut self) -> eyre::Result<Descriptor> {
+        let index = if let Some(index) = self.free_list.pop() {
+            index
+        } else if self.next_persistent < self.persistent_capacity {
+            self.next_persistent += 1;
+            self.next_persistent - 1
+        } else {
+            eyre::bail!(
+                "All {} persistent descriptors are in use",
+                self.persistent_capacity
+            );
+        };
+
+        Ok(self.layout.descriptor(index))
+    }
+
+    pub fn free(&mut self, descriptor: Descriptor) -> eyre::Result<()> {
+        let index = descriptor.index;
+        if index >= self.next_persistent {
+            eyre::bail!("Descriptor {index} is not a persistent allocation");
+        }
+        if self.free_list.contains(&index) {
+            eyre::bail!("Descriptor {index} was freed twice");
+        }
+
+        self.free_list.push(index);
+        Ok(())
+    }
+
+    pub fn persistent_in_use(&self) -> u32 {
+        self.next_persistent - self.free_list.len() as u32
+    }
+
+    /// Allocate `count` contiguous transient descriptors, valid until the current frame retires.
+    pub fn allocate_transient(&mut self, count: u32) -> eyre::Result<DescriptorRange> {
+        let Some(allocation) = self.ring.allocate(u64::from(count), 1) else {
+            eyre::bail!(
+                "Cannot allocate {count} transient descriptors: {} of {} in use",
+                self.ring.used(),
+                self.ring.capacity()
+            );
+        };
+
+        Ok(DescriptorRange {
+            first: self
+                .layout
+                .descriptor(self.persistent_capacity + allocation.offset as u32),
+            count,
+            increment: self.layout.increment,
+        })
+    }
+
+    /// Mark everything allocated from the ring so far as belonging to the frame signalled with
+    /// `fence_value`.
+    pub fn finish_frame(&mut self, fence_value: u64) {
+        self.ring.finish_frame(fence_value);
+    }
+
+    /// Reclaim transient descriptors of frames whose fence value has completed.
+    pub fn retire(&mut self, completed_fence_value: u64) {
+        self.ring.retire(completed_fence_value);
+    }
+
+    pub fn transient_in_use(&self) -> u32 {
+        self.ring.used() as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two persistent descriptors followed by a ring of eight transient ones.
+    fn allocator() -> DescriptorAllocator {
+        let layout = DescriptorHeapLayout {
+            cpu_start: CpuDescriptorHandle(1000),
+            gpu_start: Some(GpuDescriptorHandle(5000)),
+            increment: 32,
+            capacity: 10,
+        };
+        DescriptorAllocator::new(layout, 2).unwrap()
+    }
+
+    #[test]
+    fn persistent_capacity_must_fit_in_the_heap() {
+        let layout = *allocator().layout();
+        assert!(DescriptorAllocator::new(layout, 11).is_err());
+        assert!(DescriptorAllocator::new(layout, 10).is_ok());
+    }
+
+    #[test]
+    fn persistent_descriptors_run_out_and_are_recycled() {
+        let mut allocator = allocator();
+        let first = allocator.allocate().unwrap();
+        let second = allocator.allocate().unwrap();
+        assert_eq!(second.cpu, CpuDescriptorHandle(1032));
+        assert_eq!(second.gpu, Some(GpuDescriptorHandle(5032)));
+        assert!(allocator.allocate().is_err());
+
+        allocator.free(first).unwrap();
+        assert!(allocator.free(first).is_err());
+        assert_eq!(allocator.persistent_in_use(), 1);
+        assert_eq!(allocator.allocate().unwrap(), first);
+        assert_eq!(allocator.persistent_in_use(), 2);
+    }
+
+    #[test]
+    fn transient_descriptors_are_not_persistent() {
+        let mut allocator = allocator();
+        let range = allocator.allocate_transient(1).unwrap();
+        assert!(allocator.free(range.first).is_err());
+    }
+
+    #[test]
+    fn transient_descriptors_follow_the_persistent_ones() {
+        let mut allocator = allocator();
+        let range = allocator.allocate_transient(3).unwrap();
+        assert_eq!(range.first.index, 2);
+        assert_eq!(range.cpu(2), CpuDescriptorHandle(1000 + 4 * 32));
+        assert_eq!(range.gpu(2), Some(GpuDescriptorHandle(5000 + 4 * 32)));
+    }
+
+    #[test]
+    fn transient_descriptors_are_reclaimed_per_frame() {
+        let mut allocator = allocator();
+        allocator.allocate_transient(3).unwrap();
+        allocator.finish_frame(1);
+        assert_eq!(allocator.allocate_transient(3).unwrap().first.index, 5);
+        allocator.finish_frame(2);
+
+        // Three more would wrap, skipping the last two, onto frame 1's descriptors.
+        assert!(allocator.allocate_transient(3).is_err());
+        allocator.retire(1);
+        assert_eq!(allocator.transient_in_use(), 3);
+        assert_eq!(allocator.allocate_transient(3).unwrap().first.index, 2);
+        allocator.finish_frame(3);
+        assert_eq!(allocator.transient_in_use(), 8);
+
+        allocator.retire(3);
+        assert_eq!(allocator.transient_in_use(), 0);
+        assert!(allocator.allocate_transient(9).is_err());
+        assert!(allocator.allocate_transient(8).is_ok());
+    }
+}
@@ -0,0 +1,318 @@
+use std::time::Duration;
+
+// DXGI HRESULTs, spelled out so classification works on plain codes.
+pub const DXGI_ERROR_INVALID_CALL: i32 = 0x887A0001_u32 as i32;
+pub const DXGI_ERROR_DEVICE_REMOVED: i32 = 0x887A0005_u32 as i32;
+pub const DXGI_ERROR_DEVICE_HUNG: i32 = 0x887A0006_u32 as i32;
+pub const DXGI_ERROR_DEVICE_RESET: i32 = 0x887A0007_u32 as i32;
+pub const DXGI_ERROR_DRIVER_INTERNAL_ERROR: i32 = 0x887A0020_u32 as i32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceLossKind {
+    /// The adapter went away: driver update, eGPU unplugged, TDR recovery.
+    Removed,
+    /// The GPU stopped responding to commands.
+    Hung,
+    /// The GPU was reset because of badly formed commands.
+    Reset,
+    DriverInternalError,
+    /// Only reported by `GetDeviceRemovedReason`: the application made an invalid call, so
+    /// recreating the device would fail the same way.
+    InvalidCall,
+}
+
+impl DeviceLossKind {
+    /// Classify the HRESULT a failing call returned; `None` for errors that are not device loss.
+    pub fn from_error_code(code: i32) -> Option<Self> {
+        match code {
+            DXGI_ERROR_DEVICE_REMOVED => Some(Self::Removed),
+            DXGI_ERROR_DEVICE_HUNG => Some(Self::Hung),
+            DXGI_ERROR_DEVICE_RESET => Some(Self::Reset),
+            DXGI_ERROR_DRIVER_INTERNAL_ERROR => Some(Self::DriverInternalError),
+            _ => None,
+        }
+    }
+
+    /// Classify the HRESULT `GetDeviceRemovedReason` returned.
+    pub fn from_removed_reason(code: i32) -> Option<Self> {
+        match code {
+            DXGI_ERROR_INVALID_CALL => Some(Self::InvalidCall),
+            code => Self::from_error_code(code),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeviceLoss {
+    /// What the failing call reported.
+    pub kind: DeviceLossKind,
+    /// What `GetDeviceRemovedReason` reported, when the device could be asked.
+    pub removed_reason: Option<DeviceLossKind>,
+}
+
+impl std::error::Error for DeviceLoss {}
+
+impl std::fmt::Display for DeviceLoss {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.removed_reason {
+            Some(reason) => write!(
+                f,
+                "device lost ({:?}, removed reason {reason:?})",
+                self.kind
+            ),
+            None => write!(f, "device lost ({:?})", self.kind),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecoveryDecision {
+    /// Wait this long, then recreate the renderer.
+    Recreate {
+        after: Duration,
+    },
+    GiveUp,
+}
+
+/// Decides whether and when to recreate the renderer after the device is lost.
+pub trait RecoveryPolicy {
+    fn on_device_lost(&mut self, loss: &DeviceLoss) -> RecoveryDecision;
+
+    /// Called after every successfully rendered frame.
+    fn on_frame_rendered(&mut self);
+}
+
+/// Retries a bounded number of times with linearly increasing delays. The attempt count resets
+/// once the renderer has run for `stable_frames` frames, so an occasional TDR days apart does
+/// not use up the budget.
+#[derive(Debug, Clone)]
+pub struct BoundedRetryPolicy {
+    max_attempts: u32,
+    backoff: Duration,
+    stable_frames: u32,
+    attempts: u32,
+    frames_since_loss: u32,
+}
+
+impl BoundedRetryPolicy {
+    pub fn new(max_attempts: u32, backoff: Duration, stable_frames: u32) -> Self {
+        Self {
+            max_attempts,
+            backoff,
+            stable_frames,
+            attempts: 0,
+            frames_since_loss: 0,
+        }
+    }
+
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+}
+
+impl Default for BoundedRetryPolicy {
+    fn default() -> Self {
+        Self::new(3, Duration::from_millis(250), 600)
+    }
+}
+
+impl RecoveryPolicy for BoundedRetryPolicy {
+    fn on_device_lost(&mut self, loss: &DeviceLoss) -> RecoveryDecision {
+        self.frames_since_loss = 0;
+        if loss.removed_reason == Some(DeviceLossKind::InvalidCall) {
+            return RecoveryDecision::GiveUp;
+        }
+
+        self.attempts += 1;
+        if self.attempts > self.max_attempts {
+            return RecoveryDecision::GiveUp;
+        }
+        RecoveryDecision::Recreate {
+            after: self.backoff * self.attempts,
+        }
+    }
+
+    fn on_frame_rendered(&mut self) {
+        self.frames_since_loss = self.frames_since_loss.saturating_add(1);
+        if self.frames_since_loss >= self.stable_frames {
+            self.attempts = 0;
+        }
+    }
+}
+
+/// Why an attempt to recreate the renderer failed.
+#[derive(Debug)]
+pub enum RecreateFailure {
+    /// The new device was lost too; ask the policy again.
+    DeviceLost(DeviceLoss),
+    Fatal(eyre::Report),
+}
+
+/// Recreate the renderer after `loss`, consulting `policy` before every attempt. `attempt` is
+/// given the delay the policy chose and is responsible for waiting it out.
+pub fn recreate_after_loss<P: RecoveryPolicy, T>(
+    policy: &mut P,
+    loss: DeviceLoss,
+    mut attempt: impl FnMut(Duration) -> Result<T, RecreateFailure>,
+) -> eyre::Result<T> {
+    let mut loss = loss;
+    loop {
+        let RecoveryDecision::Recreate { after } = policy.on_device_lost(&loss) else {
+            return Err(eyre::Report::new(loss).wrap_err("Giving up on renderer recovery"));
+        };
+
+        match attempt(after) {
+            Ok(recreated) => return Ok(recreated),
+            Err(RecreateFailure::DeviceLost(next)) => loss = next,
+            Err(RecreateFailure::Fatal(error)) => {
+                return Err(error.wrap_err(format!("Failed to recreate the renderer after {loss}")));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BACKOFF: Duration = Duration::from_millis(10);
+
+    fn loss(kind: DeviceLossKind) -> DeviceLoss {
+        DeviceLoss {
+            kind,
+            removed_reason: None,
+        }
+    }
+
+    #[test]
+    fn device_loss_codes_are_classified() {
+        assert_eq!(
+            DeviceLossKind::from_error_code(DXGI_ERROR_DEVICE_REMOVED),
+            Some(DeviceLossKind::Removed)
+        );
+        assert_eq!(
+            DeviceLossKind::from_error_code(DXGI_ERROR_DEVICE_HUNG),
+            Some(DeviceLossKind::Hung)
+        );
+        assert_eq!(
+            DeviceLossKind::from_error_code(DXGI_ERROR_DEVICE_RESET),
+            Some(DeviceLossKind::Reset)
+        );
+        assert_eq!(
+            DeviceLossKind::from_error_code(DXGI_ERROR_DRIVER_INTERNAL_ERROR),
+            Some(DeviceLossKind::DriverInternalError)
+        );
+        // E_FAIL
+        assert_eq!(DeviceLossKind::from_error_code(0x80004005_u32 as i32), None);
+        // Invalid calls are only a removed reason, not a loss in themselves.
+        assert_eq!(
+            DeviceLossKind::from_error_code(DXGI_ERROR_INVALID_CALL),
+            None
+        );
+        assert_eq!(
+            DeviceLossKind::from_removed_reason(DXGI_ERROR_INVALID_CALL),
+            Some(DeviceLossKind::InvalidCall)
+        );
+        assert_eq!(
+            DeviceLossKind::from_removed_reason(DXGI_ERROR_DEVICE_HUNG),
+            Some(DeviceLossKind::Hung)
+        );
+    }
+
+    #[test]
+    fn backoff_grows_linearly_until_attempts_run_out() {
+        let mut policy = BoundedRetryPolicy::new(3, BACKOFF, 100);
+        let decisions: Vec<_> = (0..4)
+            .map(|_| policy.on_device_lost(&loss(DeviceLossKind::Hung)))
+            .collect();
+        assert_eq!(
+            decisions,
+            [
+                RecoveryDecision::Recreate { after: BACKOFF },
+                RecoveryDecision::Recreate { after: BACKOFF * 2 },
+                RecoveryDecision::Recreate { after: BACKOFF * 3 },
+                RecoveryDecision::GiveUp,
+            ]
+        );
+    }
+
+    #[test]
+    fn attempts_reset_after_stable_frames() {
+        let mut policy = BoundedRetryPolicy::new(1, BACKOFF, 3);
+        policy.on_device_lost(&loss(DeviceLossKind::Removed));
+        policy.on_frame_rendered();
+        policy.on_frame_rendered();
+        assert_eq!(policy.attempts(), 1);
+        policy.on_frame_rendered();
+        assert_eq!(policy.attempts(), 0);
+        assert_eq!(
+            policy.on_device_lost(&loss(DeviceLossKind::Removed)),
+            RecoveryDecision::Recreate { after: BACKOFF }
+        );
+
+        // A loss before the renderer is stable again starts the count over.
+        policy.on_frame_rendered();
+        policy.on_frame_rendered();
+        assert_eq!(
+            policy.on_device_lost(&loss(DeviceLossKind::Removed)),
+            RecoveryDecision::GiveUp
+        );
+    }
+
+    #[test]
+    fn invalid_calls_are_not_retried() {
+        let mut policy = BoundedRetryPolicy::default();
+        let loss = DeviceLoss {
+            kind: DeviceLossKind::Removed,
+            removed_reason: Some(DeviceLossKind::InvalidCall),
+        };
+        assert_eq!(policy.on_device_lost(&loss), RecoveryDecision::GiveUp);
+        assert_eq!(policy.attempts(), 0);
+    }
+
+    #[test]
+    fn recreation_retries_when_the_new_device_is_lost_too() {
+        let mut policy = BoundedRetryPolicy::new(3, BACKOFF, 100);
+        let mut delays = Vec::new();
+        let recreated = recreate_after_loss(&mut policy, loss(DeviceLossKind::Hung), |after| {
+            delays.push(after);
+            if delays.len() < 3 {
+                Err(RecreateFailure::DeviceLost(loss(DeviceLossKind::Reset)))
+            } else {
+                Ok("renderer")
+            }
+        })
+        .unwrap();
+        assert_eq!(recreated, "renderer");
+        assert_eq!(delays, [BACKOFF, BACKOFF * 2, BACKOFF * 3]);
+    }
+
+    #[test]
+    fn recreation_gives_up_when_the_policy_does() {
+        let mut policy = BoundedRetryPolicy::new(2, BACKOFF, 100);
+        let mut attempts = 0;
+        let error = recreate_after_loss(&mut policy, loss(DeviceLossKind::Hung), |_| {
+            attempts += 1;
+            Err::<(), _>(RecreateFailure::DeviceLost(loss(DeviceLossKind::Removed)))
+        })
+        .unwrap_err();
+        assert_eq!(attempts, 2);
+        assert_eq!(
+            error.downcast_ref::<DeviceLoss>(),
+            Some(&loss(DeviceLossKind::Removed))
+        );
+    }
+
+    #[test]
+    fn fatal_failures_stop_recreation() {
+        let mut policy = BoundedRetryPolicy::default();
+        let mut attempts = 0;
+        let error = recreate_after_loss(&mut policy, loss(DeviceLossKind::Hung), |_| {
+            attempts += 1;
+            Err::<(), _>(RecreateFailure::Fatal(eyre::eyre!("no adapter")))
+        })
+        .unwrap_err();
+        assert_eq!(attempts, 1);
+        assert_eq!(error.root_cause().to_string(), "no adapter");
+    }
+}
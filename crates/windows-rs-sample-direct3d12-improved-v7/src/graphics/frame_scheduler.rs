@@ -0,0 +1,134 @@
+use crate::graphics::timeline::Timeline;
+
+/// Tracks which frame slots (back buffers and their command allocators) are still in flight on
+/// the GPU.
+///
+/// A slot may only be reused once [`FrameScheduler::wait_for_frame`] has returned for it; the
+/// fence value recorded by [`FrameScheduler::signal_frame`] is what that wait blocks on.
+#[derive(Debug)]
+pub struct FrameScheduler<T: Timeline, const FRAME_COUNT: usize> {
+    timeline: T,
+    next_fence_value: u64,
+    frame_fence_values: [u64; FRAME_COUNT],
+}
+
+impl<T: Timeline, const FRAME_COUNT: usize> FrameScheduler<T, FRAME_COUNT> {
+    pub fn new(timeline: T) -> Self {
+        Self {
+            timeline,
+            next_fence_value: 1,
+            frame_fence_values: [0; FRAME_COUNT],
+        }
+    }
+
+    pub fn timeline(&self) -> &T {
+        &self.timeline
+    }
+
+    pub fn timeline_mut(&mut self) -> &mut T {
+        &mut self.timeline
+    }
+
+    /// The fence value that was signalled after the last submission for `frame_index`, or 0 if
+    /// the slot has never been used.
+    pub fn frame_fence_value(&self, frame_index: usize) -> u64 {
+        self.frame_fence_values[frame_index]
+    }
+
+    /// The fence value the next [`FrameScheduler::signal_frame`] will use.
+    pub fn next_fence_value(&self) -> u64 {
+        self.next_fence_value
+    }
+
+    pub fn completed_value(&self) -> u64 {
+        self.timeline.completed_value()
+    }
+
+    pub fn is_frame_in_flight(&self, frame_index: usize) -> bool {
+        self.timeline.completed_value() < self.frame_fence_values[frame_index]
+    }
+
+    /// Block until the GPU has finished the last submission that used `frame_index`.
+    pub fn wait_for_frame(&mut self, frame_index: usize) -> eyre::Result<()> {
+        let fence_value = self.frame_fence_values[frame_index];
+        if fence_value == 0 {
+            return Ok(());
+        }
+
+        self.timeline.wait_until(fence_value)
+    }
+
+    /// Mark the work just submitted for `frame_index` and return the fence value it will
+    /// complete at.
+    pub fn signal_frame(&mut self, frame_index: usize) -> eyre::Result<u64> {
+        let fence_value = self.next_fence_value;
+        self.timeline.signal(fence_value)?;
+        self.frame_fence_values[frame_index] = fence_value;
+        self.next_fence_value += 1;
+        Ok(fence_value)
+    }
+
+    /// Block until every submission so far has finished.
+    pub fn wait_for_gpu(&mut self) -> eyre::Result<()> {
+        let fence_value = self.next_fence_value;
+        self.timeline.signal(fence_value)?;
+        self.next_fence_value += 1;
+        self.timeline.wait_until(fence_value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::timeline::SimulatedTimeline;
+
+    #[test]
+    fn fence_values_increase_across_frames() {
+        let mut scheduler: FrameScheduler<_, 2> = FrameScheduler::new(SimulatedTimeline::new([1]));
+        assert_eq!(scheduler.signal_frame(0).unwrap(), 1);
+        assert_eq!(scheduler.signal_frame(1).unwrap(), 2);
+        scheduler.wait_for_gpu().unwrap();
+        assert_eq!(scheduler.completed_value(), 3);
+        assert_eq!(scheduler.signal_frame(0).unwrap(), 4);
+        assert_eq!(scheduler.frame_fence_value(0), 4);
+        assert_eq!(scheduler.frame_fence_value(1), 2);
+        assert_eq!(scheduler.next_fence_value(), 5);
+    }
+
+    #[test]
+    fn unused_and_completed_frames_do_not_block() {
+        let mut scheduler: FrameScheduler<_, 2> = FrameScheduler::new(SimulatedTimeline::new([5]));
+        scheduler.wait_for_frame(1).unwrap();
+        assert_eq!(scheduler.timeline().now(), 0);
+
+        scheduler.signal_frame(0).unwrap();
+        scheduler.timeline_mut().advance(5);
+        assert!(!scheduler.is_frame_in_flight(0));
+        scheduler.wait_for_frame(0).unwrap();
+        assert_eq!(scheduler.timeline().now(), 5);
+    }
+
+    #[test]
+    fn slot_is_reused_only_after_frame_count_frames_complete() {
+        const FRAME_COUNT: usize = 3;
+        let mut scheduler: FrameScheduler<_, FRAME_COUNT> =
+            FrameScheduler::new(SimulatedTimeline::new([5, 1, 9, 3]));
+        for frame in 0..100 {
+            let frame_index = frame % FRAME_COUNT;
+            let previous_fence_value = scheduler.frame_fence_value(frame_index);
+            scheduler.wait_for_frame(frame_index).unwrap();
+            assert!(!scheduler.is_frame_in_flight(frame_index));
+            assert!(scheduler.timeline().is_complete(previous_fence_value));
+
+            scheduler.timeline_mut().advance(2);
+            let fence_value = scheduler.signal_frame(frame_index).unwrap();
+            if frame >= FRAME_COUNT {
+                assert_eq!(fence_value - previous_fence_value, FRAME_COUNT as u64);
+            }
+        }
+
+        scheduler.wait_for_gpu().unwrap();
+        assert_eq!(scheduler.timeline().pending_signal_count(), 0);
+        assert!((0..FRAME_COUNT).all(|frame_index| !scheduler.is_frame_in_flight(frame_index)));
+    }
+}
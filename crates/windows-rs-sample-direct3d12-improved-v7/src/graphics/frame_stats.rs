@@ -0,0 +1,109 @@
+use crate::graphics::gpu_timing::GpuFrameTiming;
+use crate::graphics::latency::LatencyUpdate;
+use std::time::Duration;
+use std::time::Instant;
+
+/// Rolling CPU and GPU frame timings and input latency, summarised once per reporting interval.
+#[derive(Debug, Clone)]
+pub struct FrameStats {
+    interval: Duration,
+    window_start: Instant,
+    last_frame_start: Option<Instant>,
+    cpu: DurationSummary,
+    gpu: DurationSummary,
+    input_to_scanout: DurationSummary,
+    unmatched_presents: u32,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DurationSummary {
+    pub count: u32,
+    pub total: Duration,
+    pub max: Duration,
+}
+
+impl DurationSummary {
+    pub fn record(&mut self, duration: Duration) {
+        self.count += 1;
+        self.total += duration;
+        self.max = self.max.max(duration);
+    }
+
+    pub fn average(&self) -> Option<Duration> {
+        (self.count > 0).then(|| self.total / self.count)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameStatsReport {
+    pub elapsed: Duration,
+    pub frames: u32,
+    pub cpu: DurationSummary,
+    pub gpu: DurationSummary,
+    pub input_to_scanout: DurationSummary,
+    pub unmatched_presents: u32,
+}
+
+impl FrameStatsReport {
+    pub fn frames_per_second(&self) -> f64 {
+        if self.elapsed.is_zero() {
+            return 0.0;
+        }
+        f64::from(self.frames) / self.elapsed.as_secs_f64()
+    }
+}
+
+impl FrameStats {
+    pub fn new(interval: Duration, now: Instant) -> Self {
+        Self {
+            interval,
+            window_start: now,
+            last_frame_start: None,
+            cpu: DurationSummary::default(),
+            gpu: DurationSummary::default(),
+            input_to_scanout: DurationSummary::default(),
+            unmatched_presents: 0,
+        }
+    }
+
+    /// Record the start of a frame; the time since the previous call is the CPU frame time.
+    pub fn begin_frame(&mut self, now: Instant) {
+        if let Some(previous) = self.last_frame_start.replace(now) {
+            self.cpu.record(now.saturating_duration_since(previous));
+        }
+    }
+
+    pub fn record_gpu_frame(&mut self, timing: GpuFrameTiming) {
+        self.gpu.record(timing.duration);
+    }
+
+    pub fn record_latency(&mut self, update: &LatencyUpdate) {
+        for sample in &update.samples {
+            self.input_to_scanout.record(sample.input_to_scanout);
+        }
+        self.unmatched_presents += update.unmatched_presents;
+    }
+
+    pub fn record_unmatched_presents(&mut self, count: u32) {
+        self.unmatched_presents += count;
+    }
+
+    /// Return and reset the accumulated stats once the reporting interval has elapsed.
+    pub fn take_report(&mut self, now: Instant) -> Option<FrameStatsReport> {
+        let elapsed = now.saturating_duration_since(self.window_start);
+        if elapsed < self.interval {
+            return None;
+        }
+
+        let report = FrameStatsReport {
+            elapsed,
+            frames: self.cpu.count,
+            cpu: std::mem::take(&mut self.cpu),
+            gpu: std::mem::take(&mut self.gpu),
+            input_to_scanout: std::mem::take(&mut self.input_to_scanout),
+            unmatched_presents: std::mem::take(&mut self.unmatched_presents),
+        };
+        self.window_start = now;
+        Some(report)
+    }
+}
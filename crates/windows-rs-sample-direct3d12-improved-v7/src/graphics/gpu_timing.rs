@@ -0,0 +1,413 @@
+use std::time::Duration;
+use windows::Win32::Graphics::Direct3D12::*;
+use windows::Win32::Graphics::Dxgi::Common::DXGI_SAMPLE_DESC;
+use windows::Win32::System::Performance::QueryPerformanceFrequency;
+
+/// Two timestamps per frame: one before the render pass and one after it.
+pub const TIMESTAMPS_PER_FRAME: u32 = 2;
+const TIMESTAMP_SIZE: u64 = std::mem::size_of::<u64>() as u64;
+
+pub fn ticks_to_duration(ticks: u64, frequency: u64) -> Duration {
+    if frequency == 0 {
+        return Duration::ZERO;
+    }
+
+    let seconds = ticks / frequency;
+    let remainder = ticks % frequency;
+    let nanos = (u128::from(remainder) * 1_000_000_000 / u128::from(frequency)) as u32;
+    Duration::new(seconds, nanos)
+}
+
+/// A simultaneous sample of the GPU timestamp counter and the CPU's QPC, used to map GPU
+/// timestamps onto the CPU timeline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClockCalibration {
+    pub gpu_frequency: u64,
+    pub cpu_frequency: u64,
+    pub gpu_timestamp: u64,
+    pub cpu_timestamp: u64,
+}
+
+impl ClockCalibration {
+    /// Convert a GPU timestamp into QPC ticks.
+    pub fn gpu_to_cpu_ticks(&self, gpu_timestamp: u64) -> u64 {
+        let scale = |delta: u64| -> u64 {
+            (u128::from(delta) * u128::from(self.cpu_frequency)
+                / u128::from(self.gpu_frequency.max(1))) as u64
+        };
+
+        if gpu_timestamp >= self.gpu_timestamp {
+            self.cpu_timestamp + scale(gpu_timestamp - self.gpu_timestamp)
+        } else {
+            self.cpu_timestamp
+                .saturating_sub(scale(self.gpu_timestamp - gpu_timestamp))
+        }
+    }
+
+    pub fn gpu_ticks_to_duration(&self, ticks: u64) -> Duration {
+        ticks_to_duration(ticks, self.gpu_frequency)
+    }
+}
+
+/// GPU execution of a single frame's render pass.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GpuFrameTiming {
+    /// QPC tick at which the GPU started the render pass.
+    pub begin_qpc: u64,
+    /// QPC tick at which the GPU finished the render pass.
+    pub end_qpc: u64,
+    pub duration: Duration,
+}
+
+impl GpuFrameTiming {
+    /// Returns `None` if the timestamps are out of order, which happens when the GPU clock
+    /// was reset between the two queries.
+    pub fn from_timestamps(calibration: &ClockCalibration, begin: u64, end: u64) -> Option<Self> {
+        if end < begin {
+            return None;
+        }
+
+        Some(Self {
+            begin_qpc: calibration.gpu_to_cpu_ticks(begin),
+            end_qpc: calibration.gpu_to_cpu_ticks(end),
+            duration: calibration.gpu_ticks_to_duration(end - begin),
+        })
+    }
+}
+
+/// Which frame slots have timestamps resolved into the readback buffer, and the fence value
+/// that has to complete before they may be read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimestampReadbackSlots<const FRAME_COUNT: usize> {
+    pending_fence_values: [Option<u64>; FRAME_COUNT],
+}
+
+impl<const FRAME_COUNT: usize> Default for TimestampReadbackSlots<FRAME_COUNT> {
+    fn default() -> Self {
+        Self {
+            pending_fence_values: [None; FRAME_COUNT],
+        }
+    }
+}
+
+impl<const FRAME_COUNT: usize> TimestampReadbackSlots<FRAME_COUNT> {
+    pub const QUERY_COUNT: u32 = FRAME_COUNT as u32 * TIMESTAMPS_PER_FRAME;
+    pub const READBACK_SIZE: u64 = Self::QUERY_COUNT as u64 * TIMESTAMP_SIZE;
+
+    pub fn begin_query_index(slot: usize) -> u32 {
+        slot as u32 * TIMESTAMPS_PER_FRAME
+    }
+
+    pub fn end_query_index(slot: usize) -> u32 {
+        Self::begin_query_index(slot) + 1
+    }
+
+    pub fn readback_offset(slot: usize) -> u64 {
+        u64::from(Self::begin_query_index(slot)) * TIMESTAMP_SIZE
+    }
+
+    /// Byte range of `slot`'s timestamps within the readback buffer.
+    pub fn readback_range(slot: usize) -> std::ops::Range<usize> {
+        let start = Self::readback_offset(slot) as usize;
+        start..start + (TIMESTAMPS_PER_FRAME as u64 * TIMESTAMP_SIZE) as usize
+    }
+
+    /// Record that `slot`'s queries were resolved by work that completes at `fence_value`.
+    pub fn mark_submitted(&mut self, slot: usize, fence_value: u64) {
+        self.pending_fence_values[slot] = Some(fence_value);
+    }
+
+    pub fn is_pending(&self, slot: usize) -> bool {
+        self.pending_fence_values[slot].is_some()
+    }
+
+    /// Claim `slot` for reading if its resolve has completed. Each submission is handed out
+    /// at most once.
+    pub fn take_ready(&mut self, slot: usize, completed_fence_value: u64) -> bool {
+        match self.pending_fence_values[slot] {
+            Some(fence_value) if fence_value <= completed_fence_value => {
+                self.pending_fence_values[slot] = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Timestamp queries bracketing each frame's render pass, resolved into a persistently mapped
+/// readback buffer.
+#[derive(Debug)]
+pub struct GpuTimer<const FRAME_COUNT: usize> {
+    query_heap: ID3D12QueryHeap,
+    readback_buffer: ID3D12Resource,
+    readback_ptr: std::ptr::NonNull<u8>,
+    calibration: ClockCalibration,
+    slots: TimestampReadbackSlots<FRAME_COUNT>,
+}
+
+impl<const FRAME_COUNT: usize> GpuTimer<FRAME_COUNT> {
+    pub fn new(device: &ID3D12Device, command_queue: &ID3D12CommandQueue) -> eyre::Result<Self> {
+        let mut query_heap: Option<ID3D12QueryHeap> = None;
+        unsafe {
+            device.CreateQueryHeap(
+                &D3D12_QUERY_HEAP_DESC {
+                    Type: D3D12_QUERY_HEAP_TYPE_TIMESTAMP,
+                    Count: TimestampReadbackSlots::<FRAME_COUNT>::QUERY_COUNT,
+                    NodeMask: 0,
+                },
+                &mut query_heap,
+            )?
+        };
+        let query_heap = query_heap.expect("query heap should be initialized");
+
+        let mut readback_buffer: Option<ID3D12Resource> = None;
+        unsafe {
+            device.CreateCommittedResource(
+                &D3D12_HEAP_PROPERTIES {
+                    Type: D3D12_HEAP_TYPE_READBACK,
+                    ..Default::default()
+                },
+                D3D12_HEAP_FLAG_NONE,
+                &D3D12_RESOURCE_DESC {
+                    Dimension: D3D12_RESOURCE_DIMENSION_BUFFER,
+                    Width: TimestampReadbackSlots::<FRAME_COUNT>::READBACK_SIZE,
+                    Height: 1,
+                    DepthOrArraySize: 1,
+                    MipLevels: 1,
+                    SampleDesc: DXGI_SAMPLE_DESC {
+                        Count: 1,
+                        Quality: 0,
+                    },
+                    Layout: D3D12_TEXTURE_LAYOUT_ROW_MAJOR,
+                    ..Default::default()
+                },
+                D3D12_RESOURCE_STATE_COPY_DEST,
+                None,
+                &mut readback_buffer,
+            )?
+        };
+        let readback_buffer = readback_buffer.expect("readback buffer should be initialized");
+        let readback_ptr = {
+            let mut mapped = std::ptr::null_mut();
+            unsafe { readback_buffer.Map(0, None, Some(&mut mapped))? };
+            std::ptr::NonNull::new(mapped as *mut u8)
+                .ok_or_else(|| eyre::eyre!("Timestamp readback map returned a null pointer"))?
+        };
+
+        Ok(Self {
+            query_heap,
+            readback_buffer,
+            readback_ptr,
+            calibration: calibrate(command_queue)?,
+            slots: TimestampReadbackSlots::default(),
+        })
+    }
+
+    pub fn calibration(&self) -> &ClockCalibration {
+        &self.calibration
+    }
+
+    /// Re-sample the clocks; the GPU and CPU counters drift apart over long runs.
+    pub fn recalibrate(&mut self, command_queue: &ID3D12CommandQueue) -> eyre::Result<()> {
+        self.calibration = calibrate(command_queue)?;
+        Ok(())
+    }
+
+    pub fn begin(&self, command_list: &ID3D12GraphicsCommandList, slot: usize) {
+        unsafe {
+            command_list.EndQuery(
+                &self.query_heap,
+                D3D12_QUERY_TYPE_TIMESTAMP,
+                TimestampReadbackSlots::<FRAME_COUNT>::begin_query_index(slot),
+            );
+        }
+    }
+
+    pub fn end(&self, command_list: &ID3D12GraphicsCommandList, slot: usize) {
+        unsafe {
+            command_list.EndQuery(
+                &self.query_heap,
+                D3D12_QUERY_TYPE_TIMESTAMP,
+                TimestampReadbackSlots::<FRAME_COUNT>::end_query_index(slot),
+            );
+            command_list.ResolveQueryData(
+                &self.query_heap,
+                D3D12_QUERY_TYPE_TIMESTAMP,
+                TimestampReadbackSlots::<FRAME_COUNT>::begin_query_index(slot),
+                TIMESTAMPS_PER_FRAME,
+                &self.readback_buffer,
+                TimestampReadbackSlots::<FRAME_COUNT>::readback_offset(slot),
+            );
+        }
+    }
+
+    pub fn mark_submitted(&mut self, slot: usize, fence_value: u64) {
+        self.slots.mark_submitted(slot, fence_value);
+    }
+
+    /// Read `slot`'s timestamps if the frame that resolved them has completed.
+    pub fn collect(&mut self, slot: usize, completed_fence_value: u64) -> Option<GpuFrameTiming> {
+        if !self.slots.take_ready(slot, completed_fence_value) {
+            return None;
+        }
+
+        let range = TimestampReadbackSlots::<FRAME_COUNT>::readback_range(slot);
+        let mut timestamps = [0_u64; TIMESTAMPS_PER_FRAME as usize];
+        unsafe {
+            std::ptr::copy_nonoverlapping(
+                self.readback_ptr.as_ptr().add(range.start),
+                timestamps.as_mut_ptr() as *mut u8,
+                range.len(),
+            );
+        }
+
+        GpuFrameTiming::from_timestamps(&self.calibration, timestamps[0], timestamps[1])
+    }
+}
+
+impl<const FRAME_COUNT: usize> Drop for GpuTimer<FRAME_COUNT> {
+    fn drop(&mut self) {
+        unsafe {
+            self.readback_buffer.Unmap(0, None);
+        }
+    }
+}
+
+fn calibrate(command_queue: &ID3D12CommandQueue) -> eyre::Result<ClockCalibration> {
+    let gpu_frequency = unsafe { command_queue.GetTimestampFrequency() }?;
+    let mut cpu_frequency = 0_i64;
+    unsafe { QueryPerformanceFrequency(&mut cpu_frequency) }?;
+
+    let mut gpu_timestamp = 0_u64;
+    let mut cpu_timestamp = 0_u64;
+    unsafe { command_queue.GetClockCalibration(&mut gpu_timestamp, &mut cpu_timestamp) }?;
+
+    Ok(ClockCalibration {
+        gpu_frequency,
+        cpu_frequency: cpu_frequency as u64,
+        gpu_timestamp,
+        cpu_timestamp,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ticks_convert_to_whole_and_fractional_seconds() {
+        assert_eq!(ticks_to_duration(0, 1_000), Duration::ZERO);
+        assert_eq!(
+            ticks_to_duration(1_500, 1_000),
+            Duration::from_millis(1_500)
+        );
+        assert_eq!(
+            ticks_to_duration(10_000_001, 10_000_000),
+            Duration::new(1, 100)
+        );
+        // A third of a tick rounds down rather than overflowing the nanosecond field.
+        assert_eq!(ticks_to_duration(2, 3), Duration::new(0, 666_666_666));
+        assert_eq!(
+            ticks_to_duration(u64::MAX, 1),
+            Duration::from_secs(u64::MAX)
+        );
+    }
+
+    #[test]
+    fn zero_frequency_gives_zero_duration() {
+        assert_eq!(ticks_to_duration(12_345, 0), Duration::ZERO);
+    }
+
+    fn calibration() -> ClockCalibration {
+        ClockCalibration {
+            gpu_frequency: 1_000_000_000,
+            cpu_frequency: 10_000_000,
+            gpu_timestamp: 5_000_000_000,
+            cpu_timestamp: 80_000_000,
+        }
+    }
+
+    #[test]
+    fn gpu_timestamps_map_onto_the_cpu_clock() {
+        let calibration = calibration();
+        assert_eq!(calibration.gpu_to_cpu_ticks(5_000_000_000), 80_000_000);
+        // One GPU millisecond after calibration is 10,000 QPC ticks later.
+        assert_eq!(calibration.gpu_to_cpu_ticks(5_001_000_000), 80_010_000);
+        assert_eq!(
+            calibration.gpu_ticks_to_duration(1_000_000),
+            Duration::from_millis(1)
+        );
+    }
+
+    #[test]
+    fn gpu_timestamps_before_calibration_map_backwards() {
+        let calibration = calibration();
+        assert_eq!(calibration.gpu_to_cpu_ticks(4_999_000_000), 79_990_000);
+        // Further back than the QPC origin saturates instead of wrapping.
+        assert_eq!(calibration.gpu_to_cpu_ticks(0), 30_000_000);
+        let early = ClockCalibration {
+            cpu_timestamp: 5,
+            ..calibration
+        };
+        assert_eq!(early.gpu_to_cpu_ticks(0), 0);
+    }
+
+    #[test]
+    fn frame_timing_rejects_out_of_order_timestamps() {
+        let calibration = calibration();
+        let timing =
+            GpuFrameTiming::from_timestamps(&calibration, 5_000_000_000, 5_002_000_000).unwrap();
+        assert_eq!(timing.begin_qpc, 80_000_000);
+        assert_eq!(timing.end_qpc, 80_020_000);
+        assert_eq!(timing.duration, Duration::from_millis(2));
+
+        assert_eq!(
+            GpuFrameTiming::from_timestamps(&calibration, 5_002_000_000, 5_000_000_000),
+            None
+        );
+    }
+
+    #[test]
+    fn each_slot_gets_its_own_pair_of_queries() {
+        type Slots = TimestampReadbackSlots<3>;
+        assert_eq!(Slots::QUERY_COUNT, 6);
+        assert_eq!(Slots::READBACK_SIZE, 48);
+        for slot in 0..3 {
+            assert_eq!(Slots::begin_query_index(slot), slot as u32 * 2);
+            assert_eq!(Slots::end_query_index(slot), slot as u32 * 2 + 1);
+            assert_eq!(Slots::readback_offset(slot), slot as u64 * 16);
+        }
+        assert_eq!(Slots::readback_range(0), 0..16);
+        assert_eq!(Slots::readback_range(2), 32..48);
+        assert_eq!(Slots::readback_range(2).end as u64, Slots::READBACK_SIZE);
+    }
+
+    #[test]
+    fn slots_are_ready_only_after_their_fence_passes() {
+        let mut slots = TimestampReadbackSlots::<2>::default();
+        assert!(!slots.take_ready(0, u64::MAX));
+
+        slots.mark_submitted(0, 4);
+        slots.mark_submitted(1, 5);
+        assert!(slots.is_pending(0));
+        assert!(!slots.take_ready(0, 3));
+        assert!(!slots.take_ready(1, 4));
+        assert!(slots.take_ready(0, 4));
+        assert!(slots.take_ready(1, 9));
+    }
+
+    #[test]
+    fn each_submission_is_handed_out_once() {
+        let mut slots = TimestampReadbackSlots::<2>::default();
+        slots.mark_submitted(1, 7);
+        assert!(slots.take_ready(1, 7));
+        assert!(!slots.is_pending(1));
+        assert!(!slots.take_ready(1, 7));
+
+        // Reusing the slot for a later frame waits for the new fence value.
+        slots.mark_submitted(1, 9);
+        assert!(!slots.take_ready(1, 8));
+        assert!(slots.take_ready(1, 9));
+        assert!(!slots.take_ready(1, 100));
+    }
+}
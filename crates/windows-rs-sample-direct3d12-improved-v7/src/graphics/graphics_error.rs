@@ -0,0 +1,473 @@
+use crate::graphics::adapter_selection::AdapterSelectionError;
+use crate::graphics::device_recovery::DXGI_ERROR_DEVICE_HUNG;
+use crate::graphics::device_recovery::DXGI_ERROR_DEVICE_REMOVED;
+use crate::graphics::device_recovery::DXGI_ERROR_DEVICE_RESET;
+use crate::graphics::device_recovery::DXGI_ERROR_DRIVER_INTERNAL_ERROR;
+use crate::graphics::device_recovery::DXGI_ERROR_INVALID_CALL;
+use crate::graphics::device_recovery::DeviceLoss;
+use crate::graphics::device_recovery::DeviceLossKind;
+use std::path::PathBuf;
+
+// The remaining HRESULTs, spelled out so classification works on plain codes.
+const E_NOTIMPL: i32 = 0x80004001_u32 as i32;
+const E_NOINTERFACE: i32 = 0x80004002_u32 as i32;
+const E_ACCESSDENIED: i32 = 0x80070005_u32 as i32;
+const E_OUTOFMEMORY: i32 = 0x8007000E_u32 as i32;
+const E_INVALIDARG: i32 = 0x80070057_u32 as i32;
+const DXGI_ERROR_NOT_FOUND: i32 = 0x887A0002_u32 as i32;
+const DXGI_ERROR_UNSUPPORTED: i32 = 0x887A0004_u32 as i32;
+const DXGI_ERROR_NOT_CURRENTLY_AVAILABLE: i32 = 0x887A0022_u32 as i32;
+const DXGI_ERROR_REMOTE_OUTOFMEMORY: i32 = 0x887A0024_u32 as i32;
+const DXGI_ERROR_SESSION_DISCONNECTED: i32 = 0x887A0028_u32 as i32;
+const DXGI_ERROR_SDK_COMPONENT_MISSING: i32 = 0x887A002D_u32 as i32;
+const DXGI_ERROR_HW_PROTECTION_OUTOFMEMORY: i32 = 0x887A0030_u32 as i32;
+const D3D12_ERROR_ADAPTER_NOT_FOUND: i32 = 0x887E0001_u32 as i32;
+const D3D12_ERROR_DRIVER_VERSION_MISMATCH: i32 = 0x887E0002_u32 as i32;
+const D3D12_ERROR_INVALID_REDIST: i32 = 0x887E0003_u32 as i32;
+
+const FACILITY_WIN32: u32 = 0x007;
+const FACILITY_DXGI: u32 = 0x87A;
+const FACILITY_D3D12: u32 = 0x87E;
+
+/// Which API family an HRESULT belongs to, from its facility bits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HresultFacility {
+    Dxgi,
+    D3d12,
+    Win32,
+    Generic,
+}
+
+impl HresultFacility {
+    pub fn from_hresult(code: i32) -> Self {
+        match (code as u32 >> 16) & 0x1FFF {
+            FACILITY_DXGI => Self::Dxgi,
+            FACILITY_D3D12 => Self::D3d12,
+            FACILITY_WIN32 => Self::Win32,
+            _ => Self::Generic,
+        }
+    }
+}
+
+impl std::fmt::Display for HresultFacility {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Dxgi => "DXGI",
+            Self::D3d12 => "D3D12",
+            Self::Win32 => "Win32",
+            Self::Generic => "COM",
+        })
+    }
+}
+
+/// What kind of failure ended the run, used to pick a remediation hint and an exit code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCategory {
+    /// The device was removed, hung or reset and could not be recovered.
+    DeviceLost,
+    /// No adapter matched the selection or could create a D3D12 device.
+    NoAdapter,
+    /// The adapter, driver or OS does not support something the sample asked for.
+    Unsupported,
+    /// The debug layer or another SDK component is not installed.
+    DebugLayerUnavailable,
+    OutOfMemory,
+    /// The sample passed invalid arguments to the API.
+    InvalidCall,
+    ShaderCompilation,
+    /// Cached pipeline state was created by a different adapter or driver.
+    PipelineCacheMismatch,
+    /// A windowing or other Win32 call failed.
+    Win32,
+    Other,
+}
+
+impl ErrorCategory {
+    /// Classify the HRESULT a failing call returned.
+    pub fn from_hresult(code: i32) -> Self {
+        match code {
+            DXGI_ERROR_DEVICE_REMOVED
+            | DXGI_ERROR_DEVICE_HUNG
+            | DXGI_ERROR_DEVICE_RESET
+            | DXGI_ERROR_DRIVER_INTERNAL_ERROR
+            | DXGI_ERROR_SESSION_DISCONNECTED => Self::DeviceLost,
+            DXGI_ERROR_NOT_FOUND => Self::NoAdapter,
+            DXGI_ERROR_UNSUPPORTED
+            | DXGI_ERROR_NOT_CURRENTLY_AVAILABLE
+            | E_NOTIMPL
+            | E_NOINTERFACE
+            | D3D12_ERROR_INVALID_REDIST => Self::Unsupported,
+            DXGI_ERROR_SDK_COMPONENT_MISSING => Self::DebugLayerUnavailable,
+            E_OUTOFMEMORY
+            | DXGI_ERROR_REMOTE_OUTOFMEMORY
+            | DXGI_ERROR_HW_PROTECTION_OUTOFMEMORY => Self::OutOfMemory,
+            DXGI_ERROR_INVALID_CALL | E_INVALIDARG => Self::InvalidCall,
+            // A cached pipeline blob from another adapter or driver fails with these.
+            D3D12_ERROR_ADAPTER_NOT_FOUND | D3D12_ERROR_DRIVER_VERSION_MISMATCH => {
+                Self::PipelineCacheMismatch
+            }
+            E_ACCESSDENIED => Self::Win32,
+            code if HresultFacility::from_hresult(code) == HresultFacility::Win32 => Self::Win32,
+            _ => Self::Other,
+        }
+    }
+
+    /// What the user can do about it, when there is something.
+    pub fn hint(self) -> Option<&'static str> {
+        match self {
+            Self::DeviceLost => Some(
+                "The GPU driver reset or was updated. Check for driver updates, or rerun with --warp to render on the CPU.",
+            ),
+            Self::NoAdapter => Some(
+                "No adapter matched --adapter or supports the requested feature level. Run `adapters list` to see what is available, update the GPU driver, or rerun with --warp.",
+            ),
+            Self::Unsupported => Some(
+                "The adapter or driver does not support this feature. Rerun with --warp to check whether it is hardware specific.",
+            ),
+            Self::DebugLayerUnavailable => Some(
+                "The D3D12 debug layer is missing. Enable Developer Mode or install the \"Graphics Tools\" optional feature, or use a release build.",
+            ),
+            Self::OutOfMemory => Some(
+                "The GPU ran out of memory. Close other GPU-heavy applications or use a smaller window.",
+            ),
+            Self::InvalidCall => Some(
+                "The sample made an invalid API call. Run a debug build so the debug layer reports which one.",
+            ),
+            Self::ShaderCompilation => Some("Fix the shader diagnostics above and run again."),
+            Self::PipelineCacheMismatch => Some(
+                "Cached pipeline state is from another adapter or driver. Delete the pipeline cache or rerun with --no-pipeline-cache.",
+            ),
+            Self::Win32 | Self::Other => None,
+        }
+    }
+
+    /// Whether the failed operation can be retried, the renderer recovered, or the run is over.
+    pub fn disposition(self) -> ErrorDisposition {
+        match self {
+            Self::PipelineCacheMismatch => ErrorDisposition::Retry,
+            Self::DeviceLost => ErrorDisposition::Recover,
+            _ => ErrorDisposition::Fatal,
+        }
+    }
+
+    /// The process exit code for a run that failed with this category.
+    pub fn exit_code(self) -> u8 {
+        match self {
+            Self::Other => 1,
+            Self::DeviceLost => 10,
+            Self::NoAdapter => 11,
+            Self::Unsupported => 12,
+            Self::DebugLayerUnavailable => 13,
+            Self::OutOfMemory => 14,
+            Self::InvalidCall => 15,
+            Self::ShaderCompilation => 16,
+            Self::PipelineCacheMismatch => 17,
+            Self::Win32 => 18,
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorCategory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::DeviceLost => "device lost",
+            Self::NoAdapter => "no suitable adapter",
+            Self::Unsupported => "unsupported",
+            Self::DebugLayerUnavailable => "debug layer unavailable",
+            Self::OutOfMemory => "out of memory",
+            Self::InvalidCall => "invalid call",
+            Self::ShaderCompilation => "shader compilation",
+            Self::PipelineCacheMismatch => "pipeline cache mismatch",
+            Self::Win32 => "Win32",
+            Self::Other => "other",
+        })
+    }
+}
+
+/// How the renderer responds to a failure in an [`ErrorCategory`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorDisposition {
+    /// Try the operation again without what it depended on, such as a cached pipeline blob.
+    Retry,
+    /// Recreate the device and renderer, as [`crate::graphics::device_recovery`] decides.
+    Recover,
+    /// Report the error and exit with the category's exit code.
+    Fatal,
+}
+
+impl ErrorDisposition {
+    pub fn from_hresult(code: i32) -> Self {
+        ErrorCategory::from_hresult(code).disposition()
+    }
+}
+
+/// A shader failed to compile; `diagnostics` is the compiler's messages rendered against the
+/// source.
+#[derive(Debug, Clone)]
+pub struct ShaderCompileError {
+    pub path: PathBuf,
+    pub entry_point: String,
+    pub target: String,
+    pub code: i32,
+    pub diagnostics: String,
+}
+
+impl std::error::Error for ShaderCompileError {}
+
+impl std::fmt::Display for ShaderCompileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Failed to compile {} ({}, {}) with HRESULT {:#010X}:\n{}",
+            self.path.display(),
+            self.entry_point,
+            self.target,
+            self.code as u32,
+            self.diagnostics
+        )
+    }
+}
+
+/// The category of the first classifiable cause in `report`'s chain. `hresult` extracts the
+/// HRESULT from an error in the chain, if it carries one.
+///
+/// A [`DeviceLoss`] the renderer gave up recovering from counts as device loss, unless the
+/// device was removed because of an invalid call.
+pub fn report_category(
+    report: &eyre::Report,
+    hresult: impl Fn(&(dyn std::error::Error + 'static)) -> Option<i32>,
+) -> ErrorCategory {
+    report
+        .chain()
+        .find_map(|cause| {
+            if cause.is::<ShaderCompileError>() {
+                Some(ErrorCategory::ShaderCompilation)
+            } else if cause.is::<AdapterSelectionError>() {
+                Some(ErrorCategory::NoAdapter)
+            } else if let Some(loss) = cause.downcast_ref::<DeviceLoss>() {
+                Some(match loss.removed_reason {
+                    Some(DeviceLossKind::InvalidCall) => ErrorCategory::InvalidCall,
+                    _ => ErrorCategory::DeviceLost,
+                })
+            } else {
+                hresult(cause).map(ErrorCategory::from_hresult)
+            }
+        })
+        .unwrap_or(ErrorCategory::Other)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::device_recovery::BoundedRetryPolicy;
+    use crate::graphics::device_recovery::recreate_after_loss;
+    use std::time::Duration;
+
+    const ALL: [ErrorCategory; 10] = [
+        ErrorCategory::DeviceLost,
+        ErrorCategory::NoAdapter,
+        ErrorCategory::Unsupported,
+        ErrorCategory::DebugLayerUnavailable,
+        ErrorCategory::OutOfMemory,
+        ErrorCategory::InvalidCall,
+        ErrorCategory::ShaderCompilation,
+        ErrorCategory::PipelineCacheMismatch,
+        ErrorCategory::Win32,
+        ErrorCategory::Other,
+    ];
+
+    /// Stands in for `windows::core::Error`.
+    #[derive(Debug)]
+    struct HresultError(i32);
+
+    impl std::fmt::Display for HresultError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "HRESULT {:#010X}", self.0 as u32)
+        }
+    }
+
+    impl std::error::Error for HresultError {}
+
+    fn hresult(cause: &(dyn std::error::Error + 'static)) -> Option<i32> {
+        cause.downcast_ref::<HresultError>().map(|error| error.0)
+    }
+
+    #[test]
+    fn hresults_map_to_categories() {
+        let cases = [
+            (DXGI_ERROR_DEVICE_REMOVED, ErrorCategory::DeviceLost),
+            (DXGI_ERROR_DEVICE_HUNG, ErrorCategory::DeviceLost),
+            (DXGI_ERROR_DEVICE_RESET, ErrorCategory::DeviceLost),
+            (DXGI_ERROR_DRIVER_INTERNAL_ERROR, ErrorCategory::DeviceLost),
+            (DXGI_ERROR_SESSION_DISCONNECTED, ErrorCategory::DeviceLost),
+            (DXGI_ERROR_NOT_FOUND, ErrorCategory::NoAdapter),
+            (DXGI_ERROR_UNSUPPORTED, ErrorCategory::Unsupported),
+            (
+                DXGI_ERROR_NOT_CURRENTLY_AVAILABLE,
+                ErrorCategory::Unsupported,
+            ),
+            (E_NOTIMPL, ErrorCategory::Unsupported),
+            (E_NOINTERFACE, ErrorCategory::Unsupported),
+            (
+                DXGI_ERROR_SDK_COMPONENT_MISSING,
+                ErrorCategory::DebugLayerUnavailable,
+            ),
+            (E_OUTOFMEMORY, ErrorCategory::OutOfMemory),
+            (DXGI_ERROR_REMOTE_OUTOFMEMORY, ErrorCategory::OutOfMemory),
+            (
+                DXGI_ERROR_HW_PROTECTION_OUTOFMEMORY,
+                ErrorCategory::OutOfMemory,
+            ),
+            (DXGI_ERROR_INVALID_CALL, ErrorCategory::InvalidCall),
+            (E_INVALIDARG, ErrorCategory::InvalidCall),
+            (
+                D3D12_ERROR_ADAPTER_NOT_FOUND,
+                ErrorCategory::PipelineCacheMismatch,
+            ),
+            (
+                D3D12_ERROR_DRIVER_VERSION_MISMATCH,
+                ErrorCategory::PipelineCacheMismatch,
+            ),
+            (D3D12_ERROR_INVALID_REDIST, ErrorCategory::Unsupported),
+            (E_ACCESSDENIED, ErrorCategory::Win32),
+            // HRESULT_FROM_WIN32(ERROR_CLASS_DOES_NOT_EXIST)
+            (0x80070582_u32 as i32, ErrorCategory::Win32),
+            // E_FAIL
+            (0x80004005_u32 as i32, ErrorCategory::Other),
+        ];
+        for (code, category) in cases {
+            assert_eq!(
+                ErrorCategory::from_hresult(code),
+                category,
+                "{:#010X}",
+                code as u32
+            );
+        }
+    }
+
+    #[test]
+    fn categories_are_retried_recovered_or_fatal() {
+        assert_eq!(
+            ErrorDisposition::from_hresult(D3D12_ERROR_DRIVER_VERSION_MISMATCH),
+            ErrorDisposition::Retry
+        );
+        assert_eq!(
+            ErrorDisposition::from_hresult(DXGI_ERROR_DEVICE_HUNG),
+            ErrorDisposition::Recover
+        );
+        assert_eq!(
+            ErrorDisposition::from_hresult(DXGI_ERROR_SESSION_DISCONNECTED),
+            ErrorDisposition::Recover
+        );
+        // A broken D3D12 redistributable does not go away on retry.
+        assert_eq!(
+            ErrorDisposition::from_hresult(D3D12_ERROR_INVALID_REDIST),
+            ErrorDisposition::Fatal
+        );
+        assert_eq!(
+            ErrorDisposition::from_hresult(E_OUTOFMEMORY),
+            ErrorDisposition::Fatal
+        );
+        assert_eq!(
+            ErrorDisposition::from_hresult(DXGI_ERROR_INVALID_CALL),
+            ErrorDisposition::Fatal
+        );
+
+        let retried: Vec<_> = ALL
+            .into_iter()
+            .filter(|category| category.disposition() == ErrorDisposition::Retry)
+            .collect();
+        assert_eq!(retried, [ErrorCategory::PipelineCacheMismatch]);
+        let recovered: Vec<_> = ALL
+            .into_iter()
+            .filter(|category| category.disposition() == ErrorDisposition::Recover)
+            .collect();
+        assert_eq!(recovered, [ErrorCategory::DeviceLost]);
+    }
+
+    #[test]
+    fn facilities_come_from_the_facility_bits() {
+        assert_eq!(
+            HresultFacility::from_hresult(DXGI_ERROR_DEVICE_REMOVED),
+            HresultFacility::Dxgi
+        );
+        assert_eq!(
+            HresultFacility::from_hresult(D3D12_ERROR_ADAPTER_NOT_FOUND),
+            HresultFacility::D3d12
+        );
+        assert_eq!(
+            HresultFacility::from_hresult(E_ACCESSDENIED),
+            HresultFacility::Win32
+        );
+        assert_eq!(
+            HresultFacility::from_hresult(E_NOTIMPL),
+            HresultFacility::Generic
+        );
+    }
+
+    #[test]
+    fn exit_codes_are_distinct_and_nonzero() {
+        let mut codes: Vec<u8> = ALL.iter().map(|category| category.exit_code()).collect();
+        codes.sort_unstable();
+        codes.dedup();
+        assert_eq!(codes.len(), ALL.len());
+        assert!(!codes.contains(&0));
+        assert_eq!(ErrorCategory::Other.exit_code(), 1);
+    }
+
+    #[test]
+    fn only_win32_and_other_lack_hints() {
+        for category in ALL {
+            assert_eq!(
+                category.hint().is_none(),
+                matches!(category, ErrorCategory::Win32 | ErrorCategory::Other),
+                "{category}"
+            );
+        }
+    }
+
+    #[test]
+    fn reports_are_classified_by_the_first_known_cause() {
+        let report = eyre::Report::new(HresultError(DXGI_ERROR_DEVICE_HUNG)).wrap_err("Present");
+        assert_eq!(report_category(&report, hresult), ErrorCategory::DeviceLost);
+
+        let report = eyre::Report::new(ShaderCompileError {
+            path: "shaders.hlsl".into(),
+            entry_point: "VSMain".into(),
+            target: "vs_5_0".into(),
+            code: 0x80004005_u32 as i32,
+            diagnostics: "error X3004: undeclared identifier".into(),
+        })
+        .wrap_err("Failed to build the pipeline");
+        assert_eq!(
+            report_category(&report, hresult),
+            ErrorCategory::ShaderCompilation
+        );
+
+        assert_eq!(
+            report_category(&eyre::eyre!("no HRESULT"), hresult),
+            ErrorCategory::Other
+        );
+    }
+
+    #[test]
+    fn unrecovered_device_loss_is_classified_by_its_removed_reason() {
+        let mut policy = BoundedRetryPolicy::new(0, Duration::ZERO, 1);
+        let hung = DeviceLoss {
+            kind: DeviceLossKind::Hung,
+            removed_reason: None,
+        };
+        let report = recreate_after_loss(&mut policy, hung, |_| Ok(())).unwrap_err();
+        assert_eq!(report_category(&report, hresult), ErrorCategory::DeviceLost);
+
+        let invalid_call = DeviceLoss {
+            kind: DeviceLossKind::Removed,
+            removed_reason: Some(DeviceLossKind::InvalidCall),
+        };
+        let report = recreate_after_loss(&mut policy, invalid_call, |_| Ok(())).unwrap_err();
+        assert_eq!(
+            report_category(&report, hresult),
+            ErrorCategory::InvalidCall
+        );
+    }
+}
@@ -0,0 +1,463 @@
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::mpsc;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
+use tracing::debug;
+
+/// Reports which watched files changed. The renderer polls modification times; anything else
+/// that can name changed paths, such as a scripted list of events, works too.
+pub trait ChangeSource: Send + 'static {
+    /// Paths that changed since the last call.
+    fn poll_changes(&mut self) -> Vec<PathBuf>;
+}
+
+/// Watches files by comparing their modification times on every poll, so no platform file
+/// watcher is needed. A file that disappears or reappears counts as changed.
+///
+/// Clones share the watch list, so a rebuild can add the files it turned out to depend on.
+#[derive(Debug, Clone, Default)]
+pub struct ModifiedTimeSource {
+    files: Arc<Mutex<Vec<WatchedFile>>>,
+}
+
+#[derive(Debug, Clone)]
+struct WatchedFile {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+impl ModifiedTimeSource {
+    pub fn new(paths: impl IntoIterator<Item = PathBuf>) -> Self {
+        let source = Self::default();
+        source.watch(paths);
+        source
+    }
+
+    /// Start watching any of `paths` not already watched. Files are never unwatched; one that is
+    /// no longer a dependency only costs a rebuild when it changes.
+    pub fn watch(&self, paths: impl IntoIterator<Item = PathBuf>) {
+        let mut files = self.files.lock().expect("watch list lock poisoned");
+        for path in paths {
+            if !files.iter().any(|file| file.path == path) {
+                let modified = modified_time(&path);
+                files.push(WatchedFile { path, modified });
+            }
+        }
+    }
+
+    pub fn watched(&self) -> Vec<PathBuf> {
+        let files = self.files.lock().expect("watch list lock poisoned");
+        files.iter().map(|file| file.path.clone()).collect()
+    }
+}
+
+impl ChangeSource for ModifiedTimeSource {
+    fn poll_changes(&mut self) -> Vec<PathBuf> {
+        let mut changed = Vec::new();
+        let mut files = self.files.lock().expect("watch list lock poisoned");
+        for file in files.iter_mut() {
+            let modified = modified_time(&file.path);
+            if modified != file.modified {
+                file.modified = modified;
+                changed.push(file.path.clone());
+            }
+        }
+        changed
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// A request to rebuild after `paths` changed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReloadRequest {
+    /// Increases with every request, so results can be ordered however the builds finish.
+    pub version: u64,
+    pub paths: Vec<PathBuf>,
+}
+
+/// Collects changes until none have arrived for the debounce period, so that an editor that
+/// saves a file in several writes triggers a single rebuild.
+#[derive(Debug, Clone)]
+pub struct ReloadDebouncer {
+    debounce: Duration,
+    changed: Vec<PathBuf>,
+    last_change: Option<Instant>,
+    next_version: u64,
+}
+
+impl ReloadDebouncer {
+    pub fn new(debounce: Duration) -> Self {
+        Self {
+            debounce,
+            changed: Vec::new(),
+            last_change: None,
+            next_version: 1,
+        }
+    }
+
+    pub fn on_change(&mut self, path: PathBuf, now: Instant) {
+        if !self.changed.contains(&path) {
+            self.changed.push(path);
+        }
+        self.last_change = Some(now);
+    }
+
+    /// The rebuild to start once the changes have settled.
+    pub fn poll(&mut self, now: Instant) -> Option<ReloadRequest> {
+        let last_change = self.last_change?;
+        if now.saturating_duration_since(last_change) < self.debounce {
+            return None;
+        }
+        self.last_change = None;
+        let version = self.next_version;
+        self.next_version += 1;
+        Some(ReloadRequest {
+            version,
+            paths: std::mem::take(&mut self.changed),
+        })
+    }
+}
+
+/// Turns a [`ChangeSource`] into debounced [`ReloadRequest`]s.
+#[derive(Debug, Clone)]
+pub struct AssetWatcher<S> {
+    source: S,
+    debouncer: ReloadDebouncer,
+}
+
+impl<S: ChangeSource> AssetWatcher<S> {
+    pub fn new(source: S, debounce: Duration) -> Self {
+        Self {
+            source,
+            debouncer: ReloadDebouncer::new(debounce),
+        }
+    }
+
+    pub fn tick(&mut self, now: Instant) -> Option<ReloadRequest> {
+        for path in self.source.poll_changes() {
+            self.debouncer.on_change(path, now);
+        }
+        self.debouncer.poll(now)
+    }
+}
+
+/// Drops results older than one already accepted, which happens when an earlier build finishes
+/// after a later one. A failed build still counts: its files are newer than the earlier result's.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct VersionGate {
+    latest: u64,
+}
+
+impl VersionGate {
+    pub fn accept(&mut self, version: u64) -> bool {
+        if version <= self.latest {
+            return false;
+        }
+        self.latest = version;
+        true
+    }
+
+    pub fn latest(&self) -> u64 {
+        self.latest
+    }
+}
+
+/// A finished rebuild.
+#[derive(Debug)]
+pub struct Reloaded<T> {
+    pub version: u64,
+    pub paths: Vec<PathBuf>,
+    pub result: eyre::Result<T>,
+}
+
+/// Rebuilds an asset on the tokio runtime whenever its files change, and hands the results to
+/// the thread that owns the asset. Dropping the reloader stops the watch.
+#[derive(Debug)]
+pub struct HotReloader<T> {
+    results: mpsc::Receiver<Reloaded<T>>,
+    gate: VersionGate,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl<T: Send + 'static> HotReloader<T> {
+    pub fn spawn<S: ChangeSource>(
+        runtime: &tokio::runtime::Handle,
+        mut watcher: AssetWatcher<S>,
+        poll_interval: Duration,
+        build: impl Fn(&ReloadRequest) -> eyre::Result<T> + Send + Sync + 'static,
+    ) -> Self {
+        let (sender, results) = mpsc::channel();
+        let build = Arc::new(build);
+        let task = runtime.spawn(async move {
+            let mut interval = tokio::time::interval(poll_interval);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                // Polling reads file metadata, which blocks, so it runs off the runtime's workers.
+                let polled = tokio::task::spawn_blocking(move || {
+                    let request = watcher.tick(Instant::now());
+                    (watcher, request)
+                })
+                .await;
+                let Ok((returned, request)) = polled else {
+                    // The change source panicked and took the watcher with it.
+                    break;
+                };
+                watcher = returned;
+                let Some(request) = request else {
+                    continue;
+                };
+                debug!(version = request.version, paths = ?request.paths, "Rebuilding changed assets");
+                let build = Arc::clone(&build);
+                let sender = sender.clone();
+                // Builds block and may overlap; the receiving side keeps only the newest.
+                tokio::task::spawn_blocking(move || {
+                    let result = build(&request);
+                    let _ = sender.send(Reloaded {
+                        version: request.version,
+                        paths: request.paths,
+                        result,
+                    });
+                });
+            }
+        });
+        Self {
+            results,
+            gate: VersionGate::default(),
+            task,
+        }
+    }
+
+    /// Builds that finished since the last call, oldest first, without any that finished after
+    /// a newer one was already returned.
+    pub fn take_results(&mut self) -> Vec<Reloaded<T>> {
+        let mut results: Vec<Reloaded<T>> = self.results.try_iter().collect();
+        results.sort_by_key(|reloaded| reloaded.version);
+        results.retain(|reloaded| self.gate.accept(reloaded.version));
+        results
+    }
+}
+
+impl<T> Drop for HotReloader<T> {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Objects swapped out while the GPU may still be using them, kept alive until the fence value
+/// of the last submission that could reference them has completed.
+#[derive(Debug)]
+pub struct RetiredObjects<T> {
+    retiring: Vec<(u64, T)>,
+}
+
+impl<T> Default for RetiredObjects<T> {
+    fn default() -> Self {
+        Self {
+            retiring: Vec::new(),
+        }
+    }
+}
+
+impl<T> RetiredObjects<T> {
+    pub fn retire(&mut self, object: T, fence_value: u64) {
+        self.retiring.push((fence_value, object));
+    }
+
+    /// Release every object whose fence value has completed, returning how many were released.
+    pub fn release(&mut self, completed_fence_value: u64) -> usize {
+        let count = self.retiring.len();
+        self.retiring
+            .retain(|(fence_value, _)| *fence_value > completed_fence_value);
+        count - self.retiring.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.retiring.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.retiring.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    /// Reports a scripted batch of paths per poll.
+    #[derive(Debug, Clone, Default)]
+    struct ScriptedSource {
+        polls: Arc<Mutex<VecDeque<Vec<PathBuf>>>>,
+    }
+
+    impl ScriptedSource {
+        fn push(&self, paths: &[&str]) {
+            let paths = paths.iter().map(PathBuf::from).collect();
+            self.polls.lock().unwrap().push_back(paths);
+        }
+    }
+
+    impl ChangeSource for ScriptedSource {
+        fn poll_changes(&mut self) -> Vec<PathBuf> {
+            self.polls.lock().unwrap().pop_front().unwrap_or_default()
+        }
+    }
+
+    fn paths(paths: &[&str]) -> Vec<PathBuf> {
+        paths.iter().map(PathBuf::from).collect()
+    }
+
+    #[test]
+    fn modified_times_report_edits_removals_and_recreation() {
+        let directory =
+            std::env::temp_dir().join(format!("hot_reload_test_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("shaders.hlsl");
+        std::fs::write(&path, "float4 main() : SV_Target { return 0; }").unwrap();
+
+        let changed = vec![path.clone()];
+        let mut source = ModifiedTimeSource::new([path.clone(), path.clone()]);
+        assert_eq!(source.watched(), changed);
+        assert!(source.poll_changes().is_empty());
+
+        let file = std::fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(60))
+            .unwrap();
+        drop(file);
+        assert_eq!(source.poll_changes(), changed);
+        assert!(source.poll_changes().is_empty());
+
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(source.poll_changes(), changed);
+        assert!(source.poll_changes().is_empty());
+
+        std::fs::write(&path, "").unwrap();
+        assert_eq!(source.poll_changes(), changed);
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn clones_share_the_watch_list() {
+        let source = ModifiedTimeSource::new(paths(&["a.hlsl"]));
+        let clone = source.clone();
+        clone.watch(paths(&["b.hlsl", "a.hlsl"]));
+        assert_eq!(source.watched(), paths(&["a.hlsl", "b.hlsl"]));
+    }
+
+    #[test]
+    fn debouncer_waits_for_changes_to_settle() {
+        let start = Instant::now();
+        let debounce = Duration::from_millis(100);
+        let mut debouncer = ReloadDebouncer::new(debounce);
+        assert_eq!(debouncer.poll(start), None);
+
+        debouncer.on_change(PathBuf::from("a.hlsl"), start);
+        debouncer.on_change(PathBuf::from("b.hlsl"), start + debounce / 2);
+        debouncer.on_change(PathBuf::from("a.hlsl"), start + debounce / 2);
+        // The second write restarted the wait.
+        assert_eq!(debouncer.poll(start + debounce), None);
+        assert_eq!(
+            debouncer.poll(start + debounce * 3 / 2),
+            Some(ReloadRequest {
+                version: 1,
+                paths: paths(&["a.hlsl", "b.hlsl"]),
+            })
+        );
+        assert_eq!(debouncer.poll(start + debounce * 10), None);
+
+        debouncer.on_change(PathBuf::from("b.hlsl"), start + debounce * 10);
+        let request = debouncer.poll(start + debounce * 11).unwrap();
+        assert_eq!(request.version, 2);
+        assert_eq!(request.paths, paths(&["b.hlsl"]));
+    }
+
+    #[test]
+    fn watcher_debounces_its_source() {
+        let source = ScriptedSource::default();
+        source.push(&["a.hlsl"]);
+        source.push(&["b.hlsl"]);
+        let mut watcher = AssetWatcher::new(source, Duration::from_millis(10));
+        let start = Instant::now();
+        assert_eq!(watcher.tick(start), None);
+        assert_eq!(watcher.tick(start + Duration::from_millis(5)), None);
+        let request = watcher.tick(start + Duration::from_millis(20)).unwrap();
+        assert_eq!(request.paths, paths(&["a.hlsl", "b.hlsl"]));
+    }
+
+    #[test]
+    fn version_gate_drops_results_older_than_the_latest() {
+        let mut gate = VersionGate::default();
+        assert!(gate.accept(2));
+        assert!(!gate.accept(1));
+        assert!(!gate.accept(2));
+        assert!(gate.accept(3));
+        assert_eq!(gate.latest(), 3);
+    }
+
+    #[test]
+    fn reloader_builds_off_the_runtime_and_delivers_in_order() {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_time()
+            .build()
+            .unwrap();
+        let source = ScriptedSource::default();
+        source.push(&["shaders.hlsl"]);
+        let watcher = AssetWatcher::new(source.clone(), Duration::ZERO);
+        let mut reloader = HotReloader::spawn(
+            runtime.handle(),
+            watcher,
+            Duration::from_millis(1),
+            |request: &ReloadRequest| {
+                if request
+                    .paths
+                    .iter()
+                    .any(|path| path.ends_with("broken.hlsl"))
+                {
+                    eyre::bail!("syntax error");
+                }
+                Ok(request.version * 10)
+            },
+        );
+
+        let mut results = Vec::new();
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while results.len() < 2 && Instant::now() < deadline {
+            let taken = reloader.take_results();
+            if !taken.is_empty() && results.is_empty() {
+                source.push(&["broken.hlsl"]);
+            }
+            results.extend(taken);
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].version, 1);
+        assert_eq!(results[0].paths, paths(&["shaders.hlsl"]));
+        assert_eq!(results[0].result.as_ref().unwrap(), &10);
+        assert_eq!(results[1].version, 2);
+        assert!(results[1].result.is_err());
+    }
+
+    #[test]
+    fn retired_objects_wait_for_their_fence() {
+        let mut retired = RetiredObjects::default();
+        retired.retire("old pipeline", 3);
+        retired.retire("old root signature", 5);
+        assert_eq!(retired.release(2), 0);
+        assert_eq!(retired.release(3), 1);
+        assert_eq!(retired.len(), 1);
+        assert_eq!(retired.release(9), 1);
+        assert!(retired.is_empty());
+    }
+}
@@ -0,0 +1,416 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Presents older than this many entries are dropped without a match, which bounds memory when
+/// the swap chain never reports statistics (e.g. when the window is occluded).
+const MAX_PENDING_PRESENTS: usize = 64;
+
+/// A measured refresh period that differs from the running estimate by more than this fraction
+/// is treated as a refresh-rate change rather than jitter.
+const REFRESH_RATE_CHANGE_THRESHOLD: f64 = 0.1;
+const REFRESH_PERIOD_SMOOTHING: f64 = 0.1;
+
+/// What the renderer knows about a frame when it calls `Present`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PresentRecord {
+    /// `GetLastPresentCount` immediately after this frame's `Present`.
+    pub present_count: u32,
+    /// QPC tick at which the input used by this frame was sampled.
+    pub input_qpc: u64,
+    /// QPC tick just before `Present` was called.
+    pub present_qpc: u64,
+}
+
+/// The subset of `DXGI_FRAME_STATISTICS` the estimator needs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PresentStatistics {
+    /// The most recent present that reached the screen.
+    pub present_count: u32,
+    /// The vblank at which `present_count` reached the screen.
+    pub present_refresh_count: u32,
+    /// The vblank at which `sync_qpc` was sampled.
+    pub sync_refresh_count: u32,
+    pub sync_qpc: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LatencySample {
+    pub present_count: u32,
+    /// Age of the input when the frame started scanning out.
+    pub input_to_scanout: Duration,
+    pub present_to_scanout: Duration,
+    /// Whole refresh periods the frame waited between `Present` and scan-out.
+    pub missed_refreshes: u32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LatencyUpdate {
+    pub samples: Vec<LatencySample>,
+    /// Presents that were replaced before they were ever reported as displayed.
+    pub unmatched_presents: u32,
+    /// Set when the measured refresh period jumped, with the new period.
+    pub refresh_period_changed: Option<Duration>,
+}
+
+/// Matches presents against swap chain statistics to estimate when each frame reached the
+/// screen, and from that how old its input was.
+#[derive(Debug, Clone)]
+pub struct LatencyEstimator {
+    qpc_frequency: u64,
+    pending: VecDeque<PresentRecord>,
+    last_statistics: Option<PresentStatistics>,
+    refresh_period_ticks: Option<f64>,
+}
+
+impl LatencyEstimator {
+    pub fn new(qpc_frequency: u64) -> Self {
+        Self {
+            qpc_frequency,
+            pending: VecDeque::new(),
+            last_statistics: None,
+            refresh_period_ticks: None,
+        }
+    }
+
+    pub fn refresh_period(&self) -> Option<Duration> {
+        self.refresh_period_ticks
+            .map(|ticks| Duration::from_secs_f64(ticks / self.qpc_frequency as f64))
+    }
+
+    pub fn pending_presents(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn record_present(&mut self, record: PresentRecord) -> u32 {
+        self.pending.push_back(record);
+        let mut dropped = 0;
+        while self.pending.len() > MAX_PENDING_PRESENTS {
+            self.pending.pop_front();
+            dropped += 1;
+        }
+        dropped
+    }
+
+    /// Forget everything; used when DXGI reports that the statistics are disjoint (mode change,
+    /// fullscreen transition, device reset).
+    pub fn reset(&mut self) {
+        self.pending.clear();
+        self.last_statistics = None;
+        self.refresh_period_ticks = None;
+    }
+
+    pub fn observe(&mut self, statistics: PresentStatistics) -> LatencyUpdate {
+        let mut update = LatencyUpdate::default();
+
+        if let Some(previous) = self.last_statistics {
+            if is_before(statistics.sync_refresh_count, previous.sync_refresh_count)
+                || statistics.sync_qpc < previous.sync_qpc
+            {
+                // The counters went backwards, so nothing recorded so far can be trusted.
+                self.reset();
+            } else {
+                update.refresh_period_changed = self.update_refresh_period(&previous, &statistics);
+            }
+        }
+        self.last_statistics = Some(statistics);
+
+        let Some(scanout_qpc) = self.scanout_qpc(&statistics) else {
+            return update;
+        };
+
+        while let Some(record) = self.pending.front().copied() {
+            if is_before(statistics.present_count, record.present_count) {
+                break;
+            }
+
+            self.pending.pop_front();
+            if record.present_count != statistics.present_count {
+                update.unmatched_presents += 1;
+                continue;
+            }
+
+            update.samples.push(LatencySample {
+                present_count: record.present_count,
+                input_to_scanout: self
+                    .ticks_to_duration(scanout_qpc.saturating_sub(record.input_qpc)),
+                present_to_scanout: self
+                    .ticks_to_duration(scanout_qpc.saturating_sub(record.present_qpc)),
+                missed_refreshes: self.refresh_period_ticks.map_or(0, |period| {
+                    (scanout_qpc.saturating_sub(record.present_qpc) as f64 / period) as u32
+                }),
+            });
+        }
+
+        update
+    }
+
+    fn update_refresh_period(
+        &mut self,
+        previous: &PresentStatistics,
+        current: &PresentStatistics,
+    ) -> Option<Duration> {
+        let refreshes = current
+            .sync_refresh_count
+            .wrapping_sub(previous.sync_refresh_count);
+        if refreshes == 0 {
+            return None;
+        }
+
+        let measured = (current.sync_qpc - previous.sync_qpc) as f64 / f64::from(refreshes);
+        match self.refresh_period_ticks {
+            None => {
+                self.refresh_period_ticks = Some(measured);
+                None
+            }
+            Some(period)
+                if ((measured - period) / period).abs() > REFRESH_RATE_CHANGE_THRESHOLD =>
+            {
+                self.refresh_period_ticks = Some(measured);
+                self.refresh_period()
+            }
+            Some(period) => {
+                self.refresh_period_ticks =
+                    Some(period + (measured - period) * REFRESH_PERIOD_SMOOTHING);
+                None
+            }
+        }
+    }
+
+    /// The QPC time at which `statistics.present_count` started scanning out, extrapolated back
+    /// from the latest vblank sample.
+    fn scanout_qpc(&self, statistics: &PresentStatistics) -> Option<u64> {
+        let refreshes_since = statistics
+            .sync_refresh_count
+            .wrapping_sub(statistics.present_refresh_count);
+        if refreshes_since == 0 {
+            return Some(statistics.sync_qpc);
+        }
+        if refreshes_since > u32::MAX / 2 {
+            return None;
+        }
+
+        let period = self.refresh_period_ticks?;
+        let offset = (period * f64::from(refreshes_since)).round() as u64;
+        statistics.sync_qpc.checked_sub(offset)
+    }
+
+    fn ticks_to_duration(&self, ticks: u64) -> Duration {
+        crate::graphics::gpu_timing::ticks_to_duration(ticks, self.qpc_frequency)
+    }
+}
+
+/// Wrapping comparison for DXGI's 32-bit present and refresh counters.
+fn is_before(a: u32, b: u32) -> bool {
+    (a.wrapping_sub(b) as i32) < 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One QPC tick per microsecond keeps the expected durations readable.
+    const QPC_FREQUENCY: u64 = 1_000_000;
+    const PERIOD: u64 = 16_000;
+
+    fn statistics(
+        present_count: u32,
+        present_refresh_count: u32,
+        sync_refresh_count: u32,
+        sync_qpc: u64,
+    ) -> PresentStatistics {
+        PresentStatistics {
+            present_count,
+            present_refresh_count,
+            sync_refresh_count,
+            sync_qpc,
+        }
+    }
+
+    fn present(present_count: u32, input_qpc: u64, present_qpc: u64) -> PresentRecord {
+        PresentRecord {
+            present_count,
+            input_qpc,
+            present_qpc,
+        }
+    }
+
+    /// An estimator that has seen two vblanks `PERIOD` apart, ending at refresh 101.
+    fn calibrated() -> LatencyEstimator {
+        let mut estimator = LatencyEstimator::new(QPC_FREQUENCY);
+        estimator.observe(statistics(0, 0, 100, 100 * PERIOD));
+        estimator.observe(statistics(0, 0, 101, 101 * PERIOD));
+        assert_eq!(estimator.refresh_period(), Some(Duration::from_millis(16)));
+        estimator
+    }
+
+    #[test]
+    fn matching_present_produces_a_sample() {
+        let mut estimator = calibrated();
+        estimator.record_present(present(1, 101 * PERIOD + 4_000, 101 * PERIOD + 9_000));
+
+        let update = estimator.observe(statistics(1, 102, 102, 102 * PERIOD));
+        assert_eq!(
+            update.samples,
+            [LatencySample {
+                present_count: 1,
+                input_to_scanout: Duration::from_millis(12),
+                present_to_scanout: Duration::from_millis(7),
+                missed_refreshes: 0,
+            }]
+        );
+        assert_eq!(update.unmatched_presents, 0);
+        assert_eq!(estimator.pending_presents(), 0);
+    }
+
+    #[test]
+    fn scanout_is_extrapolated_back_from_a_later_vblank() {
+        let mut estimator = calibrated();
+        estimator.record_present(present(1, 101 * PERIOD, 101 * PERIOD));
+
+        // Present 1 reached the screen at refresh 103, but the sample was taken at 105.
+        let update = estimator.observe(statistics(1, 103, 105, 105 * PERIOD));
+        let [sample] = update.samples[..] else {
+            panic!("expected one sample, got {:?}", update.samples);
+        };
+        assert_eq!(sample.present_to_scanout, Duration::from_millis(32));
+        assert_eq!(sample.missed_refreshes, 2);
+    }
+
+    #[test]
+    fn presents_skipped_by_the_statistics_are_unmatched() {
+        let mut estimator = calibrated();
+        for present_count in 1..=3 {
+            estimator.record_present(present(present_count, 101 * PERIOD, 101 * PERIOD));
+        }
+
+        let update = estimator.observe(statistics(3, 102, 102, 102 * PERIOD));
+        assert_eq!(update.unmatched_presents, 2);
+        assert_eq!(update.samples.len(), 1);
+        assert_eq!(update.samples[0].present_count, 3);
+    }
+
+    #[test]
+    fn presents_not_yet_displayed_stay_pending() {
+        let mut estimator = calibrated();
+        estimator.record_present(present(1, 101 * PERIOD, 101 * PERIOD));
+        estimator.record_present(present(2, 101 * PERIOD, 101 * PERIOD));
+
+        let update = estimator.observe(statistics(0, 101, 102, 102 * PERIOD));
+        assert_eq!(update, LatencyUpdate::default());
+        assert_eq!(estimator.pending_presents(), 2);
+
+        let update = estimator.observe(statistics(1, 103, 103, 103 * PERIOD));
+        assert_eq!(update.samples.len(), 1);
+        assert_eq!(estimator.pending_presents(), 1);
+    }
+
+    #[test]
+    fn presents_wait_until_the_refresh_period_is_known() {
+        let mut estimator = LatencyEstimator::new(QPC_FREQUENCY);
+        estimator.record_present(present(1, 0, 0));
+
+        // The scan-out time cannot be extrapolated without a period.
+        let update = estimator.observe(statistics(1, 1, 3, 3 * PERIOD));
+        assert!(update.samples.is_empty());
+        assert_eq!(estimator.pending_presents(), 1);
+    }
+
+    #[test]
+    fn oldest_presents_are_dropped_past_the_limit() {
+        let mut estimator = calibrated();
+        for present_count in 1..=MAX_PENDING_PRESENTS as u32 {
+            assert_eq!(estimator.record_present(present(present_count, 0, 0)), 0);
+        }
+        assert_eq!(
+            estimator.record_present(present(MAX_PENDING_PRESENTS as u32 + 1, 0, 0)),
+            1
+        );
+        assert_eq!(estimator.pending_presents(), MAX_PENDING_PRESENTS);
+
+        // Present 1 is gone, so everything up to the reported one but itself is unmatched.
+        let update = estimator.observe(statistics(
+            MAX_PENDING_PRESENTS as u32 + 1,
+            102,
+            102,
+            102 * PERIOD,
+        ));
+        assert_eq!(update.unmatched_presents, MAX_PENDING_PRESENTS as u32 - 1);
+        assert_eq!(update.samples.len(), 1);
+    }
+
+    #[test]
+    fn small_period_changes_are_smoothed() {
+        let mut estimator = calibrated();
+        // 3% longer than the estimate.
+        let update = estimator.observe(statistics(0, 0, 102, 101 * PERIOD + 16_480));
+        assert_eq!(update.refresh_period_changed, None);
+        assert_eq!(
+            estimator.refresh_period(),
+            Some(Duration::from_micros(16_048))
+        );
+    }
+
+    #[test]
+    fn large_period_changes_are_reported() {
+        let mut estimator = calibrated();
+        // The display switched from 62.5 Hz to 125 Hz.
+        let update = estimator.observe(statistics(0, 0, 103, 101 * PERIOD + 16_000));
+        assert_eq!(
+            update.refresh_period_changed,
+            Some(Duration::from_millis(8))
+        );
+        assert_eq!(estimator.refresh_period(), Some(Duration::from_millis(8)));
+    }
+
+    #[test]
+    fn counters_compare_across_wraparound() {
+        assert!(is_before(u32::MAX, 0));
+        assert!(is_before(u32::MAX - 5, 3));
+        assert!(!is_before(0, u32::MAX));
+        assert!(!is_before(7, 7));
+        assert!(is_before(1, 2));
+    }
+
+    #[test]
+    fn presents_match_across_counter_wraparound() {
+        let mut estimator = LatencyEstimator::new(QPC_FREQUENCY);
+        estimator.observe(statistics(u32::MAX - 1, u32::MAX, u32::MAX, 100 * PERIOD));
+        estimator.observe(statistics(u32::MAX - 1, u32::MAX, 0, 101 * PERIOD));
+        assert_eq!(estimator.refresh_period(), Some(Duration::from_millis(16)));
+
+        estimator.record_present(present(u32::MAX, 101 * PERIOD, 101 * PERIOD));
+        estimator.record_present(present(0, 101 * PERIOD, 101 * PERIOD));
+        estimator.record_present(present(1, 101 * PERIOD, 101 * PERIOD));
+
+        let update = estimator.observe(statistics(0, 1, 1, 102 * PERIOD));
+        assert_eq!(update.unmatched_presents, 1);
+        assert_eq!(update.samples.len(), 1);
+        assert_eq!(update.samples[0].present_count, 0);
+        assert_eq!(estimator.pending_presents(), 1);
+    }
+
+    #[test]
+    fn refresh_counter_going_backwards_resets() {
+        let mut estimator = calibrated();
+        estimator.record_present(present(1, 0, 0));
+
+        let update = estimator.observe(statistics(0, 0, 50, 102 * PERIOD));
+        assert_eq!(update, LatencyUpdate::default());
+        assert_eq!(estimator.pending_presents(), 0);
+        assert_eq!(estimator.refresh_period(), None);
+    }
+
+    #[test]
+    fn sync_time_going_backwards_resets() {
+        let mut estimator = calibrated();
+        estimator.record_present(present(1, 0, 0));
+
+        estimator.observe(statistics(0, 0, 102, 50 * PERIOD));
+        assert_eq!(estimator.pending_presents(), 0);
+        assert_eq!(estimator.refresh_period(), None);
+
+        // The sample after the reset is the new baseline for measuring the period.
+        estimator.observe(statistics(0, 0, 103, 50 * PERIOD + PERIOD));
+        assert_eq!(estimator.refresh_period(), Some(Duration::from_millis(16)));
+    }
+}
@@ -1,64 +1,297 @@
+pub mod adapter_selection;
+pub mod cursor_transform;
+pub mod d3d12_backend;
+pub mod debug_messages;
+pub mod descriptor_allocator;
+pub mod device_recovery;
+pub mod frame_scheduler;
+pub mod frame_stats;
+pub mod gpu_timing;
+pub mod graphics_error;
+pub mod hot_reload;
+pub mod latency;
+pub mod resize;
+pub mod resource_state;
+pub mod ring_allocator;
+pub mod root_signature;
+pub mod scene;
+pub mod shader_include;
+pub mod shader_source;
+pub mod timeline;
+pub mod upload_buffer;
+
+use crate::graphics::adapter_selection::AdapterOptions;
+use crate::graphics::adapter_selection::FeatureLevel;
+use crate::graphics::adapter_selection::GpuPreference;
+use crate::graphics::adapter_selection::select_adapter;
+use crate::graphics::cursor_transform::CONSTANT_BUFFER_ALIGNMENT;
+use crate::graphics::cursor_transform::CursorConstants;
+use crate::graphics::d3d12_backend::D3d12Bindings;
+use crate::graphics::d3d12_backend::PresentRequest;
+use crate::graphics::d3d12_backend::d3d12_cpu_handle;
+use crate::graphics::d3d12_backend::record_commands;
+use crate::graphics::d3d12_backend::transition_barrier;
+use crate::graphics::debug_messages::DebugLayerOptions;
+use crate::graphics::debug_messages::DebugMessage;
+use crate::graphics::debug_messages::MessageCursor;
+use crate::graphics::debug_messages::MessageSeverity;
+use crate::graphics::debug_messages::MessageSource;
+use crate::graphics::debug_messages::debug_layer_options;
+use crate::graphics::descriptor_allocator::CpuDescriptorHandle;
+use crate::graphics::descriptor_allocator::DescriptorAllocator;
+use crate::graphics::descriptor_allocator::DescriptorHeapLayout;
+use crate::graphics::descriptor_allocator::GpuDescriptorHandle;
+use crate::graphics::device_recovery::BoundedRetryPolicy;
+use crate::graphics::device_recovery::DeviceLoss;
+use crate::graphics::device_recovery::DeviceLossKind;
+use crate::graphics::device_recovery::RecoveryPolicy;
+use crate::graphics::device_recovery::RecreateFailure;
+use crate::graphics::device_recovery::recreate_after_loss;
+use crate::graphics::frame_scheduler::FrameScheduler;
+use crate::graphics::frame_stats::FrameStats;
+use crate::graphics::frame_stats::FrameStatsReport;
+use crate::graphics::gpu_timing::GpuTimer;
+use crate::graphics::graphics_error::ErrorCategory;
+use crate::graphics::graphics_error::ErrorDisposition;
+use crate::graphics::graphics_error::ShaderCompileError;
+use crate::graphics::graphics_error::report_category;
+use crate::graphics::hot_reload::AssetWatcher;
+use crate::graphics::hot_reload::HotReloader;
+use crate::graphics::hot_reload::ModifiedTimeSource;
+use crate::graphics::hot_reload::RetiredObjects;
+use crate::graphics::latency::LatencyEstimator;
+use crate::graphics::latency::PresentRecord;
+use crate::graphics::latency::PresentStatistics;
+use crate::graphics::resize::ResizeAction;
+use crate::graphics::resize::ResizeController;
+use crate::graphics::resize::WindowSizeEvent;
+use crate::graphics::resize::initial_swap_chain_size;
+use crate::graphics::resource_state::ResourceId;
+use crate::graphics::resource_state::ResourceStateTracker;
+use crate::graphics::root_signature::AddressMode;
+use crate::graphics::root_signature::BorderColor;
+use crate::graphics::root_signature::DataFlag;
+use crate::graphics::root_signature::DescriptorRange;
+use crate::graphics::root_signature::DescriptorsFlag;
+use crate::graphics::root_signature::RegisterKind;
+use crate::graphics::root_signature::RootDescriptorKind;
+use crate::graphics::root_signature::RootParameter;
+use crate::graphics::root_signature::RootParameterKind;
+use crate::graphics::root_signature::RootSignatureBuilder;
+use crate::graphics::root_signature::RootSignatureDescription;
+use crate::graphics::root_signature::RootSignatureVersion;
+use crate::graphics::root_signature::SamplerFilter;
+use crate::graphics::root_signature::ShaderVisibility;
+use crate::graphics::root_signature::StaticSampler;
+use crate::graphics::scene::FrameDescription;
+use crate::graphics::scene::SceneDraws;
+use crate::graphics::scene::Vertex;
+use crate::graphics::scene::build_scene_vertices;
+use crate::graphics::scene::record_scene_frame;
+use crate::graphics::scene::vertex_bytes;
+use crate::graphics::shader_include::IncludeId;
+use crate::graphics::shader_include::IncludeKind;
+use crate::graphics::shader_include::IncludeResolver;
+use crate::graphics::shader_include::IncludeTracker;
+use crate::graphics::shader_include::normalize;
+use crate::graphics::shader_source::SHADER_DIR_ENV;
+use crate::graphics::shader_source::ShaderSource;
+use crate::graphics::timeline::FenceTimeline;
+use crate::graphics::upload_buffer::UploadBuffer;
+use crate::graphics::upload_buffer::create_default_buffer;
+use crate::graphics::upload_buffer::create_upload_buffer;
+use color_eyre::Section;
+use color_eyre::SectionExt;
+use command_ir::CommandRecorder;
+use command_ir::DRAW_CONSTANT_COUNT;
+use command_ir::ResourceState;
+use device_caps::AdapterInfo;
+use device_caps::DeviceCaps;
+use device_caps::root_signature_version;
+use device_caps::shader_model;
+use device_caps::tier;
+use device_caps::versioned_tier;
+use dxbc::DxbcContainer;
 use eyre::Context;
+use frame_capture::CaptureOptions;
+use frame_capture::CursorSample;
+use frame_capture::FrameCapture;
+use frame_capture::FrameCapturer;
+use pipeline_cache::PIPELINE_CACHE_MAX_BYTES;
+use pipeline_cache::PipelineCache;
+use pipeline_cache::PipelineCacheKey;
+use pipeline_cache::PipelineKeyBuilder;
+use pipeline_description::BlendTarget;
+use pipeline_description::Define;
+use pipeline_description::PipelineDescription;
+use pipeline_description::ShaderPermutation;
+use pipeline_description::ShaderStage;
+use shader_diagnostics::Diagnostics;
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::ffi::c_void;
+use std::path::Path;
 use std::path::PathBuf;
-use std::ptr::NonNull;
+use std::cell::RefCell;
+use std::time::Duration;
+use std::time::Instant;
 use teamy_windows::module::get_current_module;
 use teamy_windows::string::EasyPCWSTR;
 use tracing::info;
+use tracing::warn;
+use vertex_layout::VertexLayout;
 use windows::Win32::Foundation::{E_FAIL, FALSE, HANDLE, HWND, LPARAM, LRESULT, POINT, RECT, TRUE, WPARAM};
-use windows::Win32::Graphics::Direct3D::Fxc::{D3DCOMPILE_DEBUG, D3DCOMPILE_SKIP_OPTIMIZATION, D3DCompileFromFile};
-use windows::Win32::Graphics::Direct3D::{D3D_FEATURE_LEVEL_11_0, D3D_PRIMITIVE_TOPOLOGY_TRIANGLELIST, ID3DBlob};
+use windows::Win32::Graphics::Direct3D::Fxc::{D3DCOMPILE_DEBUG, D3DCOMPILE_SKIP_OPTIMIZATION, D3DCompile};
+use windows::Win32::Graphics::Direct3D::{
+    D3D_FEATURE_LEVEL, D3D_INCLUDE_SYSTEM, D3D_SHADER_MACRO, D3D_INCLUDE_TYPE, ID3DBlob, ID3DInclude, ID3DInclude_Impl,
+};
 use windows::Win32::Graphics::Direct3D12::*;
 use windows::Win32::Graphics::Dxgi::Common::*;
 use windows::Win32::Graphics::Dxgi::*;
-use windows::Win32::System::Threading::{CreateEventW, INFINITE, WaitForSingleObjectEx};
+use windows::Win32::System::Performance::{QueryPerformanceCounter, QueryPerformanceFrequency};
+use windows::Win32::System::Threading::{INFINITE, WaitForSingleObjectEx};
 use windows::Win32::UI::WindowsAndMessaging::*;
-use windows::core::{Error, HSTRING, Interface, Owned, PCSTR, s, w};
+use windows::core::{Error, Interface, Owned, PCSTR, w};
 
 const FRAME_COUNT: usize = 2;
+/// How many of the debug layer's most recent messages to attach to error reports.
+const DEBUG_MESSAGE_REPORT_LIMIT: u64 = 10;
+const FRAME_STATS_INTERVAL: Duration = Duration::from_secs(1);
+const RESIZE_DEBOUNCE: Duration = Duration::from_millis(100);
+const SHADER_RELOAD_DEBOUNCE: Duration = Duration::from_millis(150);
+const SHADER_RELOAD_POLL_INTERVAL: Duration = Duration::from_millis(250);
+const UPLOAD_BUFFER_CAPACITY: u64 = 64 * 1024;
+const UPLOAD_BUFFER_MAX_CAPACITY: u64 = 16 * 1024 * 1024;
 const WINDOW_CLASS_NAME: windows::core::PCWSTR = w!("DirectXLearningTransparentTriangleV6");
 
-const TRIANGLE_VERTEX_COUNT: usize = 3;
-const CURSOR_RING_SEGMENTS: usize = 48;
-const CURSOR_RING_VERTEX_COUNT: usize = CURSOR_RING_SEGMENTS * 6;
-const CURSOR_ARM_COUNT: usize = 4;
-const CURSOR_ARM_VERTEX_COUNT: usize = CURSOR_ARM_COUNT * 6;
-const MAX_VERTEX_COUNT: usize = TRIANGLE_VERTEX_COUNT + CURSOR_RING_VERTEX_COUNT + CURSOR_ARM_VERTEX_COUNT;
-
-const CURSOR_RING_INNER_RADIUS: f32 = 14.0;
-const CURSOR_RING_OUTER_RADIUS: f32 = 17.5;
-const CURSOR_ARM_LENGTH: f32 = 18.0;
-const CURSOR_ARM_THICKNESS: f32 = 2.5;
-const CURSOR_ARM_GAP: f32 = 7.0;
-
-#[repr(C)]
-#[derive(Clone, Copy, Debug)]
-struct Vertex {
-    position: [f32; 3],
-    color: [f32; 4],
-}
-
 #[derive(Debug, Clone)]
 pub struct TransparentTriangleOptions {
     pub width: u32,
     pub height: u32,
     pub use_warp_device: bool,
+    pub adapter: AdapterOptions,
     pub title: String,
+    pub capture: Option<CaptureOptions>,
+    /// Where compiled pipeline states are cached between runs; `None` disables the cache.
+    pub pipeline_cache: Option<PathBuf>,
+    /// Rebuild the pipeline whenever its shader or pipeline file changes.
+    pub hot_reload: bool,
+    /// Where the pipeline file and its shaders are read from.
+    pub shader_source: ShaderSource,
+    /// Values for the pipeline's permutation keys; the rest keep their defaults.
+    pub defines: Vec<Define>,
+    /// Fail shader compilation on warnings instead of logging them.
+    pub warnings_as_errors: bool,
 }
 
 pub fn run(options: TransparentTriangleOptions) -> eyre::Result<()> {
     info!(?options, "Starting transparent triangle sample");
+    info!(shaders = %options.shader_source, "Loading shaders");
+
+    let result =
+        run_until_closed(&options).with_section(|| format!("{options:#?}").header("Options:"));
+    // Every renderer has been dropped by now, so anything still alive has leaked.
+    if debug_layer_options().enabled {
+        report_live_objects();
+    }
+    result
+}
+
+/// Classify `report` by the first cause in its chain that says what went wrong.
+pub fn classify_error(report: &eyre::Report) -> ErrorCategory {
+    report_category(report, hresult)
+}
+
+/// The HRESULT carried by `cause`, if it is a Windows error.
+pub fn hresult(cause: &(dyn std::error::Error + 'static)) -> Option<i32> {
+    cause.downcast_ref::<Error>().map(|error| error.code().0)
+}
 
-    let hwnd = create_window(&options)?;
-    let mut renderer = Renderer::new(hwnd, &options)?;
+fn run_until_closed(options: &TransparentTriangleOptions) -> eyre::Result<()> {
+    let hwnd = create_window(options)?;
+    let mut scene = SceneState::new(options)?;
+    let mut recovery = BoundedRetryPolicy::default();
+    let mut renderer = Renderer::new(hwnd, options, &scene)?;
 
     unsafe {
         let _ = ShowWindow(hwnd, SW_SHOW);
     }
 
-    message_loop(&mut renderer)
+    loop {
+        let error = match message_loop(&mut renderer, &mut scene, &mut recovery) {
+            Ok(()) => return Ok(()),
+            Err(error) => error,
+        };
+        renderer.debug_messages.drain();
+        let Some(loss) = device_loss(&error, Some(&renderer.device)) else {
+            return Err(renderer.diagnostics.attach(error));
+        };
+
+        warn!(%loss, "Device lost, recreating the renderer");
+        // The old device has to be released before a new one can be created on the adapter.
+        drop(renderer);
+        renderer = recreate_after_loss(&mut recovery, loss, |after| {
+            std::thread::sleep(after);
+            Renderer::new(hwnd, options, &scene).map_err(|error| match device_loss(&error, None) {
+                Some(loss) => {
+                    warn!(%loss, "Device lost again while recreating the renderer");
+                    RecreateFailure::DeviceLost(loss)
+                }
+                None => RecreateFailure::Fatal(error),
+            })
+        })?;
+        info!(attempts = recovery.attempts(), "Renderer recreated");
+    }
+}
+
+/// State that outlives a renderer, so that recovering from device loss does not reset the scene.
+#[derive(Debug)]
+struct SceneState {
+    vertices: Vec<Vertex>,
+    draws: SceneDraws,
+    capturer: Option<FrameCapturer>,
+    frame_number: u64,
+}
+
+impl SceneState {
+    fn new(options: &TransparentTriangleOptions) -> eyre::Result<Self> {
+        let (vertices, draws) = build_scene_vertices();
+        Ok(Self {
+            vertices,
+            draws,
+            capturer: options.capture.clone().map(FrameCapturer::new).transpose()?,
+            frame_number: 0,
+        })
+    }
+}
+
+/// Classify `error` as device loss if it came from a call that reported the device as removed,
+/// asking `device` why when it is still available.
+fn device_loss(error: &eyre::Report, device: Option<&ID3D12Device>) -> Option<DeviceLoss> {
+    let code = error.chain().find_map(hresult)?;
+    let kind = DeviceLossKind::from_error_code(code)?;
+    let removed_reason = device
+        .and_then(|device| unsafe { device.GetDeviceRemovedReason() }.err())
+        .and_then(|reason| DeviceLossKind::from_removed_reason(reason.code().0));
+    Some(DeviceLoss {
+        kind,
+        removed_reason,
+    })
+}
+
+thread_local! {
+    /// `WM_SIZE` notifications queued by `window_proc` for the message loop.
+    static WINDOW_SIZE_EVENTS: RefCell<Vec<WindowSizeEvent>> = const { RefCell::new(Vec::new()) };
 }
 
-fn message_loop(renderer: &mut Renderer) -> eyre::Result<()> {
+fn message_loop(
+    renderer: &mut Renderer,
+    scene: &mut SceneState,
+    recovery: &mut impl RecoveryPolicy,
+) -> eyre::Result<()> {
+    let (width, height) = renderer.size();
+    let mut resize = ResizeController::new(width, height, RESIZE_DEBOUNCE);
+
     loop {
         let mut message = MSG::default();
         while unsafe { PeekMessageW(&mut message, None, 0, 0, PM_REMOVE) }.into() {
@@ -72,7 +305,27 @@ fn message_loop(renderer: &mut Renderer) -> eyre::Result<()> {
             }
         }
 
-        renderer.render()?;
+        let now = Instant::now();
+        for event in WINDOW_SIZE_EVENTS.with_borrow_mut(std::mem::take) {
+            resize.on_event(event, now);
+        }
+
+        match resize.poll(now) {
+            ResizeAction::Render => {}
+            ResizeAction::Paused => {
+                // Nothing to present until the window is restored.
+                unsafe { WaitMessage() }.wrap_err("Failed to wait for window messages")?;
+                continue;
+            }
+            ResizeAction::Resize { width, height } => {
+                renderer.resize(width, height)?;
+                resize.resized(width, height);
+            }
+        }
+
+        renderer.render(scene)?;
+        renderer.debug_messages.drain();
+        recovery.on_frame_rendered();
     }
 }
 
@@ -149,6 +402,18 @@ unsafe extern "system" fn window_proc(
             }
             LRESULT(0)
         }
+        WM_SIZE => {
+            let event = if wparam.0 as u32 == SIZE_MINIMIZED {
+                WindowSizeEvent::Minimized
+            } else {
+                WindowSizeEvent::Resized {
+                    width: (lparam.0 & 0xFFFF) as u32,
+                    height: ((lparam.0 >> 16) & 0xFFFF) as u32,
+                }
+            };
+            WINDOW_SIZE_EVENTS.with_borrow_mut(|events| events.push(event));
+            LRESULT(0)
+        }
         WM_NCHITTEST => LRESULT(HTCAPTION as isize),
         _ => unsafe { DefWindowProcW(hwnd, message, wparam, lparam) },
     }
@@ -158,38 +423,73 @@ unsafe extern "system" fn window_proc(
 struct Renderer {
     hwnd: HWND,
     _dxgi_factory: IDXGIFactory4,
-    _device: ID3D12Device,
+    device: ID3D12Device,
+    diagnostics: DeviceDiagnostics,
+    debug_messages: DebugMessagePump,
     allow_tearing: bool,
     command_queue: ID3D12CommandQueue,
     swap_chain: IDXGISwapChain3,
-    render_targets: [ID3D12Resource; FRAME_COUNT],
-    rtv_heap: ID3D12DescriptorHeap,
-    rtv_descriptor_size: u32,
+    render_targets: Vec<ID3D12Resource>,
+    render_target_ids: Vec<ResourceId>,
+    resource_states: ResourceStateTracker<ResourceState>,
+    _rtv_heap: ID3D12DescriptorHeap,
+    rtv_handles: [D3D12_CPU_DESCRIPTOR_HANDLE; FRAME_COUNT],
     command_allocators: [ID3D12CommandAllocator; FRAME_COUNT],
     command_list: ID3D12GraphicsCommandList,
-    fence: ID3D12Fence,
-    next_fence_value: u64,
-    frame_fence_values: [u64; FRAME_COUNT],
-    fence_event: Owned<HANDLE>,
+    frames: FrameScheduler<FenceTimeline, FRAME_COUNT>,
+    gpu_timer: GpuTimer<FRAME_COUNT>,
+    frame_stats: FrameStats,
+    latency: LatencyEstimator,
     frame_latency_waitable_object: Owned<HANDLE>,
     root_signature: ID3D12RootSignature,
+    pipeline: PipelineDescription,
     pipeline_state: ID3D12PipelineState,
+    shader_reload: Option<HotReloader<ReloadedPipeline>>,
+    /// Pipeline states replaced by hot reload that in-flight frames may still use.
+    retired_pipeline_states: RetiredObjects<ID3D12PipelineState>,
     vertex_buffer: ID3D12Resource,
-    vertex_buffer_ptr: NonNull<Vertex>,
-    vertex_buffer_view: D3D12_VERTEX_BUFFER_VIEW,
-    scratch_vertices: Vec<Vertex>,
-    viewport: D3D12_VIEWPORT,
-    scissor_rect: RECT,
+    vertex_buffer_size: u32,
+    recorder: CommandRecorder,
+    upload: UploadBuffer,
     width: u32,
     height: u32,
 }
 
 impl Renderer {
-    fn new(hwnd: HWND, options: &TransparentTriangleOptions) -> eyre::Result<Self> {
-        let (dxgi_factory, device) = create_device(options.use_warp_device)?;
+    fn new(
+        hwnd: HWND,
+        options: &TransparentTriangleOptions,
+        scene: &SceneState,
+    ) -> eyre::Result<Self> {
+        let (dxgi_factory, adapter, device) =
+            create_device(options.use_warp_device, &options.adapter)?;
+        let diagnostics = DeviceDiagnostics::new(&adapter, &device);
+        Self::with_device(
+            hwnd,
+            options,
+            scene,
+            dxgi_factory,
+            &adapter,
+            device,
+            diagnostics.clone(),
+        )
+        .map_err(|error| diagnostics.attach(error))
+    }
+
+    fn with_device(
+        hwnd: HWND,
+        options: &TransparentTriangleOptions,
+        scene: &SceneState,
+        dxgi_factory: IDXGIFactory4,
+        adapter: &AdapterInfo,
+        device: ID3D12Device,
+        diagnostics: DeviceDiagnostics,
+    ) -> eyre::Result<Self> {
+        let debug_messages = DebugMessagePump::new(&device, debug_layer_options());
         let allow_tearing = supports_allow_tearing(&dxgi_factory);
         let command_queue = create_command_queue(&device)?;
         let (width, height) = client_size(hwnd)?;
+        let (width, height) = initial_swap_chain_size(width, height);
         let swap_chain =
             create_swap_chain(&dxgi_factory, &command_queue, hwnd, width, height, allow_tearing)?;
         unsafe { dxgi_factory.MakeWindowAssociation(hwnd, DXGI_MWA_NO_ALT_ENTER)? };
@@ -197,13 +497,33 @@ impl Renderer {
         let frame_latency_waitable_object = unsafe {
             Owned::new(swap_chain.GetFrameLatencyWaitableObject())
         };
-        info!(allow_tearing, "Swap chain tearing support");
+        if allow_tearing {
+            info!("Swap chain tearing supported");
+        } else {
+            warn!("Tearing unsupported, falling back to presenting without DXGI_PRESENT_ALLOW_TEARING");
+        }
 
-        let (rtv_heap, rtv_descriptor_size, render_targets) =
-            create_render_targets(&device, &swap_chain)?;
+        let (rtv_heap, rtv_handles) = create_rtv_heap(&device)?;
+        let render_targets = create_render_targets(&device, &swap_chain, &rtv_handles)?;
+        let mut resource_states = ResourceStateTracker::default();
+        let render_target_ids = register_render_targets(&mut resource_states);
         let command_allocators = create_command_allocators(&device)?;
         let root_signature = create_root_signature(&device)?;
-        let pipeline_state = create_pipeline_state(&device, &root_signature)?;
+        let pipeline = options.shader_source.load_pipeline()?;
+        let shaders = compile_pipeline_shaders(
+            &pipeline,
+            &options.shader_source,
+            &options.defines,
+            options.warnings_as_errors,
+        )?;
+        let pipeline_state = create_pipeline_state(
+            &device,
+            &root_signature,
+            &pipeline,
+            &shaders,
+            adapter,
+            options.pipeline_cache.as_deref(),
+        )?;
         let command_list: ID3D12GraphicsCommandList = unsafe {
             device.CreateCommandList(
                 0,
@@ -213,124 +533,236 @@ impl Renderer {
             )
         }?;
         unsafe { command_list.Close()? };
-
-        let (vertex_buffer, vertex_buffer_view) = create_vertex_buffer(&device)?;
-        let vertex_buffer_ptr = {
-            let mut mapped = std::ptr::null_mut();
-            unsafe { vertex_buffer.Map(0, None, Some(&mut mapped))? };
-            NonNull::new(mapped as *mut Vertex)
-                .ok_or_else(|| eyre::eyre!("Vertex buffer map returned a null pointer"))?
-        };
-        let fence: ID3D12Fence = unsafe { device.CreateFence(0, D3D12_FENCE_FLAG_NONE) }?;
-        let fence_event = unsafe { Owned::new(CreateEventW(None, false, false, None)?) };
-
-        let viewport = D3D12_VIEWPORT {
-            TopLeftX: 0.0,
-            TopLeftY: 0.0,
-            Width: width as f32,
-            Height: height as f32,
-            MinDepth: D3D12_MIN_DEPTH,
-            MaxDepth: D3D12_MAX_DEPTH,
-        };
-        let scissor_rect = RECT {
-            left: 0,
-            top: 0,
-            right: width as i32,
-            bottom: height as i32,
+        let shader_reload = if options.hot_reload {
+            spawn_shader_reload(
+                &device,
+                &root_signature,
+                &pipeline,
+                &shaders,
+                adapter,
+                options,
+            )
+        } else {
+            None
         };
 
+        let upload = UploadBuffer::new(&device, UPLOAD_BUFFER_CAPACITY, UPLOAD_BUFFER_MAX_CAPACITY)?;
+        let mut frames = FrameScheduler::new(FenceTimeline::new(&device, &command_queue)?);
+        let vertex_buffer = create_vertex_buffer(
+            &device,
+            &command_queue,
+            &command_allocators[0],
+            &command_list,
+            &mut frames,
+            &scene.vertices,
+        )?;
+        let gpu_timer = GpuTimer::new(&device, &command_queue)?;
+        info!(calibration = ?gpu_timer.calibration(), "GPU timestamp calibration");
+
         Ok(Self {
             hwnd,
             _dxgi_factory: dxgi_factory,
-            _device: device,
+            device,
+            diagnostics,
+            debug_messages,
             allow_tearing,
             command_queue,
             swap_chain,
             render_targets,
-            rtv_heap,
-            rtv_descriptor_size,
+            render_target_ids,
+            resource_states,
+            _rtv_heap: rtv_heap,
+            rtv_handles,
             command_allocators,
             command_list,
-            fence,
-            next_fence_value: 1,
-            frame_fence_values: [0; FRAME_COUNT],
-            fence_event,
+            frames,
+            gpu_timer,
+            frame_stats: FrameStats::new(FRAME_STATS_INTERVAL, Instant::now()),
+            latency: LatencyEstimator::new(query_performance_frequency()?),
             frame_latency_waitable_object,
             root_signature,
+            pipeline,
             pipeline_state,
+            shader_reload,
+            retired_pipeline_states: RetiredObjects::default(),
             vertex_buffer,
-            vertex_buffer_ptr,
-            vertex_buffer_view,
-            scratch_vertices: Vec::with_capacity(MAX_VERTEX_COUNT),
-            viewport,
-            scissor_rect,
+            vertex_buffer_size: std::mem::size_of_val(scene.vertices.as_slice()) as u32,
+            recorder: CommandRecorder::new(),
+            upload,
             width,
             height,
         })
     }
 
-    fn render(&mut self) -> eyre::Result<()> {
+    fn render(&mut self, scene: &mut SceneState) -> eyre::Result<()> {
         self.wait_for_frame_latency()?;
+        self.frame_stats.begin_frame(Instant::now());
         let frame_index = unsafe { self.swap_chain.GetCurrentBackBufferIndex() as usize };
-        self.wait_for_frame(frame_index)?;
-
-        let cursor_position = self.sample_cursor_position()?;
-        let vertex_count = self.update_scene_vertices(cursor_position);
+        self.frames.wait_for_frame(frame_index)?;
+        if let Some(timing) = self
+            .gpu_timer
+            .collect(frame_index, self.frames.completed_value())
+        {
+            self.frame_stats.record_gpu_frame(timing);
+        }
+        self.upload.retire(self.frames.completed_value());
+        self.apply_shader_reloads();
+
+        // Allocated now and written after recording so the cursor can be late-latched.
+        let cursor_constants_slice = self.upload.allocate(
+            std::mem::size_of::<[f32; 4]>() as u64,
+            CONSTANT_BUFFER_ALIGNMENT as u64,
+        )?;
+
+        self.recorder.clear();
+        record_scene_frame(
+            &mut self.recorder,
+            &FrameDescription {
+                back_buffer: frame_index,
+                cursor_constants_address: cursor_constants_slice.gpu_address,
+                width: self.width,
+                height: self.height,
+                vertex_buffer_size: self.vertex_buffer_size,
+                draws: scene.draws,
+            },
+        );
 
-        let current_target = &self.render_targets[frame_index];
         let command_allocator = &self.command_allocators[frame_index];
-
         unsafe {
             command_allocator.Reset()?;
             self.command_list
                 .Reset(command_allocator, &self.pipeline_state)?;
-
-            self.command_list.SetGraphicsRootSignature(&self.root_signature);
-            self.command_list.RSSetViewports(&[self.viewport]);
-            self.command_list.RSSetScissorRects(&[self.scissor_rect]);
-
-            self.command_list.ResourceBarrier(&[transition_barrier(
-                current_target,
-                D3D12_RESOURCE_STATE_PRESENT,
-                D3D12_RESOURCE_STATE_RENDER_TARGET,
-            )]);
-
-            let rtv_handle = D3D12_CPU_DESCRIPTOR_HANDLE {
-                ptr: self.rtv_heap.GetCPUDescriptorHandleForHeapStart().ptr
-                    + frame_index * self.rtv_descriptor_size as usize,
-            };
-            self.command_list
-                .OMSetRenderTargets(1, Some(&rtv_handle), false, None);
-
-            let clear_color = [0.0_f32, 0.0_f32, 0.0_f32, 0.0_f32];
-            self.command_list
-                .ClearRenderTargetView(rtv_handle, &clear_color, None);
-            self.command_list
-                .IASetPrimitiveTopology(D3D_PRIMITIVE_TOPOLOGY_TRIANGLELIST);
-            self.command_list
-                .IASetVertexBuffers(0, Some(&[self.vertex_buffer_view]));
-            self.command_list.DrawInstanced(vertex_count as u32, 1, 0, 0);
-
-            self.command_list.ResourceBarrier(&[transition_barrier(
-                current_target,
-                D3D12_RESOURCE_STATE_RENDER_TARGET,
-                D3D12_RESOURCE_STATE_PRESENT,
-            )]);
-            self.command_list.Close()?;
         }
+        self.gpu_timer.begin(&self.command_list, frame_index);
+        let present = record_commands(
+            &self.command_list,
+            &D3d12Bindings {
+                render_targets: &self.render_targets,
+                render_target_ids: &self.render_target_ids,
+                rtv_handles: &self.rtv_handles,
+                root_signature: &self.root_signature,
+                pipeline_state: &self.pipeline_state,
+                vertex_buffer: &self.vertex_buffer,
+            },
+            &mut self.resource_states,
+            self.recorder.commands(),
+        )?;
+        for issue in self.resource_states.take_issues() {
+            warn!(?issue, "Resource state transition does not match the tracked state");
+        }
+        self.gpu_timer.end(&self.command_list, frame_index);
+        unsafe { self.command_list.Close()? };
+
+        // The command list only references the cursor constant buffer, so the cursor can be
+        // sampled after recording and as close to submission as possible.
+        let input_qpc = query_performance_counter()?;
+        let cursor_position = self.sample_cursor_position()?;
+        let cursor_constants = CursorConstants::from_cursor_position(
+            self.width as f32,
+            self.height as f32,
+            cursor_position,
+        );
+        self.upload
+            .write(&cursor_constants_slice, &cursor_constants.to_shader_layout())?;
+        self.capture_frame(scene, CursorSample {
+            position: cursor_position,
+            ndc: cursor_constants.cursor_ndc,
+        })?;
 
         let command_lists = [Some(self.command_list.cast::<ID3D12CommandList>()?)];
         unsafe {
             self.command_queue.ExecuteCommandLists(&command_lists);
+        }
+
+        if present == PresentRequest::Present {
             let present_flags = if self.allow_tearing {
                 DXGI_PRESENT_ALLOW_TEARING
             } else {
                 DXGI_PRESENT(0)
             };
-            self.swap_chain.Present(0, present_flags).ok()?;
+            let present_qpc = query_performance_counter()?;
+            unsafe { self.swap_chain.Present(0, present_flags).ok()? };
+            self.record_present(input_qpc, present_qpc)?;
+        }
+
+        let fence_value = self.frames.signal_frame(frame_index)?;
+        self.gpu_timer.mark_submitted(frame_index, fence_value);
+        self.upload.finish_frame(fence_value);
+
+        scene.frame_number += 1;
+
+        if let Some(report) = self.frame_stats.take_report(Instant::now()) {
+            log_frame_stats(&report);
+            self.gpu_timer.recalibrate(&self.command_queue)?;
         }
 
-        self.signal_frame(frame_index)
+        Ok(())
+    }
+
+    fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// Swap in pipelines rebuilt since the last frame. The replaced pipeline state is kept until
+    /// the frames already submitted with it have finished; a failed rebuild keeps the current one.
+    fn apply_shader_reloads(&mut self) {
+        let completed = self.frames.completed_value();
+        self.retired_pipeline_states.release(completed);
+        let Some(shader_reload) = &mut self.shader_reload else {
+            return;
+        };
+        for reloaded in shader_reload.take_results() {
+            match reloaded.result {
+                Ok(ReloadedPipeline {
+                    pipeline,
+                    pipeline_state,
+                }) => {
+                    let replaced = std::mem::replace(&mut self.pipeline_state, pipeline_state);
+                    // The last submitted frame is the last one that can reference it.
+                    let last_submitted = self.frames.next_fence_value() - 1;
+                    self.retired_pipeline_states.retire(replaced, last_submitted);
+                    self.pipeline = pipeline;
+                    info!(version = reloaded.version, paths = ?reloaded.paths, "Reloaded shaders");
+                }
+                Err(error) => warn!(
+                    version = reloaded.version,
+                    paths = ?reloaded.paths,
+                    ?error,
+                    "Shader reload failed, keeping the previous pipeline"
+                ),
+            }
+        }
+    }
+
+    /// Rebuild the back buffers at a new size. The GPU must be idle and every reference to the
+    /// old buffers released before `ResizeBuffers`.
+    fn resize(&mut self, width: u32, height: u32) -> eyre::Result<()> {
+        info!(width, height, "Resizing swap chain");
+        self.frames.wait_for_gpu()?;
+
+        for id in self.render_target_ids.drain(..) {
+            self.resource_states.unregister(id);
+        }
+        self.render_targets.clear();
+
+        unsafe {
+            self.swap_chain.ResizeBuffers(
+                FRAME_COUNT as u32,
+                width,
+                height,
+                DXGI_FORMAT_UNKNOWN,
+                swap_chain_flags(self.allow_tearing),
+            )
+        }
+        .wrap_err("Failed to resize swap chain buffers")?;
+
+        self.render_targets = create_render_targets(&self.device, &self.swap_chain, &self.rtv_handles)?;
+        self.render_target_ids = register_render_targets(&mut self.resource_states);
+        self.width = width;
+        self.height = height;
+        // Present statistics from before the resize cannot be matched against new presents.
+        self.latency.reset();
+        Ok(())
     }
 
     fn wait_for_frame_latency(&self) -> eyre::Result<()> {
@@ -362,133 +794,663 @@ impl Renderer {
         Ok(Some((x, y)))
     }
 
-    fn update_scene_vertices(&mut self, cursor_position: Option<(f32, f32)>) -> usize {
-        self.scratch_vertices.clear();
-        append_demo_triangle(&mut self.scratch_vertices);
+    fn capture_frame(&self, scene: &mut SceneState, cursor: CursorSample) -> eyre::Result<()> {
+        let Some(capturer) = scene.capturer.as_mut().filter(|capturer| capturer.wants_frame())
+        else {
+            return Ok(());
+        };
 
-        if let Some((cursor_x, cursor_y)) = cursor_position {
-            append_cursor_target(
-                &mut self.scratch_vertices,
-                self.width as f32,
-                self.height as f32,
-                cursor_x,
-                cursor_y,
-            );
-        }
+        let capture = FrameCapture {
+            pipeline: self.pipeline.capture_settings(),
+            cursor,
+            vertex_stride: Vertex::STRIDE,
+            vertex_bytes: vertex_bytes(&scene.vertices),
+            ..FrameCapture::from_commands(
+                scene.frame_number,
+                self.width,
+                self.height,
+                self.recorder.commands(),
+            )
+        };
+        let path = capturer.write(&capture)?;
+        info!(path = %path.display(), frame = scene.frame_number, "Captured frame");
+        Ok(())
+    }
 
-        // Keep the upload buffer mapped to avoid extra per-frame CPU jitter.
-        unsafe {
-            std::ptr::copy_nonoverlapping(
-                self.scratch_vertices.as_ptr(),
-                self.vertex_buffer_ptr.as_ptr(),
-                self.scratch_vertices.len(),
-            );
+    fn record_present(&mut self, input_qpc: u64, present_qpc: u64) -> eyre::Result<()> {
+        let present_count = unsafe { self.swap_chain.GetLastPresentCount() }?;
+        let dropped = self.latency.record_present(PresentRecord {
+            present_count,
+            input_qpc,
+            present_qpc,
+        });
+        self.frame_stats.record_unmatched_presents(dropped);
+
+        let mut statistics = DXGI_FRAME_STATISTICS::default();
+        match unsafe { self.swap_chain.GetFrameStatistics(&mut statistics) } {
+            Ok(()) => {
+                let update = self.latency.observe(PresentStatistics {
+                    present_count: statistics.PresentCount,
+                    present_refresh_count: statistics.PresentRefreshCount,
+                    sync_refresh_count: statistics.SyncRefreshCount,
+                    sync_qpc: statistics.SyncQPCTime.max(0) as u64,
+                });
+                if let Some(refresh_period) = update.refresh_period_changed {
+                    info!(?refresh_period, "Display refresh period changed");
+                }
+                self.frame_stats.record_latency(&update);
+            }
+            Err(error) if error.code() == DXGI_ERROR_FRAME_STATISTICS_DISJOINT => {
+                self.latency.reset();
+            }
+            // Statistics are unavailable until the first present reaches the screen.
+            Err(_) => {}
         }
 
-        self.scratch_vertices.len()
+        Ok(())
     }
+}
 
-    fn wait_for_frame(&self, frame_index: usize) -> eyre::Result<()> {
-        let fence_value = self.frame_fence_values[frame_index];
-        if fence_value == 0 {
-            return Ok(());
-        }
+impl Drop for Renderer {
+    fn drop(&mut self) {
+        let _ = self.frames.wait_for_gpu();
+    }
+}
 
-        unsafe {
-            if self.fence.GetCompletedValue() < fence_value {
-                self.fence.SetEventOnCompletion(fence_value, *self.fence_event)?;
-                WaitForSingleObjectEx(*self.fence_event, INFINITE, false);
-            }
-        }
+fn log_frame_stats(report: &FrameStatsReport) {
+    let milliseconds = |duration: Option<Duration>| duration.map(|duration| duration.as_secs_f64() * 1000.0);
+    info!(
+        fps = report.frames_per_second(),
+        cpu_frame_ms = milliseconds(report.cpu.average()),
+        cpu_frame_max_ms = milliseconds(Some(report.cpu.max)),
+        gpu_frame_ms = milliseconds(report.gpu.average()),
+        gpu_frame_max_ms = milliseconds(Some(report.gpu.max)),
+        gpu_frames = report.gpu.count,
+        input_to_scanout_ms = milliseconds(report.input_to_scanout.average()),
+        input_to_scanout_max_ms = milliseconds(Some(report.input_to_scanout.max)),
+        unmatched_presents = report.unmatched_presents,
+        "Frame stats"
+    );
+}
 
-        Ok(())
+fn query_performance_counter() -> eyre::Result<u64> {
+    let mut counter = 0_i64;
+    unsafe { QueryPerformanceCounter(&mut counter) }?;
+    Ok(counter as u64)
+}
+
+fn query_performance_frequency() -> eyre::Result<u64> {
+    let mut frequency = 0_i64;
+    unsafe { QueryPerformanceFrequency(&mut frequency) }?;
+    Ok(frequency as u64)
+}
+
+/// Enumerate adapters in `preference` order, for `adapters list`.
+pub fn list_adapters(preference: GpuPreference) -> eyre::Result<Vec<AdapterInfo>> {
+    let dxgi_factory: IDXGIFactory4 = unsafe { CreateDXGIFactory2(DXGI_CREATE_FACTORY_FLAGS(0)) }?;
+    Ok(enumerate_adapters(&dxgi_factory, preference)?
+        .into_iter()
+        .map(|(info, _)| info)
+        .collect())
+}
+
+/// Create a device the way the renderer does and report what it supports, for `device caps`.
+pub fn query_device_caps(use_warp_device: bool, adapter: &AdapterOptions) -> eyre::Result<DeviceCaps> {
+    let (dxgi_factory, info, device) = create_device(use_warp_device, adapter)?;
+    let mut caps = DeviceCaps::new(info);
+
+    let requested = FeatureLevel::ALL.map(|level| D3D_FEATURE_LEVEL(level.raw()));
+    let mut levels = D3D12_FEATURE_DATA_FEATURE_LEVELS {
+        NumFeatureLevels: requested.len() as u32,
+        pFeatureLevelsRequested: requested.as_ptr(),
+        ..Default::default()
+    };
+    check_feature_support(&device, D3D12_FEATURE_FEATURE_LEVELS, &mut levels)?;
+    let max_level = FeatureLevel::from_raw(levels.MaxSupportedFeatureLevel.0);
+    caps.push(
+        "max feature level",
+        max_level.map_or_else(|| format!("{:#x}", levels.MaxSupportedFeatureLevel.0), |level| level.to_string()),
+    );
+    caps.push(
+        "feature levels",
+        FeatureLevel::ALL
+            .into_iter()
+            .filter(|level| max_level.is_some_and(|max| *level <= max))
+            .map(|level| level.to_string())
+            .collect::<Vec<_>>()
+            .join(", "),
+    );
+
+    // Runtimes reject values newer than they know, so step down until one is accepted.
+    let shader_model_value = (D3D_SHADER_MODEL_5_1.0..=D3D_SHADER_MODEL_6_9.0)
+        .rev()
+        .filter(|value| *value == D3D_SHADER_MODEL_5_1.0 || *value >= D3D_SHADER_MODEL_6_0.0)
+        .find_map(|value| {
+            let mut data = D3D12_FEATURE_DATA_SHADER_MODEL {
+                HighestShaderModel: D3D_SHADER_MODEL(value),
+            };
+            check_feature_support(&device, D3D12_FEATURE_SHADER_MODEL, &mut data)
+                .ok()
+                .map(|()| data.HighestShaderModel.0)
+        });
+    caps.push(
+        "shader model",
+        shader_model_value.map_or_else(|| "unknown".to_string(), shader_model),
+    );
+
+    let root_signature_value = [
+        D3D_ROOT_SIGNATURE_VERSION_1_2,
+        D3D_ROOT_SIGNATURE_VERSION_1_1,
+        D3D_ROOT_SIGNATURE_VERSION_1_0,
+    ]
+    .into_iter()
+    .find_map(|version| {
+        let mut data = D3D12_FEATURE_DATA_ROOT_SIGNATURE {
+            HighestVersion: version,
+        };
+        check_feature_support(&device, D3D12_FEATURE_ROOT_SIGNATURE, &mut data)
+            .ok()
+            .map(|()| data.HighestVersion.0)
+    });
+    caps.push(
+        "root signature version",
+        root_signature_value.map_or_else(|| "unknown".to_string(), root_signature_version),
+    );
+
+    let mut options = D3D12_FEATURE_DATA_D3D12_OPTIONS::default();
+    check_feature_support(&device, D3D12_FEATURE_D3D12_OPTIONS, &mut options)?;
+    caps.push("resource binding tier", tier(options.ResourceBindingTier.0));
+    caps.push("resource heap tier", tier(options.ResourceHeapTier.0));
+    caps.push("tiled resources tier", tier(options.TiledResourcesTier.0));
+    caps.push(
+        "conservative rasterization tier",
+        tier(options.ConservativeRasterizationTier.0),
+    );
+    caps.push("cross node sharing tier", tier(options.CrossNodeSharingTier.0));
+    caps.push("double precision shader ops", options.DoublePrecisionFloatShaderOps.as_bool());
+    caps.push("output merger logic op", options.OutputMergerLogicOp.as_bool());
+    caps.push("typed UAV load additional formats", options.TypedUAVLoadAdditionalFormats.as_bool());
+    caps.push("rasterizer ordered views", options.ROVsSupported.as_bool());
+    caps.push(
+        "max GPU virtual address bits per resource",
+        options.MaxGPUVirtualAddressBitsPerResource,
+    );
+
+    let mut options1 = D3D12_FEATURE_DATA_D3D12_OPTIONS1::default();
+    if check_feature_support(&device, D3D12_FEATURE_D3D12_OPTIONS1, &mut options1).is_ok() {
+        caps.push("wave ops", options1.WaveOps.as_bool());
+        caps.push(
+            "wave lane count",
+            format!("{}..={}", options1.WaveLaneCountMin, options1.WaveLaneCountMax),
+        );
+        caps.push("int64 shader ops", options1.Int64ShaderOps.as_bool());
     }
 
-    fn signal_frame(&mut self, frame_index: usize) -> eyre::Result<()> {
-        let fence_value = self.next_fence_value;
-        unsafe {
-            self.command_queue.Signal(&self.fence, fence_value)?;
-        }
-        self.frame_fence_values[frame_index] = fence_value;
-        self.next_fence_value += 1;
-        Ok(())
+    let mut options5 = D3D12_FEATURE_DATA_D3D12_OPTIONS5::default();
+    if check_feature_support(&device, D3D12_FEATURE_D3D12_OPTIONS5, &mut options5).is_ok() {
+        caps.push("render passes tier", tier(options5.RenderPassesTier.0));
+        caps.push("raytracing tier", versioned_tier(options5.RaytracingTier.0, 10));
     }
 
-    fn wait_for_gpu(&mut self) -> eyre::Result<()> {
-        let fence_value = self.next_fence_value;
-        unsafe {
-            self.command_queue.Signal(&self.fence, fence_value)?;
-            if self.fence.GetCompletedValue() < fence_value {
-                self.fence.SetEventOnCompletion(fence_value, *self.fence_event)?;
-                WaitForSingleObjectEx(*self.fence_event, INFINITE, false);
-            }
-        }
-        self.next_fence_value += 1;
-        Ok(())
+    let mut options6 = D3D12_FEATURE_DATA_D3D12_OPTIONS6::default();
+    if check_feature_support(&device, D3D12_FEATURE_D3D12_OPTIONS6, &mut options6).is_ok() {
+        caps.push(
+            "variable shading rate tier",
+            tier(options6.VariableShadingRateTier.0),
+        );
     }
+
+    let mut options7 = D3D12_FEATURE_DATA_D3D12_OPTIONS7::default();
+    if check_feature_support(&device, D3D12_FEATURE_D3D12_OPTIONS7, &mut options7).is_ok() {
+        caps.push("mesh shader tier", versioned_tier(options7.MeshShaderTier.0, 10));
+        caps.push(
+            "sampler feedback tier",
+            versioned_tier(options7.SamplerFeedbackTier.0, 100),
+        );
+    }
+
+    let mut options12 = D3D12_FEATURE_DATA_D3D12_OPTIONS12::default();
+    if check_feature_support(&device, D3D12_FEATURE_D3D12_OPTIONS12, &mut options12).is_ok() {
+        caps.push("enhanced barriers", options12.EnhancedBarriersSupported.as_bool());
+    }
+
+    caps.push("tearing", supports_allow_tearing(&dxgi_factory));
+    Ok(caps)
 }
 
-impl Drop for Renderer {
-    fn drop(&mut self) {
-        let _ = self.wait_for_gpu();
-        unsafe {
-            self.vertex_buffer.Unmap(0, None);
-        }
+fn check_feature_support<T>(
+    device: &ID3D12Device,
+    feature: D3D12_FEATURE,
+    data: &mut T,
+) -> windows::core::Result<()> {
+    unsafe {
+        device.CheckFeatureSupport(
+            feature,
+            data as *mut T as *mut _,
+            std::mem::size_of::<T>() as u32,
+        )
     }
 }
 
-fn create_device(use_warp_device: bool) -> eyre::Result<(IDXGIFactory4, ID3D12Device)> {
+fn create_device(
+    use_warp_device: bool,
+    options: &AdapterOptions,
+) -> eyre::Result<(IDXGIFactory4, AdapterInfo, ID3D12Device)> {
+    let debug_layer = debug_layer_options();
     let mut dxgi_flags = DXGI_CREATE_FACTORY_FLAGS(0);
-    if cfg!(debug_assertions) {
+    if debug_layer.enabled {
         unsafe {
             let mut debug = None;
-            if D3D12GetDebugInterface::<ID3D12Debug>(&mut debug).is_ok() {
-                if let Some(debug) = debug {
-                    debug.EnableDebugLayer();
-                    dxgi_flags |= DXGI_CREATE_FACTORY_DEBUG;
+            match D3D12GetDebugInterface::<ID3D12Debug>(&mut debug) {
+                Ok(()) => {
+                    if let Some(debug) = debug {
+                        debug.EnableDebugLayer();
+                        dxgi_flags |= DXGI_CREATE_FACTORY_DEBUG;
+                        if debug_layer.gpu_based_validation {
+                            enable_gpu_based_validation(&debug);
+                        }
+                    }
                 }
+                Err(error) => warn!(
+                    %error,
+                    hint = ErrorCategory::DebugLayerUnavailable.hint(),
+                    "D3D12 debug layer unavailable, continuing without it"
+                ),
             }
         }
     }
 
     let dxgi_factory: IDXGIFactory4 = unsafe { CreateDXGIFactory2(dxgi_flags) }?;
-    let adapter = if use_warp_device {
-        info!("Using WARP adapter");
-        unsafe { dxgi_factory.EnumWarpAdapter() }?
+    let (info, adapter) = if use_warp_device {
+        info!("Using the WARP software adapter as requested by --warp");
+        let adapter: IDXGIAdapter1 = unsafe { dxgi_factory.EnumWarpAdapter() }?;
+        let description = unsafe { adapter.GetDesc1() }?;
+        (adapter_info(0, &description), adapter)
     } else {
-        get_hardware_adapter(&dxgi_factory)?
+        select_hardware_adapter(&dxgi_factory, options)?
     };
+    info!(
+        adapter = %info.description,
+        luid = format_args!("{:#018x}", info.luid),
+        feature_level = %options.minimum_feature_level,
+        "Creating device"
+    );
 
+    let feature_level = D3D_FEATURE_LEVEL(options.minimum_feature_level.raw());
     let mut device = None;
-    unsafe { D3D12CreateDevice(&adapter, D3D_FEATURE_LEVEL_11_0, &mut device) }?;
+    unsafe { D3D12CreateDevice(&adapter, feature_level, &mut device) }?;
     let device = device.expect("device should be initialized after D3D12CreateDevice succeeds");
-    Ok((dxgi_factory, device))
+    Ok((dxgi_factory, info, device))
 }
 
-fn get_hardware_adapter(factory: &IDXGIFactory4) -> eyre::Result<IDXGIAdapter1> {
+/// Must be called before the device is created.
+fn enable_gpu_based_validation(debug: &ID3D12Debug) {
+    match debug.cast::<ID3D12Debug1>() {
+        Ok(debug) => {
+            unsafe { debug.SetEnableGPUBasedValidation(true) };
+            info!("GPU-based validation enabled");
+        }
+        Err(error) => warn!(%error, "GPU-based validation unavailable, continuing without it"),
+    }
+}
+
+fn select_hardware_adapter(
+    factory: &IDXGIFactory4,
+    options: &AdapterOptions,
+) -> eyre::Result<(AdapterInfo, IDXGIAdapter1)> {
+    let mut adapters = enumerate_adapters(factory, options.gpu_preference)?;
+    let infos: Vec<AdapterInfo> = adapters.iter().map(|(info, _)| info.clone()).collect();
+    let selected = select_adapter(
+        &infos,
+        options.selector.as_ref(),
+        options.minimum_feature_level,
+        |info, feature_level| {
+            let mut test_device: Option<ID3D12Device> = None;
+            unsafe {
+                D3D12CreateDevice(
+                    &adapters[info.index as usize].1,
+                    D3D_FEATURE_LEVEL(feature_level.raw()),
+                    &mut test_device,
+                )
+            }
+            .is_ok()
+        },
+    )?;
+    Ok(adapters.swap_remove(selected.index as usize))
+}
+
+fn enumerate_adapters(
+    factory: &IDXGIFactory4,
+    preference: GpuPreference,
+) -> eyre::Result<Vec<(AdapterInfo, IDXGIAdapter1)>> {
+    let by_preference = match preference {
+        GpuPreference::Unspecified => None,
+        GpuPreference::HighPerformance => Some(DXGI_GPU_PREFERENCE_HIGH_PERFORMANCE),
+        GpuPreference::MinimumPower => Some(DXGI_GPU_PREFERENCE_MINIMUM_POWER),
+    };
+    let factory6: Option<IDXGIFactory6> = match by_preference {
+        Some(_) => Some(
+            factory
+                .cast()
+                .wrap_err("GPU preference requires IDXGIFactory6 (Windows 10 1803 or later)")?,
+        ),
+        None => None,
+    };
+
+    let mut adapters = Vec::new();
     for index in 0.. {
-        let adapter = match unsafe { factory.EnumAdapters1(index) } {
+        let adapter = match (&factory6, by_preference) {
+            (Some(factory6), Some(preference)) => unsafe {
+                factory6.EnumAdapterByGpuPreference::<IDXGIAdapter1>(index, preference)
+            },
+            _ => unsafe { factory.EnumAdapters1(index) },
+        };
+        let adapter = match adapter {
             Ok(adapter) => adapter,
             Err(error) if error.code() == DXGI_ERROR_NOT_FOUND => break,
             Err(error) => return Err(error.into()),
         };
-
         let description = unsafe { adapter.GetDesc1() }?;
-        let is_software = (DXGI_ADAPTER_FLAG(description.Flags as i32) & DXGI_ADAPTER_FLAG_SOFTWARE)
-            != DXGI_ADAPTER_FLAG_NONE;
-        if is_software {
-            continue;
+        adapters.push((adapter_info(index, &description), adapter));
+    }
+    Ok(adapters)
+}
+
+fn adapter_info(index: u32, description: &DXGI_ADAPTER_DESC1) -> AdapterInfo {
+    let name_length = description
+        .Description
+        .iter()
+        .position(|&unit| unit == 0)
+        .unwrap_or(description.Description.len());
+    let flags = DXGI_ADAPTER_FLAG(description.Flags as i32);
+    AdapterInfo {
+        index,
+        description: String::from_utf16_lossy(&description.Description[..name_length]),
+        vendor_id: description.VendorId,
+        device_id: description.DeviceId,
+        luid: (u64::from(description.AdapterLuid.HighPart as u32) << 32)
+            | u64::from(description.AdapterLuid.LowPart),
+        dedicated_video_memory: description.DedicatedVideoMemory as u64,
+        software: (flags & DXGI_ADAPTER_FLAG_SOFTWARE) != DXGI_ADAPTER_FLAG_NONE,
+        remote: (flags & DXGI_ADAPTER_FLAG_REMOTE) != DXGI_ADAPTER_FLAG_NONE,
+    }
+}
+
+/// Context attached to errors raised while a device is in use: which adapter it is on and what
+/// the debug layer said last.
+#[derive(Debug, Clone)]
+struct DeviceDiagnostics {
+    adapter: String,
+    /// Only available when the debug layer is enabled.
+    info_queue: Option<ID3D12InfoQueue>,
+}
+
+impl DeviceDiagnostics {
+    fn new(adapter: &AdapterInfo, device: &ID3D12Device) -> Self {
+        Self {
+            adapter: format!(
+                "{} (vendor {:#06x}, device {:#06x}, LUID {:#018x}, {} MiB dedicated video memory)",
+                adapter.description,
+                adapter.vendor_id,
+                adapter.device_id,
+                adapter.luid,
+                adapter.dedicated_video_memory / (1024 * 1024)
+            ),
+            info_queue: device.cast().ok(),
         }
+    }
 
-        let mut test_device: Option<ID3D12Device> = None;
-        if unsafe { D3D12CreateDevice(&adapter, D3D_FEATURE_LEVEL_11_0, &mut test_device) }
-            .is_ok()
-        {
-            return Ok(adapter);
+    fn attach(&self, error: eyre::Report) -> eyre::Report {
+        let error = error.section(self.adapter.clone().header("Adapter:"));
+        let messages = self.last_messages(DEBUG_MESSAGE_REPORT_LIMIT);
+        if messages.is_empty() {
+            error
+        } else {
+            error.section(messages.join("\n").header("Last debug messages:"))
+        }
+    }
+
+    fn last_messages(&self, limit: u64) -> Vec<String> {
+        let Some(info_queue) = &self.info_queue else {
+            return Vec::new();
+        };
+        let count = unsafe { info_queue.GetNumStoredMessages() };
+        (count.saturating_sub(limit)..count)
+            .filter_map(|index| stored_message(info_queue, index))
+            .map(|message| format!("[{}] {}", message.severity, message.description))
+            .collect()
+    }
+}
+
+fn stored_message(info_queue: &ID3D12InfoQueue, index: u64) -> Option<DebugMessage> {
+    let mut length = 0;
+    unsafe { info_queue.GetMessage(index, None, &mut length) }.ok()?;
+    // D3D12_MESSAGE is followed by its description, so the buffer is sized by the queue.
+    let mut buffer = vec![0_u64; length.div_ceil(std::mem::size_of::<u64>())];
+    let message = buffer.as_mut_ptr() as *mut D3D12_MESSAGE;
+    unsafe { info_queue.GetMessage(index, Some(message), &mut length) }.ok()?;
+    let message = unsafe { &*message };
+    let description = unsafe {
+        std::slice::from_raw_parts(message.pDescription, message.DescriptionByteLength)
+    };
+    Some(DebugMessage {
+        source: MessageSource::D3d12,
+        severity: MessageSeverity::from_raw(message.Severity.0),
+        category: message.Category.0,
+        id: message.ID.0,
+        description: message_text(description),
+    })
+}
+
+fn stored_dxgi_message(
+    info_queue: &IDXGIInfoQueue,
+    producer: windows::core::GUID,
+    index: u64,
+) -> Option<DebugMessage> {
+    let mut length = 0;
+    unsafe { info_queue.GetMessage(producer, index, None, &mut length) }.ok()?;
+    let mut buffer = vec![0_u64; length.div_ceil(std::mem::size_of::<u64>())];
+    let message = buffer.as_mut_ptr() as *mut DXGI_INFO_QUEUE_MESSAGE;
+    unsafe { info_queue.GetMessage(producer, index, Some(message), &mut length) }.ok()?;
+    let message = unsafe { &*message };
+    let description = unsafe {
+        std::slice::from_raw_parts(message.pDescription, message.DescriptionByteLength)
+    };
+    Some(DebugMessage {
+        source: if message.Producer == DXGI_DEBUG_DXGI {
+            MessageSource::Dxgi
+        } else {
+            MessageSource::D3d12
+        },
+        severity: MessageSeverity::from_raw(message.Severity.0),
+        category: message.Category.0,
+        id: message.ID,
+        description: message_text(description),
+    })
+}
+
+fn message_text(description: &[u8]) -> String {
+    String::from_utf8_lossy(description)
+        .trim_end_matches('\0')
+        .to_string()
+}
+
+/// Forwards what the debug layer reports into `tracing`, a frame at a time.
+///
+/// Messages stay in the queues so that [`DeviceDiagnostics`] can still attach the most recent
+/// ones to an error report.
+#[derive(Debug)]
+struct DebugMessagePump {
+    /// Only available when the debug layer is enabled.
+    d3d12: Option<(ID3D12InfoQueue, MessageCursor)>,
+    /// The DXGI queue is per process, so it only carries DXGI's own messages here; D3D12's come
+    /// from the device's queue.
+    dxgi: Option<(IDXGIInfoQueue, MessageCursor)>,
+    options: DebugLayerOptions,
+}
+
+impl DebugMessagePump {
+    fn new(device: &ID3D12Device, options: &DebugLayerOptions) -> Self {
+        let d3d12 = device.cast::<ID3D12InfoQueue>().ok().map(|info_queue| {
+            if let Err(error) = configure_d3d12_info_queue(&info_queue, options) {
+                warn!(%error, "Failed to configure the D3D12 info queue");
+            }
+            (info_queue, MessageCursor::default())
+        });
+        let dxgi = options
+            .enabled
+            .then(|| unsafe { DXGIGetDebugInterface1::<IDXGIInfoQueue>(0) }.ok())
+            .flatten()
+            .map(|info_queue| {
+                if let Err(error) = configure_dxgi_info_queue(&info_queue, options) {
+                    warn!(%error, "Failed to configure the DXGI info queue");
+                }
+                // Messages from before this renderer were forwarded by the one it replaces.
+                let mut cursor = MessageCursor::default();
+                let _ = unsafe {
+                    cursor.advance(
+                        info_queue.GetNumStoredMessages(DXGI_DEBUG_DXGI),
+                        info_queue.GetNumMessagesDiscardedByMessageCountLimit(DXGI_DEBUG_DXGI),
+                    )
+                };
+                (info_queue, cursor)
+            });
+        Self {
+            d3d12,
+            dxgi,
+            options: options.clone(),
+        }
+    }
+
+    fn drain(&mut self) {
+        if let Some((info_queue, cursor)) = &mut self.d3d12 {
+            let (pending, missed) = unsafe {
+                cursor.advance(
+                    info_queue.GetNumStoredMessages(),
+                    info_queue.GetNumMessagesDiscardedByMessageCountLimit(),
+                )
+            };
+            report_missed(MessageSource::D3d12, missed);
+            pending
+                .filter_map(|index| stored_message(info_queue, index))
+                .filter(|message| self.options.forwards(message))
+                .for_each(|message| message.emit());
+        }
+        if let Some((info_queue, cursor)) = &mut self.dxgi {
+            forward_dxgi_messages(info_queue, DXGI_DEBUG_DXGI, cursor, &self.options);
         }
     }
+}
+
+fn configure_d3d12_info_queue(
+    info_queue: &ID3D12InfoQueue,
+    options: &DebugLayerOptions,
+) -> windows::core::Result<()> {
+    for severity in options.break_severities() {
+        unsafe { info_queue.SetBreakOnSeverity(D3D12_MESSAGE_SEVERITY(severity.raw()), true)? };
+    }
+    if !options.muted_ids.is_empty() {
+        let mut ids: Vec<D3D12_MESSAGE_ID> =
+            options.muted_ids.iter().copied().map(D3D12_MESSAGE_ID).collect();
+        let filter = D3D12_INFO_QUEUE_FILTER {
+            DenyList: D3D12_INFO_QUEUE_FILTER_DESC {
+                NumIDs: ids.len() as u32,
+                pIDList: ids.as_mut_ptr(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        unsafe { info_queue.AddStorageFilterEntries(&filter)? };
+    }
+    Ok(())
+}
+
+fn configure_dxgi_info_queue(
+    info_queue: &IDXGIInfoQueue,
+    options: &DebugLayerOptions,
+) -> windows::core::Result<()> {
+    for severity in options.break_severities() {
+        unsafe {
+            info_queue.SetBreakOnSeverity(
+                DXGI_DEBUG_DXGI,
+                DXGI_INFO_QUEUE_MESSAGE_SEVERITY(severity.raw()),
+                true,
+            )?
+        };
+    }
+    if !options.muted_ids.is_empty() {
+        let mut ids = options.muted_ids.clone();
+        let filter = DXGI_INFO_QUEUE_FILTER {
+            DenyList: DXGI_INFO_QUEUE_FILTER_DESC {
+                NumIDs: ids.len() as u32,
+                pIDList: ids.as_mut_ptr(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        unsafe { info_queue.AddStorageFilterEntries(DXGI_DEBUG_DXGI, &filter)? };
+    }
+    Ok(())
+}
+
+fn forward_dxgi_messages(
+    info_queue: &IDXGIInfoQueue,
+    producer: windows::core::GUID,
+    cursor: &mut MessageCursor,
+    options: &DebugLayerOptions,
+) -> usize {
+    let (pending, missed) = unsafe {
+        cursor.advance(
+            info_queue.GetNumStoredMessages(producer),
+            info_queue.GetNumMessagesDiscardedByMessageCountLimit(producer),
+        )
+    };
+    report_missed(MessageSource::Dxgi, missed);
+    pending
+        .filter_map(|index| stored_dxgi_message(info_queue, producer, index))
+        .filter(|message| options.forwards(message))
+        .inspect(DebugMessage::emit)
+        .count()
+}
+
+fn report_missed(source: MessageSource, missed: u64) {
+    if missed > 0 {
+        warn!(
+            %source,
+            missed,
+            "Debug messages were discarded by the info queue before they could be forwarded"
+        );
+    }
+}
 
-    Err(Error::new(E_FAIL, "No suitable D3D12 adapter found").into())
+/// Ask DXGI to report the DXGI and D3D12 objects that are still alive and forward the report,
+/// which lists each leaked object with its reference counts.
+fn report_live_objects() {
+    let result = unsafe {
+        DXGIGetDebugInterface1::<IDXGIDebug1>(0).and_then(|debug| {
+            let info_queue = DXGIGetDebugInterface1::<IDXGIInfoQueue>(0)?;
+            let mut cursor = MessageCursor::default();
+            let _ = cursor.advance(
+                info_queue.GetNumStoredMessages(DXGI_DEBUG_ALL),
+                info_queue.GetNumMessagesDiscardedByMessageCountLimit(DXGI_DEBUG_ALL),
+            );
+            debug.ReportLiveObjects(
+                DXGI_DEBUG_ALL,
+                DXGI_DEBUG_RLO_DETAIL | DXGI_DEBUG_RLO_IGNORE_INTERNAL,
+            )?;
+            Ok(forward_dxgi_messages(
+                &info_queue,
+                DXGI_DEBUG_ALL,
+                &mut cursor,
+                debug_layer_options(),
+            ))
+        })
+    };
+    match result {
+        Ok(0) => info!("No live DXGI or D3D12 objects at shutdown"),
+        Ok(reported) => warn!(reported, "Live DXGI or D3D12 objects at shutdown"),
+        Err(error) => warn!(%error, "Failed to report live objects"),
+    }
 }
 
 fn create_command_queue(device: &ID3D12Device) -> eyre::Result<ID3D12CommandQueue> {
@@ -519,11 +1481,6 @@ fn create_swap_chain(
     height: u32,
     allow_tearing: bool,
 ) -> eyre::Result<IDXGISwapChain3> {
-    let mut flags = DXGI_SWAP_CHAIN_FLAG_FRAME_LATENCY_WAITABLE_OBJECT.0 as u32;
-    if allow_tearing {
-        flags |= DXGI_SWAP_CHAIN_FLAG_ALLOW_TEARING.0 as u32;
-    }
-
     let description = DXGI_SWAP_CHAIN_DESC1 {
         Width: width,
         Height: height,
@@ -535,7 +1492,7 @@ fn create_swap_chain(
         Scaling: DXGI_SCALING_STRETCH,
         SwapEffect: DXGI_SWAP_EFFECT_FLIP_DISCARD,
         AlphaMode: DXGI_ALPHA_MODE_IGNORE,
-        Flags: flags,
+        Flags: swap_chain_flags(allow_tearing).0 as u32,
     };
 
     let swap_chain: IDXGISwapChain1 = unsafe {
@@ -544,6 +1501,15 @@ fn create_swap_chain(
     Ok(swap_chain.cast()?)
 }
 
+/// The flags the swap chain is created with; `ResizeBuffers` must be given the same ones.
+fn swap_chain_flags(allow_tearing: bool) -> DXGI_SWAP_CHAIN_FLAG {
+    let mut flags = DXGI_SWAP_CHAIN_FLAG_FRAME_LATENCY_WAITABLE_OBJECT;
+    if allow_tearing {
+        flags |= DXGI_SWAP_CHAIN_FLAG_ALLOW_TEARING;
+    }
+    flags
+}
+
 fn supports_allow_tearing(factory: &IDXGIFactory4) -> bool {
     let Ok(factory) = factory.cast::<IDXGIFactory5>() else {
         return false;
@@ -569,124 +1535,505 @@ fn client_size(hwnd: HWND) -> eyre::Result<(u32, u32)> {
     Ok((width, height))
 }
 
+fn create_rtv_heap(
+    device: &ID3D12Device,
+) -> eyre::Result<(ID3D12DescriptorHeap, [D3D12_CPU_DESCRIPTOR_HANDLE; FRAME_COUNT])> {
+    let (rtv_heap, mut rtv_descriptors) = create_descriptor_heap(
+        device,
+        D3D12_DESCRIPTOR_HEAP_TYPE_RTV,
+        FRAME_COUNT as u32,
+        FRAME_COUNT as u32,
+    )?;
+    let mut rtv_handles = [D3D12_CPU_DESCRIPTOR_HANDLE::default(); FRAME_COUNT];
+    for handle in &mut rtv_handles {
+        *handle = d3d12_cpu_handle(rtv_descriptors.allocate()?.cpu);
+    }
+
+    Ok((rtv_heap, rtv_handles))
+}
+
+/// Fetch the swap chain's back buffers and write their views into `rtv_handles`.
 fn create_render_targets(
     device: &ID3D12Device,
     swap_chain: &IDXGISwapChain3,
-) -> eyre::Result<(
-    ID3D12DescriptorHeap,
-    u32,
-    [ID3D12Resource; FRAME_COUNT],
-)> {
-    let rtv_heap: ID3D12DescriptorHeap = unsafe {
+    rtv_handles: &[D3D12_CPU_DESCRIPTOR_HANDLE; FRAME_COUNT],
+) -> eyre::Result<Vec<ID3D12Resource>> {
+    let mut render_targets = Vec::with_capacity(FRAME_COUNT);
+    for (index, handle) in rtv_handles.iter().enumerate() {
+        let resource: ID3D12Resource = unsafe { swap_chain.GetBuffer(index as u32) }?;
+        unsafe { device.CreateRenderTargetView(&resource, None, *handle) };
+        render_targets.push(resource);
+    }
+
+    Ok(render_targets)
+}
+
+fn register_render_targets(
+    resource_states: &mut ResourceStateTracker<ResourceState>,
+) -> Vec<ResourceId> {
+    (0..FRAME_COUNT)
+        .map(|index| resource_states.register(format!("back buffer {index}"), ResourceState::Present))
+        .collect()
+}
+
+/// Create a descriptor heap and an allocator over it; shader-visible heaps are used for
+/// CBV/SRV/UAV and sampler descriptors.
+fn create_descriptor_heap(
+    device: &ID3D12Device,
+    heap_type: D3D12_DESCRIPTOR_HEAP_TYPE,
+    capacity: u32,
+    persistent_capacity: u32,
+) -> eyre::Result<(ID3D12DescriptorHeap, DescriptorAllocator)> {
+    let shader_visible = heap_type == D3D12_DESCRIPTOR_HEAP_TYPE_CBV_SRV_UAV
+        || heap_type == D3D12_DESCRIPTOR_HEAP_TYPE_SAMPLER;
+    let heap: ID3D12DescriptorHeap = unsafe {
         device.CreateDescriptorHeap(&D3D12_DESCRIPTOR_HEAP_DESC {
-            Type: D3D12_DESCRIPTOR_HEAP_TYPE_RTV,
-            NumDescriptors: FRAME_COUNT as u32,
+            Type: heap_type,
+            NumDescriptors: capacity,
+            Flags: if shader_visible {
+                D3D12_DESCRIPTOR_HEAP_FLAG_SHADER_VISIBLE
+            } else {
+                D3D12_DESCRIPTOR_HEAP_FLAG_NONE
+            },
             ..Default::default()
         })?
     };
-    let rtv_descriptor_size = unsafe {
-        device.GetDescriptorHandleIncrementSize(D3D12_DESCRIPTOR_HEAP_TYPE_RTV)
+
+    let layout = DescriptorHeapLayout {
+        cpu_start: CpuDescriptorHandle(unsafe { heap.GetCPUDescriptorHandleForHeapStart() }.ptr),
+        gpu_start: shader_visible.then(|| {
+            GpuDescriptorHandle(unsafe { heap.GetGPUDescriptorHandleForHeapStart() }.ptr)
+        }),
+        increment: unsafe { device.GetDescriptorHandleIncrementSize(heap_type) },
+        capacity,
     };
-    let heap_start = unsafe { rtv_heap.GetCPUDescriptorHandleForHeapStart() };
+    Ok((heap, DescriptorAllocator::new(layout, persistent_capacity)?))
+}
 
-    let mut render_targets = std::array::from_fn(|_| None::<ID3D12Resource>);
-    for (index, slot) in render_targets.iter_mut().enumerate() {
-        let resource: ID3D12Resource = unsafe { swap_chain.GetBuffer(index as u32) }?;
-        let descriptor = D3D12_CPU_DESCRIPTOR_HANDLE {
-            ptr: heap_start.ptr + index * rtv_descriptor_size as usize,
-        };
-        unsafe { device.CreateRenderTargetView(&resource, None, descriptor) };
-        *slot = Some(resource);
+/// The bindings `shaders.hlsl` declares: the draw constants and the frame constant buffer.
+fn root_signature_description() -> eyre::Result<RootSignatureDescription> {
+    RootSignatureBuilder::default()
+        .allow_input_layout()
+        .constants(0, DRAW_CONSTANT_COUNT, ShaderVisibility::Vertex)
+        .cbv(1, ShaderVisibility::Vertex)
+        .build()
+}
+
+fn create_root_signature(device: &ID3D12Device) -> eyre::Result<ID3D12RootSignature> {
+    let mut description = root_signature_description()?;
+    let mut data = D3D12_FEATURE_DATA_ROOT_SIGNATURE {
+        HighestVersion: D3D_ROOT_SIGNATURE_VERSION_1_1,
+    };
+    let supports_1_1 = check_feature_support(device, D3D12_FEATURE_ROOT_SIGNATURE, &mut data)
+        .is_ok()
+        && data.HighestVersion.0 >= D3D_ROOT_SIGNATURE_VERSION_1_1.0;
+    if !supports_1_1 {
+        description.version = RootSignatureVersion::V1_0;
+        description
+            .validate()
+            .wrap_err("The device only supports root signature version 1.0")?;
     }
 
-    Ok((rtv_heap, rtv_descriptor_size, render_targets.map(Option::unwrap)))
+    let signature = serialize_root_signature(&description)?;
+    Ok(unsafe { device.CreateRootSignature(0, blob_bytes(&signature))? })
 }
 
-fn create_root_signature(device: &ID3D12Device) -> eyre::Result<ID3D12RootSignature> {
-    let description = D3D12_ROOT_SIGNATURE_DESC {
-        Flags: D3D12_ROOT_SIGNATURE_FLAG_ALLOW_INPUT_ASSEMBLER_INPUT_LAYOUT,
-        ..Default::default()
+/// Serialize a validated `description` at its version.
+fn serialize_root_signature(description: &RootSignatureDescription) -> eyre::Result<ID3DBlob> {
+    let static_samplers: Vec<D3D12_STATIC_SAMPLER_DESC> = description
+        .static_samplers
+        .iter()
+        .map(static_sampler_desc)
+        .collect();
+    let flags = if description.allow_input_layout {
+        D3D12_ROOT_SIGNATURE_FLAG_ALLOW_INPUT_ASSEMBLER_INPUT_LAYOUT
+    } else {
+        D3D12_ROOT_SIGNATURE_FLAG_NONE
+    };
+    let versioned = match description.version {
+        RootSignatureVersion::V1_0 => {
+            let ranges: Vec<Vec<D3D12_DESCRIPTOR_RANGE>> = description
+                .parameters
+                .iter()
+                .map(|parameter| table_ranges(parameter, descriptor_range))
+                .collect();
+            let parameters: Vec<D3D12_ROOT_PARAMETER> = description
+                .parameters
+                .iter()
+                .zip(&ranges)
+                .map(|(parameter, ranges)| root_parameter(parameter, ranges))
+                .collect();
+            serialize_versioned_root_signature(&D3D12_VERSIONED_ROOT_SIGNATURE_DESC {
+                Version: D3D_ROOT_SIGNATURE_VERSION_1_0,
+                Anonymous: D3D12_VERSIONED_ROOT_SIGNATURE_DESC_0 {
+                    Desc_1_0: D3D12_ROOT_SIGNATURE_DESC {
+                        NumParameters: parameters.len() as u32,
+                        pParameters: parameters.as_ptr(),
+                        NumStaticSamplers: static_samplers.len() as u32,
+                        pStaticSamplers: static_samplers.as_ptr(),
+                        Flags: flags,
+                    },
+                },
+            })
+        }
+        RootSignatureVersion::V1_1 => {
+            let ranges: Vec<Vec<D3D12_DESCRIPTOR_RANGE1>> = description
+                .parameters
+                .iter()
+                .map(|parameter| table_ranges(parameter, descriptor_range1))
+                .collect();
+            let parameters: Vec<D3D12_ROOT_PARAMETER1> = description
+                .parameters
+                .iter()
+                .zip(&ranges)
+                .map(|(parameter, ranges)| root_parameter1(parameter, ranges))
+                .collect();
+            serialize_versioned_root_signature(&D3D12_VERSIONED_ROOT_SIGNATURE_DESC {
+                Version: D3D_ROOT_SIGNATURE_VERSION_1_1,
+                Anonymous: D3D12_VERSIONED_ROOT_SIGNATURE_DESC_0 {
+                    Desc_1_1: D3D12_ROOT_SIGNATURE_DESC1 {
+                        NumParameters: parameters.len() as u32,
+                        pParameters: parameters.as_ptr(),
+                        NumStaticSamplers: static_samplers.len() as u32,
+                        pStaticSamplers: static_samplers.as_ptr(),
+                        Flags: flags,
+                    },
+                },
+            })
+        }
     };
+    versioned.wrap_err("Failed to serialize root signature")
+}
 
+fn serialize_versioned_root_signature(
+    description: &D3D12_VERSIONED_ROOT_SIGNATURE_DESC,
+) -> eyre::Result<ID3DBlob> {
     let mut signature = None;
     let mut error = None;
-    unsafe {
-        D3D12SerializeRootSignature(
-            &description,
-            D3D_ROOT_SIGNATURE_VERSION_1,
-            &mut signature,
-            Some(&mut error),
-        )
-    }
-    .map_err(|err| shader_error(err, error).wrap_err("Failed to serialize root signature"))?;
+    unsafe { D3D12SerializeVersionedRootSignature(description, &mut signature, Some(&mut error)) }
+        .map_err(|err| shader_error(err, error))?;
+    Ok(signature.expect("root signature blob should be initialized"))
+}
 
-    let signature = signature.expect("root signature blob should be initialized");
-    Ok(unsafe {
-        device.CreateRootSignature(
-            0,
-            std::slice::from_raw_parts(
-                signature.GetBufferPointer() as *const u8,
-                signature.GetBufferSize(),
-            ),
-        )?
-    })
+fn root_parameter(
+    parameter: &RootParameter,
+    ranges: &[D3D12_DESCRIPTOR_RANGE],
+) -> D3D12_ROOT_PARAMETER {
+    let (parameter_type, anonymous) = match &parameter.kind {
+        RootParameterKind::Constants {
+            register,
+            space,
+            count,
+        } => (
+            D3D12_ROOT_PARAMETER_TYPE_32BIT_CONSTANTS,
+            D3D12_ROOT_PARAMETER_0 {
+                Constants: root_constants(*register, *space, *count),
+            },
+        ),
+        RootParameterKind::Descriptor {
+            kind,
+            register,
+            space,
+            ..
+        } => (
+            root_descriptor_type(*kind),
+            D3D12_ROOT_PARAMETER_0 {
+                Descriptor: D3D12_ROOT_DESCRIPTOR {
+                    ShaderRegister: *register,
+                    RegisterSpace: *space,
+                },
+            },
+        ),
+        RootParameterKind::Table(_) => (
+            D3D12_ROOT_PARAMETER_TYPE_DESCRIPTOR_TABLE,
+            D3D12_ROOT_PARAMETER_0 {
+                DescriptorTable: D3D12_ROOT_DESCRIPTOR_TABLE {
+                    NumDescriptorRanges: ranges.len() as u32,
+                    pDescriptorRanges: ranges.as_ptr(),
+                },
+            },
+        ),
+    };
+    D3D12_ROOT_PARAMETER {
+        ParameterType: parameter_type,
+        Anonymous: anonymous,
+        ShaderVisibility: shader_visibility(parameter.visibility),
+    }
 }
 
-fn create_pipeline_state(
-    device: &ID3D12Device,
-    root_signature: &ID3D12RootSignature,
-) -> eyre::Result<ID3D12PipelineState> {
-    let compile_flags = if cfg!(debug_assertions) {
-        D3DCOMPILE_DEBUG | D3DCOMPILE_SKIP_OPTIMIZATION
-    } else {
-        0
+fn root_parameter1(
+    parameter: &RootParameter,
+    ranges: &[D3D12_DESCRIPTOR_RANGE1],
+) -> D3D12_ROOT_PARAMETER1 {
+    let (parameter_type, anonymous) = match &parameter.kind {
+        RootParameterKind::Constants {
+            register,
+            space,
+            count,
+        } => (
+            D3D12_ROOT_PARAMETER_TYPE_32BIT_CONSTANTS,
+            D3D12_ROOT_PARAMETER1_0 {
+                Constants: root_constants(*register, *space, *count),
+            },
+        ),
+        RootParameterKind::Descriptor {
+            kind,
+            register,
+            space,
+            data,
+        } => (
+            root_descriptor_type(*kind),
+            D3D12_ROOT_PARAMETER1_0 {
+                Descriptor: D3D12_ROOT_DESCRIPTOR1 {
+                    ShaderRegister: *register,
+                    RegisterSpace: *space,
+                    Flags: match data {
+                        DataFlag::Default => D3D12_ROOT_DESCRIPTOR_FLAG_NONE,
+                        DataFlag::Volatile => D3D12_ROOT_DESCRIPTOR_FLAG_DATA_VOLATILE,
+                        DataFlag::StaticWhileSetAtExecute => {
+                            D3D12_ROOT_DESCRIPTOR_FLAG_DATA_STATIC_WHILE_SET_AT_EXECUTE
+                        }
+                        DataFlag::Static => D3D12_ROOT_DESCRIPTOR_FLAG_DATA_STATIC,
+                    },
+                },
+            },
+        ),
+        RootParameterKind::Table(_) => (
+            D3D12_ROOT_PARAMETER_TYPE_DESCRIPTOR_TABLE,
+            D3D12_ROOT_PARAMETER1_0 {
+                DescriptorTable: D3D12_ROOT_DESCRIPTOR_TABLE1 {
+                    NumDescriptorRanges: ranges.len() as u32,
+                    pDescriptorRanges: ranges.as_ptr(),
+                },
+            },
+        ),
     };
+    D3D12_ROOT_PARAMETER1 {
+        ParameterType: parameter_type,
+        Anonymous: anonymous,
+        ShaderVisibility: shader_visibility(parameter.visibility),
+    }
+}
 
-    let shader_path = shader_path();
-    let shader_path_hstring: HSTRING = shader_path.to_string_lossy().as_ref().into();
-    let vertex_shader = compile_shader(&shader_path_hstring, s!("VSMain"), s!("vs_5_0"), compile_flags)?;
-    let pixel_shader = compile_shader(&shader_path_hstring, s!("PSMain"), s!("ps_5_0"), compile_flags)?;
+/// A table's ranges, converted; empty for other parameters. Tables point into these, so they
+/// are built before the parameters and outlive them.
+fn table_ranges<T>(parameter: &RootParameter, convert: fn(&DescriptorRange) -> T) -> Vec<T> {
+    match &parameter.kind {
+        RootParameterKind::Table(ranges) => ranges.iter().map(convert).collect(),
+        _ => Vec::new(),
+    }
+}
 
-    let input_layout = [
-        D3D12_INPUT_ELEMENT_DESC {
-            SemanticName: s!("POSITION"),
-            Format: DXGI_FORMAT_R32G32B32_FLOAT,
-            ..Default::default()
+fn root_constants(register: u32, space: u32, count: u32) -> D3D12_ROOT_CONSTANTS {
+    D3D12_ROOT_CONSTANTS {
+        ShaderRegister: register,
+        RegisterSpace: space,
+        Num32BitValues: count,
+    }
+}
+
+fn root_descriptor_type(kind: RootDescriptorKind) -> D3D12_ROOT_PARAMETER_TYPE {
+    match kind {
+        RootDescriptorKind::ConstantBuffer => D3D12_ROOT_PARAMETER_TYPE_CBV,
+        RootDescriptorKind::ShaderResource => D3D12_ROOT_PARAMETER_TYPE_SRV,
+        RootDescriptorKind::UnorderedAccess => D3D12_ROOT_PARAMETER_TYPE_UAV,
+    }
+}
+
+fn descriptor_range(range: &DescriptorRange) -> D3D12_DESCRIPTOR_RANGE {
+    let range = descriptor_range1(range);
+    D3D12_DESCRIPTOR_RANGE {
+        RangeType: range.RangeType,
+        NumDescriptors: range.NumDescriptors,
+        BaseShaderRegister: range.BaseShaderRegister,
+        RegisterSpace: range.RegisterSpace,
+        OffsetInDescriptorsFromTableStart: range.OffsetInDescriptorsFromTableStart,
+    }
+}
+
+fn descriptor_range1(range: &DescriptorRange) -> D3D12_DESCRIPTOR_RANGE1 {
+    let descriptors = match range.descriptors {
+        DescriptorsFlag::Default => D3D12_DESCRIPTOR_RANGE_FLAG_NONE,
+        DescriptorsFlag::Volatile => D3D12_DESCRIPTOR_RANGE_FLAG_DESCRIPTORS_VOLATILE,
+        DescriptorsFlag::StaticKeepingBufferBoundsChecks => {
+            D3D12_DESCRIPTOR_RANGE_FLAG_DESCRIPTORS_STATIC_KEEPING_BUFFER_BOUNDS_CHECKS
+        }
+    };
+    let data = match range.data {
+        DataFlag::Default => D3D12_DESCRIPTOR_RANGE_FLAG_NONE,
+        DataFlag::Volatile => D3D12_DESCRIPTOR_RANGE_FLAG_DATA_VOLATILE,
+        DataFlag::StaticWhileSetAtExecute => {
+            D3D12_DESCRIPTOR_RANGE_FLAG_DATA_STATIC_WHILE_SET_AT_EXECUTE
+        }
+        DataFlag::Static => D3D12_DESCRIPTOR_RANGE_FLAG_DATA_STATIC,
+    };
+    D3D12_DESCRIPTOR_RANGE1 {
+        RangeType: match range.kind {
+            RegisterKind::ConstantBuffer => D3D12_DESCRIPTOR_RANGE_TYPE_CBV,
+            RegisterKind::ShaderResource => D3D12_DESCRIPTOR_RANGE_TYPE_SRV,
+            RegisterKind::UnorderedAccess => D3D12_DESCRIPTOR_RANGE_TYPE_UAV,
+            RegisterKind::Sampler => D3D12_DESCRIPTOR_RANGE_TYPE_SAMPLER,
         },
-        D3D12_INPUT_ELEMENT_DESC {
-            SemanticName: s!("COLOR"),
-            Format: DXGI_FORMAT_R32G32B32A32_FLOAT,
-            AlignedByteOffset: 12,
-            ..Default::default()
+        // An unbounded range is written as UINT_MAX descriptors.
+        NumDescriptors: range.count.unwrap_or(u32::MAX),
+        BaseShaderRegister: range.base_register,
+        RegisterSpace: range.space,
+        Flags: descriptors | data,
+        OffsetInDescriptorsFromTableStart: range
+            .offset
+            .unwrap_or(D3D12_DESCRIPTOR_RANGE_OFFSET_APPEND),
+    }
+}
+
+fn static_sampler_desc(sampler: &StaticSampler) -> D3D12_STATIC_SAMPLER_DESC {
+    let (filter, max_anisotropy) = match sampler.filter {
+        SamplerFilter::Point => (D3D12_FILTER_MIN_MAG_MIP_POINT, 1),
+        SamplerFilter::Linear => (D3D12_FILTER_MIN_MAG_MIP_LINEAR, 1),
+        SamplerFilter::Anisotropic(anisotropy) => (D3D12_FILTER_ANISOTROPIC, anisotropy),
+    };
+    let address = match sampler.address {
+        AddressMode::Wrap => D3D12_TEXTURE_ADDRESS_MODE_WRAP,
+        AddressMode::Mirror => D3D12_TEXTURE_ADDRESS_MODE_MIRROR,
+        AddressMode::Clamp => D3D12_TEXTURE_ADDRESS_MODE_CLAMP,
+        AddressMode::Border => D3D12_TEXTURE_ADDRESS_MODE_BORDER,
+    };
+    D3D12_STATIC_SAMPLER_DESC {
+        Filter: filter,
+        AddressU: address,
+        AddressV: address,
+        AddressW: address,
+        MipLODBias: 0.0,
+        MaxAnisotropy: max_anisotropy,
+        ComparisonFunc: D3D12_COMPARISON_FUNC_NEVER,
+        BorderColor: match sampler.border_color {
+            BorderColor::TransparentBlack => D3D12_STATIC_BORDER_COLOR_TRANSPARENT_BLACK,
+            BorderColor::OpaqueBlack => D3D12_STATIC_BORDER_COLOR_OPAQUE_BLACK,
+            BorderColor::OpaqueWhite => D3D12_STATIC_BORDER_COLOR_OPAQUE_WHITE,
         },
-    ];
+        MinLOD: 0.0,
+        MaxLOD: D3D12_FLOAT32_MAX,
+        ShaderRegister: sampler.register,
+        RegisterSpace: sampler.space,
+        ShaderVisibility: shader_visibility(sampler.visibility),
+    }
+}
+
+fn shader_visibility(visibility: ShaderVisibility) -> D3D12_SHADER_VISIBILITY {
+    match visibility {
+        ShaderVisibility::All => D3D12_SHADER_VISIBILITY_ALL,
+        ShaderVisibility::Vertex => D3D12_SHADER_VISIBILITY_VERTEX,
+        ShaderVisibility::Hull => D3D12_SHADER_VISIBILITY_HULL,
+        ShaderVisibility::Domain => D3D12_SHADER_VISIBILITY_DOMAIN,
+        ShaderVisibility::Geometry => D3D12_SHADER_VISIBILITY_GEOMETRY,
+        ShaderVisibility::Pixel => D3D12_SHADER_VISIBILITY_PIXEL,
+        ShaderVisibility::Amplification => D3D12_SHADER_VISIBILITY_AMPLIFICATION,
+        ShaderVisibility::Mesh => D3D12_SHADER_VISIBILITY_MESH,
+    }
+}
 
-    let blend_target = D3D12_RENDER_TARGET_BLEND_DESC {
-        BlendEnable: TRUE,
+/// Premultiplied-alpha blending onto the transparent back buffer.
+fn render_target_blend(target: BlendTarget) -> D3D12_RENDER_TARGET_BLEND_DESC {
+    D3D12_RENDER_TARGET_BLEND_DESC {
+        BlendEnable: target.enable.into(),
         LogicOpEnable: FALSE,
-        SrcBlend: D3D12_BLEND_ONE,
-        DestBlend: D3D12_BLEND_INV_SRC_ALPHA,
-        BlendOp: D3D12_BLEND_OP_ADD,
-        SrcBlendAlpha: D3D12_BLEND_ONE,
-        DestBlendAlpha: D3D12_BLEND_INV_SRC_ALPHA,
-        BlendOpAlpha: D3D12_BLEND_OP_ADD,
+        SrcBlend: D3D12_BLEND(target.src),
+        DestBlend: D3D12_BLEND(target.dest),
+        BlendOp: D3D12_BLEND_OP(target.op),
+        SrcBlendAlpha: D3D12_BLEND(target.src_alpha),
+        DestBlendAlpha: D3D12_BLEND(target.dest_alpha),
+        BlendOpAlpha: D3D12_BLEND_OP(target.op_alpha),
         LogicOp: D3D12_LOGIC_OP_NOOP,
-        RenderTargetWriteMask: D3D12_COLOR_WRITE_ENABLE_ALL.0 as u8,
+        RenderTargetWriteMask: target.write_mask,
+    }
+}
+
+fn create_pipeline_state(
+    device: &ID3D12Device,
+    root_signature: &ID3D12RootSignature,
+    pipeline: &PipelineDescription,
+    shaders: &PipelineShaders,
+    adapter: &AdapterInfo,
+    cache_path: Option<&Path>,
+) -> eyre::Result<ID3D12PipelineState> {
+    // The renderer only ever binds the swap chain's back buffer.
+    if pipeline
+        .render_targets
+        .iter()
+        .map(|format| DXGI_FORMAT(format.raw()))
+        .ne([DXGI_FORMAT_B8G8R8A8_UNORM])
+    {
+        eyre::bail!(
+            "The pipeline renders to {:?}, but the renderer only has a B8G8R8A8_UNORM back buffer",
+            pipeline.render_targets
+        );
+    }
+
+    let vertex_shader = &shaders.vertex_shader;
+    let pixel_shader = &shaders.pixel_shader;
+
+    let semantics = pipeline
+        .input_layout
+        .iter()
+        .map(|element| CString::new(element.semantic.as_str()))
+        .collect::<Result<Vec<_>, _>>()?;
+    let input_layout: Vec<D3D12_INPUT_ELEMENT_DESC> = if pipeline.input_layout.is_empty() {
+        // Derived from the vertex struct, so it always matches the vertex buffer.
+        check_vertex_shader_inputs(vertex_shader)?;
+        Vertex::ELEMENTS
+            .iter()
+            .map(|element| D3D12_INPUT_ELEMENT_DESC {
+                SemanticName: PCSTR(element.semantic.as_ptr().cast()),
+                SemanticIndex: element.semantic_index,
+                Format: DXGI_FORMAT(element.format.raw()),
+                InputSlot: 0,
+                AlignedByteOffset: element.offset,
+                InputSlotClass: D3D12_INPUT_CLASSIFICATION_PER_VERTEX_DATA,
+                InstanceDataStepRate: 0,
+            })
+            .collect()
+    } else {
+        pipeline
+            .input_layout
+            .iter()
+            .zip(&semantics)
+            .map(|(element, semantic)| D3D12_INPUT_ELEMENT_DESC {
+                SemanticName: PCSTR(semantic.as_ptr().cast()),
+                SemanticIndex: element.semantic_index,
+                Format: DXGI_FORMAT(element.format.raw()),
+                InputSlot: element.slot,
+                AlignedByteOffset: element.offset,
+                InputSlotClass: D3D12_INPUT_CLASSIFICATION_PER_VERTEX_DATA,
+                InstanceDataStepRate: 0,
+            })
+            .collect()
     };
 
-    let description = D3D12_GRAPHICS_PIPELINE_STATE_DESC {
+    let mut blend_targets = [D3D12_RENDER_TARGET_BLEND_DESC::default(); 8];
+    for (target, preset) in blend_targets.iter_mut().zip(&pipeline.blend) {
+        *target = render_target_blend(preset.target());
+    }
+    let mut rtv_formats = [DXGI_FORMAT_UNKNOWN; 8];
+    for (slot, format) in rtv_formats.iter_mut().zip(&pipeline.render_targets) {
+        *slot = DXGI_FORMAT(format.raw());
+    }
+
+    let mut description = D3D12_GRAPHICS_PIPELINE_STATE_DESC {
         pRootSignature: std::mem::ManuallyDrop::new(Some(root_signature.clone())),
-        VS: shader_bytecode(&vertex_shader),
-        PS: shader_bytecode(&pixel_shader),
+        VS: shader_bytecode(vertex_shader),
+        PS: shader_bytecode(pixel_shader),
         BlendState: D3D12_BLEND_DESC {
             AlphaToCoverageEnable: FALSE,
-            IndependentBlendEnable: FALSE,
-            RenderTarget: [blend_target; 8],
+            IndependentBlendEnable: pipeline
+                .blend
+                .windows(2)
+                .any(|pair| pair[0] != pair[1])
+                .into(),
+            RenderTarget: blend_targets,
         },
         SampleMask: u32::MAX,
         RasterizerState: D3D12_RASTERIZER_DESC {
-            FillMode: D3D12_FILL_MODE_SOLID,
-            CullMode: D3D12_CULL_MODE_NONE,
+            FillMode: D3D12_FILL_MODE(pipeline.fill.raw()),
+            CullMode: D3D12_CULL_MODE(pipeline.cull.raw()),
             FrontCounterClockwise: FALSE,
             DepthBias: D3D12_DEFAULT_DEPTH_BIAS,
             DepthBiasClamp: D3D12_DEFAULT_DEPTH_BIAS_CLAMP,
@@ -706,62 +2053,424 @@ fn create_pipeline_state(
             pInputElementDescs: input_layout.as_ptr(),
             NumElements: input_layout.len() as u32,
         },
-        PrimitiveTopologyType: D3D12_PRIMITIVE_TOPOLOGY_TYPE_TRIANGLE,
-        NumRenderTargets: 1,
-        RTVFormats: [
-            DXGI_FORMAT_B8G8R8A8_UNORM,
-            DXGI_FORMAT_UNKNOWN,
-            DXGI_FORMAT_UNKNOWN,
-            DXGI_FORMAT_UNKNOWN,
-            DXGI_FORMAT_UNKNOWN,
-            DXGI_FORMAT_UNKNOWN,
-            DXGI_FORMAT_UNKNOWN,
-            DXGI_FORMAT_UNKNOWN,
-        ],
+        PrimitiveTopologyType: D3D12_PRIMITIVE_TOPOLOGY_TYPE(pipeline.topology.raw()),
+        NumRenderTargets: pipeline.render_targets.len() as u32,
+        RTVFormats: rtv_formats,
         SampleDesc: DXGI_SAMPLE_DESC { Count: 1, Quality: 0 },
         ..Default::default()
     };
 
-    Ok(unsafe { device.CreateGraphicsPipelineState(&description) }?)
+    let Some(cache_path) = cache_path else {
+        return Ok(unsafe { device.CreateGraphicsPipelineState(&description) }?);
+    };
+    let key = pipeline_cache_key(
+        &description,
+        vertex_shader,
+        pixel_shader,
+        &shaders.permutation,
+        &input_layout,
+        adapter,
+    );
+    let mut cache = PipelineCache::load(cache_path, PIPELINE_CACHE_MAX_BYTES);
+
+    let mut pipeline_state = None;
+    if let Some(blob) = cache.get(key) {
+        description.CachedPSO = D3D12_CACHED_PIPELINE_STATE {
+            pCachedBlob: blob.as_ptr().cast(),
+            CachedBlobSizeInBytes: blob.len(),
+        };
+        match unsafe { device.CreateGraphicsPipelineState(&description) } {
+            Ok(cached) => {
+                info!(%key, "Pipeline state loaded from cache");
+                pipeline_state = Some(cached);
+            }
+            // A driver update or a different adapter invalidates cached blobs.
+            Err(error) if rejected_cached_blob(&error) => {
+                warn!(%key, %error, "Driver rejected the cached pipeline state, rebuilding it");
+                cache.remove(key);
+            }
+            Err(error) => return Err(error.into()),
+        }
+        description.CachedPSO = D3D12_CACHED_PIPELINE_STATE::default();
+    }
+
+    let pipeline_state = match pipeline_state {
+        Some(pipeline_state) => pipeline_state,
+        None => {
+            let pipeline_state: ID3D12PipelineState =
+                unsafe { device.CreateGraphicsPipelineState(&description) }?;
+            match unsafe { pipeline_state.GetCachedBlob() } {
+                Ok(blob) => cache.insert(key, blob_bytes(&blob).to_vec()),
+                Err(error) => warn!(%error, "Failed to read the pipeline state's cached blob"),
+            }
+            pipeline_state
+        }
+    };
+    if let Err(error) = cache.save(cache_path) {
+        warn!(?error, "Failed to save the pipeline cache");
+    }
+    Ok(pipeline_state)
+}
+
+/// Whether creating a pipeline state failed because of its cached blob, so that building it
+/// without one can succeed.
+fn rejected_cached_blob(error: &Error) -> bool {
+    let category = ErrorCategory::from_hresult(error.code().0);
+    // Some drivers report a stale blob as an invalid argument rather than a mismatch.
+    category.disposition() == ErrorDisposition::Retry || category == ErrorCategory::InvalidCall
+}
+
+/// Everything that decides whether a cached blob can be reused: the bytecode and the permutation
+/// it was compiled in, input layout, fixed-function state and the adapter it was created on. The driver checks its own version
+/// when the blob is used.
+fn pipeline_cache_key(
+    description: &D3D12_GRAPHICS_PIPELINE_STATE_DESC,
+    vertex_shader: &ID3DBlob,
+    pixel_shader: &ID3DBlob,
+    permutation: &ShaderPermutation,
+    input_layout: &[D3D12_INPUT_ELEMENT_DESC],
+    adapter: &AdapterInfo,
+) -> PipelineCacheKey {
+    let mut key = PipelineKeyBuilder::default()
+        .bytes(blob_bytes(vertex_shader))
+        .bytes(blob_bytes(pixel_shader))
+        .bytes(&permutation.key().to_le_bytes());
+    for element in input_layout {
+        key = key
+            .str(&unsafe { element.SemanticName.to_string() }.unwrap_or_default())
+            .u32(element.SemanticIndex)
+            .u32(element.Format.0 as u32)
+            .u32(element.InputSlot)
+            .u32(element.AlignedByteOffset);
+    }
+    key.str(&format!("{:?}", description.BlendState))
+        .u32(description.SampleMask)
+        .str(&format!("{:?}", description.RasterizerState))
+        .str(&format!("{:?}", description.DepthStencilState))
+        .u32(description.PrimitiveTopologyType.0 as u32)
+        .str(&format!("{:?}", description.RTVFormats))
+        .str(&adapter.description)
+        .u32(adapter.vendor_id)
+        .u32(adapter.device_id)
+        .finish()
+}
+
+/// Fail with every mismatch listed when the vertex shader reads inputs that `Vertex` does not
+/// provide, rather than with the bare `E_INVALIDARG` pipeline creation would return.
+fn check_vertex_shader_inputs(vertex_shader: &ID3DBlob) -> eyre::Result<()> {
+    let container = DxbcContainer::parse(blob_bytes(vertex_shader))
+        .wrap_err("Failed to read the compiled vertex shader")?;
+    let Some(signature) = &container.input_signature else {
+        warn!("The compiled vertex shader has no input signature to check");
+        return Ok(());
+    };
+    let problems = signature.check_input_layout(Vertex::ELEMENTS);
+    if !problems.is_empty() {
+        eyre::bail!(
+            "The vertex shader does not match the vertex layout:\n  {}",
+            problems.join("\n  ")
+        );
+    }
+    Ok(())
+}
+
+/// A pipeline's compiled shaders.
+#[derive(Debug)]
+struct PipelineShaders {
+    vertex_shader: ID3DBlob,
+    pixel_shader: ID3DBlob,
+    permutation: ShaderPermutation,
+    /// Every file the shaders include, for hot reload to watch.
+    includes: Vec<PathBuf>,
+}
+
+/// Compile the pipeline's shaders in the permutation `defines` select.
+fn compile_pipeline_shaders(
+    pipeline: &PipelineDescription,
+    shader_source: &ShaderSource,
+    defines: &[Define],
+    warnings_as_errors: bool,
+) -> eyre::Result<PipelineShaders> {
+    let permutation = ShaderPermutation::select(&pipeline.permutation_keys, defines)?;
+    info!(%permutation, key = format_args!("{:016x}", permutation.key()), "Compiling shaders");
+    let settings = CompileSettings {
+        flags: if cfg!(debug_assertions) {
+            D3DCOMPILE_DEBUG | D3DCOMPILE_SKIP_OPTIMIZATION
+        } else {
+            0
+        },
+        defines: permutation.defines(),
+        warnings_as_errors,
+    };
+    let source = shader_source.read(&pipeline.shader)?;
+    let resolver = shader_source.include_resolver(&pipeline.include_paths);
+
+    let (vertex_shader, mut includes) = compile_stage(
+        &pipeline.shader,
+        &source,
+        &pipeline.vertex_shader,
+        &settings,
+        &*resolver,
+    )?;
+    let (pixel_shader, pixel_includes) = compile_stage(
+        &pipeline.shader,
+        &source,
+        &pipeline.pixel_shader,
+        &settings,
+        &*resolver,
+    )?;
+    for include in pixel_includes {
+        if !includes.contains(&include) {
+            includes.push(include);
+        }
+    }
+    Ok(PipelineShaders {
+        vertex_shader,
+        pixel_shader,
+        permutation,
+        includes,
+    })
+}
+
+/// How every stage of a pipeline is compiled.
+struct CompileSettings<'a> {
+    flags: u32,
+    /// `(name, definition)` pairs.
+    defines: &'a [(String, String)],
+    warnings_as_errors: bool,
 }
 
+fn compile_stage(
+    path: &Path,
+    source: &str,
+    stage: &ShaderStage,
+    settings: &CompileSettings<'_>,
+    resolver: impl IncludeResolver,
+) -> eyre::Result<(ID3DBlob, Vec<PathBuf>)> {
+    let entry_point = CString::new(stage.entry_point.as_str())?;
+    let target = CString::new(stage.target.as_str())?;
+    compile_shader(
+        path,
+        source,
+        PCSTR(entry_point.as_ptr().cast()),
+        PCSTR(target.as_ptr().cast()),
+        settings,
+        resolver,
+    )
+}
+
+/// Compile one entry point of `source`, the text of the file at `path`, returning the bytecode
+/// and the files it included. The compiler's messages are logged as they are parsed, and a
+/// failure carries them rendered against the source.
 fn compile_shader(
-    path: &HSTRING,
+    path: &Path,
+    source: &str,
     entry_point: PCSTR,
     target: PCSTR,
-    flags: u32,
-) -> eyre::Result<ID3DBlob> {
+    settings: &CompileSettings<'_>,
+    resolver: impl IncludeResolver,
+) -> eyre::Result<(ID3DBlob, Vec<PathBuf>)> {
+    // Compiler messages name the file by this.
+    let source_name = CString::new(path.to_string_lossy().as_bytes())?;
+    let define_strings = settings
+        .defines
+        .iter()
+        .map(|(name, definition)| {
+            Ok((
+                CString::new(name.as_str())?,
+                CString::new(definition.as_str())?,
+            ))
+        })
+        .collect::<eyre::Result<Vec<_>>>()?;
+    // Terminated by a null entry.
+    let macros: Vec<D3D_SHADER_MACRO> = define_strings
+        .iter()
+        .map(|(name, definition)| D3D_SHADER_MACRO {
+            Name: PCSTR(name.as_ptr().cast()),
+            Definition: PCSTR(definition.as_ptr().cast()),
+        })
+        .chain([D3D_SHADER_MACRO::default()])
+        .collect();
+    let handler = IncludeHandler::new(resolver, path.to_path_buf());
+    let include = ID3DInclude::new(&handler);
     let mut shader = None;
     let mut error = None;
-    unsafe {
-        D3DCompileFromFile(
-            path,
-            None,
-            None,
+    let result = unsafe {
+        D3DCompile(
+            source.as_ptr().cast(),
+            source.len(),
+            PCSTR(source_name.as_ptr().cast()),
+            Some(macros.as_ptr()),
+            &*include,
             entry_point,
             target,
-            flags,
+            settings.flags,
             0,
             &mut shader,
             Some(&mut error),
         )
+    };
+
+    let mut diagnostics = error
+        .as_ref()
+        .map(|blob| Diagnostics::parse_bytes(blob_bytes(blob)))
+        .unwrap_or_default();
+    let promoted = settings.warnings_as_errors && diagnostics.has_warnings();
+    if promoted {
+        diagnostics.promote_warnings();
+    }
+    diagnostics.emit();
+
+    if result.is_err() || diagnostics.has_errors() {
+        // The compiler only reports that an include failed to open; say why first.
+        let mut messages = handler.errors.take();
+        let sources: Vec<(PathBuf, String)> = std::iter::once((normalize(path), source.into()))
+            .chain(handler.sources.take())
+            .collect();
+        if !diagnostics.is_empty() {
+            messages.push(diagnostics.render(|file| source_named(&sources, file)));
+        }
+        if promoted {
+            messages.push("Warnings are treated as errors (--warnings-as-errors)".to_string());
+        }
+        return Err(match result {
+            Err(err) if messages.is_empty() => err.into(),
+            result => eyre::Report::new(ShaderCompileError {
+                path: path.to_path_buf(),
+                entry_point: unsafe { entry_point.to_string() }.unwrap_or_default(),
+                target: unsafe { target.to_string() }.unwrap_or_default(),
+                code: result.err().map_or(E_FAIL, |err| err.code()).0,
+                diagnostics: messages.join("\n\n"),
+            }),
+        });
     }
-    .map_err(|err| shader_error(err, error))?;
 
-    Ok(shader.expect("shader blob should be initialized"))
+    let includes = handler.tracker.borrow().dependencies();
+    Ok((shader.expect("shader blob should be initialized"), includes))
 }
 
-fn shader_error(error: windows::core::Error, blob: Option<ID3DBlob>) -> eyre::Error {
-    if let Some(blob) = blob {
-        let bytes = unsafe {
-            std::slice::from_raw_parts(blob.GetBufferPointer() as *const u8, blob.GetBufferSize())
+/// The text of the file a compiler message names: the root by the source name it was compiled
+/// with, an include by its resolved path or the name it was included by.
+fn source_named<'a>(sources: &'a [(PathBuf, String)], file: &str) -> Option<&'a str> {
+    let file = normalize(Path::new(file));
+    sources
+        .iter()
+        .find(|(path, _)| *path == file)
+        .or_else(|| sources.iter().find(|(path, _)| path.ends_with(&file)))
+        .map(|(_, source)| source.as_str())
+}
+
+/// Serves the compiler's `#include`s from an [`IncludeTracker`]. The compiler identifies the
+/// including file by the data pointer `Open` returned for it, or null for the root file.
+struct IncludeHandler<R> {
+    tracker: RefCell<IncludeTracker<R>>,
+    /// Sources the compiler has open, by data pointer, kept alive until it closes them.
+    open: RefCell<HashMap<usize, OpenInclude>>,
+    /// Why includes failed, since the compiler cannot be told.
+    errors: RefCell<Vec<String>>,
+    /// Every include opened, to show the lines compiler messages point at.
+    sources: RefCell<Vec<(PathBuf, String)>>,
+}
+
+struct OpenInclude {
+    id: IncludeId,
+    _source: Box<[u8]>,
+}
+
+impl<R: IncludeResolver> IncludeHandler<R> {
+    fn new(resolver: R, root: PathBuf) -> Self {
+        Self {
+            tracker: RefCell::new(IncludeTracker::new(resolver, root)),
+            open: RefCell::default(),
+            errors: RefCell::default(),
+            sources: RefCell::default(),
+        }
+    }
+
+    fn open_include(
+        &self,
+        kind: IncludeKind,
+        name: &str,
+        parent_data: *const c_void,
+    ) -> eyre::Result<(*mut c_void, u32)> {
+        let parent = self
+            .open
+            .borrow()
+            .get(&(parent_data as usize))
+            .map(|open| open.id);
+        let (id, resolved) = self.tracker.borrow_mut().open(kind, name, parent)?;
+        // An empty include still needs a valid pointer to be closed by.
+        let source: Box<[u8]> = if resolved.source.is_empty() {
+            Box::new(*b"\n")
+        } else {
+            resolved.source.into_boxed_slice()
         };
-        eyre::eyre!("{error}: {}", String::from_utf8_lossy(bytes).trim())
-    } else {
-        error.into()
+        let bytes = u32::try_from(source.len())
+            .wrap_err_with(|| format!("Include {} is too large", resolved.path.display()))?;
+        self.sources
+            .borrow_mut()
+            .push((resolved.path, String::from_utf8_lossy(&source).into_owned()));
+        let data = source.as_ptr().cast_mut().cast::<c_void>();
+        self.open.borrow_mut().insert(
+            data as usize,
+            OpenInclude {
+                id,
+                _source: source,
+            },
+        );
+        Ok((data, bytes))
+    }
+}
+
+impl<R: IncludeResolver> ID3DInclude_Impl for IncludeHandler<R> {
+    fn Open(
+        &self,
+        includetype: D3D_INCLUDE_TYPE,
+        pfilename: &PCSTR,
+        pparentdata: *const c_void,
+        ppdata: *mut *mut c_void,
+        pbytes: *mut u32,
+    ) -> windows::core::Result<()> {
+        let kind = if includetype == D3D_INCLUDE_SYSTEM {
+            IncludeKind::System
+        } else {
+            IncludeKind::Local
+        };
+        let name = unsafe { pfilename.to_string() }.unwrap_or_default();
+        match self.open_include(kind, &name, pparentdata) {
+            Ok((data, bytes)) => {
+                unsafe {
+                    *ppdata = data;
+                    *pbytes = bytes;
+                }
+                Ok(())
+            }
+            Err(error) => {
+                self.errors.borrow_mut().push(format!("{error:#}"));
+                Err(E_FAIL.into())
+            }
+        }
+    }
+
+    fn Close(&self, pdata: *const c_void) -> windows::core::Result<()> {
+        self.open.borrow_mut().remove(&(pdata as usize));
+        Ok(())
     }
 }
 
+fn shader_error(error: windows::core::Error, blob: Option<ID3DBlob>) -> eyre::Error {
+    let Some(blob) = blob else {
+        return error.into();
+    };
+    let diagnostics = Diagnostics::parse_bytes(blob_bytes(&blob));
+    diagnostics.emit();
+    eyre::eyre!("{error}:\n{}", diagnostics.render(|_| None))
+}
+
+fn blob_bytes(blob: &ID3DBlob) -> &[u8] {
+    unsafe { std::slice::from_raw_parts(blob.GetBufferPointer() as *const u8, blob.GetBufferSize()) }
+}
+
 fn shader_bytecode(shader: &ID3DBlob) -> D3D12_SHADER_BYTECODE {
     D3D12_SHADER_BYTECODE {
         pShaderBytecode: unsafe { shader.GetBufferPointer() },
@@ -769,265 +2478,120 @@ fn shader_bytecode(shader: &ID3DBlob) -> D3D12_SHADER_BYTECODE {
     }
 }
 
-fn shader_path() -> PathBuf {
-    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
-        .join("src")
-        .join("shaders.hlsl")
+/// A pipeline rebuilt by shader hot reload.
+#[derive(Debug)]
+struct ReloadedPipeline {
+    pipeline: PipelineDescription,
+    pipeline_state: ID3D12PipelineState,
 }
 
-fn create_vertex_buffer(
+/// Watch the pipeline file, its shader and everything the shader includes, rebuilding the
+/// pipeline state on the tokio runtime the CLI runs on. Only a shader directory can change.
+fn spawn_shader_reload(
     device: &ID3D12Device,
-) -> eyre::Result<(ID3D12Resource, D3D12_VERTEX_BUFFER_VIEW)> {
-    let buffer_size = (std::mem::size_of::<Vertex>() * MAX_VERTEX_COUNT) as u64;
-
-    let mut vertex_buffer = None;
-    unsafe {
-        device.CreateCommittedResource(
-            &D3D12_HEAP_PROPERTIES {
-                Type: D3D12_HEAP_TYPE_UPLOAD,
-                ..Default::default()
-            },
-            D3D12_HEAP_FLAG_NONE,
-            &D3D12_RESOURCE_DESC {
-                Dimension: D3D12_RESOURCE_DIMENSION_BUFFER,
-                Width: buffer_size,
-                Height: 1,
-                DepthOrArraySize: 1,
-                MipLevels: 1,
-                SampleDesc: DXGI_SAMPLE_DESC { Count: 1, Quality: 0 },
-                Layout: D3D12_TEXTURE_LAYOUT_ROW_MAJOR,
-                ..Default::default()
-            },
-            D3D12_RESOURCE_STATE_GENERIC_READ,
-            None,
-            &mut vertex_buffer,
-        )?
+    root_signature: &ID3D12RootSignature,
+    pipeline: &PipelineDescription,
+    shaders: &PipelineShaders,
+    adapter: &AdapterInfo,
+    options: &TransparentTriangleOptions,
+) -> Option<HotReloader<ReloadedPipeline>> {
+    if !options.shader_source.is_editable() {
+        warn!(
+            "Shader hot reload needs --shader-dir or {SHADER_DIR_ENV}, continuing with the embedded shaders"
+        );
+        return None;
+    }
+    let runtime = match tokio::runtime::Handle::try_current() {
+        Ok(runtime) => runtime,
+        Err(error) => {
+            warn!(%error, "Shader hot reload needs a tokio runtime, continuing without it");
+            return None;
+        }
     };
-    let vertex_buffer: ID3D12Resource =
-        vertex_buffer.expect("vertex buffer should be initialized");
-
-    Ok((
-        vertex_buffer.clone(),
-        D3D12_VERTEX_BUFFER_VIEW {
-            BufferLocation: unsafe { vertex_buffer.GetGPUVirtualAddress() },
-            SizeInBytes: buffer_size as u32,
-            StrideInBytes: std::mem::size_of::<Vertex>() as u32,
+    let pipeline_path = options.shader_source.pipeline_path();
+    let source = ModifiedTimeSource::new(
+        [pipeline_path, pipeline.shader.clone()]
+            .into_iter()
+            .chain(shaders.includes.iter().cloned()),
+    );
+    info!(watched = ?source.watched(), "Watching shaders for changes");
+
+    let device = device.clone();
+    let root_signature = root_signature.clone();
+    let adapter = adapter.clone();
+    let cache_path = options.pipeline_cache.clone();
+    let shader_source = options.shader_source.clone();
+    let defines = options.defines.clone();
+    let warnings_as_errors = options.warnings_as_errors;
+    Some(HotReloader::spawn(
+        &runtime,
+        AssetWatcher::new(source.clone(), SHADER_RELOAD_DEBOUNCE),
+        SHADER_RELOAD_POLL_INTERVAL,
+        move |_| {
+            let pipeline = shader_source.load_pipeline()?;
+            let shaders =
+                compile_pipeline_shaders(&pipeline, &shader_source, &defines, warnings_as_errors)?;
+            // Pick up a shader the pipeline file switched to and includes added since.
+            source.watch(
+                std::iter::once(pipeline.shader.clone()).chain(shaders.includes.iter().cloned()),
+            );
+            let pipeline_state = create_pipeline_state(
+                &device,
+                &root_signature,
+                &pipeline,
+                &shaders,
+                &adapter,
+                cache_path.as_deref(),
+            )?;
+            Ok(ReloadedPipeline {
+                pipeline,
+                pipeline_state,
+            })
         },
     ))
 }
 
-fn append_demo_triangle(vertices: &mut Vec<Vertex>) {
-    vertices.extend_from_slice(&[
-        Vertex {
-            position: [0.0, 0.6, 0.0],
-            color: [1.0, 0.2, 0.2, 0.85],
-        },
-        Vertex {
-            position: [0.55, -0.4, 0.0],
-            color: [0.2, 1.0, 0.4, 0.85],
-        },
-        Vertex {
-            position: [-0.55, -0.4, 0.0],
-            color: [0.2, 0.5, 1.0, 0.85],
-        },
-    ]);
-}
-
-fn append_cursor_target(
-    vertices: &mut Vec<Vertex>,
-    width: f32,
-    height: f32,
-    cursor_x: f32,
-    cursor_y: f32,
-) {
-    let ring_color = [1.0, 1.0, 1.0, 0.95];
-    let arm_color = [1.0, 0.15, 0.15, 0.95];
-
-    append_ring(
-        vertices,
-        width,
-        height,
-        cursor_x,
-        cursor_y,
-        CURSOR_RING_INNER_RADIUS,
-        CURSOR_RING_OUTER_RADIUS,
-        ring_color,
-    );
-
-    append_rect(
-        vertices,
-        width,
-        height,
-        cursor_x - CURSOR_ARM_THICKNESS * 0.5,
-        cursor_y - CURSOR_RING_OUTER_RADIUS - CURSOR_ARM_LENGTH,
-        CURSOR_ARM_THICKNESS,
-        CURSOR_ARM_LENGTH - CURSOR_ARM_GAP,
-        arm_color,
-    );
-    append_rect(
-        vertices,
-        width,
-        height,
-        cursor_x - CURSOR_ARM_THICKNESS * 0.5,
-        cursor_y + CURSOR_RING_OUTER_RADIUS + CURSOR_ARM_GAP,
-        CURSOR_ARM_THICKNESS,
-        CURSOR_ARM_LENGTH - CURSOR_ARM_GAP,
-        arm_color,
-    );
-    append_rect(
-        vertices,
-        width,
-        height,
-        cursor_x - CURSOR_RING_OUTER_RADIUS - CURSOR_ARM_LENGTH,
-        cursor_y - CURSOR_ARM_THICKNESS * 0.5,
-        CURSOR_ARM_LENGTH - CURSOR_ARM_GAP,
-        CURSOR_ARM_THICKNESS,
-        arm_color,
-    );
-    append_rect(
-        vertices,
-        width,
-        height,
-        cursor_x + CURSOR_RING_OUTER_RADIUS + CURSOR_ARM_GAP,
-        cursor_y - CURSOR_ARM_THICKNESS * 0.5,
-        CURSOR_ARM_LENGTH - CURSOR_ARM_GAP,
-        CURSOR_ARM_THICKNESS,
-        arm_color,
-    );
-}
-
-fn append_ring(
-    vertices: &mut Vec<Vertex>,
-    width: f32,
-    height: f32,
-    center_x: f32,
-    center_y: f32,
-    inner_radius: f32,
-    outer_radius: f32,
-    color: [f32; 4],
-) {
-    for segment in 0..CURSOR_RING_SEGMENTS {
-        let angle_start = std::f32::consts::TAU * segment as f32 / CURSOR_RING_SEGMENTS as f32;
-        let angle_end = std::f32::consts::TAU * (segment + 1) as f32 / CURSOR_RING_SEGMENTS as f32;
-
-        let inner_start = (
-            center_x + inner_radius * angle_start.cos(),
-            center_y + inner_radius * angle_start.sin(),
-        );
-        let inner_end = (
-            center_x + inner_radius * angle_end.cos(),
-            center_y + inner_radius * angle_end.sin(),
-        );
-        let outer_start = (
-            center_x + outer_radius * angle_start.cos(),
-            center_y + outer_radius * angle_start.sin(),
-        );
-        let outer_end = (
-            center_x + outer_radius * angle_end.cos(),
-            center_y + outer_radius * angle_end.sin(),
-        );
+/// Create the static scene geometry in a default heap buffer. The vertices go through a staging
+/// upload buffer once, and the GPU is waited on so the staging buffer can be released.
+fn create_vertex_buffer(
+    device: &ID3D12Device,
+    command_queue: &ID3D12CommandQueue,
+    command_allocator: &ID3D12CommandAllocator,
+    command_list: &ID3D12GraphicsCommandList,
+    frames: &mut FrameScheduler<FenceTimeline, FRAME_COUNT>,
+    vertices: &[Vertex],
+) -> eyre::Result<ID3D12Resource> {
+    let size = std::mem::size_of_val(vertices) as u64;
+    let vertex_buffer = create_default_buffer(device, size)?;
+    let staging = create_upload_buffer(device, size)?;
 
-        push_quad(
-            vertices,
-            width,
-            height,
-            outer_start,
-            outer_end,
-            inner_end,
-            inner_start,
-            color,
-        );
+    unsafe {
+        let mut mapped = std::ptr::null_mut();
+        staging.Map(0, None, Some(&mut mapped))?;
+        std::ptr::copy_nonoverlapping(vertices.as_ptr(), mapped as *mut Vertex, vertices.len());
+        staging.Unmap(0, None);
     }
-}
 
-fn append_rect(
-    vertices: &mut Vec<Vertex>,
-    width: f32,
-    height: f32,
-    left: f32,
-    top: f32,
-    rect_width: f32,
-    rect_height: f32,
-    color: [f32; 4],
-) {
-    push_quad(
-        vertices,
-        width,
-        height,
-        (left, top),
-        (left + rect_width, top),
-        (left + rect_width, top + rect_height),
-        (left, top + rect_height),
-        color,
+    let barrier = transition_barrier(
+        &vertex_buffer,
+        D3D12_RESOURCE_STATE_COPY_DEST,
+        D3D12_RESOURCE_STATE_VERTEX_AND_CONSTANT_BUFFER,
     );
-}
-
-fn push_quad(
-    vertices: &mut Vec<Vertex>,
-    width: f32,
-    height: f32,
-    top_left: (f32, f32),
-    top_right: (f32, f32),
-    bottom_right: (f32, f32),
-    bottom_left: (f32, f32),
-    color: [f32; 4],
-) {
-    let top_left = to_ndc(width, height, top_left.0, top_left.1);
-    let top_right = to_ndc(width, height, top_right.0, top_right.1);
-    let bottom_right = to_ndc(width, height, bottom_right.0, bottom_right.1);
-    let bottom_left = to_ndc(width, height, bottom_left.0, bottom_left.1);
-
-    vertices.extend_from_slice(&[
-        Vertex {
-            position: top_left,
-            color,
-        },
-        Vertex {
-            position: top_right,
-            color,
-        },
-        Vertex {
-            position: bottom_right,
-            color,
-        },
-        Vertex {
-            position: top_left,
-            color,
-        },
-        Vertex {
-            position: bottom_right,
-            color,
-        },
-        Vertex {
-            position: bottom_left,
-            color,
-        },
-    ]);
-}
+    unsafe {
+        command_allocator.Reset()?;
+        command_list.Reset(command_allocator, None)?;
+        command_list.CopyBufferRegion(&vertex_buffer, 0, &staging, 0, size);
+        command_list.ResourceBarrier(std::slice::from_ref(&barrier));
+        command_list.Close()?;
+    }
+    // `transition_barrier` holds a reference to the buffer; release it now that the barrier has
+    // been recorded.
+    let transition = std::mem::ManuallyDrop::into_inner(unsafe { barrier.Anonymous.Transition });
+    drop(std::mem::ManuallyDrop::into_inner(transition.pResource));
 
-fn to_ndc(width: f32, height: f32, x: f32, y: f32) -> [f32; 3] {
-    [
-        (x / width) * 2.0 - 1.0,
-        1.0 - (y / height) * 2.0,
-        0.0,
-    ]
-}
+    let command_lists = [Some(command_list.cast::<ID3D12CommandList>()?)];
+    unsafe { command_queue.ExecuteCommandLists(&command_lists) };
+    frames.wait_for_gpu()?;
 
-fn transition_barrier(
-    resource: &ID3D12Resource,
-    before: D3D12_RESOURCE_STATES,
-    after: D3D12_RESOURCE_STATES,
-) -> D3D12_RESOURCE_BARRIER {
-    D3D12_RESOURCE_BARRIER {
-        Type: D3D12_RESOURCE_BARRIER_TYPE_TRANSITION,
-        Flags: D3D12_RESOURCE_BARRIER_FLAG_NONE,
-        Anonymous: D3D12_RESOURCE_BARRIER_0 {
-            Transition: std::mem::ManuallyDrop::new(D3D12_RESOURCE_TRANSITION_BARRIER {
-                pResource: std::mem::ManuallyDrop::new(Some(resource.clone())),
-                StateBefore: before,
-                StateAfter: after,
-                Subresource: D3D12_RESOURCE_BARRIER_ALL_SUBRESOURCES,
-            }),
-        },
-    }
+    Ok(vertex_buffer)
 }
@@ -0,0 +1,236 @@
+use std::time::Duration;
+use std::time::Instant;
+
+/// `D3D12_REQ_TEXTURE2D_U_OR_V_DIMENSION`: back buffers cannot be larger than this, however wide
+/// a window spanning several monitors gets.
+pub const MAX_SWAP_CHAIN_DIMENSION: u32 = 16384;
+
+/// Back buffers cannot be empty, so a window that starts out zero-sized still gets this.
+pub const MIN_SWAP_CHAIN_DIMENSION: u32 = 1;
+
+/// A `WM_SIZE` notification, reduced to what the renderer cares about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowSizeEvent {
+    Minimized,
+    Resized { width: u32, height: u32 },
+}
+
+/// What the message loop should do this iteration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResizeAction {
+    /// Render at the current swap chain size.
+    Render,
+    /// Nothing is visible; wait for window messages instead of rendering.
+    Paused,
+    /// Rebuild the swap chain buffers at this size, then call [`ResizeController::resized`].
+    Resize { width: u32, height: u32 },
+}
+
+/// Decides when the swap chain needs rebuilding.
+///
+/// Size changes are debounced so that dragging a window edge does not rebuild the swap chain on
+/// every message, and zero-sized or minimised windows pause rendering instead of trying to create
+/// empty back buffers. Sizes beyond [`MAX_SWAP_CHAIN_DIMENSION`] are clamped to it.
+#[derive(Debug, Clone)]
+pub struct ResizeController {
+    debounce: Duration,
+    swap_chain_size: (u32, u32),
+    /// The latest size reported by the window and when it arrived.
+    requested: Option<((u32, u32), Instant)>,
+    minimized: bool,
+}
+
+impl ResizeController {
+    pub fn new(width: u32, height: u32, debounce: Duration) -> Self {
+        Self {
+            debounce,
+            swap_chain_size: clamp_size(width, height),
+            requested: None,
+            minimized: false,
+        }
+    }
+
+    pub fn swap_chain_size(&self) -> (u32, u32) {
+        self.swap_chain_size
+    }
+
+    pub fn on_event(&mut self, event: WindowSizeEvent, now: Instant) {
+        match event {
+            WindowSizeEvent::Minimized => self.minimized = true,
+            WindowSizeEvent::Resized { width, height } => {
+                self.minimized = false;
+                self.requested = Some((clamp_size(width, height), now));
+            }
+        }
+    }
+
+    pub fn poll(&mut self, now: Instant) -> ResizeAction {
+        if self.minimized {
+            return ResizeAction::Paused;
+        }
+
+        let Some((size, requested_at)) = self.requested else {
+            return ResizeAction::Render;
+        };
+        if size.0 == 0 || size.1 == 0 {
+            return ResizeAction::Paused;
+        }
+        if size == self.swap_chain_size {
+            self.requested = None;
+            return ResizeAction::Render;
+        }
+        if now.saturating_duration_since(requested_at) < self.debounce {
+            // Keep presenting the old buffers, stretched, until the size settles.
+            return ResizeAction::Render;
+        }
+
+        ResizeAction::Resize {
+            width: size.0,
+            height: size.1,
+        }
+    }
+
+    /// Record that the swap chain now has `width` x `height` buffers.
+    pub fn resized(&mut self, width: u32, height: u32) {
+        self.swap_chain_size = clamp_size(width, height);
+        if self
+            .requested
+            .is_some_and(|(size, _)| size == self.swap_chain_size)
+        {
+            self.requested = None;
+        }
+    }
+}
+
+/// The size to create the first swap chain at for a `width` x `height` client area: clamped like
+/// every later resize, and never smaller than [`MIN_SWAP_CHAIN_DIMENSION`].
+pub fn initial_swap_chain_size(width: u32, height: u32) -> (u32, u32) {
+    let (width, height) = clamp_size(width, height);
+    (
+        width.max(MIN_SWAP_CHAIN_DIMENSION),
+        height.max(MIN_SWAP_CHAIN_DIMENSION),
+    )
+}
+
+fn clamp_size(width: u32, height: u32) -> (u32, u32) {
+    (
+        width.min(MAX_SWAP_CHAIN_DIMENSION),
+        height.min(MAX_SWAP_CHAIN_DIMENSION),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEBOUNCE: Duration = Duration::from_millis(100);
+
+    fn resized(width: u32, height: u32) -> WindowSizeEvent {
+        WindowSizeEvent::Resized { width, height }
+    }
+
+    #[test]
+    fn unchanged_size_keeps_rendering() {
+        let start = Instant::now();
+        let mut controller = ResizeController::new(800, 600, DEBOUNCE);
+        assert_eq!(controller.poll(start), ResizeAction::Render);
+        controller.on_event(resized(800, 600), start);
+        assert_eq!(controller.poll(start + DEBOUNCE * 2), ResizeAction::Render);
+    }
+
+    #[test]
+    fn resize_waits_until_the_size_settles() {
+        let start = Instant::now();
+        let at = |millis| start + Duration::from_millis(millis);
+        let mut controller = ResizeController::new(800, 600, DEBOUNCE);
+
+        controller.on_event(resized(900, 600), at(10));
+        assert_eq!(controller.poll(at(50)), ResizeAction::Render);
+        // A new size restarts the debounce.
+        controller.on_event(resized(1000, 600), at(60));
+        assert_eq!(controller.poll(at(120)), ResizeAction::Render);
+        assert_eq!(
+            controller.poll(at(160)),
+            ResizeAction::Resize {
+                width: 1000,
+                height: 600,
+            }
+        );
+        // Until the swap chain has been rebuilt, the resize is still wanted.
+        assert_eq!(
+            controller.poll(at(170)),
+            ResizeAction::Resize {
+                width: 1000,
+                height: 600,
+            }
+        );
+
+        controller.resized(1000, 600);
+        assert_eq!(controller.swap_chain_size(), (1000, 600));
+        assert_eq!(controller.poll(at(180)), ResizeAction::Render);
+    }
+
+    #[test]
+    fn returning_to_the_current_size_cancels_the_resize() {
+        let start = Instant::now();
+        let mut controller = ResizeController::new(800, 600, DEBOUNCE);
+        controller.on_event(resized(900, 600), start);
+        controller.on_event(resized(800, 600), start);
+        assert_eq!(controller.poll(start + DEBOUNCE), ResizeAction::Render);
+    }
+
+    #[test]
+    fn minimized_and_zero_sized_windows_pause() {
+        let start = Instant::now();
+        let mut controller = ResizeController::new(800, 600, DEBOUNCE);
+        controller.on_event(WindowSizeEvent::Minimized, start);
+        assert_eq!(controller.poll(start + DEBOUNCE), ResizeAction::Paused);
+
+        // Restoring to the same size resumes without rebuilding.
+        controller.on_event(resized(800, 600), start + DEBOUNCE);
+        assert_eq!(controller.poll(start + DEBOUNCE), ResizeAction::Render);
+
+        controller.on_event(resized(0, 600), start);
+        assert_eq!(controller.poll(start + DEBOUNCE * 2), ResizeAction::Paused);
+        controller.on_event(resized(800, 0), start);
+        assert_eq!(controller.poll(start + DEBOUNCE * 2), ResizeAction::Paused);
+        assert_eq!(controller.swap_chain_size(), (800, 600));
+    }
+
+    #[test]
+    fn sizes_are_clamped_to_the_largest_texture() {
+        let start = Instant::now();
+        let mut controller = ResizeController::new(800, 600, DEBOUNCE);
+        controller.on_event(resized(20_000, 600), start);
+        assert_eq!(
+            controller.poll(start + DEBOUNCE),
+            ResizeAction::Resize {
+                width: MAX_SWAP_CHAIN_DIMENSION,
+                height: 600,
+            }
+        );
+        controller.resized(MAX_SWAP_CHAIN_DIMENSION, 600);
+
+        // Growing further past the limit changes nothing.
+        controller.on_event(resized(30_000, 600), start + DEBOUNCE);
+        assert_eq!(controller.poll(start + DEBOUNCE * 3), ResizeAction::Render);
+
+        let controller = ResizeController::new(800, 40_000, DEBOUNCE);
+        assert_eq!(
+            controller.swap_chain_size(),
+            (800, MAX_SWAP_CHAIN_DIMENSION)
+        );
+    }
+    #[test]
+    fn initial_size_is_clamped_and_never_empty() {
+        assert_eq!(initial_swap_chain_size(800, 600), (800, 600));
+        assert_eq!(
+            initial_swap_chain_size(40_000, 600),
+            (MAX_SWAP_CHAIN_DIMENSION, 600)
+        );
+        assert_eq!(
+            initial_swap_chain_size(0, 0),
+            (MIN_SWAP_CHAIN_DIMENSION, MIN_SWAP_CHAIN_DIMENSION)
+        );
+    }
+}
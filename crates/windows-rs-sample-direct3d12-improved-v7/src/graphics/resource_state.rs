@@ -0,0 +1,448 @@
+use std::fmt::Debug;
+
+/// An opaque handle to a resource registered with a [`ResourceStateTracker`].
+///
+/// Slots are reused after [`ResourceStateTracker::unregister`], so the handle carries the slot's
+/// generation; a handle kept past its resource's unregistration is rejected rather than
+/// referring to whatever was registered in the slot next.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ResourceId {
+    index: u32,
+    generation: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StateTransition<S> {
+    pub resource: ResourceId,
+    pub before: S,
+    pub after: S,
+}
+
+/// A hand-written transition that disagreed with the tracked state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransitionIssue<S> {
+    /// The transition claimed the resource was in `claimed` but it was in `actual`.
+    Mismatched {
+        resource: ResourceId,
+        name: String,
+        claimed: S,
+        actual: S,
+    },
+    /// The transition left the resource in the state it was already in.
+    Redundant {
+        resource: ResourceId,
+        name: String,
+        state: S,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct TrackedResource<S> {
+    name: String,
+    state: S,
+}
+
+#[derive(Debug, Clone)]
+struct Slot<S> {
+    /// Bumped each time the slot's resource is unregistered.
+    generation: u32,
+    resource: Option<TrackedResource<S>>,
+}
+
+/// Knows the current state of each tracked resource and turns "I need state X" requests into
+/// batches of transitions.
+///
+/// The tracker is generic over the state type so it can be driven without a device; the D3D12
+/// backend maps its states to `D3D12_RESOURCE_STATES` when it issues the barriers.
+#[derive(Debug, Clone)]
+pub struct ResourceStateTracker<S> {
+    slots: Vec<Slot<S>>,
+    pending: Vec<StateTransition<S>>,
+    issues: Vec<TransitionIssue<S>>,
+    validate: bool,
+}
+
+impl<S: Copy + Eq + Debug> Default for ResourceStateTracker<S> {
+    fn default() -> Self {
+        Self::new(cfg!(debug_assertions))
+    }
+}
+
+impl<S: Copy + Eq + Debug> ResourceStateTracker<S> {
+    /// `validate` enables reporting of mismatched and redundant hand-written transitions.
+    pub fn new(validate: bool) -> Self {
+        Self {
+            slots: Vec::new(),
+            pending: Vec::new(),
+            issues: Vec::new(),
+            validate,
+        }
+    }
+
+    pub fn register(&mut self, name: impl Into<String>, initial_state: S) -> ResourceId {
+        let resource = TrackedResource {
+            name: name.into(),
+            state: initial_state,
+        };
+        if let Some(index) = self.slots.iter().position(|slot| slot.resource.is_none()) {
+            let slot = &mut self.slots[index];
+            slot.resource = Some(resource);
+            return ResourceId {
+                index: index as u32,
+                generation: slot.generation,
+            };
+        }
+        self.slots.push(Slot {
+            generation: 0,
+            resource: Some(resource),
+        });
+        ResourceId {
+            index: self.slots.len() as u32 - 1,
+            generation: 0,
+        }
+    }
+
+    /// Stop tracking `resource`, dropping any transitions still pending for it. Unregistering a
+    /// stale handle does nothing.
+    pub fn unregister(&mut self, resource: ResourceId) {
+        if self.tracked(resource).is_none() {
+            return;
+        }
+        let slot = &mut self.slots[resource.index as usize];
+        slot.resource = None;
+        slot.generation = slot.generation.wrapping_add(1);
+        self.pending
+            .retain(|transition| transition.resource != resource);
+    }
+
+    pub fn name(&self, resource: ResourceId) -> Option<&str> {
+        self.tracked(resource).map(|tracked| tracked.name.as_str())
+    }
+
+    /// The state `resource` will be in once the pending transitions are issued.
+    pub fn state(&self, resource: ResourceId) -> Option<S> {
+        self.tracked(resource).map(|tracked| tracked.state)
+    }
+
+    /// Request that `resource` be in `state` for the next command. Requests for the state the
+    /// resource is already in are free, and repeated requests before a flush collapse into one
+    /// transition.
+    pub fn require(&mut self, resource: ResourceId, state: S) -> eyre::Result<()> {
+        let tracked = self.tracked_mut(resource)?;
+        let before = tracked.state;
+        if before == state {
+            return Ok(());
+        }
+        tracked.state = state;
+
+        if let Some(index) = self
+            .pending
+            .iter()
+            .position(|transition| transition.resource == resource)
+        {
+            if self.pending[index].before == state {
+                self.pending.remove(index);
+            } else {
+                self.pending[index].after = state;
+            }
+            return Ok(());
+        }
+
+        self.pending.push(StateTransition {
+            resource,
+            before,
+            after: state,
+        });
+        Ok(())
+    }
+
+    /// Apply a transition written out by hand, reporting it if it does not match the tracked
+    /// state, which includes any request still pending for `resource`.
+    ///
+    /// Returns the transitions to issue, in order: the pending request for `resource`, if there
+    /// is one, followed by the transition as written. The pending request is taken out of the
+    /// next [`flush`](Self::flush) so it is not issued after the hand-written transition.
+    pub fn transition(
+        &mut self,
+        resource: ResourceId,
+        before: S,
+        after: S,
+    ) -> eyre::Result<Vec<StateTransition<S>>> {
+        let validate = self.validate;
+        let tracked = self.tracked_mut(resource)?;
+        let issue = if !validate {
+            None
+        } else if tracked.state != before {
+            Some(TransitionIssue::Mismatched {
+                resource,
+                name: tracked.name.clone(),
+                claimed: before,
+                actual: tracked.state,
+            })
+        } else if before == after {
+            Some(TransitionIssue::Redundant {
+                resource,
+                name: tracked.name.clone(),
+                state: after,
+            })
+        } else {
+            None
+        };
+        tracked.state = after;
+        self.issues.extend(issue);
+
+        let mut transitions = Vec::with_capacity(2);
+        if let Some(index) = self
+            .pending
+            .iter()
+            .position(|transition| transition.resource == resource)
+        {
+            transitions.push(self.pending.remove(index));
+        }
+        transitions.push(StateTransition {
+            resource,
+            before,
+            after,
+        });
+        Ok(transitions)
+    }
+
+    pub fn has_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Take the transitions requested since the last flush, to be issued as one barrier batch.
+    pub fn flush(&mut self) -> Vec<StateTransition<S>> {
+        std::mem::take(&mut self.pending)
+    }
+
+    pub fn take_issues(&mut self) -> Vec<TransitionIssue<S>> {
+        std::mem::take(&mut self.issues)
+    }
+
+    fn tracked(&self, resource: ResourceId) -> Option<&TrackedResource<S>> {
+        let slot = self.slots.get(resource.index as usize)?;
+        if slot.generation != resource.generation {
+            return None;
+        }
+        slot.resource.as_ref()
+    }
+
+    fn tracked_mut(&mut self, resource: ResourceId) -> eyre::Result<&mut TrackedResource<S>> {
+        let Some(slot) = self.slots.get_mut(resource.index as usize) else {
+            eyre::bail!("Resource {resource:?} is not tracked");
+        };
+        if slot.generation != resource.generation {
+            eyre::bail!(
+                "Resource {resource:?} is stale: it was unregistered and its slot is now at \
+                 generation {}",
+                slot.generation
+            );
+        }
+        slot.resource
+            .as_mut()
+            .ok_or_else(|| eyre::eyre!("Resource {resource:?} is not tracked"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum State {
+        Present,
+        RenderTarget,
+        CopySource,
+    }
+
+    fn transition(resource: ResourceId, before: State, after: State) -> StateTransition<State> {
+        StateTransition {
+            resource,
+            before,
+            after,
+        }
+    }
+
+    #[test]
+    fn requiring_the_current_state_is_free() {
+        let mut tracker = ResourceStateTracker::new(true);
+        let back_buffer = tracker.register("back buffer", State::Present);
+        tracker.require(back_buffer, State::Present).unwrap();
+        assert!(!tracker.has_pending());
+        assert_eq!(tracker.flush(), []);
+    }
+
+    #[test]
+    fn repeated_requests_collapse_into_one_transition() {
+        let mut tracker = ResourceStateTracker::new(true);
+        let back_buffer = tracker.register("back buffer", State::Present);
+        tracker.require(back_buffer, State::RenderTarget).unwrap();
+        tracker.require(back_buffer, State::CopySource).unwrap();
+        assert_eq!(tracker.state(back_buffer), Some(State::CopySource));
+        assert_eq!(
+            tracker.flush(),
+            [transition(back_buffer, State::Present, State::CopySource)]
+        );
+    }
+
+    #[test]
+    fn returning_to_the_original_state_cancels_the_request() {
+        let mut tracker = ResourceStateTracker::new(true);
+        let back_buffer = tracker.register("back buffer", State::Present);
+        tracker.require(back_buffer, State::RenderTarget).unwrap();
+        tracker.require(back_buffer, State::Present).unwrap();
+        assert!(!tracker.has_pending());
+        assert_eq!(tracker.state(back_buffer), Some(State::Present));
+    }
+
+    #[test]
+    fn flush_batches_every_resource_and_starts_over() {
+        let mut tracker = ResourceStateTracker::new(true);
+        let first = tracker.register("first", State::Present);
+        let second = tracker.register("second", State::Present);
+        tracker.require(first, State::RenderTarget).unwrap();
+        tracker.require(second, State::CopySource).unwrap();
+        assert_eq!(
+            tracker.flush(),
+            [
+                transition(first, State::Present, State::RenderTarget),
+                transition(second, State::Present, State::CopySource),
+            ]
+        );
+        assert!(!tracker.has_pending());
+
+        tracker.require(first, State::Present).unwrap();
+        assert_eq!(
+            tracker.flush(),
+            [transition(first, State::RenderTarget, State::Present)]
+        );
+    }
+
+    #[test]
+    fn mismatched_and_redundant_transitions_are_reported() {
+        let mut tracker = ResourceStateTracker::new(true);
+        let back_buffer = tracker.register("back buffer", State::Present);
+
+        // Written as if the buffer were already a render target.
+        let issued = tracker
+            .transition(back_buffer, State::RenderTarget, State::CopySource)
+            .unwrap();
+        assert_eq!(
+            issued,
+            [transition(
+                back_buffer,
+                State::RenderTarget,
+                State::CopySource
+            )]
+        );
+        tracker
+            .transition(back_buffer, State::CopySource, State::CopySource)
+            .unwrap();
+
+        assert_eq!(
+            tracker.take_issues(),
+            [
+                TransitionIssue::Mismatched {
+                    resource: back_buffer,
+                    name: "back buffer".to_string(),
+                    claimed: State::RenderTarget,
+                    actual: State::Present,
+                },
+                TransitionIssue::Redundant {
+                    resource: back_buffer,
+                    name: "back buffer".to_string(),
+                    state: State::CopySource,
+                },
+            ]
+        );
+        assert_eq!(tracker.take_issues(), []);
+    }
+
+    #[test]
+    fn transitions_are_not_checked_without_validation() {
+        let mut tracker = ResourceStateTracker::new(false);
+        let back_buffer = tracker.register("back buffer", State::Present);
+        tracker
+            .transition(back_buffer, State::RenderTarget, State::CopySource)
+            .unwrap();
+        tracker
+            .transition(back_buffer, State::CopySource, State::CopySource)
+            .unwrap();
+        assert_eq!(tracker.take_issues(), []);
+        // The tracked state still follows the transitions.
+        assert_eq!(tracker.state(back_buffer), Some(State::CopySource));
+    }
+
+    #[test]
+    fn transition_takes_over_a_pending_request() {
+        let mut tracker = ResourceStateTracker::new(true);
+        let back_buffer = tracker.register("back buffer", State::Present);
+        tracker.require(back_buffer, State::RenderTarget).unwrap();
+
+        let issued = tracker
+            .transition(back_buffer, State::RenderTarget, State::CopySource)
+            .unwrap();
+        assert_eq!(
+            issued,
+            [
+                transition(back_buffer, State::Present, State::RenderTarget),
+                transition(back_buffer, State::RenderTarget, State::CopySource),
+            ]
+        );
+        assert_eq!(tracker.take_issues(), []);
+        assert!(!tracker.has_pending());
+
+        // Later requests start from the hand-written transition's state.
+        tracker.require(back_buffer, State::Present).unwrap();
+        assert_eq!(
+            tracker.flush(),
+            [transition(back_buffer, State::CopySource, State::Present)]
+        );
+    }
+
+    #[test]
+    fn unregister_drops_pending_transitions() {
+        let mut tracker = ResourceStateTracker::new(true);
+        let first = tracker.register("first", State::Present);
+        let second = tracker.register("second", State::Present);
+        tracker.require(first, State::RenderTarget).unwrap();
+        tracker.require(second, State::RenderTarget).unwrap();
+
+        tracker.unregister(first);
+        assert_eq!(tracker.state(first), None);
+        assert_eq!(tracker.name(first), None);
+        assert!(tracker.require(first, State::Present).is_err());
+        assert_eq!(
+            tracker.flush(),
+            [transition(second, State::Present, State::RenderTarget)]
+        );
+    }
+
+    #[test]
+    fn stale_ids_do_not_reach_the_resource_that_reused_their_slot() {
+        let mut tracker = ResourceStateTracker::new(true);
+        let old = tracker.register("old back buffer", State::RenderTarget);
+        tracker.unregister(old);
+        let new = tracker.register("new back buffer", State::Present);
+        assert_ne!(old, new);
+
+        assert_eq!(tracker.state(old), None);
+        assert_eq!(tracker.name(new), Some("new back buffer"));
+        assert!(tracker.require(old, State::CopySource).is_err());
+        assert!(
+            tracker
+                .transition(old, State::RenderTarget, State::Present)
+                .is_err()
+        );
+
+        // Unregistering the stale id again leaves the new resource alone.
+        tracker.unregister(old);
+        assert_eq!(tracker.state(new), Some(State::Present));
+        tracker.require(new, State::RenderTarget).unwrap();
+        assert_eq!(
+            tracker.flush(),
+            [transition(new, State::Present, State::RenderTarget)]
+        );
+    }
+}
@@ -0,0 +1,220 @@
+use std::collections::VecDeque;
+
+/// A sub-allocation handed out by a [`RingAllocator`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RingAllocation {
+    pub offset: u64,
+    pub size: u64,
+}
+
+/// Offset arithmetic for a ring of per-frame allocations.
+///
+/// Each frame allocates linearly from the head of the ring. [`RingAllocator::finish_frame`]
+/// tags everything allocated so far with that frame's fence value, and
+/// [`RingAllocator::retire`] moves the tail past it once the fence has completed. Allocations
+/// never straddle the end of the ring; the remainder is skipped instead.
+#[derive(Debug, Clone)]
+pub struct RingAllocator {
+    capacity: u64,
+    /// Total units ever allocated, including alignment and wraparound padding.
+    head: u64,
+    /// Total units ever reclaimed.
+    tail: u64,
+    /// Head at the end of each submitted frame, with that frame's fence value.
+    in_flight: VecDeque<(u64, u64)>,
+}
+
+impl RingAllocator {
+    pub fn new(capacity: u64) -> Self {
+        Self {
+            capacity,
+            head: 0,
+            tail: 0,
+            in_flight: VecDeque::new(),
+        }
+    }
+
+    pub fn capacity(&self) -> u64 {
+        self.capacity
+    }
+
+    pub fn used(&self) -> u64 {
+        self.head - self.tail
+    }
+
+    /// Allocate `size` units whose offset is a multiple of `alignment`, or `None` if the ring
+    /// does not have room until more frames retire.
+    pub fn allocate(&mut self, size: u64, alignment: u64) -> Option<RingAllocation> {
+        debug_assert!(alignment.is_power_of_two());
+        if size == 0 || size > self.capacity {
+            return None;
+        }
+
+        let offset = self.head % self.capacity;
+        let aligned = align_up(offset, alignment);
+        let start = if aligned + size > self.capacity {
+            0
+        } else {
+            aligned
+        };
+        let mut padding = if start == 0 && offset != 0 {
+            self.capacity - offset
+        } else {
+            start - offset
+        };
+
+        if self.head == self.tail {
+            // Nothing is in flight, so the skipped space is free as well.
+            self.head += padding;
+            self.tail = self.head;
+            padding = 0;
+        }
+        if self.used() + padding + size > self.capacity {
+            return None;
+        }
+
+        self.head += padding + size;
+        Some(RingAllocation {
+            offset: start,
+            size,
+        })
+    }
+
+    /// Mark everything allocated so far as belonging to the frame signalled with `fence_value`.
+    pub fn finish_frame(&mut self, fence_value: u64) {
+        match self.in_flight.back() {
+            Some((_, head)) if *head == self.head => {}
+            _ => self.in_flight.push_back((fence_value, self.head)),
+        }
+    }
+
+    /// Reclaim the allocations of frames whose fence value has completed.
+    pub fn retire(&mut self, completed_fence_value: u64) {
+        while let Some(&(fence_value, head)) = self.in_flight.front() {
+            if fence_value > completed_fence_value {
+                break;
+            }
+            self.tail = self.tail.max(head);
+            self.in_flight.pop_front();
+        }
+    }
+}
+
+pub fn align_up(value: u64, alignment: u64) -> u64 {
+    debug_assert!(alignment.is_power_of_two());
+    (value + alignment - 1) & !(alignment - 1)
+}
+
+/// The capacity to grow a ring of `current` bytes to so that an allocation of `size` with
+/// `alignment` fits, doubling each time, or `None` if that would exceed `max_capacity`.
+pub fn grown_capacity(current: u64, size: u64, alignment: u64, max_capacity: u64) -> Option<u64> {
+    let required = size.checked_add(alignment)?;
+    let mut capacity = current.max(1);
+    while capacity < required || capacity == current {
+        capacity = capacity.checked_mul(2)?;
+    }
+    (capacity <= max_capacity).then_some(capacity)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `D3D12_CONSTANT_BUFFER_DATA_PLACEMENT_ALIGNMENT`.
+    const CBV_ALIGNMENT: u64 = 256;
+
+    #[test]
+    fn constant_buffers_are_256_byte_aligned() {
+        let mut ring = RingAllocator::new(1024);
+        assert_eq!(ring.allocate(16, CBV_ALIGNMENT).unwrap().offset, 0);
+        assert_eq!(ring.allocate(16, CBV_ALIGNMENT).unwrap().offset, 256);
+        assert_eq!(ring.allocate(300, CBV_ALIGNMENT).unwrap().offset, 512);
+        // The alignment padding counts as used.
+        assert_eq!(ring.used(), 812);
+        assert_eq!(ring.allocate(4, 4).unwrap().offset, 812);
+    }
+
+    #[test]
+    fn allocation_that_does_not_fit_at_the_end_wraps_to_the_start() {
+        let mut ring = RingAllocator::new(1024);
+        ring.allocate(700, 1).unwrap();
+        ring.finish_frame(1);
+        ring.retire(1);
+
+        // 324 bytes remain at the end; the 400 byte allocation skips them.
+        let allocation = ring.allocate(400, 1).unwrap();
+        assert_eq!(allocation.offset, 0);
+        assert_eq!(ring.used(), 400);
+    }
+
+    #[test]
+    fn wraparound_padding_is_charged_while_frames_are_in_flight() {
+        let mut ring = RingAllocator::new(1024);
+        ring.allocate(300, CBV_ALIGNMENT).unwrap();
+        ring.finish_frame(1);
+        ring.allocate(300, CBV_ALIGNMENT).unwrap();
+        ring.finish_frame(2);
+        ring.retire(1);
+
+        assert_eq!(ring.allocate(300, CBV_ALIGNMENT).unwrap().offset, 0);
+        // 212 bytes of padding at the end, up to the 1024 byte capacity.
+        assert_eq!(ring.used(), 512 + 212 + 300);
+    }
+
+    #[test]
+    fn memory_owned_by_an_in_flight_frame_is_not_reused() {
+        let mut ring = RingAllocator::new(1024);
+        assert_eq!(ring.allocate(300, CBV_ALIGNMENT).unwrap().offset, 0);
+        ring.finish_frame(1);
+        assert_eq!(ring.allocate(300, CBV_ALIGNMENT).unwrap().offset, 512);
+        ring.finish_frame(2);
+
+        // Wrapping to the start would overwrite frame 1.
+        assert!(ring.allocate(300, CBV_ALIGNMENT).is_none());
+        ring.retire(0);
+        assert!(ring.allocate(300, CBV_ALIGNMENT).is_none());
+
+        ring.retire(1);
+        assert_eq!(ring.used(), 812 - 300);
+        assert_eq!(ring.allocate(300, CBV_ALIGNMENT).unwrap().offset, 0);
+        ring.finish_frame(3);
+
+        // Frame 2 still owns 512..812.
+        assert!(ring.allocate(300, CBV_ALIGNMENT).is_none());
+        ring.retire(3);
+        assert_eq!(ring.used(), 0);
+    }
+
+    #[test]
+    fn frames_that_allocate_nothing_retire_nothing() {
+        let mut ring = RingAllocator::new(1024);
+        ring.allocate(100, 1).unwrap();
+        ring.finish_frame(1);
+        ring.finish_frame(2);
+        ring.retire(2);
+        assert_eq!(ring.used(), 0);
+    }
+
+    #[test]
+    fn whole_ring_and_oversized_allocations() {
+        let mut ring = RingAllocator::new(1024);
+        assert_eq!(ring.allocate(1024, CBV_ALIGNMENT).unwrap().offset, 0);
+        assert!(ring.allocate(1, 1).is_none());
+        ring.finish_frame(1);
+        ring.retire(1);
+        assert_eq!(ring.allocate(1, 1).unwrap().offset, 0);
+        assert!(ring.allocate(0, 1).is_none());
+        assert!(ring.allocate(1025, 1).is_none());
+    }
+
+    #[test]
+    fn capacity_doubles_until_the_allocation_fits() {
+        assert_eq!(grown_capacity(1024, 100, 256, 1 << 20), Some(2048));
+        assert_eq!(grown_capacity(1024, 5000, 256, 1 << 20), Some(8192));
+        assert_eq!(grown_capacity(1024, 5000, 256, 4096), None);
+        assert_eq!(grown_capacity(0, 1, 1, 16), Some(2));
+        assert_eq!(grown_capacity(1024, u64::MAX, 256, u64::MAX), None);
+        assert_eq!(align_up(257, 256), 512);
+        assert_eq!(align_up(512, 256), 512);
+    }
+}
@@ -0,0 +1,944 @@
+use eyre::bail;
+
+/// Root arguments share 64 DWORDs: a root constant costs one, a root descriptor two and a
+/// descriptor table one.
+pub const ROOT_SIGNATURE_DWORD_LIMIT: u32 = 64;
+
+pub const STATIC_SAMPLER_LIMIT: usize = 2032;
+
+pub const MAX_ANISOTROPY: u32 = 16;
+
+/// Which shader stages can see a parameter or static sampler.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ShaderVisibility {
+    #[default]
+    All,
+    Vertex,
+    Hull,
+    Domain,
+    Geometry,
+    Pixel,
+    Amplification,
+    Mesh,
+}
+
+impl ShaderVisibility {
+    /// Whether some stage sees both; registers only collide within a stage.
+    pub fn overlaps(self, other: Self) -> bool {
+        self == Self::All || other == Self::All || self == other
+    }
+}
+
+/// The HLSL register class a binding occupies: `b`, `t`, `u` or `s`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RegisterKind {
+    ConstantBuffer,
+    ShaderResource,
+    UnorderedAccess,
+    Sampler,
+}
+
+impl RegisterKind {
+    pub fn prefix(self) -> char {
+        match self {
+            Self::ConstantBuffer => 'b',
+            Self::ShaderResource => 't',
+            Self::UnorderedAccess => 'u',
+            Self::Sampler => 's',
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RootSignatureVersion {
+    V1_0,
+    /// Adds the descriptor and data flags, which let the driver assume bound data does not
+    /// change.
+    V1_1,
+}
+
+/// How long the data behind a descriptor stays unchanged. `Default` is the version's default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DataFlag {
+    #[default]
+    Default,
+    Volatile,
+    StaticWhileSetAtExecute,
+    Static,
+}
+
+/// How long the descriptors in a table stay unchanged. `Default` is the version's default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DescriptorsFlag {
+    #[default]
+    Default,
+    Volatile,
+    StaticKeepingBufferBoundsChecks,
+}
+
+/// The views that can be bound directly in the root signature, without a table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RootDescriptorKind {
+    ConstantBuffer,
+    ShaderResource,
+    UnorderedAccess,
+}
+
+impl RootDescriptorKind {
+    pub fn register_kind(self) -> RegisterKind {
+        match self {
+            Self::ConstantBuffer => RegisterKind::ConstantBuffer,
+            Self::ShaderResource => RegisterKind::ShaderResource,
+            Self::UnorderedAccess => RegisterKind::UnorderedAccess,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RootParameterKind {
+    /// `count` 32-bit values read as a constant buffer at `register`.
+    Constants {
+        register: u32,
+        space: u32,
+        count: u32,
+    },
+    Descriptor {
+        kind: RootDescriptorKind,
+        register: u32,
+        space: u32,
+        data: DataFlag,
+    },
+    Table(Vec<DescriptorRange>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RootParameter {
+    pub kind: RootParameterKind,
+    pub visibility: ShaderVisibility,
+}
+
+impl RootParameter {
+    /// The DWORDs the parameter takes from [`ROOT_SIGNATURE_DWORD_LIMIT`].
+    pub fn dword_cost(&self) -> u32 {
+        match &self.kind {
+            RootParameterKind::Constants { count, .. } => *count,
+            RootParameterKind::Descriptor { .. } => 2,
+            RootParameterKind::Table(_) => 1,
+        }
+    }
+}
+
+/// Consecutive registers bound to consecutive descriptors of a table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DescriptorRange {
+    pub kind: RegisterKind,
+    pub base_register: u32,
+    /// `None` for an unbounded range, which covers every register from `base_register` on.
+    pub count: Option<u32>,
+    pub space: u32,
+    /// Descriptors from the start of the table; `None` to follow the previous range.
+    pub offset: Option<u32>,
+    pub descriptors: DescriptorsFlag,
+    pub data: DataFlag,
+}
+
+impl DescriptorRange {
+    pub fn new(kind: RegisterKind, base_register: u32, count: u32) -> Self {
+        Self {
+            kind,
+            base_register,
+            count: Some(count),
+            space: 0,
+            offset: None,
+            descriptors: DescriptorsFlag::Default,
+            data: DataFlag::Default,
+        }
+    }
+
+    pub fn unbounded(kind: RegisterKind, base_register: u32) -> Self {
+        Self {
+            count: None,
+            ..Self::new(kind, base_register, 0)
+        }
+    }
+
+    pub fn space(mut self, space: u32) -> Self {
+        self.space = space;
+        self
+    }
+
+    pub fn offset(mut self, offset: u32) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    pub fn descriptors(mut self, flag: DescriptorsFlag) -> Self {
+        self.descriptors = flag;
+        self
+    }
+
+    pub fn data(mut self, flag: DataFlag) -> Self {
+        self.data = flag;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SamplerFilter {
+    Point,
+    Linear,
+    /// With the maximum anisotropy, from 1 to [`MAX_ANISOTROPY`].
+    Anisotropic(u32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressMode {
+    Wrap,
+    Mirror,
+    Clamp,
+    Border,
+}
+
+/// Static samplers can only border with these colors.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BorderColor {
+    #[default]
+    TransparentBlack,
+    OpaqueBlack,
+    OpaqueWhite,
+}
+
+/// A sampler baked into the root signature, using the same address mode on every axis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StaticSampler {
+    pub register: u32,
+    pub space: u32,
+    pub visibility: ShaderVisibility,
+    pub filter: SamplerFilter,
+    pub address: AddressMode,
+    pub border_color: BorderColor,
+}
+
+impl StaticSampler {
+    pub fn new(register: u32, filter: SamplerFilter, address: AddressMode) -> Self {
+        Self {
+            register,
+            space: 0,
+            visibility: ShaderVisibility::All,
+            filter,
+            address,
+            border_color: BorderColor::default(),
+        }
+    }
+
+    pub fn space(mut self, space: u32) -> Self {
+        self.space = space;
+        self
+    }
+
+    pub fn visibility(mut self, visibility: ShaderVisibility) -> Self {
+        self.visibility = visibility;
+        self
+    }
+
+    pub fn border_color(mut self, color: BorderColor) -> Self {
+        self.border_color = color;
+        self
+    }
+}
+
+/// A root signature as the shaders see it, before it is translated to D3D12 structures.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RootSignatureDescription {
+    pub version: RootSignatureVersion,
+    pub parameters: Vec<RootParameter>,
+    pub static_samplers: Vec<StaticSampler>,
+    /// Whether pipelines using the signature can read vertices through an input layout.
+    pub allow_input_layout: bool,
+}
+
+impl Default for RootSignatureDescription {
+    fn default() -> Self {
+        Self {
+            version: RootSignatureVersion::V1_1,
+            parameters: Vec::new(),
+            static_samplers: Vec::new(),
+            allow_input_layout: false,
+        }
+    }
+}
+
+/// Where in a root signature something is, for problems to point at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BindingSite {
+    Parameter(usize),
+    Range { parameter: usize, range: usize },
+    StaticSampler(usize),
+}
+
+impl std::fmt::Display for BindingSite {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Parameter(parameter) => write!(f, "parameter {parameter}"),
+            Self::Range { parameter, range } => write!(f, "range {range} of parameter {parameter}"),
+            Self::StaticSampler(sampler) => write!(f, "static sampler {sampler}"),
+        }
+    }
+}
+
+/// Something D3D12 would reject when serializing or creating the root signature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RootSignatureProblem {
+    OverBudget {
+        dwords: u32,
+    },
+    NoConstants(BindingSite),
+    EmptyTable(BindingSite),
+    EmptyRange(BindingSite),
+    /// Samplers live in their own descriptor heap, so a table cannot mix them with views.
+    MixedSamplerTable(BindingSite),
+    /// A range after an unbounded one cannot be placed after it.
+    AppendAfterUnbounded(BindingSite),
+    RegisterOverflow(BindingSite),
+    Overlap {
+        first: BindingSite,
+        second: BindingSite,
+        kind: RegisterKind,
+        register: u32,
+        space: u32,
+    },
+    FlagsNeedVersion1_1(BindingSite),
+    InvalidFlags {
+        site: BindingSite,
+        reason: &'static str,
+    },
+    TooManyStaticSamplers {
+        count: usize,
+    },
+    InvalidAnisotropy {
+        site: BindingSite,
+        anisotropy: u32,
+    },
+}
+
+impl std::fmt::Display for RootSignatureProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::OverBudget { dwords } => write!(
+                f,
+                "the parameters take {dwords} DWORDs, more than the {ROOT_SIGNATURE_DWORD_LIMIT} \
+                 available"
+            ),
+            Self::NoConstants(site) => write!(f, "{site} has no root constants"),
+            Self::EmptyTable(site) => write!(f, "{site} is a descriptor table without ranges"),
+            Self::EmptyRange(site) => write!(f, "{site} has no descriptors"),
+            Self::MixedSamplerTable(site) => {
+                write!(f, "{site} mixes sampler ranges with view ranges")
+            }
+            Self::AppendAfterUnbounded(site) => write!(
+                f,
+                "{site} follows an unbounded range, so it needs an explicit offset"
+            ),
+            Self::RegisterOverflow(site) => write!(f, "{site} runs past the last register"),
+            Self::Overlap {
+                first,
+                second,
+                kind,
+                register,
+                space,
+            } => write!(
+                f,
+                "{first} and {second} both bind {}{register} in space{space}",
+                kind.prefix()
+            ),
+            Self::FlagsNeedVersion1_1(site) => {
+                write!(
+                    f,
+                    "{site} sets flags, which need root signature version 1.1"
+                )
+            }
+            Self::InvalidFlags { site, reason } => write!(f, "{site} {reason}"),
+            Self::TooManyStaticSamplers { count } => write!(
+                f,
+                "{count} static samplers is more than the {STATIC_SAMPLER_LIMIT} allowed"
+            ),
+            Self::InvalidAnisotropy { site, anisotropy } => write!(
+                f,
+                "{site} has anisotropy {anisotropy}; expected 1 to {MAX_ANISOTROPY}"
+            ),
+        }
+    }
+}
+
+/// The registers one binding occupies, `end` exclusive and `None` when unbounded.
+struct RegisterSpan {
+    site: BindingSite,
+    kind: RegisterKind,
+    space: u32,
+    start: u32,
+    end: Option<u32>,
+    visibility: ShaderVisibility,
+}
+
+impl RegisterSpan {
+    /// The first register both spans bind, if they can collide.
+    fn overlap(&self, other: &Self) -> Option<u32> {
+        if self.kind != other.kind
+            || self.space != other.space
+            || !self.visibility.overlaps(other.visibility)
+        {
+            return None;
+        }
+        let start = self.start.max(other.start);
+        let before_end = |end: Option<u32>| end.is_none_or(|end| start < end);
+        (before_end(self.end) && before_end(other.end)).then_some(start)
+    }
+}
+
+impl RootSignatureDescription {
+    pub fn dword_cost(&self) -> u32 {
+        self.parameters.iter().map(RootParameter::dword_cost).sum()
+    }
+
+    /// Everything wrong with the description, in the order the parameters and samplers are
+    /// declared.
+    pub fn problems(&self) -> Vec<RootSignatureProblem> {
+        let mut problems = Vec::new();
+        let dwords = self.dword_cost();
+        if dwords > ROOT_SIGNATURE_DWORD_LIMIT {
+            problems.push(RootSignatureProblem::OverBudget { dwords });
+        }
+        if self.static_samplers.len() > STATIC_SAMPLER_LIMIT {
+            problems.push(RootSignatureProblem::TooManyStaticSamplers {
+                count: self.static_samplers.len(),
+            });
+        }
+
+        let mut spans = Vec::new();
+        for (index, parameter) in self.parameters.iter().enumerate() {
+            let site = BindingSite::Parameter(index);
+            let visibility = parameter.visibility;
+            match &parameter.kind {
+                RootParameterKind::Constants {
+                    register,
+                    space,
+                    count,
+                } => {
+                    if *count == 0 {
+                        problems.push(RootSignatureProblem::NoConstants(site));
+                    }
+                    spans.push(RegisterSpan {
+                        site,
+                        kind: RegisterKind::ConstantBuffer,
+                        space: *space,
+                        start: *register,
+                        end: register.checked_add(1),
+                        visibility,
+                    });
+                }
+                RootParameterKind::Descriptor {
+                    kind,
+                    register,
+                    space,
+                    data,
+                } => {
+                    if *data != DataFlag::Default && self.version < RootSignatureVersion::V1_1 {
+                        problems.push(RootSignatureProblem::FlagsNeedVersion1_1(site));
+                    }
+                    spans.push(RegisterSpan {
+                        site,
+                        kind: kind.register_kind(),
+                        space: *space,
+                        start: *register,
+                        end: register.checked_add(1),
+                        visibility,
+                    });
+                }
+                RootParameterKind::Table(ranges) => {
+                    self.table_problems(index, ranges, visibility, &mut problems, &mut spans);
+                }
+            }
+        }
+
+        for (index, sampler) in self.static_samplers.iter().enumerate() {
+            let site = BindingSite::StaticSampler(index);
+            if let SamplerFilter::Anisotropic(anisotropy) = sampler.filter
+                && !(1..=MAX_ANISOTROPY).contains(&anisotropy)
+            {
+                problems.push(RootSignatureProblem::InvalidAnisotropy { site, anisotropy });
+            }
+            spans.push(RegisterSpan {
+                site,
+                kind: RegisterKind::Sampler,
+                space: sampler.space,
+                start: sampler.register,
+                end: sampler.register.checked_add(1),
+                visibility: sampler.visibility,
+            });
+        }
+
+        for (index, first) in spans.iter().enumerate() {
+            for second in &spans[index + 1..] {
+                if let Some(register) = first.overlap(second) {
+                    problems.push(RootSignatureProblem::Overlap {
+                        first: first.site,
+                        second: second.site,
+                        kind: first.kind,
+                        register,
+                        space: first.space,
+                    });
+                }
+            }
+        }
+        problems
+    }
+
+    pub fn validate(&self) -> eyre::Result<()> {
+        let problems = self.problems();
+        if !problems.is_empty() {
+            let problems: Vec<String> = problems.iter().map(ToString::to_string).collect();
+            bail!("Invalid root signature:\n  {}", problems.join("\n  "));
+        }
+        Ok(())
+    }
+
+    fn table_problems(
+        &self,
+        parameter: usize,
+        ranges: &[DescriptorRange],
+        visibility: ShaderVisibility,
+        problems: &mut Vec<RootSignatureProblem>,
+        spans: &mut Vec<RegisterSpan>,
+    ) {
+        if ranges.is_empty() {
+            problems.push(RootSignatureProblem::EmptyTable(BindingSite::Parameter(
+                parameter,
+            )));
+            return;
+        }
+        let samplers = ranges
+            .iter()
+            .filter(|range| range.kind == RegisterKind::Sampler)
+            .count();
+        if samplers != 0 && samplers != ranges.len() {
+            problems.push(RootSignatureProblem::MixedSamplerTable(
+                BindingSite::Parameter(parameter),
+            ));
+        }
+
+        for (index, range) in ranges.iter().enumerate() {
+            let site = BindingSite::Range {
+                parameter,
+                range: index,
+            };
+            if range.count == Some(0) {
+                problems.push(RootSignatureProblem::EmptyRange(site));
+            }
+            if index > 0 && ranges[index - 1].count.is_none() && range.offset.is_none() {
+                problems.push(RootSignatureProblem::AppendAfterUnbounded(site));
+            }
+            let end = match range.count {
+                Some(count) => match range.base_register.checked_add(count) {
+                    Some(end) => Some(end),
+                    None => {
+                        problems.push(RootSignatureProblem::RegisterOverflow(site));
+                        None
+                    }
+                },
+                None => None,
+            };
+            if let Some(reason) = range_flag_problem(range) {
+                problems.push(RootSignatureProblem::InvalidFlags { site, reason });
+            }
+            let has_flags =
+                range.descriptors != DescriptorsFlag::Default || range.data != DataFlag::Default;
+            if has_flags && self.version < RootSignatureVersion::V1_1 {
+                problems.push(RootSignatureProblem::FlagsNeedVersion1_1(site));
+            }
+            spans.push(RegisterSpan {
+                site,
+                kind: range.kind,
+                space: range.space,
+                start: range.base_register,
+                end,
+                visibility,
+            });
+        }
+    }
+}
+
+/// Flag combinations D3D12 rejects for a range.
+fn range_flag_problem(range: &DescriptorRange) -> Option<&'static str> {
+    if range.kind == RegisterKind::Sampler && range.data != DataFlag::Default {
+        return Some("sets a data flag on samplers, which have no data");
+    }
+    if range.kind == RegisterKind::Sampler
+        && range.descriptors == DescriptorsFlag::StaticKeepingBufferBoundsChecks
+    {
+        return Some("keeps buffer bounds checks on samplers, which are not buffers");
+    }
+    if range.descriptors == DescriptorsFlag::Volatile && range.data == DataFlag::Static {
+        return Some("has volatile descriptors, so their data cannot be static");
+    }
+    None
+}
+
+/// Builds a [`RootSignatureDescription`], validating it on [`Self::build`]. Parameters are
+/// numbered in the order they are added, which is the index `SetGraphicsRoot*` takes.
+#[derive(Debug, Clone, Default)]
+pub struct RootSignatureBuilder {
+    description: RootSignatureDescription,
+}
+
+impl RootSignatureBuilder {
+    pub fn version(mut self, version: RootSignatureVersion) -> Self {
+        self.description.version = version;
+        self
+    }
+
+    pub fn allow_input_layout(mut self) -> Self {
+        self.description.allow_input_layout = true;
+        self
+    }
+
+    pub fn parameter(mut self, kind: RootParameterKind, visibility: ShaderVisibility) -> Self {
+        self.description
+            .parameters
+            .push(RootParameter { kind, visibility });
+        self
+    }
+
+    /// `count` root constants at `b<register>`.
+    pub fn constants(self, register: u32, count: u32, visibility: ShaderVisibility) -> Self {
+        self.parameter(
+            RootParameterKind::Constants {
+                register,
+                space: 0,
+                count,
+            },
+            visibility,
+        )
+    }
+
+    pub fn cbv(self, register: u32, visibility: ShaderVisibility) -> Self {
+        self.descriptor(RootDescriptorKind::ConstantBuffer, register, visibility)
+    }
+
+    pub fn srv(self, register: u32, visibility: ShaderVisibility) -> Self {
+        self.descriptor(RootDescriptorKind::ShaderResource, register, visibility)
+    }
+
+    pub fn uav(self, register: u32, visibility: ShaderVisibility) -> Self {
+        self.descriptor(RootDescriptorKind::UnorderedAccess, register, visibility)
+    }
+
+    pub fn table(
+        self,
+        ranges: impl IntoIterator<Item = DescriptorRange>,
+        visibility: ShaderVisibility,
+    ) -> Self {
+        self.parameter(
+            RootParameterKind::Table(ranges.into_iter().collect()),
+            visibility,
+        )
+    }
+
+    pub fn static_sampler(mut self, sampler: StaticSampler) -> Self {
+        self.description.static_samplers.push(sampler);
+        self
+    }
+
+    /// The description so far, unvalidated.
+    pub fn description(&self) -> &RootSignatureDescription {
+        &self.description
+    }
+
+    pub fn build(self) -> eyre::Result<RootSignatureDescription> {
+        self.description.validate()?;
+        Ok(self.description)
+    }
+
+    fn descriptor(
+        self,
+        kind: RootDescriptorKind,
+        register: u32,
+        visibility: ShaderVisibility,
+    ) -> Self {
+        self.parameter(
+            RootParameterKind::Descriptor {
+                kind,
+                register,
+                space: 0,
+                data: DataFlag::Default,
+            },
+            visibility,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use RegisterKind::ConstantBuffer;
+    use RegisterKind::Sampler;
+    use RegisterKind::ShaderResource;
+    use RegisterKind::UnorderedAccess;
+    use ShaderVisibility as Visibility;
+
+    fn problems(builder: &RootSignatureBuilder) -> Vec<String> {
+        builder
+            .description()
+            .problems()
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn sample_signature_is_valid() {
+        let description = RootSignatureBuilder::default()
+            .allow_input_layout()
+            .constants(0, 4, Visibility::Vertex)
+            .cbv(1, Visibility::Vertex)
+            .table(
+                [
+                    DescriptorRange::new(ShaderResource, 0, 2),
+                    DescriptorRange::new(UnorderedAccess, 0, 1),
+                ],
+                Visibility::Pixel,
+            )
+            .table([DescriptorRange::new(Sampler, 0, 1)], Visibility::Pixel)
+            .static_sampler(StaticSampler::new(
+                1,
+                SamplerFilter::Linear,
+                AddressMode::Clamp,
+            ))
+            .build()
+            .unwrap();
+        assert_eq!(description.dword_cost(), 4 + 2 + 1 + 1);
+    }
+
+    #[test]
+    fn parameters_must_fit_in_64_dwords() {
+        let full = RootSignatureBuilder::default()
+            .constants(0, 62, Visibility::All)
+            .cbv(1, Visibility::All);
+        assert_eq!(full.clone().build().unwrap().dword_cost(), 64);
+        assert_eq!(
+            problems(&full.srv(0, Visibility::All)),
+            ["the parameters take 66 DWORDs, more than the 64 available"]
+        );
+    }
+
+    #[test]
+    fn overlapping_registers_are_reported_per_stage_and_space() {
+        let builder = RootSignatureBuilder::default()
+            .cbv(0, Visibility::Vertex)
+            // Another stage may reuse b0.
+            .constants(0, 1, Visibility::Pixel)
+            .table(
+                [DescriptorRange::new(ShaderResource, 2, 4)],
+                Visibility::All,
+            )
+            .srv(5, Visibility::Pixel)
+            .table(
+                [DescriptorRange::unbounded(ShaderResource, 10).space(1)],
+                Visibility::All,
+            )
+            // The unbounded range is in space1, so t100 in space0 is free.
+            .srv(100, Visibility::All)
+            .table(
+                [
+                    DescriptorRange::new(ShaderResource, 0, 1).space(1),
+                    DescriptorRange::new(ShaderResource, 1000, 1).space(1),
+                ],
+                Visibility::Vertex,
+            )
+            .static_sampler(StaticSampler::new(
+                0,
+                SamplerFilter::Point,
+                AddressMode::Wrap,
+            ))
+            .table([DescriptorRange::new(Sampler, 0, 1)], Visibility::Pixel);
+        let found = builder.description().problems();
+        assert_eq!(
+            found,
+            [
+                RootSignatureProblem::Overlap {
+                    first: BindingSite::Range {
+                        parameter: 2,
+                        range: 0
+                    },
+                    second: BindingSite::Parameter(3),
+                    kind: ShaderResource,
+                    register: 5,
+                    space: 0,
+                },
+                RootSignatureProblem::Overlap {
+                    first: BindingSite::Range {
+                        parameter: 4,
+                        range: 0
+                    },
+                    second: BindingSite::Range {
+                        parameter: 6,
+                        range: 1
+                    },
+                    kind: ShaderResource,
+                    register: 1000,
+                    space: 1,
+                },
+                RootSignatureProblem::Overlap {
+                    first: BindingSite::Range {
+                        parameter: 7,
+                        range: 0
+                    },
+                    second: BindingSite::StaticSampler(0),
+                    kind: Sampler,
+                    register: 0,
+                    space: 0,
+                },
+            ]
+        );
+        assert_eq!(
+            found[0].to_string(),
+            "range 0 of parameter 2 and parameter 3 both bind t5 in space0"
+        );
+    }
+
+    #[test]
+    fn malformed_tables_are_reported() {
+        let builder = RootSignatureBuilder::default()
+            .table([], Visibility::All)
+            .table(
+                [
+                    DescriptorRange::new(Sampler, 0, 1),
+                    DescriptorRange::new(ShaderResource, 0, 0),
+                ],
+                Visibility::All,
+            )
+            .table(
+                [
+                    DescriptorRange::unbounded(ShaderResource, 10),
+                    DescriptorRange::new(ShaderResource, 0, 1),
+                    DescriptorRange::new(ShaderResource, 1, 1).offset(100),
+                ],
+                Visibility::Pixel,
+            )
+            .table(
+                [DescriptorRange::new(ConstantBuffer, 8, u32::MAX)],
+                Visibility::All,
+            )
+            .constants(20, 0, Visibility::All);
+        assert_eq!(
+            problems(&builder),
+            [
+                "parameter 0 is a descriptor table without ranges",
+                "parameter 1 mixes sampler ranges with view ranges",
+                "range 1 of parameter 1 has no descriptors",
+                "range 1 of parameter 2 follows an unbounded range, so it needs an explicit offset",
+                "range 0 of parameter 3 runs past the last register",
+                "parameter 4 has no root constants",
+                "range 0 of parameter 3 and parameter 4 both bind b20 in space0",
+            ]
+        );
+    }
+
+    #[test]
+    fn flags_need_version_1_1() {
+        let builder =
+            RootSignatureBuilder::default()
+                .version(RootSignatureVersion::V1_0)
+                .table(
+                    [DescriptorRange::new(ShaderResource, 0, 1).data(DataFlag::Static)],
+                    Visibility::All,
+                )
+                .table(
+                    [DescriptorRange::new(ShaderResource, 1, 1)
+                        .descriptors(DescriptorsFlag::Volatile)],
+                    Visibility::All,
+                )
+                .parameter(
+                    RootParameterKind::Descriptor {
+                        kind: RootDescriptorKind::ConstantBuffer,
+                        register: 0,
+                        space: 0,
+                        data: DataFlag::StaticWhileSetAtExecute,
+                    },
+                    Visibility::All,
+                )
+                .table(
+                    [DescriptorRange::new(ShaderResource, 2, 1)],
+                    Visibility::All,
+                );
+        assert_eq!(
+            problems(&builder),
+            [
+                "range 0 of parameter 0 sets flags, which need root signature version 1.1",
+                "range 0 of parameter 1 sets flags, which need root signature version 1.1",
+                "parameter 2 sets flags, which need root signature version 1.1",
+            ]
+        );
+        assert!(builder.version(RootSignatureVersion::V1_1).build().is_ok());
+    }
+
+    #[test]
+    fn contradictory_range_flags_are_rejected() {
+        let builder = RootSignatureBuilder::default()
+            .table(
+                [DescriptorRange::new(Sampler, 0, 1).data(DataFlag::Static)],
+                Visibility::All,
+            )
+            .table(
+                [DescriptorRange::new(Sampler, 1, 1)
+                    .descriptors(DescriptorsFlag::StaticKeepingBufferBoundsChecks)],
+                Visibility::All,
+            )
+            .table(
+                [DescriptorRange::new(ShaderResource, 0, 1)
+                    .descriptors(DescriptorsFlag::Volatile)
+                    .data(DataFlag::Static)],
+                Visibility::All,
+            )
+            .table(
+                [DescriptorRange::new(ShaderResource, 1, 1)
+                    .descriptors(DescriptorsFlag::Volatile)
+                    .data(DataFlag::StaticWhileSetAtExecute)],
+                Visibility::All,
+            );
+        assert_eq!(
+            builder.build().unwrap_err().to_string(),
+            "Invalid root signature:\n  \
+             range 0 of parameter 0 sets a data flag on samplers, which have no data\n  \
+             range 0 of parameter 1 keeps buffer bounds checks on samplers, which are not buffers\n  \
+             range 0 of parameter 2 has volatile descriptors, so their data cannot be static"
+        );
+    }
+
+    #[test]
+    fn static_samplers_are_limited() {
+        let builder = RootSignatureBuilder::default().static_sampler(StaticSampler::new(
+            0,
+            SamplerFilter::Anisotropic(17),
+            AddressMode::Border,
+        ));
+        assert_eq!(
+            problems(&builder),
+            ["static sampler 0 has anisotropy 17; expected 1 to 16"]
+        );
+
+        let builder = (0..=STATIC_SAMPLER_LIMIT as u32).fold(
+            RootSignatureBuilder::default(),
+            |builder, register| {
+                builder.static_sampler(StaticSampler::new(
+                    register,
+                    SamplerFilter::Point,
+                    AddressMode::Wrap,
+                ))
+            },
+        );
+        assert_eq!(
+            problems(&builder),
+            ["2033 static samplers is more than the 2032 allowed"]
+        );
+    }
+}
@@ -0,0 +1,339 @@
+use crate::graphics::cursor_transform::DrawConstants;
+use command_ir::CommandRecorder;
+use command_ir::PipelineId;
+use command_ir::ResourceState;
+use command_ir::ScissorRect;
+use command_ir::VertexBufferRange;
+use command_ir::VertexRange;
+use command_ir::Viewport;
+use vertex_layout::VertexLayout;
+
+const TRIANGLE_VERTEX_COUNT: usize = 3;
+const CURSOR_RING_SEGMENTS: usize = 48;
+const CURSOR_RING_VERTEX_COUNT: usize = CURSOR_RING_SEGMENTS * 6;
+const CURSOR_ARM_COUNT: usize = 4;
+const CURSOR_ARM_VERTEX_COUNT: usize = CURSOR_ARM_COUNT * 6;
+pub const SCENE_VERTEX_COUNT: usize =
+    TRIANGLE_VERTEX_COUNT + CURSOR_RING_VERTEX_COUNT + CURSOR_ARM_VERTEX_COUNT;
+
+const CURSOR_RING_INNER_RADIUS: f32 = 14.0;
+const CURSOR_RING_OUTER_RADIUS: f32 = 17.5;
+const CURSOR_ARM_LENGTH: f32 = 18.0;
+const CURSOR_ARM_THICKNESS: f32 = 2.5;
+const CURSOR_ARM_GAP: f32 = 7.0;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, VertexLayout)]
+pub struct Vertex {
+    pub position: [f32; 3],
+    #[semantic("COLOR")]
+    pub color: [f32; 4],
+}
+
+/// Where each part of the scene lives in the static vertex buffer.
+#[derive(Clone, Copy, Debug)]
+pub struct SceneDraws {
+    pub triangle: VertexRange,
+    pub cursor: VertexRange,
+}
+
+/// Everything frame recording needs to know about the current back buffer and window.
+#[derive(Clone, Copy, Debug)]
+pub struct FrameDescription {
+    pub back_buffer: usize,
+    /// GPU address of this frame's late-latched cursor constants.
+    pub cursor_constants_address: u64,
+    pub width: u32,
+    pub height: u32,
+    pub vertex_buffer_size: u32,
+    pub draws: SceneDraws,
+}
+
+pub const CLEAR_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 0.0];
+
+/// Record one frame of the scene: the triangle in NDC, then the cursor target offset by the
+/// late-latched cursor position.
+pub fn record_scene_frame(recorder: &mut CommandRecorder, frame: &FrameDescription) {
+    recorder.set_viewport(Viewport::full(frame.width, frame.height));
+    recorder.set_scissor(ScissorRect::full(frame.width, frame.height));
+
+    recorder.require_state(frame.back_buffer, ResourceState::RenderTarget);
+    recorder.set_render_target(frame.back_buffer);
+    recorder.clear_render_target(frame.back_buffer, CLEAR_COLOR);
+
+    recorder.bind_pipeline(PipelineId::TRANSPARENT);
+
+    recorder.bind_vertex_range(VertexBufferRange {
+        offset_bytes: 0,
+        size_bytes: frame.vertex_buffer_size,
+        stride_bytes: Vertex::STRIDE,
+    });
+    recorder.bind_cursor_constants(frame.cursor_constants_address);
+
+    recorder.set_draw_constants(DrawConstants::IDENTITY.to_root_constants());
+    recorder.draw(frame.draws.triangle);
+
+    recorder.set_draw_constants(
+        DrawConstants::cursor(frame.width as f32, frame.height as f32).to_root_constants(),
+    );
+    recorder.draw(frame.draws.cursor);
+
+    recorder.require_state(frame.back_buffer, ResourceState::Present);
+    recorder.present();
+}
+
+pub fn build_scene_vertices() -> (Vec<Vertex>, SceneDraws) {
+    let mut vertices = Vec::with_capacity(SCENE_VERTEX_COUNT);
+
+    append_demo_triangle(&mut vertices);
+    let triangle = VertexRange {
+        start: 0,
+        count: vertices.len() as u32,
+    };
+
+    append_cursor_target(&mut vertices);
+    let cursor = VertexRange {
+        start: triangle.count,
+        count: vertices.len() as u32 - triangle.count,
+    };
+
+    (vertices, SceneDraws { triangle, cursor })
+}
+
+/// The vertex buffer contents as uploaded: `Vertex` is `repr(C)` with only `f32` fields, so this
+/// matches the GPU's view byte for byte.
+pub fn vertex_bytes(vertices: &[Vertex]) -> Vec<u8> {
+    vertices
+        .iter()
+        .flat_map(|vertex| vertex.position.into_iter().chain(vertex.color))
+        .flat_map(f32::to_le_bytes)
+        .collect()
+}
+
+fn append_demo_triangle(vertices: &mut Vec<Vertex>) {
+    vertices.extend_from_slice(&[
+        Vertex {
+            position: [0.0, 0.6, 0.0],
+            color: [1.0, 0.2, 0.2, 0.85],
+        },
+        Vertex {
+            position: [0.55, -0.4, 0.0],
+            color: [0.2, 1.0, 0.4, 0.85],
+        },
+        Vertex {
+            position: [-0.55, -0.4, 0.0],
+            color: [0.2, 0.5, 1.0, 0.85],
+        },
+    ]);
+}
+
+/// Cursor geometry in pixels relative to the cursor hotspot; the vertex shader scales it to NDC
+/// and offsets it by the late-latched cursor position.
+fn append_cursor_target(vertices: &mut Vec<Vertex>) {
+    let ring_color = [1.0, 1.0, 1.0, 0.95];
+    let arm_color = [1.0, 0.15, 0.15, 0.95];
+
+    append_ring(
+        vertices,
+        CURSOR_RING_INNER_RADIUS,
+        CURSOR_RING_OUTER_RADIUS,
+        ring_color,
+    );
+
+    append_rect(
+        vertices,
+        -CURSOR_ARM_THICKNESS * 0.5,
+        -CURSOR_RING_OUTER_RADIUS - CURSOR_ARM_LENGTH,
+        CURSOR_ARM_THICKNESS,
+        CURSOR_ARM_LENGTH - CURSOR_ARM_GAP,
+        arm_color,
+    );
+    append_rect(
+        vertices,
+        -CURSOR_ARM_THICKNESS * 0.5,
+        CURSOR_RING_OUTER_RADIUS + CURSOR_ARM_GAP,
+        CURSOR_ARM_THICKNESS,
+        CURSOR_ARM_LENGTH - CURSOR_ARM_GAP,
+        arm_color,
+    );
+    append_rect(
+        vertices,
+        -CURSOR_RING_OUTER_RADIUS - CURSOR_ARM_LENGTH,
+        -CURSOR_ARM_THICKNESS * 0.5,
+        CURSOR_ARM_LENGTH - CURSOR_ARM_GAP,
+        CURSOR_ARM_THICKNESS,
+        arm_color,
+    );
+    append_rect(
+        vertices,
+        CURSOR_RING_OUTER_RADIUS + CURSOR_ARM_GAP,
+        -CURSOR_ARM_THICKNESS * 0.5,
+        CURSOR_ARM_LENGTH - CURSOR_ARM_GAP,
+        CURSOR_ARM_THICKNESS,
+        arm_color,
+    );
+}
+
+fn append_ring(vertices: &mut Vec<Vertex>, inner_radius: f32, outer_radius: f32, color: [f32; 4]) {
+    for segment in 0..CURSOR_RING_SEGMENTS {
+        let angle_start = std::f32::consts::TAU * segment as f32 / CURSOR_RING_SEGMENTS as f32;
+        let angle_end = std::f32::consts::TAU * (segment + 1) as f32 / CURSOR_RING_SEGMENTS as f32;
+
+        let inner_start = (
+            inner_radius * angle_start.cos(),
+            inner_radius * angle_start.sin(),
+        );
+        let inner_end = (
+            inner_radius * angle_end.cos(),
+            inner_radius * angle_end.sin(),
+        );
+        let outer_start = (
+            outer_radius * angle_start.cos(),
+            outer_radius * angle_start.sin(),
+        );
+        let outer_end = (
+            outer_radius * angle_end.cos(),
+            outer_radius * angle_end.sin(),
+        );
+
+        push_quad(
+            vertices,
+            outer_start,
+            outer_end,
+            inner_end,
+            inner_start,
+            color,
+        );
+    }
+}
+
+fn append_rect(
+    vertices: &mut Vec<Vertex>,
+    left: f32,
+    top: f32,
+    rect_width: f32,
+    rect_height: f32,
+    color: [f32; 4],
+) {
+    push_quad(
+        vertices,
+        (left, top),
+        (left + rect_width, top),
+        (left + rect_width, top + rect_height),
+        (left, top + rect_height),
+        color,
+    );
+}
+
+fn push_quad(
+    vertices: &mut Vec<Vertex>,
+    top_left: (f32, f32),
+    top_right: (f32, f32),
+    bottom_right: (f32, f32),
+    bottom_left: (f32, f32),
+    color: [f32; 4],
+) {
+    let top_left = [top_left.0, top_left.1, 0.0];
+    let top_right = [top_right.0, top_right.1, 0.0];
+    let bottom_right = [bottom_right.0, bottom_right.1, 0.0];
+    let bottom_left = [bottom_left.0, bottom_left.1, 0.0];
+
+    vertices.extend_from_slice(&[
+        Vertex {
+            position: top_left,
+            color,
+        },
+        Vertex {
+            position: top_right,
+            color,
+        },
+        Vertex {
+            position: bottom_right,
+            color,
+        },
+        Vertex {
+            position: top_left,
+            color,
+        },
+        Vertex {
+            position: bottom_right,
+            color,
+        },
+        Vertex {
+            position: bottom_left,
+            color,
+        },
+    ]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use command_ir::Command;
+
+    #[test]
+    fn scene_vertices_cover_both_draws() {
+        let (vertices, draws) = build_scene_vertices();
+        assert_eq!(vertices.len(), SCENE_VERTEX_COUNT);
+        assert_eq!(draws.triangle, VertexRange { start: 0, count: 3 });
+        assert_eq!(draws.cursor.start, draws.triangle.count);
+        assert_eq!(
+            (draws.cursor.start + draws.cursor.count) as usize,
+            SCENE_VERTEX_COUNT
+        );
+        assert_eq!(
+            vertex_bytes(&vertices).len(),
+            SCENE_VERTEX_COUNT * Vertex::STRIDE as usize
+        );
+    }
+
+    #[test]
+    fn scene_frame_records_barrier_clear_bindings_draws_barrier() {
+        let (vertices, draws) = build_scene_vertices();
+        let frame = FrameDescription {
+            back_buffer: 1,
+            cursor_constants_address: 0x1000,
+            width: 640,
+            height: 480,
+            vertex_buffer_size: vertices.len() as u32 * Vertex::STRIDE,
+            draws,
+        };
+        let mut recorder = CommandRecorder::new();
+        record_scene_frame(&mut recorder, &frame);
+
+        assert_eq!(
+            recorder.commands(),
+            [
+                Command::SetViewport(Viewport::full(640, 480)),
+                Command::SetScissor(ScissorRect::full(640, 480)),
+                Command::RequireState {
+                    back_buffer: 1,
+                    state: ResourceState::RenderTarget,
+                },
+                Command::SetRenderTarget { back_buffer: 1 },
+                Command::Clear {
+                    back_buffer: 1,
+                    color: CLEAR_COLOR,
+                },
+                Command::BindPipeline(PipelineId::TRANSPARENT),
+                Command::BindVertexRange(VertexBufferRange {
+                    offset_bytes: 0,
+                    size_bytes: frame.vertex_buffer_size,
+                    stride_bytes: Vertex::STRIDE,
+                }),
+                Command::BindCursorConstants {
+                    gpu_address: 0x1000,
+                },
+                Command::SetDrawConstants(DrawConstants::IDENTITY.to_root_constants()),
+                Command::Draw(draws.triangle),
+                Command::SetDrawConstants(DrawConstants::cursor(640.0, 480.0).to_root_constants()),
+                Command::Draw(draws.cursor),
+                Command::RequireState {
+                    back_buffer: 1,
+                    state: ResourceState::Present,
+                },
+                Command::Present,
+            ]
+        );
+    }
+}
@@ -0,0 +1,427 @@
+use eyre::Context;
+use eyre::bail;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IncludeKind {
+    /// `#include "file"`: next to the including file first, then the search paths.
+    Local,
+    /// `#include <file>`: the search paths only.
+    System,
+}
+
+/// One `#include` directive.
+#[derive(Debug, Clone, Copy)]
+pub struct IncludeRequest<'a> {
+    pub kind: IncludeKind,
+    pub name: &'a str,
+    /// The file containing the directive.
+    pub parent: &'a Path,
+}
+
+impl IncludeRequest<'_> {
+    /// Where the included file may be, in the order to try them.
+    pub fn candidates(&self, search_paths: &[PathBuf]) -> Vec<PathBuf> {
+        let name = Path::new(self.name);
+        if name.is_absolute() {
+            return vec![normalize(name)];
+        }
+        let beside_parent = match self.kind {
+            IncludeKind::Local => self.parent.parent().map(|directory| directory.join(name)),
+            IncludeKind::System => None,
+        };
+        beside_parent
+            .into_iter()
+            .chain(search_paths.iter().map(|directory| directory.join(name)))
+            .map(|path| normalize(&path))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedInclude {
+    pub path: PathBuf,
+    pub source: Vec<u8>,
+}
+
+/// Finds the files `#include` directives name.
+pub trait IncludeResolver {
+    /// The file `request` names, or `None` when it is in none of the places looked.
+    fn resolve(&self, request: &IncludeRequest<'_>) -> eyre::Result<Option<ResolvedInclude>>;
+}
+
+/// Includes read from disk.
+#[derive(Debug, Clone, Default)]
+pub struct FileResolver {
+    pub search_paths: Vec<PathBuf>,
+}
+
+impl IncludeResolver for FileResolver {
+    fn resolve(&self, request: &IncludeRequest<'_>) -> eyre::Result<Option<ResolvedInclude>> {
+        for path in request.candidates(&self.search_paths) {
+            match std::fs::read(&path) {
+                Ok(source) => return Ok(Some(ResolvedInclude { path, source })),
+                Err(error) if error.kind() == std::io::ErrorKind::NotFound => {}
+                Err(error) => {
+                    return Err(error)
+                        .wrap_err_with(|| format!("Failed to read include {}", path.display()));
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Includes served from memory, such as sources embedded with `include_str!` or generated at
+/// runtime. Paths are virtual and compared after removing `.` and `..` components.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryResolver {
+    files: HashMap<PathBuf, Cow<'static, str>>,
+    pub search_paths: Vec<PathBuf>,
+}
+
+impl MemoryResolver {
+    /// A resolver over `(path, source)` pairs built into the binary.
+    pub fn embedded(files: &[(&'static str, &'static str)]) -> Self {
+        let mut resolver = Self::default();
+        for (path, source) in files {
+            resolver.insert(path, *source);
+        }
+        resolver
+    }
+
+    pub fn insert(&mut self, path: impl AsRef<Path>, source: impl Into<Cow<'static, str>>) {
+        self.files.insert(normalize(path.as_ref()), source.into());
+    }
+
+    pub fn get(&self, path: &Path) -> Option<&str> {
+        self.files
+            .get(&normalize(path))
+            .map(|source| source.as_ref())
+    }
+}
+
+impl IncludeResolver for MemoryResolver {
+    fn resolve(&self, request: &IncludeRequest<'_>) -> eyre::Result<Option<ResolvedInclude>> {
+        Ok(request
+            .candidates(&self.search_paths)
+            .into_iter()
+            .find_map(|path| {
+                let source = self.files.get(&path)?.as_bytes().to_vec();
+                Some(ResolvedInclude { path, source })
+            }))
+    }
+}
+
+impl<R: IncludeResolver + ?Sized> IncludeResolver for &R {
+    fn resolve(&self, request: &IncludeRequest<'_>) -> eyre::Result<Option<ResolvedInclude>> {
+        (**self).resolve(request)
+    }
+}
+
+/// Tries the first resolver, then the second.
+impl<A: IncludeResolver, B: IncludeResolver> IncludeResolver for (A, B) {
+    fn resolve(&self, request: &IncludeRequest<'_>) -> eyre::Result<Option<ResolvedInclude>> {
+        match self.0.resolve(request)? {
+            Some(resolved) => Ok(Some(resolved)),
+            None => self.1.resolve(request),
+        }
+    }
+}
+
+/// Identifies a file opened by an [`IncludeTracker`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IncludeId(usize);
+
+/// Resolves the includes of one compilation, rejecting cycles and recording every file the
+/// shader depends on.
+///
+/// Include guards do not make a cycle acceptable: the chain is reported rather than leaving the
+/// compiler to recurse until it hits its nesting limit.
+#[derive(Debug, Clone)]
+pub struct IncludeTracker<R> {
+    resolver: R,
+    root: PathBuf,
+    /// Every opened file and the include it was opened from; `None` for the root.
+    opened: Vec<(PathBuf, Option<IncludeId>)>,
+}
+
+impl<R: IncludeResolver> IncludeTracker<R> {
+    pub fn new(resolver: R, root: impl Into<PathBuf>) -> Self {
+        Self {
+            resolver,
+            root: normalize(&root.into()),
+            opened: Vec::new(),
+        }
+    }
+
+    /// Resolve `name` as included from `parent`, or from the root file when `parent` is `None`.
+    pub fn open(
+        &mut self,
+        kind: IncludeKind,
+        name: &str,
+        parent: Option<IncludeId>,
+    ) -> eyre::Result<(IncludeId, ResolvedInclude)> {
+        let chain = self.chain(parent);
+        let parent_path = chain.last().copied().unwrap_or(&self.root);
+        let request = IncludeRequest {
+            kind,
+            name,
+            parent: parent_path,
+        };
+        let Some(resolved) = self.resolver.resolve(&request)? else {
+            bail!(
+                "Cannot find include {name:?} from {}",
+                parent_path.display()
+            );
+        };
+        if resolved.path == self.root || chain.contains(&resolved.path.as_path()) {
+            let cycle: Vec<String> = std::iter::once(self.root.as_path())
+                .chain(chain)
+                .chain(std::iter::once(resolved.path.as_path()))
+                .map(|path| path.display().to_string())
+                .collect();
+            bail!("Include cycle: {}", cycle.join(" -> "));
+        }
+
+        let id = IncludeId(self.opened.len());
+        self.opened.push((resolved.path.clone(), parent));
+        Ok((id, resolved))
+    }
+
+    pub fn path(&self, id: IncludeId) -> &Path {
+        &self.opened[id.0].0
+    }
+
+    /// Every file included so far, in the order first included, without the root.
+    pub fn dependencies(&self) -> Vec<PathBuf> {
+        let mut dependencies: Vec<PathBuf> = Vec::new();
+        for (path, _) in &self.opened {
+            if !dependencies.contains(path) {
+                dependencies.push(path.clone());
+            }
+        }
+        dependencies
+    }
+
+    /// The files from the root's first include down to `id`, outermost first.
+    fn chain(&self, mut id: Option<IncludeId>) -> Vec<&Path> {
+        let mut chain = Vec::new();
+        while let Some(current) = id {
+            let (path, parent) = &self.opened[current.0];
+            chain.push(path.as_path());
+            id = *parent;
+        }
+        chain.reverse();
+        chain
+    }
+}
+
+/// `path` without `.` components and with `..` applied where possible, without touching the
+/// filesystem.
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if matches!(
+                    normalized.components().next_back(),
+                    Some(Component::Normal(_))
+                ) {
+                    normalized.pop();
+                } else {
+                    normalized.push(component);
+                }
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(paths: &[&str]) -> Vec<PathBuf> {
+        paths.iter().map(PathBuf::from).collect()
+    }
+
+    #[test]
+    fn local_includes_look_beside_the_parent_before_the_search_paths() {
+        let request = IncludeRequest {
+            kind: IncludeKind::Local,
+            name: "common.hlsli",
+            parent: Path::new("/src/lighting/main.hlsl"),
+        };
+        let search_paths = paths(&["/lib", "/vendor/./include"]);
+        assert_eq!(
+            request.candidates(&search_paths),
+            paths(&[
+                "/src/lighting/common.hlsli",
+                "/lib/common.hlsli",
+                "/vendor/include/common.hlsli",
+            ])
+        );
+
+        let request = IncludeRequest {
+            kind: IncludeKind::System,
+            ..request
+        };
+        assert_eq!(
+            request.candidates(&search_paths),
+            paths(&["/lib/common.hlsli", "/vendor/include/common.hlsli"])
+        );
+
+        let request = IncludeRequest {
+            name: "/abs/../abs/common.hlsli",
+            ..request
+        };
+        assert_eq!(
+            request.candidates(&search_paths),
+            paths(&["/abs/common.hlsli"])
+        );
+    }
+
+    #[test]
+    fn quoted_and_angle_bracket_includes_resolve_differently() {
+        let mut files = MemoryResolver {
+            search_paths: paths(&["/lib"]),
+            ..MemoryResolver::default()
+        };
+        files.insert("/src/common.hlsli", "local");
+        files.insert("/lib/common.hlsli", "library");
+        let mut tracker = IncludeTracker::new(files, "/src/main.hlsl");
+
+        let (_, local) = tracker
+            .open(IncludeKind::Local, "common.hlsli", None)
+            .unwrap();
+        assert_eq!(local.path, Path::new("/src/common.hlsli"));
+        assert_eq!(local.source, b"local");
+
+        let (_, system) = tracker
+            .open(IncludeKind::System, "common.hlsli", None)
+            .unwrap();
+        assert_eq!(system.path, Path::new("/lib/common.hlsli"));
+        assert_eq!(system.source, b"library");
+    }
+
+    #[test]
+    fn nested_includes_are_relative_to_their_parent() {
+        let files = MemoryResolver::embedded(&[("/src/a/b.hlsli", "b"), ("/src/c.hlsli", "c")]);
+        let mut tracker = IncludeTracker::new(files, "/src/./main.hlsl");
+        let (b, _) = tracker.open(IncludeKind::Local, "a/b.hlsli", None).unwrap();
+        let (c, resolved) = tracker
+            .open(IncludeKind::Local, "../c.hlsli", Some(b))
+            .unwrap();
+        assert_eq!(resolved.path, Path::new("/src/c.hlsli"));
+        assert_eq!(tracker.path(c), Path::new("/src/c.hlsli"));
+    }
+
+    #[test]
+    fn dependencies_are_listed_once_in_first_include_order() {
+        let files = MemoryResolver::embedded(&[("/src/a/b.hlsli", "b"), ("/src/c.hlsli", "c")]);
+        let mut tracker = IncludeTracker::new(files, "/src/main.hlsl");
+        let (b, _) = tracker.open(IncludeKind::Local, "a/b.hlsli", None).unwrap();
+        tracker
+            .open(IncludeKind::Local, "../c.hlsli", Some(b))
+            .unwrap();
+        tracker
+            .open(IncludeKind::Local, "./a/../c.hlsli", None)
+            .unwrap();
+        assert_eq!(
+            tracker.dependencies(),
+            paths(&["/src/a/b.hlsli", "/src/c.hlsli"])
+        );
+    }
+
+    #[test]
+    fn cycles_are_reported_with_the_chain() {
+        let files = MemoryResolver::embedded(&[
+            ("/s/main.hlsl", "main"),
+            ("/s/a.hlsli", "a"),
+            ("/s/b.hlsli", "b"),
+        ]);
+        let mut tracker = IncludeTracker::new(files, "/s/main.hlsl");
+        let (a, _) = tracker.open(IncludeKind::Local, "a.hlsli", None).unwrap();
+        let (b, _) = tracker
+            .open(IncludeKind::Local, "b.hlsli", Some(a))
+            .unwrap();
+
+        let error = tracker
+            .open(IncludeKind::Local, "a.hlsli", Some(b))
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Include cycle: /s/main.hlsl -> /s/a.hlsli -> /s/b.hlsli -> /s/a.hlsli"
+        );
+        let error = tracker
+            .open(IncludeKind::Local, "main.hlsl", Some(b))
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Include cycle: /s/main.hlsl -> /s/a.hlsli -> /s/b.hlsli -> /s/main.hlsl"
+        );
+
+        // Including a file that a sibling already included is not a cycle.
+        tracker.open(IncludeKind::Local, "b.hlsli", None).unwrap();
+    }
+
+    #[test]
+    fn missing_includes_name_the_parent() {
+        let files = MemoryResolver::embedded(&[("/s/a.hlsli", "a")]);
+        let mut tracker = IncludeTracker::new(files, "/s/main.hlsl");
+        let (a, _) = tracker.open(IncludeKind::Local, "a.hlsli", None).unwrap();
+        let error = tracker
+            .open(IncludeKind::Local, "missing.hlsli", Some(a))
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Cannot find include \"missing.hlsli\" from /s/a.hlsli"
+        );
+    }
+
+    #[test]
+    fn files_on_disk_fall_back_to_memory() {
+        let directory =
+            std::env::temp_dir().join(format!("shader_include_test_{}", std::process::id()));
+        std::fs::create_dir_all(directory.join("include")).unwrap();
+        std::fs::write(directory.join("include/disk.hlsli"), "disk").unwrap();
+
+        let files = FileResolver {
+            search_paths: vec![directory.join("include")],
+        };
+        let mut memory = MemoryResolver::embedded(&[("/embedded/memory.hlsli", "memory")]);
+        memory.search_paths = paths(&["/embedded"]);
+        let mut tracker = IncludeTracker::new((files, &memory), directory.join("main.hlsl"));
+
+        let (_, disk) = tracker
+            .open(IncludeKind::System, "disk.hlsli", None)
+            .unwrap();
+        assert_eq!(disk.source, b"disk");
+        let (_, embedded) = tracker
+            .open(IncludeKind::System, "memory.hlsli", None)
+            .unwrap();
+        assert_eq!(embedded.path, Path::new("/embedded/memory.hlsli"));
+        assert_eq!(
+            tracker.dependencies(),
+            [
+                directory.join("include/disk.hlsli"),
+                PathBuf::from("/embedded/memory.hlsli")
+            ]
+        );
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn normalize_applies_parent_components_lexically() {
+        assert_eq!(normalize(Path::new("/a/./b/../c")), Path::new("/a/c"));
+        assert_eq!(normalize(Path::new("../x/./y/../z")), Path::new("../x/z"));
+        assert_eq!(normalize(Path::new("a/../../b")), Path::new("../b"));
+    }
+}
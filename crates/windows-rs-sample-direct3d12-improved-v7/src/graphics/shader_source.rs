@@ -0,0 +1,256 @@
+use crate::graphics::shader_include::FileResolver;
+use crate::graphics::shader_include::IncludeResolver;
+use crate::graphics::shader_include::MemoryResolver;
+use eyre::Context;
+use pipeline_description::PipelineDescription;
+use pipeline_description::parse_pipeline;
+use std::ffi::OsString;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// Reads shaders from this directory instead of the embedded ones, unless `--shader-dir` is given.
+pub const SHADER_DIR_ENV: &str = "D3D12_SAMPLE_SHADER_DIR";
+
+/// The pipeline `window show` renders with, relative to the shader directory.
+pub const PIPELINE_FILE: &str = "transparent_triangle.pipeline.toml";
+
+/// The shader directory's files as built into the executable, so it runs wherever it is copied.
+pub const EMBEDDED_SHADERS: &[(&str, &str)] = &[
+    (
+        PIPELINE_FILE,
+        include_str!("../transparent_triangle.pipeline.toml"),
+    ),
+    ("shaders.hlsl", include_str!("../shaders.hlsl")),
+];
+
+/// Where embedded files appear to be in paths and compiler messages.
+const EMBEDDED_ROOT: &str = "<embedded>";
+
+/// Where the pipeline file and the shaders it names are read from.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum ShaderSource {
+    #[default]
+    Embedded,
+    /// A directory laid out like `src`, for editing shaders without rebuilding.
+    Directory {
+        path: PathBuf,
+        origin: ShaderDirOrigin,
+    },
+}
+
+/// What chose the shader directory, for the log line saying which shaders are used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShaderDirOrigin {
+    Flag,
+    Environment,
+}
+
+impl ShaderSource {
+    /// `--shader-dir` wins over [`SHADER_DIR_ENV`]; an empty variable counts as unset.
+    pub fn select(flag: Option<PathBuf>, environment: Option<OsString>) -> Self {
+        if let Some(path) = flag {
+            return Self::Directory {
+                path,
+                origin: ShaderDirOrigin::Flag,
+            };
+        }
+        match environment {
+            Some(path) if !path.is_empty() => Self::Directory {
+                path: path.into(),
+                origin: ShaderDirOrigin::Environment,
+            },
+            _ => Self::Embedded,
+        }
+    }
+
+    /// [`Self::select`] with the variable from this process's environment.
+    pub fn from_environment(flag: Option<PathBuf>) -> Self {
+        Self::select(flag, std::env::var_os(SHADER_DIR_ENV))
+    }
+
+    /// The directory relative paths start from; a virtual one for embedded files.
+    pub fn root(&self) -> &Path {
+        match self {
+            Self::Embedded => Path::new(EMBEDDED_ROOT),
+            Self::Directory { path, .. } => path,
+        }
+    }
+
+    pub fn pipeline_path(&self) -> PathBuf {
+        self.root().join(PIPELINE_FILE)
+    }
+
+    /// Whether the files can change while running, so hot reload has something to watch.
+    pub fn is_editable(&self) -> bool {
+        matches!(self, Self::Directory { .. })
+    }
+
+    pub fn read(&self, path: &Path) -> eyre::Result<String> {
+        match self {
+            Self::Embedded => self
+                .embedded_files()
+                .get(path)
+                .map(str::to_string)
+                .ok_or_else(|| eyre::eyre!("{} is not an embedded shader file", path.display())),
+            Self::Directory { .. } => std::fs::read_to_string(path)
+                .wrap_err_with(|| format!("Failed to read {}", path.display())),
+        }
+    }
+
+    pub fn load_pipeline(&self) -> eyre::Result<PipelineDescription> {
+        let path = self.pipeline_path();
+        parse_pipeline(&self.read(&path)?, &path)
+    }
+
+    /// Resolves `#include`s from the same place as the shaders.
+    pub fn include_resolver(
+        &self,
+        search_paths: &[PathBuf],
+    ) -> Box<dyn IncludeResolver + Send + Sync> {
+        match self {
+            Self::Embedded => {
+                let mut files = self.embedded_files();
+                files.search_paths = search_paths.to_vec();
+                Box::new(files)
+            }
+            Self::Directory { .. } => Box::new(FileResolver {
+                search_paths: search_paths.to_vec(),
+            }),
+        }
+    }
+
+    fn embedded_files(&self) -> MemoryResolver {
+        let mut files = MemoryResolver::default();
+        for (name, source) in EMBEDDED_SHADERS {
+            files.insert(self.root().join(name), *source);
+        }
+        files
+    }
+}
+
+impl std::fmt::Display for ShaderSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Embedded => write!(f, "embedded shaders"),
+            Self::Directory {
+                path,
+                origin: ShaderDirOrigin::Flag,
+            } => write!(f, "shaders in {} (--shader-dir)", path.display()),
+            Self::Directory {
+                path,
+                origin: ShaderDirOrigin::Environment,
+            } => write!(f, "shaders in {} ({SHADER_DIR_ENV})", path.display()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::shader_include::IncludeKind;
+    use crate::graphics::shader_include::IncludeTracker;
+
+    #[test]
+    fn embedded_shaders_are_the_default() {
+        assert_eq!(ShaderSource::select(None, None), ShaderSource::Embedded);
+        assert_eq!(
+            ShaderSource::select(None, Some(OsString::new())),
+            ShaderSource::Embedded
+        );
+        assert!(!ShaderSource::Embedded.is_editable());
+        assert_eq!(ShaderSource::Embedded.to_string(), "embedded shaders");
+    }
+
+    #[test]
+    fn shader_dir_flag_wins_over_the_environment() {
+        let source = ShaderSource::select(Some("flag".into()), Some("environment".into()));
+        assert_eq!(
+            source,
+            ShaderSource::Directory {
+                path: "flag".into(),
+                origin: ShaderDirOrigin::Flag,
+            }
+        );
+        assert!(source.is_editable());
+        assert_eq!(source.to_string(), "shaders in flag (--shader-dir)");
+        assert_eq!(
+            source.pipeline_path(),
+            Path::new("flag").join(PIPELINE_FILE)
+        );
+
+        let source = ShaderSource::select(None, Some("environment".into()));
+        assert_eq!(
+            source,
+            ShaderSource::Directory {
+                path: "environment".into(),
+                origin: ShaderDirOrigin::Environment,
+            }
+        );
+        assert_eq!(
+            source.to_string(),
+            "shaders in environment (D3D12_SAMPLE_SHADER_DIR)"
+        );
+    }
+
+    #[test]
+    fn embedded_files_are_read_by_virtual_path() {
+        let source = ShaderSource::Embedded;
+        let pipeline = source.read(&source.pipeline_path()).unwrap();
+        assert!(pipeline.contains("shader = \"shaders.hlsl\""));
+        let shader = source.read(&source.root().join("shaders.hlsl")).unwrap();
+        assert!(shader.contains("VSMain"));
+        assert!(
+            source
+                .read(Path::new("<embedded>/./include/../shaders.hlsl"))
+                .is_ok()
+        );
+        assert_eq!(
+            source
+                .read(Path::new("missing.hlsl"))
+                .unwrap_err()
+                .to_string(),
+            "missing.hlsl is not an embedded shader file"
+        );
+    }
+
+    #[test]
+    fn embedded_includes_resolve_from_memory() {
+        let source = ShaderSource::Embedded;
+        let resolver = source.include_resolver(&[source.root().to_path_buf()]);
+        let mut tracker = IncludeTracker::new(&*resolver, "<embedded>/effects/main.hlsl");
+        let (_, resolved) = tracker
+            .open(IncludeKind::System, "shaders.hlsl", None)
+            .unwrap();
+        assert_eq!(resolved.path, source.root().join("shaders.hlsl"));
+        assert!(
+            tracker
+                .open(IncludeKind::Local, "shaders.hlsl", None)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn directory_files_are_read_from_disk() {
+        let directory =
+            std::env::temp_dir().join(format!("shader_source_test_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(directory.join("common.hlsli"), "disk").unwrap();
+
+        let source = ShaderSource::select(Some(directory.clone()), None);
+        assert_eq!(source.root(), directory);
+        assert_eq!(
+            source.read(&directory.join("common.hlsli")).unwrap(),
+            "disk"
+        );
+        let error = source.read(&directory.join("shaders.hlsl")).unwrap_err();
+        assert!(error.to_string().starts_with("Failed to read"), "{error}");
+
+        let resolver = source.include_resolver(&[]);
+        let mut tracker = IncludeTracker::new(&*resolver, directory.join("shaders.hlsl"));
+        let (_, resolved) = tracker
+            .open(IncludeKind::Local, "common.hlsli", None)
+            .unwrap();
+        assert_eq!(resolved.source, b"disk");
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,243 @@
+use std::collections::VecDeque;
+use windows::Win32::Foundation::HANDLE;
+use windows::Win32::Graphics::Direct3D12::D3D12_FENCE_FLAG_NONE;
+use windows::Win32::Graphics::Direct3D12::ID3D12CommandQueue;
+use windows::Win32::Graphics::Direct3D12::ID3D12Device;
+use windows::Win32::Graphics::Direct3D12::ID3D12Fence;
+use windows::Win32::System::Threading::CreateEventW;
+use windows::Win32::System::Threading::INFINITE;
+use windows::Win32::System::Threading::WaitForSingleObjectEx;
+use windows::core::Owned;
+
+/// A monotonically increasing GPU progress counter.
+///
+/// `ID3D12Fence` is the real implementation; [`SimulatedTimeline`] stands in for a GPU queue
+/// so that frame synchronisation can be exercised without hardware.
+pub trait Timeline {
+    /// Enqueue a signal that sets the timeline to `value` once all previously submitted work
+    /// has finished.
+    fn signal(&mut self, value: u64) -> eyre::Result<()>;
+
+    /// The highest value the GPU has reached so far.
+    fn completed_value(&self) -> u64;
+
+    /// Block until the timeline has reached at least `value`.
+    fn wait_until(&mut self, value: u64) -> eyre::Result<()>;
+}
+
+#[derive(Debug)]
+pub struct FenceTimeline {
+    command_queue: ID3D12CommandQueue,
+    fence: ID3D12Fence,
+    fence_event: Owned<HANDLE>,
+}
+
+impl FenceTimeline {
+    pub fn new(device: &ID3D12Device, command_queue: &ID3D12CommandQueue) -> eyre::Result<Self> {
+        let fence: ID3D12Fence = unsafe { device.CreateFence(0, D3D12_FENCE_FLAG_NONE) }?;
+        let fence_event = unsafe { Owned::new(CreateEventW(None, false, false, None)?) };
+        Ok(Self {
+            command_queue: command_queue.clone(),
+            fence,
+            fence_event,
+        })
+    }
+}
+
+impl Timeline for FenceTimeline {
+    fn signal(&mut self, value: u64) -> eyre::Result<()> {
+        unsafe { self.command_queue.Signal(&self.fence, value)? };
+        Ok(())
+    }
+
+    fn completed_value(&self) -> u64 {
+        unsafe { self.fence.GetCompletedValue() }
+    }
+
+    fn wait_until(&mut self, value: u64) -> eyre::Result<()> {
+        if self.completed_value() >= value {
+            return Ok(());
+        }
+
+        unsafe {
+            self.fence.SetEventOnCompletion(value, *self.fence_event)?;
+            WaitForSingleObjectEx(*self.fence_event, INFINITE, false);
+        }
+
+        Ok(())
+    }
+}
+
+/// A fake GPU queue driven by a virtual clock.
+///
+/// Each signal completes a configurable number of ticks after the previous signal finished,
+/// mirroring a queue that executes submissions in order. Waiting advances the clock to the
+/// moment the requested value completes.
+#[derive(Debug, Clone)]
+pub struct SimulatedTimeline {
+    now: u64,
+    completed: u64,
+    queue_idle_at: u64,
+    execution_delays: Vec<u64>,
+    next_delay: usize,
+    pending: VecDeque<PendingSignal>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct PendingSignal {
+    value: u64,
+    completes_at: u64,
+}
+
+impl SimulatedTimeline {
+    /// `execution_delays` is cycled through, one entry per signal. An empty list means work
+    /// completes immediately.
+    pub fn new(execution_delays: impl Into<Vec<u64>>) -> Self {
+        Self {
+            now: 0,
+            completed: 0,
+            queue_idle_at: 0,
+            execution_delays: execution_delays.into(),
+            next_delay: 0,
+            pending: VecDeque::new(),
+        }
+    }
+
+    pub fn now(&self) -> u64 {
+        self.now
+    }
+
+    /// Let `ticks` of CPU time pass without waiting on the GPU.
+    pub fn advance(&mut self, ticks: u64) {
+        self.now += ticks;
+        self.retire();
+    }
+
+    /// Whether the GPU has finished everything up to and including `value`.
+    pub fn is_complete(&self, value: u64) -> bool {
+        self.completed >= value
+    }
+
+    /// The number of signals that have been submitted but not yet reached.
+    pub fn pending_signal_count(&self) -> usize {
+        self.pending.len()
+    }
+
+    fn take_delay(&mut self) -> u64 {
+        if self.execution_delays.is_empty() {
+            return 0;
+        }
+
+        let delay = self.execution_delays[self.next_delay];
+        self.next_delay = (self.next_delay + 1) % self.execution_delays.len();
+        delay
+    }
+
+    fn retire(&mut self) {
+        while let Some(signal) = self.pending.front() {
+            if signal.completes_at > self.now {
+                break;
+            }
+            self.completed = self.completed.max(signal.value);
+            self.pending.pop_front();
+        }
+    }
+}
+
+impl Timeline for SimulatedTimeline {
+    fn signal(&mut self, value: u64) -> eyre::Result<()> {
+        let last_value = self
+            .pending
+            .back()
+            .map_or(self.completed, |signal| signal.value);
+        if value <= last_value {
+            eyre::bail!("Timeline values must increase: signalled {value} after {last_value}");
+        }
+
+        let delay = self.take_delay();
+        let completes_at = self.queue_idle_at.max(self.now) + delay;
+        self.queue_idle_at = completes_at;
+        self.pending.push_back(PendingSignal {
+            value,
+            completes_at,
+        });
+        self.retire();
+        Ok(())
+    }
+
+    fn completed_value(&self) -> u64 {
+        self.completed
+    }
+
+    fn wait_until(&mut self, value: u64) -> eyre::Result<()> {
+        if self.completed >= value {
+            return Ok(());
+        }
+
+        let Some(signal) = self.pending.iter().find(|signal| signal.value >= value) else {
+            eyre::bail!("Waiting for timeline value {value} which was never signalled");
+        };
+        self.now = self.now.max(signal.completes_at);
+        self.retire();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signals_complete_in_submission_order() {
+        let mut timeline = SimulatedTimeline::new([10, 1]);
+        timeline.signal(1).unwrap();
+        timeline.signal(2).unwrap();
+
+        // The second signal is quick but queued behind the first.
+        timeline.advance(5);
+        assert_eq!(timeline.completed_value(), 0);
+        timeline.advance(5);
+        assert_eq!(timeline.completed_value(), 1);
+        timeline.advance(1);
+        assert_eq!(timeline.completed_value(), 2);
+        assert_eq!(timeline.pending_signal_count(), 0);
+    }
+
+    #[test]
+    fn signal_rejects_values_that_do_not_increase() {
+        let mut timeline = SimulatedTimeline::new([3]);
+        timeline.signal(2).unwrap();
+        assert!(timeline.signal(2).is_err());
+        assert!(timeline.signal(1).is_err());
+        timeline.signal(3).unwrap();
+    }
+
+    #[test]
+    fn wait_until_advances_the_clock_to_completion() {
+        let mut timeline = SimulatedTimeline::new([4, 6]);
+        timeline.signal(1).unwrap();
+        timeline.signal(2).unwrap();
+        timeline.wait_until(2).unwrap();
+        assert_eq!(timeline.now(), 10);
+        assert!(timeline.is_complete(2));
+    }
+
+    #[test]
+    fn waiting_on_a_completed_value_returns_immediately() {
+        let mut timeline = SimulatedTimeline::new([4]);
+        timeline.signal(1).unwrap();
+        timeline.wait_until(1).unwrap();
+        let now = timeline.now();
+
+        timeline.wait_until(1).unwrap();
+        timeline.wait_until(0).unwrap();
+        assert_eq!(timeline.now(), now);
+    }
+
+    #[test]
+    fn waiting_on_an_unsignalled_value_fails() {
+        let mut timeline = SimulatedTimeline::new([]);
+        timeline.signal(1).unwrap();
+        assert!(timeline.wait_until(2).is_err());
+    }
+}
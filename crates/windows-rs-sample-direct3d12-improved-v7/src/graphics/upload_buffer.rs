@@ -0,0 +1,188 @@
+use crate::graphics::ring_allocator::RingAllocator;
+use crate::graphics::ring_allocator::grown_capacity;
+use std::ptr::NonNull;
+use tracing::info;
+use windows::Win32::Graphics::Direct3D12::*;
+use windows::Win32::Graphics::Dxgi::Common::DXGI_SAMPLE_DESC;
+
+/// A slice of the upload buffer that the GPU reads at `gpu_address` and the CPU writes through
+/// [`UploadBuffer::write`].
+#[derive(Debug, Clone, Copy)]
+pub struct UploadSlice {
+    pub gpu_address: u64,
+    pub size: u64,
+    ptr: NonNull<u8>,
+}
+
+#[derive(Debug)]
+struct MappedBuffer {
+    resource: ID3D12Resource,
+    ptr: NonNull<u8>,
+}
+
+impl MappedBuffer {
+    fn new(device: &ID3D12Device, capacity: u64) -> eyre::Result<Self> {
+        let resource = create_upload_buffer(device, capacity)?;
+        let mut mapped = std::ptr::null_mut();
+        unsafe { resource.Map(0, None, Some(&mut mapped))? };
+        let ptr = NonNull::new(mapped as *mut u8)
+            .ok_or_else(|| eyre::eyre!("Upload buffer map returned a null pointer"))?;
+        Ok(Self { resource, ptr })
+    }
+}
+
+impl Drop for MappedBuffer {
+    fn drop(&mut self) {
+        unsafe { self.resource.Unmap(0, None) };
+    }
+}
+
+/// A persistently mapped upload heap buffer that hands out per-frame sub-allocations for
+/// constants and dynamic geometry.
+///
+/// When a frame needs more than the ring can hold, the buffer is replaced by one twice the size
+/// (up to `max_capacity`); the old buffer is kept alive until the frames using it retire.
+#[derive(Debug)]
+pub struct UploadBuffer {
+    device: ID3D12Device,
+    current: MappedBuffer,
+    ring: RingAllocator,
+    max_capacity: u64,
+    /// Buffers replaced during the current frame.
+    replaced: Vec<MappedBuffer>,
+    /// Replaced buffers with the fence value of the last frame that used them.
+    retiring: Vec<(u64, MappedBuffer)>,
+}
+
+impl UploadBuffer {
+    pub fn new(device: &ID3D12Device, capacity: u64, max_capacity: u64) -> eyre::Result<Self> {
+        Ok(Self {
+            device: device.clone(),
+            current: MappedBuffer::new(device, capacity)?,
+            ring: RingAllocator::new(capacity),
+            max_capacity,
+            replaced: Vec::new(),
+            retiring: Vec::new(),
+        })
+    }
+
+    pub fn capacity(&self) -> u64 {
+        self.ring.capacity()
+    }
+
+    pub fn allocate(&mut self, size: u64, alignment: u64) -> eyre::Result<UploadSlice> {
+        if let Some(allocation) = self.ring.allocate(size, alignment) {
+            return Ok(self.slice(allocation.offset, size));
+        }
+
+        let Some(capacity) =
+            grown_capacity(self.ring.capacity(), size, alignment, self.max_capacity)
+        else {
+            eyre::bail!(
+                "Upload allocation of {size} bytes does not fit: {} of {} bytes in use and the buffer cannot grow past {} bytes",
+                self.ring.used(),
+                self.ring.capacity(),
+                self.max_capacity
+            );
+        };
+
+        info!(
+            old_capacity = self.ring.capacity(),
+            new_capacity = capacity,
+            "Growing upload buffer"
+        );
+        let previous = std::mem::replace(
+            &mut self.current,
+            MappedBuffer::new(&self.device, capacity)?,
+        );
+        self.replaced.push(previous);
+        self.ring = RingAllocator::new(capacity);
+
+        let allocation = self.ring.allocate(size, alignment).ok_or_else(|| {
+            eyre::eyre!("Upload allocation of {size} bytes does not fit after growing")
+        })?;
+        Ok(self.slice(allocation.offset, size))
+    }
+
+    /// Copy `data` to the start of `slice`.
+    pub fn write<T: Copy>(&self, slice: &UploadSlice, data: &[T]) -> eyre::Result<()> {
+        let size = std::mem::size_of_val(data);
+        if size as u64 > slice.size {
+            eyre::bail!(
+                "Write of {size} bytes overflows a {} byte upload slice",
+                slice.size
+            );
+        }
+
+        // The slice belongs to a frame that has not retired, so the GPU is not reading it yet.
+        unsafe {
+            std::ptr::copy_nonoverlapping(data.as_ptr() as *const u8, slice.ptr.as_ptr(), size);
+        }
+        Ok(())
+    }
+
+    pub fn finish_frame(&mut self, fence_value: u64) {
+        self.ring.finish_frame(fence_value);
+        self.retiring
+            .extend(self.replaced.drain(..).map(|buffer| (fence_value, buffer)));
+    }
+
+    pub fn retire(&mut self, completed_fence_value: u64) {
+        self.ring.retire(completed_fence_value);
+        self.retiring
+            .retain(|(fence_value, _)| *fence_value > completed_fence_value);
+    }
+
+    fn slice(&self, offset: u64, size: u64) -> UploadSlice {
+        UploadSlice {
+            gpu_address: unsafe { self.current.resource.GetGPUVirtualAddress() } + offset,
+            size,
+            ptr: unsafe { NonNull::new_unchecked(self.current.ptr.as_ptr().add(offset as usize)) },
+        }
+    }
+}
+
+pub fn create_upload_buffer(device: &ID3D12Device, size: u64) -> eyre::Result<ID3D12Resource> {
+    create_buffer(device, D3D12_HEAP_TYPE_UPLOAD, D3D12_RESOURCE_STATE_GENERIC_READ, size)
+}
+
+/// Create a GPU-local buffer in the copy destination state, ready to be filled from an upload
+/// buffer.
+pub fn create_default_buffer(device: &ID3D12Device, size: u64) -> eyre::Result<ID3D12Resource> {
+    create_buffer(device, D3D12_HEAP_TYPE_DEFAULT, D3D12_RESOURCE_STATE_COPY_DEST, size)
+}
+
+fn create_buffer(
+    device: &ID3D12Device,
+    heap_type: D3D12_HEAP_TYPE,
+    initial_state: D3D12_RESOURCE_STATES,
+    size: u64,
+) -> eyre::Result<ID3D12Resource> {
+    let mut buffer = None;
+    unsafe {
+        device.CreateCommittedResource(
+            &D3D12_HEAP_PROPERTIES {
+                Type: heap_type,
+                ..Default::default()
+            },
+            D3D12_HEAP_FLAG_NONE,
+            &D3D12_RESOURCE_DESC {
+                Dimension: D3D12_RESOURCE_DIMENSION_BUFFER,
+                Width: size,
+                Height: 1,
+                DepthOrArraySize: 1,
+                MipLevels: 1,
+                SampleDesc: DXGI_SAMPLE_DESC {
+                    Count: 1,
+                    Quality: 0,
+                },
+                Layout: D3D12_TEXTURE_LAYOUT_ROW_MAJOR,
+                ..Default::default()
+            },
+            initial_state,
+            None,
+            &mut buffer,
+        )?
+    };
+    Ok(buffer.expect("buffer should be initialized"))
+}
@@ -6,6 +6,14 @@ pub mod graphics;
 pub mod logging_init;
 
 use crate::cli::Cli;
+use crate::graphics::classify_error;
+use crate::graphics::debug_messages::DebugLayerOptions;
+use crate::graphics::debug_messages::set_debug_layer_options;
+use crate::graphics::graphics_error::HresultFacility;
+use crate::graphics::hresult;
+use color_eyre::Section;
+use color_eyre::SectionExt;
+use std::process::ExitCode;
 
 const VERSION: &str = concat!(
     env!("CARGO_PKG_VERSION"),
@@ -14,7 +22,32 @@ const VERSION: &str = concat!(
     ")"
 );
 
-pub fn main() -> eyre::Result<()> {
+/// Run the CLI, reporting any error with a remediation hint and exiting with a code that
+/// identifies its [`ErrorCategory`](crate::graphics::graphics_error::ErrorCategory).
+pub fn main() -> ExitCode {
+    let Err(report) = run() else {
+        return ExitCode::SUCCESS;
+    };
+
+    let category = classify_error(&report);
+    let mut summary = format!("{category} (exit code {})", category.exit_code());
+    if let Some(code) = report.chain().find_map(hresult) {
+        summary.push_str(&format!(
+            ", {} HRESULT {:#010X}",
+            HresultFacility::from_hresult(code),
+            code as u32
+        ));
+    }
+    let report = report.section(summary.header("Category:"));
+    let report = match category.hint() {
+        Some(hint) => report.suggestion(hint),
+        None => report,
+    };
+    eprintln!("Error: {report:?}");
+    ExitCode::from(category.exit_code())
+}
+
+fn run() -> eyre::Result<()> {
     color_eyre::install()?;
 
     let cli: Cli = figue::Driver::new(
@@ -35,6 +68,12 @@ pub fn main() -> eyre::Result<()> {
     .unwrap();
 
     logging_init::init_logging(&cli.global_args)?;
+    set_debug_layer_options(DebugLayerOptions::parse(
+        cli.global_args.debug_layer,
+        cli.global_args.gpu_validation,
+        cli.global_args.mute_debug_messages.as_deref(),
+        cli.global_args.break_on_debug_severity.as_deref(),
+    )?);
 
     #[cfg(windows)]
     {
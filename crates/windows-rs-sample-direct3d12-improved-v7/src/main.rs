@@ -1,3 +1,5 @@
-fn main() -> eyre::Result<()> {
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
     sample_direct3d12_improved_v7::main()
 }
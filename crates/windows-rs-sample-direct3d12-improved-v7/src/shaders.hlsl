@@ -1,3 +1,17 @@
+cbuffer DrawConstants : register(b0)
+{
+    float2 position_scale;
+    float cursor_weight;
+    float draw_padding;
+};
+
+cbuffer CursorConstants : register(b1)
+{
+    float2 cursor_ndc;
+    float cursor_visible;
+    float cursor_padding;
+};
+
 struct PSInput
 {
     float4 position : SV_POSITION;
@@ -7,12 +21,19 @@ struct PSInput
 PSInput VSMain(float3 position : POSITION, float4 color : COLOR)
 {
     PSInput result;
-    result.position = float4(position, 1.0);
-    result.color = color;
+    float2 transformed = position.xy * position_scale + cursor_ndc * cursor_weight;
+    result.position = float4(transformed, position.z, 1.0);
+    result.color = color * lerp(1.0, cursor_visible, cursor_weight);
     return result;
 }
 
 float4 PSMain(PSInput input) : SV_TARGET
 {
+#if GRAYSCALE
+    // Only the colour changes; alpha and blending stay as they are.
+    float luminance = dot(input.color.rgb, float3(0.2126, 0.7152, 0.0722));
+    return float4(luminance.xxx, input.color.a);
+#else
     return input.color;
+#endif
 }